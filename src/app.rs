@@ -9,7 +9,8 @@
 //!
 //! - **Panel Icon**: Displays a system monitor icon (`utilities-system-monitor-symbolic`)
 //! - **Popup Menu**: Shows options to show/hide the widget and open settings
-//! - **Widget Management**: Spawns and kills the standalone widget process
+//! - **Widget Management**: Spawns the standalone widget process and stops it
+//!   through its session bus interface
 //! - **Auto-start**: Optionally launches the widget when the applet loads
 //!
 //! # Architecture
@@ -21,7 +22,7 @@
 //! The actual monitoring widget runs as a separate process (`cosmic-widget`)
 //! to allow for layer-shell positioning and independent lifecycle management.
 
-use crate::config::Config;
+use crate::config::{Config, OVERLAY_BUS_NAME, OVERLAY_INTERFACE, OVERLAY_OBJECT_PATH};
 use crate::fl;
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
 use cosmic::iced::platform_specific::shell::commands::popup::{destroy_popup, get_popup};
@@ -109,13 +110,27 @@ impl AppModel {
 
     /// Checks if the widget process is currently running.
     ///
-    /// Uses `pgrep` to search for the `cosmic-widget` process.
-    /// This is a simple but effective approach that doesn't require
-    /// tracking PIDs manually.
+    /// The widget owns a well-known session bus name while it runs, so this
+    /// asks the bus first. `pgrep` is only used when the session bus cannot
+    /// be reached.
     ///
     /// # Returns
     /// `true` if the widget process is found, `false` otherwise.
     fn check_widget_running() -> bool {
+        match overlay_bus_name_has_owner() {
+            Ok(running) => running,
+            Err(err) => {
+                log::warn!(
+                    "Failed to query the widget's bus name, using pgrep: {}",
+                    err
+                );
+                Self::widget_process_running()
+            }
+        }
+    }
+
+    /// Fallback process check for sessions without a reachable bus.
+    fn widget_process_running() -> bool {
         if let Ok(output) = std::process::Command::new("pgrep")
             .args(["-r", "R,S,D,T,t,W,I"])
             .arg("-x")
@@ -128,6 +143,36 @@ impl AppModel {
             false
         }
     }
+
+    /// Asks the running widget to exit, falling back to `pkill`.
+    fn stop_widget() {
+        if let Err(err) = call_overlay_method("Quit") {
+            log::warn!("Failed to stop the widget over D-Bus, using pkill: {}", err);
+            // Use exact match to avoid killing cosmic-widget-applet too
+            let _ = std::process::Command::new("pkill")
+                .arg("-x")
+                .arg("cosmic-widget")
+                .spawn();
+        }
+    }
+}
+
+fn overlay_bus_name_has_owner() -> zbus::Result<bool> {
+    let connection = zbus::blocking::Connection::session()?;
+    let name = zbus::names::BusName::try_from(OVERLAY_BUS_NAME)?;
+    Ok(zbus::blocking::fdo::DBusProxy::new(&connection)?.name_has_owner(name)?)
+}
+
+fn call_overlay_method(method: &str) -> zbus::Result<()> {
+    let connection = zbus::blocking::Connection::session()?;
+    connection.call_method(
+        Some(OVERLAY_BUS_NAME),
+        OVERLAY_OBJECT_PATH,
+        Some(OVERLAY_INTERFACE),
+        method,
+        &(),
+    )?;
+    Ok(())
 }
 
 // ============================================================================
@@ -320,13 +365,8 @@ impl cosmic::Application for AppModel {
 
             Message::ToggleWidget => {
                 if self.widget_running {
-                    // Kill the widget process
-                    // Use exact match to avoid killing cosmic-widget-applet too
-                    log::info!("Stopping widget via pkill");
-                    let _ = std::process::Command::new("pkill")
-                        .arg("-x")
-                        .arg("cosmic-widget")
-                        .spawn();
+                    log::info!("Stopping widget");
+                    Self::stop_widget();
                     self.widget_running = false;
 
                     // Disable auto-start since user explicitly hid the widget
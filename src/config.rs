@@ -24,9 +24,66 @@
 
 use cosmic::cosmic_config::{self, CosmicConfigEntry, cosmic_config_derive::CosmicConfigEntry};
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 
 pub const UPDATE_INTERVAL_MS: u64 = 1_000;
 
+/// cosmic-config ID of the main configuration, also used by the default overlay.
+pub const CONFIG_ID: &str = "com.github.zoliviragh.CosmicWidget";
+
+/// Longest accepted overlay instance name.
+pub const MAX_INSTANCE_NAME_LEN: usize = 32;
+
+/// Well-known session bus name owned by the running overlay.
+pub const OVERLAY_BUS_NAME: &str = "com.github.zoliviragh.CosmicWidget";
+
+/// Object path of the overlay's D-Bus interface.
+pub const OVERLAY_OBJECT_PATH: &str = "/com/github/zoliviragh/CosmicWidget";
+
+/// Name of the overlay's D-Bus interface.
+pub const OVERLAY_INTERFACE: &str = "com.github.zoliviragh.CosmicWidget1";
+
+/// Shortest polling interval accepted for a device monitor.
+pub const MIN_POLL_INTERVAL_MS: u64 = 250;
+
+/// Longest polling interval accepted for a device monitor.
+pub const MAX_POLL_INTERVAL_MS: u64 = 300_000;
+
+/// Default TCP port of the OpenMetrics exporter.
+pub const DEFAULT_METRICS_PORT: u16 = 9863;
+
+/// History graph windows offered by the settings application, in seconds.
+pub const HISTORY_WINDOW_OPTIONS_SECS: [u64; 3] = [300, 600, 900];
+
+/// Longest history the sampler retains for graphs, in seconds.
+pub const MAX_HISTORY_WINDOW_SECS: u64 = 900;
+
+/// Shortest accepted interval between runs of a shell command, in seconds.
+pub const MIN_COMMAND_INTERVAL_SECS: u64 = 1;
+
+/// Longest a shell command may run before it is killed, in seconds.
+pub const MAX_COMMAND_TIMEOUT_SECS: u64 = 60;
+
+/// Most output kept from each stream of a shell command run, in bytes.
+pub const MAX_COMMAND_OUTPUT_BYTES: u32 = 65_536;
+
+/// Lengths of the top process lists offered by the settings application.
+pub const TOP_PROCESS_COUNT_OPTIONS: [u32; 4] = [3, 5, 8, 10];
+
+/// Longest top process list, and how many processes the sampler keeps.
+pub const MAX_TOP_PROCESSES: u32 = 10;
+
+/// Latest day of the month a network billing cycle can start on.
+pub const MAX_BILLING_DAY: u32 = 31;
+
+/// Offset of the overlay from the edges of its default top-right corner.
+const DEFAULT_WIDGET_MARGIN: i32 = 50;
+
+/// Horizontal offset from the left edge that earlier versions used as the
+/// default, which is only on screen on outputs about 7680 px wide.
+const LEGACY_DEFAULT_WIDGET_X: i32 = 7260;
+
 // ============================================================================
 // Widget Section Ordering
 // ============================================================================
@@ -55,6 +112,92 @@ pub enum WidgetSection {
     Notifications,
     /// Now playing information from MPRIS, Cider, and Emby
     Media,
+    /// Output of user-configured shell commands
+    Commands,
+    /// Processes using the most CPU and memory
+    Processes,
+    /// User-written template, identified by [`CustomSection::id`]
+    Custom(u32),
+}
+
+/// Text section drawn from a user-written template (see `src/template.rs`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomSection {
+    /// Stable identifier referenced by [`WidgetSection::Custom`].
+    pub id: u32,
+    /// Heading shown above the template. Empty hides the heading.
+    pub title: String,
+    pub template: String,
+}
+
+impl Default for CustomSection {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            title: String::from("Custom"),
+            template: String::from("CPU {cpu}  Memory {mem_used} of {mem_total}"),
+        }
+    }
+}
+
+/// Shell command run periodically for the Commands section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellCommand {
+    pub enabled: bool,
+    /// Name shown with the output. Empty shows the command line instead.
+    pub label: String,
+    /// Command line passed to `sh -c`.
+    pub command: String,
+    pub interval_secs: u64,
+    /// Seconds after which the command and its children are killed.
+    pub timeout_secs: u64,
+    /// Most output kept from stdout and from stderr, in bytes.
+    pub max_output_bytes: u32,
+    /// Show the output as a table of whitespace-separated columns instead of
+    /// its first line.
+    pub table: bool,
+}
+
+impl ShellCommand {
+    pub fn display_label(&self) -> &str {
+        let label = self.label.trim();
+        if label.is_empty() {
+            self.command.trim()
+        } else {
+            label
+        }
+    }
+
+    /// Time between runs, at least [`MIN_COMMAND_INTERVAL_SECS`].
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(MIN_COMMAND_INTERVAL_SECS))
+    }
+
+    /// Run time limit, between one second and [`MAX_COMMAND_TIMEOUT_SECS`].
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.clamp(1, MAX_COMMAND_TIMEOUT_SECS))
+    }
+
+    /// Output limit per stream, at most [`MAX_COMMAND_OUTPUT_BYTES`].
+    pub fn output_limit(&self) -> usize {
+        self.max_output_bytes.clamp(1, MAX_COMMAND_OUTPUT_BYTES) as usize
+    }
+}
+
+impl Default for ShellCommand {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            label: String::new(),
+            command: String::from("uptime -p"),
+            interval_secs: 60,
+            timeout_secs: 5,
+            max_output_bytes: 4096,
+            table: false,
+        }
+    }
 }
 
 /// Visual style used by the Iced temperature gauges.
@@ -69,6 +212,354 @@ pub enum TemperatureGaugeStyle {
     Text,
 }
 
+/// Device monitors that poll hardware on their own configurable cadence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolledMonitor {
+    /// CPU, memory, and GPU usage
+    Utilization,
+    /// Network transfer counters
+    Network,
+    /// Block device throughput counters
+    DiskIo,
+    /// CPU and GPU sensors
+    Temperatures,
+    /// Filesystem capacity
+    Storage,
+    /// Peripheral battery levels
+    Battery,
+}
+
+impl PolledMonitor {
+    pub const ALL: [PolledMonitor; 6] = [
+        PolledMonitor::Utilization,
+        PolledMonitor::Network,
+        PolledMonitor::DiskIo,
+        PolledMonitor::Temperatures,
+        PolledMonitor::Storage,
+        PolledMonitor::Battery,
+    ];
+
+    /// Returns the human-readable label shown next to the interval input.
+    pub fn label(&self) -> &'static str {
+        match self {
+            PolledMonitor::Utilization => "CPU, memory, and GPU",
+            PolledMonitor::Network => "Network",
+            PolledMonitor::DiskIo => "Disk I/O",
+            PolledMonitor::Temperatures => "Temperatures",
+            PolledMonitor::Storage => "Storage",
+            PolledMonitor::Battery => "Devices",
+        }
+    }
+}
+
+/// Graph drawn below a section from its sampled history.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistoryGraphStyle {
+    /// Live readings only.
+    #[default]
+    Off,
+    /// Thin lines, one per metric.
+    Sparkline,
+    /// Lines with a translucent fill down to the baseline.
+    Area,
+}
+
+impl HistoryGraphStyle {
+    pub const ALL: [HistoryGraphStyle; 3] = [
+        HistoryGraphStyle::Off,
+        HistoryGraphStyle::Sparkline,
+        HistoryGraphStyle::Area,
+    ];
+}
+
+/// Reading watched by an [`AlertRule`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertMetric {
+    /// CPU usage in percent.
+    CpuUsage,
+    /// Memory usage in percent.
+    MemoryUsage,
+    /// GPU usage in percent.
+    GpuUsage,
+    /// CPU temperature in degrees Celsius.
+    #[default]
+    CpuTemperature,
+    /// GPU temperature in degrees Celsius.
+    GpuTemperature,
+    /// Used space of each mounted filesystem in percent.
+    StorageUsage,
+    /// Battery level of each peripheral in percent.
+    DeviceBattery,
+    /// Traffic of the current billing cycle in percent of the network quota.
+    NetworkQuota,
+}
+
+impl AlertMetric {
+    pub const ALL: [AlertMetric; 8] = [
+        AlertMetric::CpuUsage,
+        AlertMetric::MemoryUsage,
+        AlertMetric::GpuUsage,
+        AlertMetric::CpuTemperature,
+        AlertMetric::GpuTemperature,
+        AlertMetric::StorageUsage,
+        AlertMetric::DeviceBattery,
+        AlertMetric::NetworkQuota,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            AlertMetric::CpuUsage => "CPU usage",
+            AlertMetric::MemoryUsage => "Memory usage",
+            AlertMetric::GpuUsage => "GPU usage",
+            AlertMetric::CpuTemperature => "CPU temperature",
+            AlertMetric::GpuTemperature => "GPU temperature",
+            AlertMetric::StorageUsage => "Storage usage",
+            AlertMetric::DeviceBattery => "Device battery",
+            AlertMetric::NetworkQuota => "Network quota",
+        }
+    }
+
+    /// Unit suffix appended to thresholds and readings.
+    pub fn unit(self) -> &'static str {
+        match self {
+            AlertMetric::CpuTemperature | AlertMetric::GpuTemperature => " °C",
+            _ => "%",
+        }
+    }
+
+    /// Whether the metric has one reading per filesystem or device, which a
+    /// rule's `target` can narrow down.
+    pub fn has_targets(self) -> bool {
+        matches!(self, AlertMetric::StorageUsage | AlertMetric::DeviceBattery)
+    }
+}
+
+/// Direction in which a reading crosses an alert threshold.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertComparison {
+    #[default]
+    Above,
+    Below,
+}
+
+impl AlertComparison {
+    pub const ALL: [AlertComparison; 2] = [AlertComparison::Above, AlertComparison::Below];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            AlertComparison::Above => "above",
+            AlertComparison::Below => "below",
+        }
+    }
+}
+
+/// User-defined rule that raises a desktop notification while a reading is
+/// past its threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertRule {
+    pub enabled: bool,
+    pub metric: AlertMetric,
+    pub comparison: AlertComparison,
+    /// Threshold in whole percent or degrees Celsius.
+    pub threshold: i32,
+    /// Distance the reading must move back past the threshold before the
+    /// rule can fire again.
+    pub hysteresis: u32,
+    /// Seconds the threshold must stay crossed before notifying.
+    pub duration_secs: u64,
+    /// Shortest time between two notifications for the same reading.
+    pub cooldown_secs: u64,
+    /// Mount point or device name for storage and device rules. Empty
+    /// matches every filesystem or device.
+    pub target: String,
+}
+
+impl AlertRule {
+    /// Whether `value` is past the threshold.
+    pub fn is_crossed(&self, value: f32) -> bool {
+        let threshold = self.threshold as f32;
+        match self.comparison {
+            AlertComparison::Above => value > threshold,
+            AlertComparison::Below => value < threshold,
+        }
+    }
+
+    /// Whether `value` has moved back past the threshold by the hysteresis.
+    pub fn is_cleared(&self, value: f32) -> bool {
+        let hysteresis = self.hysteresis as f32;
+        let threshold = self.threshold as f32;
+        match self.comparison {
+            AlertComparison::Above => value <= threshold - hysteresis,
+            AlertComparison::Below => value >= threshold + hysteresis,
+        }
+    }
+}
+
+impl Default for AlertRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            metric: AlertMetric::CpuTemperature,
+            comparison: AlertComparison::Above,
+            threshold: 90,
+            hysteresis: 5,
+            duration_secs: 30,
+            cooldown_secs: 300,
+            target: String::new(),
+        }
+    }
+}
+
+/// Property of a mounted filesystem that a [`StorageRule`] compares.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageRuleField {
+    #[default]
+    MountPoint,
+    /// Filesystem type as in `/proc/mounts` (e.g., "btrfs", "nfs4")
+    FilesystemType,
+    /// Mounted device path (e.g., "/dev/sda1") or kernel device name
+    /// (e.g., "dm-0")
+    Device,
+}
+
+/// Rule that shows or hides the mounts whose mount point, filesystem type
+/// or device equals `value`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageRule {
+    pub field: StorageRuleField,
+    pub value: String,
+    pub shown: bool,
+}
+
+/// Name shown for a mount instead of the detected one.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageLabel {
+    pub mount_point: String,
+    pub name: String,
+}
+
+/// Order of the rows in the storage section.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageSort {
+    /// Order in which the kernel lists the mounts
+    #[default]
+    Discovered,
+    Name,
+    MountPoint,
+    /// Fullest first
+    Usage,
+    /// Largest first
+    Size,
+}
+
+impl StorageSort {
+    pub const ALL: [StorageSort; 5] = [
+        StorageSort::Discovered,
+        StorageSort::Name,
+        StorageSort::MountPoint,
+        StorageSort::Usage,
+        StorageSort::Size,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            StorageSort::Discovered => "Mount order",
+            StorageSort::Name => "Name",
+            StorageSort::MountPoint => "Mount point",
+            StorageSort::Usage => "Fullest first",
+            StorageSort::Size => "Largest first",
+        }
+    }
+}
+
+/// Corner or edge of the output that the overlay offsets are measured from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlayAnchor {
+    /// Offsets from the left and top edges.
+    #[default]
+    TopLeft,
+    /// Centered horizontally, offset from the top edge.
+    Top,
+    /// Offsets from the right and top edges.
+    TopRight,
+    /// Offsets from the left and bottom edges.
+    BottomLeft,
+    /// Centered horizontally, offset from the bottom edge.
+    Bottom,
+    /// Offsets from the right and bottom edges.
+    BottomRight,
+}
+
+impl OverlayAnchor {
+    pub const ALL: [OverlayAnchor; 6] = [
+        OverlayAnchor::TopLeft,
+        OverlayAnchor::Top,
+        OverlayAnchor::TopRight,
+        OverlayAnchor::BottomLeft,
+        OverlayAnchor::Bottom,
+        OverlayAnchor::BottomRight,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            OverlayAnchor::TopLeft => "Top left",
+            OverlayAnchor::Top => "Top",
+            OverlayAnchor::TopRight => "Top right",
+            OverlayAnchor::BottomLeft => "Bottom left",
+            OverlayAnchor::Bottom => "Bottom",
+            OverlayAnchor::BottomRight => "Bottom right",
+        }
+    }
+
+    pub fn is_bottom(&self) -> bool {
+        matches!(
+            self,
+            OverlayAnchor::BottomLeft | OverlayAnchor::Bottom | OverlayAnchor::BottomRight
+        )
+    }
+
+    pub fn is_right(&self) -> bool {
+        matches!(self, OverlayAnchor::TopRight | OverlayAnchor::BottomRight)
+    }
+
+    pub fn is_centered(&self) -> bool {
+        matches!(self, OverlayAnchor::Top | OverlayAnchor::Bottom)
+    }
+
+    /// Layer surface margins `(top, right, bottom, left)` that place the
+    /// overlay `x` and `y` pixels away from the anchored edges.
+    pub fn margins(&self, x: i32, y: i32) -> (i32, i32, i32, i32) {
+        let (top, bottom) = if self.is_bottom() { (0, y) } else { (y, 0) };
+        let (right, left) = if self.is_centered() {
+            (0, 0)
+        } else if self.is_right() {
+            (x, 0)
+        } else {
+            (0, x)
+        };
+        (top, right, bottom, left)
+    }
+
+    /// Offsets after the pointer moved by `delta_x` and `delta_y` pixels.
+    /// Offsets measured from the right or bottom edge grow the other way.
+    pub fn dragged_offsets(&self, x: i32, y: i32, delta_x: i32, delta_y: i32) -> (i32, i32) {
+        let x = if self.is_right() {
+            x.saturating_sub(delta_x)
+        } else {
+            x.saturating_add(delta_x)
+        };
+        let y = if self.is_bottom() {
+            y.saturating_sub(delta_y)
+        } else {
+            y.saturating_add(delta_y)
+        };
+        (x, y)
+    }
+}
+
 impl WidgetSection {
     /// Returns the human-readable label for this section.
     ///
@@ -84,6 +575,9 @@ impl WidgetSection {
             WidgetSection::Weather => "Weather",
             WidgetSection::Notifications => "Notifications",
             WidgetSection::Media => "Now Playing",
+            WidgetSection::Commands => "Commands",
+            WidgetSection::Processes => "Top Processes",
+            WidgetSection::Custom(_) => "Custom",
         }
     }
 }
@@ -116,21 +610,70 @@ pub struct Config {
     /// Uses sysinfo crate to read from /proc/stat.
     pub show_cpu: bool,
 
+    /// Let a click on the CPU bar expand it to per-core usage and
+    /// frequencies, the load average, and P-core/E-core groups on hybrid
+    /// processors.
+    pub show_cpu_details: bool,
+
     /// Show memory (RAM) usage bar and percentage in the Utilization section.
     /// Displays used/total memory from /proc/meminfo.
     pub show_memory: bool,
 
+    /// Show used/total memory, swap, zram, page cache and PSI memory
+    /// pressure below the memory bar.
+    pub show_memory_details: bool,
+
     /// Show GPU usage bar and percentage in the Utilization section.
     /// Supports NVIDIA (NVML), AMD, and Intel GPUs without subprocesses.
     pub show_gpu: bool,
 
+    /// PCI addresses of GPUs left out of the per-device GPU rows. GPUs that
+    /// are not listed, including newly installed ones, are shown.
+    pub hidden_gpus: Vec<String>,
+
+    /// Show VRAM use, board power, core and memory clocks and fan speed
+    /// below each GPU row.
+    pub show_gpu_details: bool,
+
+    /// Let a click on a GPU row expand the processes with the most GPU engine
+    /// time and video memory.
+    pub show_gpu_processes: bool,
+
     /// Show network transfer rates (upload/download speeds).
     /// Displayed as a reorderable Network section.
     pub show_network: bool,
 
+    /// Virtual interfaces (e.g., "wg0") shown and counted in the network
+    /// totals. Only interfaces backed by a device are otherwise.
+    pub network_interfaces_included: Vec<String>,
+
+    /// Physical interfaces left out of the network rows and totals.
+    pub network_interfaces_excluded: Vec<String>,
+
+    /// Show the active connection, Wi-Fi access point, addresses, gateway
+    /// and VPN state from NetworkManager below the network rates.
+    pub show_network_connections: bool,
+
+    /// Show today's, this month's and this billing cycle's traffic below
+    /// the network rates.
+    pub show_network_traffic: bool,
+
+    /// Day of the month on which the network billing cycle starts, between
+    /// one and [`MAX_BILLING_DAY`]. Later than a month's last day means its
+    /// last day.
+    pub network_billing_day: u32,
+
+    /// Traffic allowed per billing cycle in GiB, summed over the counted
+    /// interfaces. 0 means no quota.
+    pub network_quota_gib: u32,
+
     /// Show aggregate disk read and write throughput.
     pub show_disk: bool,
 
+    /// Show a row per physical drive with its IOPS, request latency and
+    /// utilization below the disk throughput.
+    pub show_disk_devices: bool,
+
     // ========================================================================
     // Temperature Section
     // ========================================================================
@@ -148,6 +691,33 @@ pub struct Config {
     /// Gauge shape used by the Iced overlay.
     pub temperature_gauge_style: TemperatureGaugeStyle,
 
+    // ========================================================================
+    // History Graphs
+    // ========================================================================
+    /// Graph of CPU, memory, and GPU usage below the utilization bars.
+    pub utilization_graph_style: HistoryGraphStyle,
+
+    /// Seconds of utilization history shown by the graph.
+    pub utilization_graph_window_secs: u64,
+
+    /// Graph of download and upload rates below the network rows.
+    pub network_graph_style: HistoryGraphStyle,
+
+    /// Seconds of network history shown by the graph.
+    pub network_graph_window_secs: u64,
+
+    /// Graph of read and write rates below the disk I/O rows.
+    pub disk_io_graph_style: HistoryGraphStyle,
+
+    /// Seconds of disk I/O history shown by the graph.
+    pub disk_io_graph_window_secs: u64,
+
+    /// Graph of CPU and GPU temperatures below the gauges.
+    pub temperature_graph_style: HistoryGraphStyle,
+
+    /// Seconds of temperature history shown by the graph.
+    pub temperature_graph_window_secs: u64,
+
     // ========================================================================
     // Storage Section
     // ========================================================================
@@ -155,6 +725,27 @@ pub struct Config {
     /// Displays each mounted disk with used/total space and a progress bar.
     pub show_storage: bool,
 
+    /// Show the temperature, wear and SMART error counts of the drive below
+    /// each local filesystem, with a warning badge when its health degrades.
+    pub show_drive_health: bool,
+
+    /// Show the state of the Btrfs filesystem, ZFS pool or md array below
+    /// each filesystem, with its resync or scrub progress. A degraded pool
+    /// turns its row red.
+    pub show_pool_health: bool,
+
+    /// Rules showing or hiding mounts, applied in order so a later matching
+    /// rule overrides an earlier one. Mounts no rule matches are listed by
+    /// [`storage_mount_shown_by_default`].
+    pub storage_rules: Vec<StorageRule>,
+
+    /// Names replacing the detected ones ("Home", the drive model, the
+    /// network share), by mount point.
+    pub storage_labels: Vec<StorageLabel>,
+
+    /// Order of the storage rows.
+    pub storage_sort: StorageSort,
+
     // ========================================================================
     // Battery Section
     // ========================================================================
@@ -201,6 +792,51 @@ pub struct Config {
     /// Find this in Cider Settings → Connectivity → Remote Token.
     pub cider_api_token: String,
 
+    // ========================================================================
+    // Commands Section
+    // ========================================================================
+    /// Show the output of the configured shell commands.
+    pub show_commands: bool,
+
+    /// Shell commands run for the Commands section. Only read from the main
+    /// configuration; the commands only run while an overlay shows them.
+    pub commands: Vec<ShellCommand>,
+
+    // ========================================================================
+    // Top Processes Section
+    // ========================================================================
+    /// Show the processes using the most CPU and the most resident memory.
+    pub show_processes: bool,
+
+    /// Processes in each list, at most [`MAX_TOP_PROCESSES`].
+    pub process_count: u32,
+
+    /// Count a process tree whose processes share an executable, such as a
+    /// browser or an Electron app, as one row under its topmost process.
+    pub group_process_trees: bool,
+
+    // ========================================================================
+    // Polling Intervals
+    // ========================================================================
+    /// Milliseconds between CPU, memory, and GPU usage samples.
+    pub utilization_interval_ms: u64,
+
+    /// Milliseconds between network counter samples.
+    pub network_interval_ms: u64,
+
+    /// Milliseconds between disk I/O counter samples.
+    pub disk_io_interval_ms: u64,
+
+    /// Milliseconds between temperature sensor reads.
+    pub temperature_interval_ms: u64,
+
+    /// Milliseconds between filesystem capacity reads.
+    pub storage_interval_ms: u64,
+
+    /// Milliseconds between peripheral battery snapshots.
+    /// External fallbacks remain rate-limited to 30 seconds.
+    pub battery_interval_ms: u64,
+
     // ========================================================================
     // Clock & Date Display
     // ========================================================================
@@ -223,10 +859,19 @@ pub struct Config {
     // ========================================================================
     // Widget Position & Behavior
     // ========================================================================
-    /// X coordinate (pixels from left edge) for widget placement.
+    /// Connector name of the output to show the overlay on (e.g. "DP-1").
+    /// Empty lets the compositor choose.
+    pub overlay_output: String,
+
+    /// Corner or edge of the output that `widget_x` and `widget_y` are
+    /// measured from.
+    pub overlay_anchor: OverlayAnchor,
+
+    /// Horizontal offset (pixels from the anchored left or right edge).
+    /// Ignored for horizontally centered anchors.
     pub widget_x: i32,
 
-    /// Y coordinate (pixels from top edge) for widget placement.
+    /// Vertical offset (pixels from the anchored top or bottom edge).
     pub widget_y: i32,
 
     /// Horizontal position restored by the settings application's reset action.
@@ -245,10 +890,43 @@ pub struct Config {
     /// Users can reorder via the settings application.
     pub section_order: Vec<WidgetSection>,
 
+    /// Template sections, placed by their [`WidgetSection::Custom`] entry in
+    /// `section_order`.
+    pub custom_sections: Vec<CustomSection>,
+
     /// Automatically start the widget when the panel applet loads.
     /// If false, the widget must be manually shown via the applet menu.
     pub widget_autostart: bool,
 
+    // ========================================================================
+    // Overlay Instances
+    // ========================================================================
+    /// Names of additional overlays shown next to the default one. Each reads
+    /// its sections, position and styling from its own profile (see
+    /// [`instance_config_id`]) and shares the default overlay's monitors.
+    /// Only read from the main configuration.
+    pub overlay_instances: Vec<String>,
+
+    // ========================================================================
+    // Alerts
+    // ========================================================================
+    /// Threshold rules that raise desktop notifications. Only read from the
+    /// main configuration.
+    pub alert_rules: Vec<AlertRule>,
+
+    // ========================================================================
+    // Metrics Exporter
+    // ========================================================================
+    /// Serve the latest readings in OpenMetrics text format at `/metrics`.
+    pub enable_metrics_exporter: bool,
+
+    /// IP address the exporter listens on. Keep this on loopback unless the
+    /// readings should be reachable from other machines.
+    pub metrics_bind_address: String,
+
+    /// TCP port the exporter listens on.
+    pub metrics_port: u16,
+
     // ========================================================================
     // Advanced Settings
     // ========================================================================
@@ -256,8 +934,95 @@ pub struct Config {
     pub enable_logging: bool,
 }
 
+/// cosmic-config ID of a named overlay instance's profile.
+pub fn instance_config_id(name: &str) -> String {
+    format!("{CONFIG_ID}.Instance.{name}")
+}
+
+/// Instance names become part of a config path, so only short ASCII names
+/// made of letters, digits, `-` and `_` are accepted.
+pub fn is_valid_instance_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_INSTANCE_NAME_LEN
+        && name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '-' | '_'))
+}
+
+/// Whether a network interface is shown and counted: listed in `included`,
+/// or backed by a device and not listed in `excluded`.
+pub fn network_interface_counted(
+    included: &[String],
+    excluded: &[String],
+    name: &str,
+    physical: bool,
+) -> bool {
+    included.iter().any(|included| included == name)
+        || (physical && !excluded.iter().any(|excluded| excluded == name))
+}
+
+/// Filesystem type given to network mounts made by the desktop through GVFS.
+pub const REMOTE_FS_TYPE: &str = "gvfs";
+
+/// Whether the storage section lists a mount no [`StorageRule`] matches:
+/// the root, `/home`, top-level `/mnt` and `/media` mounts and desktop
+/// network mounts are, boot, snap and other system mounts are not.
+pub fn storage_mount_shown_by_default(mount_point: &str, fs_type: &str) -> bool {
+    if fs_type == REMOTE_FS_TYPE {
+        return true;
+    }
+    let system = [
+        "/boot",
+        "/snap",
+        "/run",
+        "/sys",
+        "/proc",
+        "/dev",
+        "/tmp",
+        "/var/snap",
+    ];
+    if system.iter().any(|prefix| mount_point.starts_with(prefix)) {
+        return false;
+    }
+    mount_point == "/"
+        || mount_point == "/home"
+        || mount_point.starts_with("/mnt/")
+        || mount_point.starts_with("/media/")
+}
+
+/// Whether the storage section lists a mount. The last rule matching its
+/// mount point, filesystem type or one of its `devices` decides.
+pub fn storage_mount_shown(
+    rules: &[StorageRule],
+    mount_point: &str,
+    fs_type: &str,
+    devices: &[&str],
+) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| match rule.field {
+            StorageRuleField::MountPoint => rule.value == mount_point,
+            StorageRuleField::FilesystemType => rule.value == fs_type,
+            StorageRuleField::Device => devices.contains(&rule.value.as_str()),
+        })
+        .map_or_else(
+            || storage_mount_shown_by_default(mount_point, fs_type),
+            |rule| rule.shown,
+        )
+}
+
+/// User-defined name of a mount, if `labels` has a non-blank one.
+pub fn storage_label<'a>(labels: &'a [StorageLabel], mount_point: &str) -> Option<&'a str> {
+    labels
+        .iter()
+        .find(|label| label.mount_point == mount_point)
+        .map(|label| label.name.trim())
+        .filter(|name| !name.is_empty())
+}
+
 impl Config {
-    pub const ALL_SECTIONS: [WidgetSection; 9] = [
+    pub const ALL_SECTIONS: [WidgetSection; 11] = [
         WidgetSection::Utilization,
         WidgetSection::Network,
         WidgetSection::DiskIo,
@@ -267,12 +1032,30 @@ impl Config {
         WidgetSection::Weather,
         WidgetSection::Notifications,
         WidgetSection::Media,
+        WidgetSection::Commands,
+        WidgetSection::Processes,
     ];
 
     /// Add every current overlay section while retaining the user's existing order.
+    /// Custom sections are appended when added and dropped when removed.
     pub fn ensure_all_sections(&mut self) -> bool {
         let mut changed = false;
 
+        let count = self.section_order.len();
+        let custom_sections = &self.custom_sections;
+        self.section_order.retain(|section| match section {
+            WidgetSection::Custom(id) => custom_sections.iter().any(|custom| custom.id == *id),
+            _ => true,
+        });
+        changed |= self.section_order.len() != count;
+        for custom in &self.custom_sections {
+            let section = WidgetSection::Custom(custom.id);
+            if !self.section_order.contains(&section) {
+                self.section_order.push(section);
+                changed = true;
+            }
+        }
+
         for (canonical_index, section) in Self::ALL_SECTIONS.iter().copied().enumerate() {
             if self.section_order.contains(&section) {
                 continue;
@@ -295,6 +1078,52 @@ impl Config {
         changed
     }
 
+    pub fn custom_section(&self, id: u32) -> Option<&CustomSection> {
+        self.custom_sections.iter().find(|custom| custom.id == id)
+    }
+
+    /// Add a custom section with an unused ID and return it.
+    pub fn add_custom_section(&mut self) -> &mut CustomSection {
+        let id = self
+            .custom_sections
+            .iter()
+            .map(|custom| custom.id + 1)
+            .max()
+            .unwrap_or(0);
+        self.custom_sections.push(CustomSection {
+            id,
+            ..CustomSection::default()
+        });
+        self.section_order.push(WidgetSection::Custom(id));
+        self.custom_sections
+            .last_mut()
+            .expect("section was just added")
+    }
+
+    /// Label of a section in the settings application; custom sections use
+    /// their title.
+    pub fn section_label(&self, section: WidgetSection) -> &str {
+        match section {
+            WidgetSection::Custom(id) => self
+                .custom_section(id)
+                .map(|custom| custom.title.trim())
+                .filter(|title| !title.is_empty())
+                .unwrap_or(section.label()),
+            _ => section.label(),
+        }
+    }
+
+    /// Named overlay instances, skipping invalid and repeated names.
+    pub fn instance_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::with_capacity(self.overlay_instances.len());
+        for name in &self.overlay_instances {
+            if is_valid_instance_name(name) && !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
     /// Capture the current position as the reset target when upgrading an
     /// existing installation that predates per-installation defaults.
     pub fn ensure_position_defaults(&mut self) -> bool {
@@ -308,11 +1137,182 @@ impl Config {
         true
     }
 
+    /// Move a position still at the old fixed default to the top-right
+    /// corner, where it is visible on any output. Returns whether it moved.
+    pub fn migrate_legacy_position(&mut self) -> bool {
+        if !matches!(
+            self.overlay_anchor,
+            OverlayAnchor::TopLeft | OverlayAnchor::TopRight
+        ) {
+            return false;
+        }
+
+        let mut changed = false;
+        if self.widget_x == LEGACY_DEFAULT_WIDGET_X {
+            self.overlay_anchor = OverlayAnchor::TopRight;
+            self.widget_x = DEFAULT_WIDGET_MARGIN;
+            changed = true;
+        }
+        if self.default_widget_x == LEGACY_DEFAULT_WIDGET_X {
+            self.default_widget_x = DEFAULT_WIDGET_MARGIN;
+            changed = true;
+        }
+        changed
+    }
+
     pub fn reset_widget_position(&mut self) {
         self.widget_x = self.default_widget_x;
         self.widget_y = self.default_widget_y;
         self.widget_movable = false;
     }
+
+    /// History graph style for a section. Sections without history are `Off`.
+    pub fn history_graph_style(&self, section: WidgetSection) -> HistoryGraphStyle {
+        match section {
+            WidgetSection::Utilization => self.utilization_graph_style,
+            WidgetSection::Network => self.network_graph_style,
+            WidgetSection::DiskIo => self.disk_io_graph_style,
+            WidgetSection::Temperatures => self.temperature_graph_style,
+            _ => HistoryGraphStyle::Off,
+        }
+    }
+
+    pub fn set_history_graph_style(&mut self, section: WidgetSection, style: HistoryGraphStyle) {
+        match section {
+            WidgetSection::Utilization => self.utilization_graph_style = style,
+            WidgetSection::Network => self.network_graph_style = style,
+            WidgetSection::DiskIo => self.disk_io_graph_style = style,
+            WidgetSection::Temperatures => self.temperature_graph_style = style,
+            _ => {}
+        }
+    }
+
+    /// Length of each top process list, between one and
+    /// [`MAX_TOP_PROCESSES`].
+    pub fn top_process_count(&self) -> usize {
+        self.process_count.clamp(1, MAX_TOP_PROCESSES) as usize
+    }
+
+    /// Day of the month on which the network billing cycle starts.
+    pub fn billing_day(&self) -> u32 {
+        self.network_billing_day.clamp(1, MAX_BILLING_DAY)
+    }
+
+    /// Network quota per billing cycle in bytes, 0 without a quota.
+    pub fn network_quota_bytes(&self) -> u64 {
+        u64::from(self.network_quota_gib) * 1024 * 1024 * 1024
+    }
+
+    /// Whether the network section shows and counts an interface.
+    pub fn shows_network_interface(&self, name: &str, physical: bool) -> bool {
+        network_interface_counted(
+            &self.network_interfaces_included,
+            &self.network_interfaces_excluded,
+            name,
+            physical,
+        )
+    }
+
+    /// Whether the storage section lists a mount.
+    pub fn shows_storage_mount(&self, mount_point: &str, fs_type: &str, devices: &[&str]) -> bool {
+        storage_mount_shown(&self.storage_rules, mount_point, fs_type, devices)
+    }
+
+    /// Show or hide a mount by its mount point, replacing earlier mount
+    /// point rules for it. No rule is kept where another rule or the default
+    /// already decides the same.
+    pub fn set_storage_mount_shown(
+        &mut self,
+        mount_point: &str,
+        fs_type: &str,
+        devices: &[&str],
+        shown: bool,
+    ) {
+        self.storage_rules
+            .retain(|rule| rule.field != StorageRuleField::MountPoint || rule.value != mount_point);
+        if self.shows_storage_mount(mount_point, fs_type, devices) != shown {
+            self.storage_rules.push(StorageRule {
+                field: StorageRuleField::MountPoint,
+                value: mount_point.to_string(),
+                shown,
+            });
+        }
+    }
+
+    /// User-defined name of a mount, if any.
+    pub fn storage_label(&self, mount_point: &str) -> Option<&str> {
+        storage_label(&self.storage_labels, mount_point)
+    }
+
+    /// Name a mount; an empty name restores the detected one.
+    pub fn set_storage_label(&mut self, mount_point: &str, name: String) {
+        self.storage_labels
+            .retain(|label| label.mount_point != mount_point);
+        if !name.trim().is_empty() {
+            self.storage_labels.push(StorageLabel {
+                mount_point: mount_point.to_string(),
+                name,
+            });
+        }
+    }
+
+    /// Seconds of history shown by a section's graph, capped to what the
+    /// sampler retains.
+    pub fn history_window_secs(&self, section: WidgetSection) -> u64 {
+        let window = match section {
+            WidgetSection::Utilization => self.utilization_graph_window_secs,
+            WidgetSection::Network => self.network_graph_window_secs,
+            WidgetSection::DiskIo => self.disk_io_graph_window_secs,
+            WidgetSection::Temperatures => self.temperature_graph_window_secs,
+            _ => 0,
+        };
+        window.clamp(1, MAX_HISTORY_WINDOW_SECS)
+    }
+
+    pub fn set_history_window_secs(&mut self, section: WidgetSection, window: u64) {
+        let window = window.clamp(1, MAX_HISTORY_WINDOW_SECS);
+        match section {
+            WidgetSection::Utilization => self.utilization_graph_window_secs = window,
+            WidgetSection::Network => self.network_graph_window_secs = window,
+            WidgetSection::DiskIo => self.disk_io_graph_window_secs = window,
+            WidgetSection::Temperatures => self.temperature_graph_window_secs = window,
+            _ => {}
+        }
+    }
+
+    /// Socket address for the metrics exporter, if enabled and valid.
+    pub fn metrics_socket_address(&self) -> Option<SocketAddr> {
+        if !self.enable_metrics_exporter {
+            return None;
+        }
+        let ip = self.metrics_bind_address.trim().parse::<IpAddr>().ok()?;
+        Some(SocketAddr::new(ip, self.metrics_port))
+    }
+
+    /// Polling interval for a monitor, clamped to the supported range.
+    pub fn poll_interval_ms(&self, monitor: PolledMonitor) -> u64 {
+        let interval = match monitor {
+            PolledMonitor::Utilization => self.utilization_interval_ms,
+            PolledMonitor::Network => self.network_interval_ms,
+            PolledMonitor::DiskIo => self.disk_io_interval_ms,
+            PolledMonitor::Temperatures => self.temperature_interval_ms,
+            PolledMonitor::Storage => self.storage_interval_ms,
+            PolledMonitor::Battery => self.battery_interval_ms,
+        };
+        interval.clamp(MIN_POLL_INTERVAL_MS, MAX_POLL_INTERVAL_MS)
+    }
+
+    pub fn set_poll_interval_ms(&mut self, monitor: PolledMonitor, interval: u64) {
+        let interval = interval.clamp(MIN_POLL_INTERVAL_MS, MAX_POLL_INTERVAL_MS);
+        match monitor {
+            PolledMonitor::Utilization => self.utilization_interval_ms = interval,
+            PolledMonitor::Network => self.network_interval_ms = interval,
+            PolledMonitor::DiskIo => self.disk_io_interval_ms = interval,
+            PolledMonitor::Temperatures => self.temperature_interval_ms = interval,
+            PolledMonitor::Storage => self.storage_interval_ms = interval,
+            PolledMonitor::Battery => self.battery_interval_ms = interval,
+        }
+    }
 }
 
 // ============================================================================
@@ -325,16 +1325,28 @@ impl Default for Config {
     /// Defaults are chosen to provide a useful out-of-box experience:
     /// - Basic system monitoring (CPU, Memory, Storage) enabled
     /// - Advanced features (GPU, Weather, Media) disabled until configured
-    /// - Widget auto-starts at position (7260, 50)
+    /// - Widget auto-starts 50 px from the top-right corner
     /// - 1-second update interval for good balance of responsiveness and efficiency
     fn default() -> Self {
         Self {
             // Utilization: Show basic system stats by default
             show_cpu: true,
+            show_cpu_details: false,
             show_memory: true,
+            show_memory_details: false,
             show_gpu: false, // Requires GPU, not always present
+            hidden_gpus: Vec::new(),
+            show_gpu_details: false,
+            show_gpu_processes: false,
             show_network: false,
+            network_interfaces_included: Vec::new(),
+            network_interfaces_excluded: Vec::new(),
+            show_network_connections: false,
+            show_network_traffic: false,
+            network_billing_day: 1,
+            network_quota_gib: 0,
             show_disk: false,
+            show_disk_devices: false,
 
             // Temperatures: Disabled by default (not all systems have sensors)
             show_cpu_temp: false,
@@ -342,8 +1354,23 @@ impl Default for Config {
             use_circular_temp_display: true,
             temperature_gauge_style: TemperatureGaugeStyle::Arc,
 
+            // History graphs: Off until chosen, five minutes when enabled
+            utilization_graph_style: HistoryGraphStyle::Off,
+            utilization_graph_window_secs: 300,
+            network_graph_style: HistoryGraphStyle::Off,
+            network_graph_window_secs: 300,
+            disk_io_graph_style: HistoryGraphStyle::Off,
+            disk_io_graph_window_secs: 300,
+            temperature_graph_style: HistoryGraphStyle::Off,
+            temperature_graph_window_secs: 300,
+
             // Storage: Show disk usage by default
             show_storage: true,
+            show_drive_health: false,
+            show_pool_health: true,
+            storage_rules: Vec::new(),
+            storage_labels: Vec::new(),
+            storage_sort: StorageSort::Discovered,
 
             // Devices: Disabled until supported hardware is detected
             show_battery: false,
@@ -362,6 +1389,23 @@ impl Default for Config {
             show_media: false,
             cider_api_token: String::new(),
 
+            // Commands: None until added in the settings application
+            show_commands: false,
+            commands: Vec::new(),
+
+            // Top processes: Disabled, five per list when enabled
+            show_processes: false,
+            process_count: 5,
+            group_process_trees: false,
+
+            // Polling: One-second sampling, matching the original shared loop
+            utilization_interval_ms: UPDATE_INTERVAL_MS,
+            network_interval_ms: UPDATE_INTERVAL_MS,
+            disk_io_interval_ms: UPDATE_INTERVAL_MS,
+            temperature_interval_ms: UPDATE_INTERVAL_MS,
+            storage_interval_ms: UPDATE_INTERVAL_MS,
+            battery_interval_ms: UPDATE_INTERVAL_MS,
+
             // Clock: Show by default with 12-hour format
             show_clock: true,
             show_date: true,
@@ -370,11 +1414,13 @@ impl Default for Config {
             // Display: Show percentages
             show_percentages: true,
 
-            // Position: 50 px from the top and right on a 7680 px-wide display
-            widget_x: 7260,
-            widget_y: 50,
-            default_widget_x: 7260,
-            default_widget_y: 50,
+            // Position: 50 px from the top and right of the output
+            overlay_output: String::new(),
+            overlay_anchor: OverlayAnchor::TopRight,
+            widget_x: DEFAULT_WIDGET_MARGIN,
+            widget_y: DEFAULT_WIDGET_MARGIN,
+            default_widget_x: DEFAULT_WIDGET_MARGIN,
+            default_widget_y: DEFAULT_WIDGET_MARGIN,
             position_defaults_initialized: false,
             widget_movable: false,
             widget_autostart: true,
@@ -390,7 +1436,21 @@ impl Default for Config {
                 WidgetSection::Weather,
                 WidgetSection::Notifications,
                 WidgetSection::Media,
+                WidgetSection::Commands,
+                WidgetSection::Processes,
             ],
+            custom_sections: Vec::new(),
+
+            // Instances: Only the default overlay
+            overlay_instances: Vec::new(),
+
+            // Alerts: None until added in the settings application
+            alert_rules: Vec::new(),
+
+            // Metrics: Off by default, loopback only when enabled
+            enable_metrics_exporter: false,
+            metrics_bind_address: String::from("127.0.0.1"),
+            metrics_port: DEFAULT_METRICS_PORT,
 
             // Advanced: Logging off by default
             enable_logging: false,
@@ -400,7 +1460,14 @@ impl Default for Config {
 
 #[cfg(test)]
 mod tests {
-    use super::{Config, TemperatureGaugeStyle, WidgetSection};
+    use super::{
+        Config, CustomSection, DEFAULT_METRICS_PORT, HistoryGraphStyle, MAX_HISTORY_WINDOW_SECS,
+        MAX_POLL_INTERVAL_MS, MIN_POLL_INTERVAL_MS, OverlayAnchor, PolledMonitor, ShellCommand,
+        StorageRule, StorageRuleField, TemperatureGaugeStyle, WidgetSection, instance_config_id,
+        is_valid_instance_name,
+    };
+    use std::net::SocketAddr;
+    use std::time::Duration;
 
     #[test]
     fn arc_is_the_backward_compatible_temperature_style() {
@@ -411,13 +1478,49 @@ mod tests {
     }
 
     #[test]
-    fn default_position_matches_the_installed_layout() {
+    fn default_position_is_the_top_right_corner() {
         let config = Config::default();
 
-        assert_eq!((config.widget_x, config.widget_y), (7260, 50));
+        assert_eq!(config.overlay_anchor, OverlayAnchor::TopRight);
+        assert_eq!((config.widget_x, config.widget_y), (50, 50));
+        assert_eq!((config.default_widget_x, config.default_widget_y), (50, 50));
+    }
+
+    #[test]
+    fn migrates_the_old_default_position_to_the_top_right_corner() {
+        let mut config = Config::default();
+        config.overlay_anchor = OverlayAnchor::TopLeft;
+        config.widget_x = 7260;
+        config.default_widget_x = 7260;
+
+        assert!(config.migrate_legacy_position());
+        assert!(!config.migrate_legacy_position());
+        assert_eq!(config.overlay_anchor, OverlayAnchor::TopRight);
+        assert_eq!((config.widget_x, config.default_widget_x), (50, 50));
+
+        // Positions the user chose are kept.
+        config.overlay_anchor = OverlayAnchor::BottomLeft;
+        config.widget_x = 7260;
+        assert!(!config.migrate_legacy_position());
+        assert_eq!(config.widget_x, 7260);
+    }
+
+    #[test]
+    fn anchors_measure_offsets_from_their_edges() {
+        assert_eq!(OverlayAnchor::TopLeft.margins(40, 20), (20, 0, 0, 40));
+        assert_eq!(OverlayAnchor::TopRight.margins(40, 20), (20, 40, 0, 0));
+        assert_eq!(OverlayAnchor::Bottom.margins(40, 20), (0, 0, 20, 0));
+        assert_eq!(OverlayAnchor::BottomRight.margins(40, 20), (0, 40, 20, 0));
+
+        // Dragging right and down moves away from the left and top edges but
+        // towards the right and bottom ones.
         assert_eq!(
-            (config.default_widget_x, config.default_widget_y),
-            (7260, 50)
+            OverlayAnchor::TopLeft.dragged_offsets(40, 20, 5, 3),
+            (45, 23)
+        );
+        assert_eq!(
+            OverlayAnchor::BottomRight.dragged_offsets(40, 20, 5, 3),
+            (35, 17)
         );
     }
 
@@ -475,4 +1578,179 @@ mod tests {
         );
         assert!(!config.ensure_all_sections());
     }
+
+    #[test]
+    fn custom_sections_follow_their_definitions() {
+        let mut config = Config::default();
+        let first = config.add_custom_section().id;
+        let second = config.add_custom_section().id;
+        assert_ne!(first, second);
+        assert!(!config.ensure_all_sections());
+
+        config.custom_sections.retain(|custom| custom.id != first);
+        config.custom_sections.push(CustomSection {
+            id: 7,
+            ..CustomSection::default()
+        });
+        assert!(config.ensure_all_sections());
+        assert!(!config.section_order.contains(&WidgetSection::Custom(first)));
+        assert_eq!(
+            config.section_order[config.section_order.len() - 2..],
+            [WidgetSection::Custom(second), WidgetSection::Custom(7)]
+        );
+    }
+
+    #[test]
+    fn shell_command_limits_are_clamped() {
+        let command = ShellCommand {
+            interval_secs: 0,
+            timeout_secs: 600,
+            max_output_bytes: u32::MAX,
+            ..ShellCommand::default()
+        };
+
+        assert_eq!(command.interval(), Duration::from_secs(1));
+        assert_eq!(command.timeout(), Duration::from_secs(60));
+        assert_eq!(command.output_limit(), 65_536);
+        assert_eq!(command.display_label(), "uptime -p");
+    }
+
+    #[test]
+    fn network_interface_lists_adjust_the_physical_default() {
+        let mut config = Config::default();
+        assert!(config.shows_network_interface("enp5s0", true));
+        assert!(!config.shows_network_interface("docker0", false));
+
+        config.network_interfaces_included = vec!["wg0".to_string()];
+        config.network_interfaces_excluded = vec!["enp5s0".to_string()];
+        assert!(!config.shows_network_interface("enp5s0", true));
+        assert!(config.shows_network_interface("wg0", false));
+        assert!(config.shows_network_interface("wlan0", true));
+    }
+
+    #[test]
+    fn later_storage_rules_override_earlier_ones_and_the_default() {
+        let mut config = Config::default();
+        assert!(config.shows_storage_mount("/", "ext4", &["/dev/sda2"]));
+        assert!(!config.shows_storage_mount("/boot", "vfat", &["/dev/sda1"]));
+        assert!(config.shows_storage_mount("/run/user/1000/gvfs/x", "gvfs", &[]));
+
+        config.storage_rules = vec![
+            StorageRule {
+                field: StorageRuleField::FilesystemType,
+                value: "btrfs".to_string(),
+                shown: false,
+            },
+            StorageRule {
+                field: StorageRuleField::Device,
+                value: "dm-0".to_string(),
+                shown: true,
+            },
+        ];
+        assert!(!config.shows_storage_mount("/home", "btrfs", &["/dev/sda3", "sda3"]));
+        assert!(config.shows_storage_mount("/srv", "btrfs", &["/dev/mapper/a", "dm-0"]));
+
+        // A toggle in the settings wins over the rules before it and is
+        // dropped again when it matches what they decide.
+        config.set_storage_mount_shown("/home", "btrfs", &["sda3"], true);
+        assert!(config.shows_storage_mount("/home", "btrfs", &["sda3"]));
+        config.set_storage_mount_shown("/home", "btrfs", &["sda3"], false);
+        assert_eq!(config.storage_rules.len(), 2);
+    }
+
+    #[test]
+    fn blank_storage_labels_restore_the_detected_name() {
+        let mut config = Config::default();
+        config.set_storage_label("/mnt/backup", "Backups".to_string());
+        assert_eq!(config.storage_label("/mnt/backup"), Some("Backups"));
+
+        config.set_storage_label("/mnt/backup", "  ".to_string());
+        assert_eq!(config.storage_label("/mnt/backup"), None);
+        assert!(config.storage_labels.is_empty());
+    }
+
+    #[test]
+    fn instance_names_are_path_safe_and_unique() {
+        assert!(is_valid_instance_name("media-left_2"));
+        assert!(!is_valid_instance_name(""));
+        assert!(!is_valid_instance_name("../settings"));
+        assert!(!is_valid_instance_name(&"x".repeat(33)));
+
+        let mut config = Config::default();
+        config.overlay_instances = vec![
+            "Left".to_string(),
+            "bad name".to_string(),
+            "Right".to_string(),
+            "Left".to_string(),
+        ];
+        assert_eq!(config.instance_names(), vec!["Left", "Right"]);
+        assert_eq!(
+            instance_config_id("Left"),
+            "com.github.zoliviragh.CosmicWidget.Instance.Left"
+        );
+    }
+
+    #[test]
+    fn metrics_address_requires_the_exporter_and_a_valid_ip() {
+        let mut config = Config::default();
+        assert_eq!(config.metrics_socket_address(), None);
+
+        config.enable_metrics_exporter = true;
+        assert_eq!(
+            config.metrics_socket_address(),
+            Some(SocketAddr::from(([127, 0, 0, 1], DEFAULT_METRICS_PORT)))
+        );
+
+        config.metrics_bind_address = "::1".to_string();
+        config.metrics_port = 9100;
+        assert_eq!(
+            config.metrics_socket_address(),
+            Some("[::1]:9100".parse().unwrap())
+        );
+
+        config.metrics_bind_address = "localhost".to_string();
+        assert_eq!(config.metrics_socket_address(), None);
+    }
+
+    #[test]
+    fn poll_intervals_are_clamped_per_monitor() {
+        let mut config = Config::default();
+
+        config.set_poll_interval_ms(PolledMonitor::Network, 500);
+        config.set_poll_interval_ms(PolledMonitor::Storage, 10);
+        config.battery_interval_ms = u64::MAX;
+
+        assert_eq!(config.poll_interval_ms(PolledMonitor::Network), 500);
+        assert_eq!(
+            config.poll_interval_ms(PolledMonitor::Storage),
+            MIN_POLL_INTERVAL_MS
+        );
+        assert_eq!(
+            config.poll_interval_ms(PolledMonitor::Battery),
+            MAX_POLL_INTERVAL_MS
+        );
+        assert_eq!(config.poll_interval_ms(PolledMonitor::Utilization), 1_000);
+    }
+
+    #[test]
+    fn history_graphs_apply_only_to_sampled_sections() {
+        let mut config = Config::default();
+
+        config.set_history_graph_style(WidgetSection::Network, HistoryGraphStyle::Area);
+        config.set_history_graph_style(WidgetSection::Weather, HistoryGraphStyle::Area);
+        config.set_history_window_secs(WidgetSection::Network, 86_400);
+
+        assert_eq!(
+            config.history_graph_style(WidgetSection::Network),
+            HistoryGraphStyle::Area
+        );
+        assert_eq!(
+            config.history_graph_style(WidgetSection::Weather),
+            HistoryGraphStyle::Off
+        );
+        assert_eq!(
+            config.history_window_secs(WidgetSection::Network),
+            MAX_HISTORY_WINDOW_SECS
+        );
+    }
 }
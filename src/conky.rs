@@ -0,0 +1,671 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Importer for conky configuration files.
+//!
+//! A conky 1.10 or newer file assigns a Lua table to `conky.config` and a
+//! string to `conky.text`. The importer maps the table's alignment, gaps,
+//! font size and colours onto the overlay placement, and turns the text into
+//! a custom template section (see `src/template.rs`). Bars and graphs enable
+//! the matching built-in section instead. Everything that has no equivalent
+//! is listed in the [`ImportReport`].
+
+use crate::config::{Config, OverlayAnchor, WidgetSection};
+use std::fmt;
+
+/// Colour names accepted besides hex values.
+const NAMED_COLORS: [(&str, &str); 11] = [
+    ("white", "#ffffff"),
+    ("black", "#000000"),
+    ("gray", "#bebebe"),
+    ("grey", "#bebebe"),
+    ("red", "#ff0000"),
+    ("green", "#00ff00"),
+    ("blue", "#0000ff"),
+    ("yellow", "#ffff00"),
+    ("orange", "#ffa500"),
+    ("cyan", "#00ffff"),
+    ("magenta", "#ff00ff"),
+];
+
+/// Outcome of a successful import.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// `update_interval` in milliseconds, which the caller applies to the
+    /// poll intervals of the profile being imported into.
+    pub update_interval_ms: Option<u64>,
+    /// Settings, variables and arguments that were not translated.
+    pub untranslated: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportError {
+    /// The file has no `conky.text`, for example because it uses the
+    /// pre-1.10 format.
+    MissingText,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::MissingText => {
+                f.write_str("No conky.text found; only conky 1.10 and newer configs are supported")
+            }
+        }
+    }
+}
+
+/// Font size and colours set in `conky.config`.
+#[derive(Debug, Default)]
+struct TextStyle {
+    size: Option<u16>,
+    color: Option<String>,
+    /// `color0` to `color9`.
+    colors: [Option<String>; 10],
+}
+
+/// Replace the sections and placement of `config` with those of a conky
+/// config. `config` is left unchanged if the file cannot be imported.
+pub fn import(source: &str, config: &mut Config) -> Result<ImportReport, ImportError> {
+    let text = assigned_string(source, "conky.text").ok_or(ImportError::MissingText)?;
+    let settings = table(source, "conky.config").unwrap_or_default();
+
+    let mut report = ImportReport::default();
+    let mut style = TextStyle::default();
+    let mut ignored_settings = Vec::new();
+
+    for (key, value) in &settings {
+        match key.as_str() {
+            "alignment" => match anchor(value) {
+                Some(anchor) => config.overlay_anchor = anchor,
+                None => note(
+                    &mut report.untranslated,
+                    format!("Alignment '{value}' has no matching anchor"),
+                ),
+            },
+            "gap_x" | "gap_y" => match value.parse::<i32>() {
+                Ok(gap) if key == "gap_x" => config.widget_x = gap,
+                Ok(gap) => config.widget_y = gap,
+                Err(_) => ignored_settings.push(key.clone()),
+            },
+            "update_interval" => match value.parse::<f64>() {
+                Ok(secs) if secs > 0.0 => {
+                    report.update_interval_ms = Some((secs * 1000.0).round() as u64);
+                }
+                _ => ignored_settings.push(key.clone()),
+            },
+            "font" => style.size = font_size(value, &mut report.untranslated),
+            "default_color" => style.color = color(value, &mut report.untranslated),
+            _ => match color_index(key) {
+                Some(index) => style.colors[index] = color(value, &mut report.untranslated),
+                None => ignored_settings.push(key.clone()),
+            },
+        }
+    }
+    if config.overlay_anchor.is_centered() && config.widget_x != 0 {
+        note(
+            &mut report.untranslated,
+            "gap_x is not used while the overlay is centered".to_string(),
+        );
+    }
+    config.widget_movable = false;
+
+    // The conky text replaces every section the profile showed before.
+    config.show_clock = false;
+    config.show_date = false;
+    config.show_cpu = false;
+    config.show_memory = false;
+    config.show_gpu = false;
+    config.show_network = false;
+    config.show_disk = false;
+    config.show_cpu_temp = false;
+    config.show_gpu_temp = false;
+    config.show_storage = false;
+    config.show_battery = false;
+    config.show_weather = false;
+    config.show_notifications = false;
+    config.show_media = false;
+    config.show_commands = false;
+    config.show_processes = false;
+    config.custom_sections.clear();
+    config.section_order.clear();
+
+    let mut translator = Translator {
+        style: &style,
+        notes: &mut report.untranslated,
+        template: String::new(),
+        has_text: false,
+        sections: Vec::new(),
+        ignored: Vec::new(),
+        config: &mut *config,
+    };
+    translator.translate(&text);
+    let Translator {
+        template,
+        has_text,
+        sections,
+        ignored,
+        ..
+    } = translator;
+
+    if has_text {
+        let custom = config.add_custom_section();
+        custom.title = String::new();
+        custom.template = template;
+    }
+    config.section_order.extend(sections);
+    config.ensure_all_sections();
+
+    if !ignored_settings.is_empty() {
+        note(
+            &mut report.untranslated,
+            format!(
+                "Settings without an equivalent: {}",
+                ignored_settings.join(", ")
+            ),
+        );
+    }
+    if !ignored.is_empty() {
+        note(
+            &mut report.untranslated,
+            format!("Variables without an equivalent: {}", ignored.join(", ")),
+        );
+    }
+    Ok(report)
+}
+
+struct Translator<'a> {
+    style: &'a TextStyle,
+    notes: &'a mut Vec<String>,
+    config: &'a mut Config,
+    template: String,
+    /// Whether the template draws anything besides markup and whitespace.
+    has_text: bool,
+    /// Built-in sections enabled by bars and graphs, in order of appearance.
+    sections: Vec<WidgetSection>,
+    /// Names of variables that were dropped.
+    ignored: Vec<String>,
+}
+
+impl Translator<'_> {
+    fn translate(&mut self, text: &str) {
+        if let Some(size) = self.style.size {
+            self.template.push_str(&format!("{{size {size}}}"));
+        }
+        if let Some(color) = &self.style.color {
+            self.template.push_str(&format!("{{color {color}}}"));
+        }
+
+        let mut rest = text;
+        while let Some(character) = rest.chars().next() {
+            let after = &rest[character.len_utf8()..];
+            match character {
+                '$' if after.starts_with('$') => {
+                    self.literal('$');
+                    rest = &after[1..];
+                }
+                '$' => {
+                    let (variable, remainder) = if let Some(braced) = after.strip_prefix('{') {
+                        match closing_brace(braced) {
+                            Some(end) => (&braced[..end], &braced[end + 1..]),
+                            None => {
+                                rest.chars().for_each(|character| self.literal(character));
+                                break;
+                            }
+                        }
+                    } else {
+                        let end = after
+                            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                            .unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    };
+                    if variable.trim().is_empty() {
+                        self.literal('$');
+                        rest = after;
+                        continue;
+                    }
+                    let (name, argument) = match variable.trim().split_once(char::is_whitespace) {
+                        Some((name, argument)) => (name, Some(argument.trim())),
+                        None => (variable.trim(), None),
+                    };
+                    rest = self.variable(name, argument, remainder);
+                }
+                _ => {
+                    self.literal(character);
+                    rest = after;
+                }
+            }
+        }
+
+        // Dropped variables often leave spaces behind.
+        self.template = self
+            .template
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string();
+    }
+
+    fn literal(&mut self, character: char) {
+        match character {
+            '{' => self.template.push_str("{{"),
+            '}' => self.template.push_str("}}"),
+            _ => self.template.push(character),
+        }
+        self.has_text |= !character.is_whitespace();
+    }
+
+    /// Translate one variable and return the text after it.
+    fn variable<'t>(&mut self, name: &str, argument: Option<&str>, rest: &'t str) -> &'t str {
+        let percent: &[&str] = &["%"];
+        let degrees: &[&str] = &["°C", "°"];
+
+        let (replacement, unit) = match (name, argument) {
+            ("time", None) => ("{time}".to_string(), None),
+            ("time", Some(format)) => (format!("{{time:{format}}}"), None),
+            ("cpu", core) => {
+                if let Some(core) = core.filter(|core| *core != "cpu0") {
+                    self.note(format!(
+                        "${{cpu {core}}} shows the total CPU usage; per-core usage is not available"
+                    ));
+                }
+                ("{cpu}".to_string(), Some(percent))
+            }
+            ("memperc", _) => ("{mem}".to_string(), Some(percent)),
+            ("mem", _) => ("{mem_used}".to_string(), None),
+            ("memmax", _) => ("{mem_total}".to_string(), None),
+            ("fs_used_perc", path) => (format!("{{disk:{}}}", path.unwrap_or("/")), Some(percent)),
+            ("fs_free", path) => (format!("{{disk_free:{}}}", path.unwrap_or("/")), None),
+            ("downspeed" | "downspeedf" | "upspeed" | "upspeedf", interface) => {
+                if let Some(interface) = interface {
+                    self.note(format!(
+                        "${{{name} {interface}}} shows the total of all interfaces"
+                    ));
+                }
+                let variable = if name.starts_with("down") {
+                    "{net_down}"
+                } else {
+                    "{net_up}"
+                };
+                (variable.to_string(), None)
+            }
+            ("diskio_read" | "diskio_write", device) => {
+                if let Some(device) = device {
+                    self.note(format!("${{{name} {device}}} shows the total of all disks"));
+                }
+                let variable = if name == "diskio_read" {
+                    "{disk_read}"
+                } else {
+                    "{disk_write}"
+                };
+                (variable.to_string(), None)
+            }
+            ("acpitemp", _) => ("{cpu_temp}".to_string(), Some(degrees)),
+            ("nvidia", Some("temp" | "gputemp")) => ("{gpu_temp}".to_string(), Some(degrees)),
+            ("nvidia", Some("gpuutil")) => ("{gpu}".to_string(), Some(percent)),
+            ("font", font) => {
+                let size = match font {
+                    Some(font) => font_size(font, self.notes).or(self.style.size),
+                    None => self.style.size,
+                };
+                self.markup(
+                    size.map_or_else(|| "{size}".to_string(), |size| format!("{{size {size}}}")),
+                );
+                return rest;
+            }
+            ("color", value) => {
+                let color = match value {
+                    Some(value) => color(value, self.notes),
+                    None => self.style.color.clone(),
+                };
+                self.color(color);
+                return rest;
+            }
+            _ => {
+                if let Some(index) = color_index(name) {
+                    let color = self.style.colors[index]
+                        .clone()
+                        .or_else(|| self.style.color.clone());
+                    self.color(color);
+                } else if !self.graph(name) {
+                    self.ignored_variable(name);
+                }
+                return rest;
+            }
+        };
+
+        self.template.push_str(&replacement);
+        self.has_text = true;
+        // Template variables carry their unit, conky's do not.
+        unit.unwrap_or_default()
+            .iter()
+            .find_map(|unit| rest.strip_prefix(unit))
+            .unwrap_or(rest)
+    }
+
+    fn markup(&mut self, markup: String) {
+        self.template.push_str(&markup);
+    }
+
+    fn color(&mut self, color: Option<String>) {
+        self.markup(color.map_or_else(
+            || "{color}".to_string(),
+            |color| format!("{{color {color}}}"),
+        ));
+    }
+
+    /// Enable the built-in section drawing a bar or graph variable.
+    fn graph(&mut self, name: &str) -> bool {
+        let section = match name {
+            "cpubar" | "cpugraph" | "cpugauge" | "loadgraph" => {
+                self.config.show_cpu = true;
+                WidgetSection::Utilization
+            }
+            "membar" | "memgraph" | "memgauge" => {
+                self.config.show_memory = true;
+                WidgetSection::Utilization
+            }
+            "nvidiabar" | "nvidiagraph" | "nvidiagauge" => {
+                self.config.show_gpu = true;
+                WidgetSection::Utilization
+            }
+            "downspeedgraph" | "upspeedgraph" => {
+                self.config.show_network = true;
+                WidgetSection::Network
+            }
+            "diskiograph" | "diskiograph_read" | "diskiograph_write" => {
+                self.config.show_disk = true;
+                WidgetSection::DiskIo
+            }
+            "fs_bar" | "fs_bar_free" => {
+                self.config.show_storage = true;
+                WidgetSection::Storage
+            }
+            _ => return false,
+        };
+        if !self.sections.contains(&section) {
+            self.sections.push(section);
+        }
+        true
+    }
+
+    fn ignored_variable(&mut self, name: &str) {
+        let name = name.trim().to_string();
+        if !self.ignored.contains(&name) {
+            self.ignored.push(name);
+        }
+    }
+
+    fn note(&mut self, text: String) {
+        note(self.notes, text);
+    }
+}
+
+fn note(notes: &mut Vec<String>, text: String) {
+    if !notes.contains(&text) {
+        notes.push(text);
+    }
+}
+
+fn anchor(alignment: &str) -> Option<OverlayAnchor> {
+    match alignment.trim() {
+        "top_left" | "tl" => Some(OverlayAnchor::TopLeft),
+        "top_middle" | "tm" => Some(OverlayAnchor::Top),
+        "top_right" | "tr" => Some(OverlayAnchor::TopRight),
+        "bottom_left" | "bl" => Some(OverlayAnchor::BottomLeft),
+        "bottom_middle" | "bm" => Some(OverlayAnchor::Bottom),
+        "bottom_right" | "br" => Some(OverlayAnchor::BottomRight),
+        _ => None,
+    }
+}
+
+/// Size of an Xft font such as `Ubuntu:style=Bold:size=12`, taken as pixels.
+fn font_size(font: &str, notes: &mut Vec<String>) -> Option<u16> {
+    let mut parts = font.split(':');
+    if parts.next().is_some_and(|family| !family.trim().is_empty()) {
+        note(
+            notes,
+            "Font families and styles are not imported; the theme font is used".to_string(),
+        );
+    }
+    parts.find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        if !matches!(key.trim(), "size" | "pixelsize") {
+            return None;
+        }
+        let size = value.trim().parse::<f32>().ok()?;
+        Some(size.round().clamp(1.0, f32::from(u16::MAX)) as u16)
+    })
+}
+
+/// `#rrggbb`, `rrggbb` or a basic colour name as `#rrggbb`.
+fn color(value: &str, notes: &mut Vec<String>) -> Option<String> {
+    let value = value.trim();
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(format!("#{}", hex.to_ascii_lowercase()));
+    }
+
+    let named = NAMED_COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        .map(|(_, hex)| hex.to_string());
+    if named.is_none() {
+        note(
+            notes,
+            format!("Colour '{value}' is not a hex value or basic colour name"),
+        );
+    }
+    named
+}
+
+/// Index of a `color0` to `color9` name.
+fn color_index(name: &str) -> Option<usize> {
+    let digit = name.strip_prefix("color")?;
+    if digit.len() != 1 {
+        return None;
+    }
+    digit.parse().ok()
+}
+
+/// Position of the `}` closing an already opened brace.
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 1;
+    for (index, character) in text.char_indices() {
+        match character {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The non-empty string assigned to `name`.
+fn assigned_string(source: &str, name: &str) -> Option<String> {
+    let (value, _) = lua_value(assignment(source, name)?);
+    (!value.is_empty()).then_some(value)
+}
+
+/// Text following `name =`, skipping commented-out lines.
+fn assignment<'a>(source: &'a str, name: &str) -> Option<&'a str> {
+    source.match_indices(name).find_map(|(index, _)| {
+        let line_start = source[..index].rfind('\n').map_or(0, |start| start + 1);
+        if source[line_start..index].contains("--") {
+            return None;
+        }
+        source[index + name.len()..]
+            .trim_start()
+            .strip_prefix('=')
+            .map(str::trim_start)
+    })
+}
+
+/// Keys and values of the Lua table assigned to `name`, in file order.
+fn table(source: &str, name: &str) -> Option<Vec<(String, String)>> {
+    let mut rest = assignment(source, name)?.strip_prefix('{')?;
+    let mut entries = Vec::new();
+
+    loop {
+        rest = skip_separators(rest);
+        let key_end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if key_end == 0 {
+            break;
+        }
+        let key = &rest[..key_end];
+        let Some(value) = rest[key_end..].trim_start().strip_prefix('=') else {
+            break;
+        };
+        let (value, remainder) = lua_value(value.trim_start());
+        entries.push((key.to_string(), value));
+        rest = remainder;
+    }
+
+    Some(entries)
+}
+
+/// A quoted string, long bracket string or bare value, and the text after it.
+fn lua_value(text: &str) -> (String, &str) {
+    if let Some(quote) = text.chars().next().filter(|c| matches!(c, '\'' | '"')) {
+        let inner = &text[1..];
+        let end = inner.find(quote).unwrap_or(inner.len());
+        return (inner[..end].to_string(), inner.get(end + 1..).unwrap_or(""));
+    }
+    if let Some((value, rest)) = long_bracket(text) {
+        return (value.to_string(), rest);
+    }
+
+    let end = [text.find([',', ';', '\n', '}']), text.find("--")]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(text.len());
+    (text[..end].trim().to_string(), &text[end..])
+}
+
+/// Contents of a `[[ ... ]]` or `[==[ ... ]==]` string, and the text after it.
+fn long_bracket(text: &str) -> Option<(&str, &str)> {
+    let level = text.strip_prefix('[')?;
+    let equals = level.len() - level.trim_start_matches('=').len();
+    let content = level[equals..].strip_prefix('[')?;
+    let close = format!("]{}]", "=".repeat(equals));
+    let end = content.find(&close)?;
+    // Like Lua, drop a line break directly after the opening bracket.
+    let value = &content[..end];
+    let value = value
+        .strip_prefix("\r\n")
+        .or_else(|| value.strip_prefix('\n'))
+        .unwrap_or(value);
+    Some((value, &content[end + close.len()..]))
+}
+
+/// Skip whitespace, separators and comments between table entries.
+fn skip_separators(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';'));
+        let Some(comment) = text.strip_prefix("--") else {
+            return text;
+        };
+        text = match long_bracket(comment) {
+            Some((_, rest)) => rest,
+            None => comment.find('\n').map_or("", |end| &comment[end..]),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImportError, import};
+    use crate::config::{Config, OverlayAnchor, WidgetSection};
+
+    #[test]
+    fn imports_the_clock_config_from_the_repository() {
+        let mut config = Config::default();
+        let report = import(include_str!("../clock.conf"), &mut config).unwrap();
+
+        assert_eq!(config.overlay_anchor, OverlayAnchor::TopRight);
+        assert_eq!((config.widget_x, config.widget_y), (60, 50));
+        assert_eq!(report.update_interval_ms, Some(1000));
+        assert_eq!(config.section_order[0], WidgetSection::Custom(0));
+        assert!(!config.show_clock && !config.show_cpu);
+        assert_eq!(
+            config.custom_sections[0].template,
+            "{size 64}{color #ffffff}{size 64}{time:%H:%M}{size 36}:{time:%S}{size 1}\n\
+             {size 24}{time:%A, %d %B %Y}{size 64}"
+        );
+        assert!(
+            report
+                .untranslated
+                .iter()
+                .any(|note| note.starts_with("Settings without an equivalent: double_buffer"))
+        );
+        assert_eq!(
+            report.untranslated.last().map(String::as_str),
+            Some("Variables without an equivalent: voffset")
+        );
+    }
+
+    #[test]
+    fn maps_system_variables_and_graphs() {
+        let source = r#"
+-- conky.text = [[commented out]]
+conky.config = {
+    alignment = 'bottom_left', -- corner
+    gap_x = 20,
+    gap_y = 30,
+    color1 = 'E5A50A',
+    --[[ block
+    comment ]]
+    double_buffer = true,
+}
+
+conky.text = [[
+CPU ${color1}${cpu cpu0}%${color} ${cpugraph}
+/home ${fs_used_perc /home}% {free} ${fs_free /home}
+$downspeed ${upspeed enp3s0} $$5 ${exec whoami}
+]]
+"#;
+        let mut config = Config::default();
+        let report = import(source, &mut config).unwrap();
+
+        assert_eq!(config.overlay_anchor, OverlayAnchor::BottomLeft);
+        assert_eq!((config.widget_x, config.widget_y), (20, 30));
+        assert_eq!(report.update_interval_ms, None);
+        assert_eq!(
+            config.custom_sections[0].template,
+            "CPU {color #e5a50a}{cpu}{color}\n\
+             /home {disk:/home} {{free}} {disk_free:/home}\n\
+             {net_down} {net_up} $5"
+        );
+        assert_eq!(
+            config.section_order[..2],
+            [WidgetSection::Custom(0), WidgetSection::Utilization]
+        );
+        assert!(config.show_cpu && !config.show_memory && !config.show_network);
+        assert_eq!(
+            report.untranslated,
+            [
+                "${upspeed enp3s0} shows the total of all interfaces",
+                "Settings without an equivalent: double_buffer",
+                "Variables without an equivalent: exec",
+            ]
+        );
+    }
+
+    #[test]
+    fn files_without_text_are_rejected_unchanged() {
+        let mut config = Config::default();
+        let source = "alignment top_right\ngap_x 20\nTEXT\n${time}\n";
+
+        assert_eq!(import(source, &mut config), Err(ImportError::MissingText));
+        assert_eq!(config, Config::default());
+    }
+}
@@ -0,0 +1,243 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Threshold alerts evaluated against each new snapshot.
+//!
+//! A rule fires once its reading has stayed past the threshold for the
+//! configured duration, and does not fire again for the same reading until
+//! the value has moved back past the threshold by the hysteresis. The
+//! cooldown additionally limits how often a reading that keeps flapping
+//! across that band can notify.
+
+use super::stats::SystemSnapshot;
+use crate::config::{AlertMetric, AlertRule};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Notification raised by a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub summary: String,
+    pub body: String,
+}
+
+#[derive(Debug, Default)]
+struct ReadingState {
+    crossed_since: Option<Instant>,
+    active: bool,
+    last_notified: Option<Instant>,
+}
+
+/// Tracks each rule's readings between snapshots.
+#[derive(Debug, Default)]
+pub struct AlertEvaluator {
+    rules: Vec<AlertRule>,
+    /// State per rule index and reading name.
+    states: HashMap<(usize, String), ReadingState>,
+}
+
+impl AlertEvaluator {
+    /// Replace the rules. Editing the rules restarts their durations.
+    pub fn set_rules(&mut self, rules: &[AlertRule]) {
+        if self.rules != rules {
+            self.rules = rules.to_vec();
+            self.states.clear();
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Alerts that should be raised for `snapshot`, taken at `now`.
+    pub fn evaluate(&mut self, snapshot: &SystemSnapshot, now: Instant) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        let mut seen = Vec::new();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !rule.enabled {
+                continue;
+            }
+
+            for (reading, value) in readings(rule, snapshot) {
+                let key = (index, reading);
+                let state = self.states.entry(key.clone()).or_default();
+
+                if rule.is_crossed(value) {
+                    let since = *state.crossed_since.get_or_insert(now);
+                    let held = now.saturating_duration_since(since)
+                        >= Duration::from_secs(rule.duration_secs);
+                    let cooled_down = state.last_notified.is_none_or(|notified| {
+                        now.saturating_duration_since(notified)
+                            >= Duration::from_secs(rule.cooldown_secs)
+                    });
+                    if !state.active && held && cooled_down {
+                        state.active = true;
+                        state.last_notified = Some(now);
+                        alerts.push(alert(rule, &key.1, value));
+                    }
+                } else if rule.is_cleared(value) {
+                    state.active = false;
+                    state.crossed_since = None;
+                } else if !state.active {
+                    // Within the hysteresis band: an active alert stays
+                    // active, a pending one has to start over.
+                    state.crossed_since = None;
+                }
+                seen.push(key);
+            }
+        }
+
+        // Forget filesystems and devices that went away.
+        self.states.retain(|key, _| seen.contains(key));
+        alerts
+    }
+}
+
+/// Named readings a rule applies to.
+fn readings(rule: &AlertRule, snapshot: &SystemSnapshot) -> Vec<(String, f32)> {
+    let target = rule.target.trim();
+    let single = |name: &str, value: f32| vec![(name.to_string(), value)];
+
+    match rule.metric {
+        AlertMetric::CpuUsage => single("CPU", snapshot.cpu_usage),
+        AlertMetric::MemoryUsage => single("Memory", snapshot.memory_usage),
+        AlertMetric::GpuUsage => single("GPU", snapshot.gpu_usage),
+        // A missing sensor reads as zero.
+        AlertMetric::CpuTemperature if snapshot.cpu_temp > 0.0 => single("CPU", snapshot.cpu_temp),
+        AlertMetric::GpuTemperature if snapshot.gpu_temp > 0.0 => single("GPU", snapshot.gpu_temp),
+        AlertMetric::CpuTemperature | AlertMetric::GpuTemperature => Vec::new(),
+        AlertMetric::StorageUsage => snapshot
+            .disks
+            .iter()
+            .filter(|disk| !disk.is_loading)
+            .filter(|disk| {
+                target.is_empty()
+                    || disk.mount_point == target
+                    || disk.subvolumes.iter().any(|subvolume| subvolume == target)
+            })
+            .map(|disk| (disk.mount_point.clone(), disk.used_percentage))
+            .collect(),
+        AlertMetric::DeviceBattery => snapshot
+            .devices
+            .iter()
+            .filter(|device| device.is_connected && !device.is_loading)
+            .filter(|device| target.is_empty() || device.name.eq_ignore_ascii_case(target))
+            .filter_map(|device| Some((device.name.clone(), f32::from(device.level?))))
+            .collect(),
+        AlertMetric::NetworkQuota if snapshot.network_quota_bytes > 0 => single(
+            "Network",
+            (snapshot.network_cycle_bytes as f64 / snapshot.network_quota_bytes as f64 * 100.0)
+                as f32,
+        ),
+        AlertMetric::NetworkQuota => Vec::new(),
+    }
+}
+
+fn alert(rule: &AlertRule, reading: &str, value: f32) -> Alert {
+    let unit = rule.metric.unit();
+    let summary = format!(
+        "{} {} {}{unit}",
+        rule.metric.label(),
+        rule.comparison.label(),
+        rule.threshold
+    );
+    let body = if rule.metric.has_targets() {
+        format!("{reading} is at {value:.0}{unit}")
+    } else {
+        format!("{} is at {value:.0}{unit}", rule.metric.label())
+    };
+    Alert { summary, body }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AlertEvaluator;
+    use crate::config::{AlertComparison, AlertMetric, AlertRule};
+    use crate::iced_widget::stats::SystemSnapshot;
+    use crate::storage::DiskInfo;
+    use std::time::{Duration, Instant};
+
+    fn cpu_temperature(value: f32) -> SystemSnapshot {
+        SystemSnapshot {
+            cpu_temp: value,
+            ..SystemSnapshot::default()
+        }
+    }
+
+    #[test]
+    fn fires_once_after_the_duration_until_cleared_by_the_hysteresis() {
+        let mut alerts = AlertEvaluator::default();
+        alerts.set_rules(&[AlertRule {
+            cooldown_secs: 0,
+            ..AlertRule::default()
+        }]);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(alerts.evaluate(&cpu_temperature(95.0), at(0)).is_empty());
+        let fired = alerts.evaluate(&cpu_temperature(95.0), at(30));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].summary, "CPU temperature above 90 °C");
+        assert_eq!(fired[0].body, "CPU temperature is at 95 °C");
+
+        // Dipping into the hysteresis band does not rearm the rule.
+        assert!(alerts.evaluate(&cpu_temperature(88.0), at(31)).is_empty());
+        assert!(alerts.evaluate(&cpu_temperature(95.0), at(70)).is_empty());
+
+        assert!(alerts.evaluate(&cpu_temperature(80.0), at(71)).is_empty());
+        assert!(alerts.evaluate(&cpu_temperature(95.0), at(72)).is_empty());
+        assert_eq!(alerts.evaluate(&cpu_temperature(95.0), at(102)).len(), 1);
+    }
+
+    #[test]
+    fn cooldown_limits_repeated_notifications() {
+        let mut alerts = AlertEvaluator::default();
+        alerts.set_rules(&[AlertRule {
+            duration_secs: 0,
+            cooldown_secs: 300,
+            ..AlertRule::default()
+        }]);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(alerts.evaluate(&cpu_temperature(95.0), at(0)).len(), 1);
+        assert!(alerts.evaluate(&cpu_temperature(70.0), at(10)).is_empty());
+        assert!(alerts.evaluate(&cpu_temperature(95.0), at(20)).is_empty());
+        assert_eq!(alerts.evaluate(&cpu_temperature(95.0), at(300)).len(), 1);
+    }
+
+    #[test]
+    fn storage_rules_watch_each_mount_point() {
+        let disk = |mount_point: &str, used_percentage| DiskInfo {
+            name: mount_point.to_string(),
+            mount_point: mount_point.to_string(),
+            used_percentage,
+            total_space: 0,
+            available_space: 0,
+            is_loading: false,
+            device: None,
+            fs_type: "ext4".to_string(),
+            source: String::new(),
+            subvolumes: Vec::new(),
+        };
+        let snapshot = SystemSnapshot {
+            disks: vec![disk("/", 97.0), disk("/home", 40.0), disk("/mnt", 99.0)],
+            ..SystemSnapshot::default()
+        };
+        let mut alerts = AlertEvaluator::default();
+        alerts.set_rules(&[AlertRule {
+            metric: AlertMetric::StorageUsage,
+            comparison: AlertComparison::Above,
+            threshold: 95,
+            duration_secs: 0,
+            ..AlertRule::default()
+        }]);
+
+        let fired = alerts.evaluate(&snapshot, Instant::now());
+        let bodies = fired
+            .iter()
+            .map(|alert| alert.body.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, ["/ is at 97%", "/mnt is at 99%"]);
+    }
+}
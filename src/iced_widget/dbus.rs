@@ -0,0 +1,486 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Session bus service for scripts, the panel applet and keyboard shortcuts.
+//!
+//! The overlay owns [`OVERLAY_BUS_NAME`] and serves one object at
+//! [`OVERLAY_OBJECT_PATH`]. Readings from the latest snapshot are typed
+//! properties that emit `PropertiesChanged` when their value changes, and the
+//! interface methods are forwarded to the overlay's update loop as
+//! [`ServiceCommand`]s.
+//!
+//! ```text
+//! busctl --user get-property com.github.zoliviragh.CosmicWidget \
+//!     /com/github/zoliviragh/CosmicWidget com.github.zoliviragh.CosmicWidget1 CpuUsage
+//! busctl --user call com.github.zoliviragh.CosmicWidget \
+//!     /com/github/zoliviragh/CosmicWidget com.github.zoliviragh.CosmicWidget1 Toggle
+//! ```
+
+use super::stats::SystemSnapshot;
+use crate::config::{OVERLAY_BUS_NAME, OVERLAY_OBJECT_PATH};
+use crate::media::PlaybackStatus;
+use futures_util::Stream;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// A request received over the bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceCommand {
+    Show,
+    Hide,
+    Toggle,
+    EnterEditMode,
+    ClearNotifications,
+    PlayPauseMedia,
+    NextMedia,
+    PreviousMedia,
+    Quit,
+}
+
+/// Values published as properties of the overlay interface.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OverlayState {
+    pub cpu_usage: f64,
+    pub memory_usage: f64,
+    pub gpu_usage: f64,
+    pub network_rx_rate: f64,
+    pub network_tx_rate: f64,
+    pub disk_read_rate: f64,
+    pub disk_write_rate: f64,
+    pub cpu_temperature: f64,
+    pub gpu_temperature: f64,
+    /// `(name, mount point, used percentage, total bytes, available bytes)`
+    pub disks: Vec<(String, String, f64, u64, u64)>,
+    /// `(name, battery level or -1, status, connected)`
+    pub devices: Vec<(String, i32, String, bool)>,
+    pub notification_count: u32,
+    pub media_player: String,
+    pub media_title: String,
+    pub media_artist: String,
+    /// `Playing`, `Paused` or `Stopped`, matching the MPRIS property.
+    pub media_status: String,
+    pub visible: bool,
+    pub edit_mode: bool,
+}
+
+impl OverlayState {
+    pub fn new(snapshot: &SystemSnapshot, visible: bool, edit_mode: bool) -> Self {
+        let media = snapshot.media.current_player().map(|(_, info)| info);
+
+        Self {
+            cpu_usage: f64::from(snapshot.cpu_usage),
+            memory_usage: f64::from(snapshot.memory_usage),
+            gpu_usage: f64::from(snapshot.gpu_usage),
+            network_rx_rate: snapshot.network_rx_rate,
+            network_tx_rate: snapshot.network_tx_rate,
+            disk_read_rate: snapshot.disk_read_rate,
+            disk_write_rate: snapshot.disk_write_rate,
+            cpu_temperature: f64::from(snapshot.cpu_temp),
+            gpu_temperature: f64::from(snapshot.gpu_temp),
+            disks: snapshot
+                .disks
+                .iter()
+                .map(|disk| {
+                    (
+                        disk.name.clone(),
+                        disk.mount_point.clone(),
+                        f64::from(disk.used_percentage),
+                        disk.total_space,
+                        disk.available_space,
+                    )
+                })
+                .collect(),
+            devices: snapshot
+                .devices
+                .iter()
+                .map(|device| {
+                    (
+                        device.name.clone(),
+                        device.level.map_or(-1, i32::from),
+                        device.status.clone().unwrap_or_default(),
+                        device.is_connected,
+                    )
+                })
+                .collect(),
+            notification_count: u32::try_from(snapshot.notifications.len()).unwrap_or(u32::MAX),
+            media_player: media
+                .map(|info| info.player_name.clone())
+                .unwrap_or_default(),
+            media_title: media.map(|info| info.title.clone()).unwrap_or_default(),
+            media_artist: media.map(|info| info.artist.clone()).unwrap_or_default(),
+            media_status: match media.map(|info| &info.status) {
+                Some(PlaybackStatus::Playing) => "Playing",
+                Some(PlaybackStatus::Paused) => "Paused",
+                Some(PlaybackStatus::Stopped) | None => "Stopped",
+            }
+            .to_string(),
+            visible,
+            edit_mode,
+        }
+    }
+}
+
+/// Handle to the bus service thread.
+pub struct OverlayService {
+    updates: UnboundedSender<OverlayState>,
+    published: Option<OverlayState>,
+}
+
+impl OverlayService {
+    /// Claim the bus name and serve the interface on a background thread.
+    ///
+    /// Failing to reach the session bus, or finding the name already owned, is
+    /// logged and leaves the overlay running without the service.
+    pub fn spawn() -> (Self, CommandReceiver) {
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let (update_tx, update_rx) = mpsc::unbounded_channel();
+
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(error) => {
+                    log::error!("Failed to start the D-Bus service runtime: {error}");
+                    return;
+                }
+            };
+            if let Err(error) = runtime.block_on(serve(command_tx, update_rx)) {
+                log::warn!("Overlay D-Bus service unavailable: {error}");
+            }
+        });
+
+        (
+            Self {
+                updates: update_tx,
+                published: None,
+            },
+            CommandReceiver(Arc::new(Mutex::new(Some(command_rx)))),
+        )
+    }
+
+    /// Update the bus properties if anything changed since the last call.
+    pub fn publish(&mut self, state: OverlayState) {
+        if self.published.as_ref() == Some(&state) {
+            return;
+        }
+        // The receiver is gone only when the service failed to start, which
+        // has already been logged.
+        let _ = self.updates.send(state.clone());
+        self.published = Some(state);
+    }
+}
+
+/// The overlay side of the command channel, handed to an iced subscription.
+///
+/// Hashes to a constant so the subscription keeps running across updates; the
+/// receiver is taken by the first stream built from it.
+#[derive(Debug, Clone)]
+pub struct CommandReceiver(Arc<Mutex<Option<UnboundedReceiver<ServiceCommand>>>>);
+
+impl Hash for CommandReceiver {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        "overlay-service-commands".hash(state);
+    }
+}
+
+pub fn command_stream(receiver: &CommandReceiver) -> impl Stream<Item = ServiceCommand> + use<> {
+    let receiver = match receiver.0.lock() {
+        Ok(mut receiver) => receiver.take(),
+        Err(poisoned) => poisoned.into_inner().take(),
+    };
+
+    futures_util::stream::unfold(receiver, |receiver| async move {
+        let mut receiver = receiver?;
+        let command = receiver.recv().await?;
+        Some((command, Some(receiver)))
+    })
+}
+
+async fn serve(
+    commands: UnboundedSender<ServiceCommand>,
+    mut updates: UnboundedReceiver<OverlayState>,
+) -> zbus::Result<()> {
+    let connection = zbus::connection::Builder::session()?
+        .name(OVERLAY_BUS_NAME)?
+        .serve_at(
+            OVERLAY_OBJECT_PATH,
+            OverlayInterface {
+                state: OverlayState::default(),
+                commands,
+            },
+        )?
+        .build()
+        .await?;
+    log::info!("Serving overlay controls as {OVERLAY_BUS_NAME}");
+
+    let interface = connection
+        .object_server()
+        .interface::<_, OverlayInterface>(OVERLAY_OBJECT_PATH)
+        .await?;
+    while let Some(state) = updates.recv().await {
+        let mut overlay = interface.get_mut().await;
+        let previous = std::mem::replace(&mut overlay.state, state);
+        overlay
+            .notify_changes(&previous, interface.signal_context())
+            .await?;
+    }
+
+    Ok(())
+}
+
+struct OverlayInterface {
+    state: OverlayState,
+    commands: UnboundedSender<ServiceCommand>,
+}
+
+impl OverlayInterface {
+    fn send(&self, command: ServiceCommand) {
+        let _ = self.commands.send(command);
+    }
+
+    async fn notify_changes(
+        &self,
+        previous: &OverlayState,
+        ctxt: &zbus::SignalContext<'_>,
+    ) -> zbus::Result<()> {
+        let state = &self.state;
+        if previous.cpu_usage != state.cpu_usage {
+            self.cpu_usage_changed(ctxt).await?;
+        }
+        if previous.memory_usage != state.memory_usage {
+            self.memory_usage_changed(ctxt).await?;
+        }
+        if previous.gpu_usage != state.gpu_usage {
+            self.gpu_usage_changed(ctxt).await?;
+        }
+        if previous.network_rx_rate != state.network_rx_rate {
+            self.network_rx_rate_changed(ctxt).await?;
+        }
+        if previous.network_tx_rate != state.network_tx_rate {
+            self.network_tx_rate_changed(ctxt).await?;
+        }
+        if previous.disk_read_rate != state.disk_read_rate {
+            self.disk_read_rate_changed(ctxt).await?;
+        }
+        if previous.disk_write_rate != state.disk_write_rate {
+            self.disk_write_rate_changed(ctxt).await?;
+        }
+        if previous.cpu_temperature != state.cpu_temperature {
+            self.cpu_temperature_changed(ctxt).await?;
+        }
+        if previous.gpu_temperature != state.gpu_temperature {
+            self.gpu_temperature_changed(ctxt).await?;
+        }
+        if previous.disks != state.disks {
+            self.disks_changed(ctxt).await?;
+        }
+        if previous.devices != state.devices {
+            self.devices_changed(ctxt).await?;
+        }
+        if previous.notification_count != state.notification_count {
+            self.notification_count_changed(ctxt).await?;
+        }
+        if previous.media_player != state.media_player {
+            self.media_player_changed(ctxt).await?;
+        }
+        if previous.media_title != state.media_title {
+            self.media_title_changed(ctxt).await?;
+        }
+        if previous.media_artist != state.media_artist {
+            self.media_artist_changed(ctxt).await?;
+        }
+        if previous.media_status != state.media_status {
+            self.media_status_changed(ctxt).await?;
+        }
+        if previous.visible != state.visible {
+            self.visible_changed(ctxt).await?;
+        }
+        if previous.edit_mode != state.edit_mode {
+            self.edit_mode_changed(ctxt).await?;
+        }
+        Ok(())
+    }
+}
+
+// The interface name must be a literal; keep it in sync with
+// `crate::config::OVERLAY_INTERFACE`.
+#[zbus::interface(name = "com.github.zoliviragh.CosmicWidget1")]
+impl OverlayInterface {
+    fn show(&self) {
+        self.send(ServiceCommand::Show);
+    }
+
+    fn hide(&self) {
+        self.send(ServiceCommand::Hide);
+    }
+
+    fn toggle(&self) {
+        self.send(ServiceCommand::Toggle);
+    }
+
+    /// Unpin the overlay so it can be dragged to a new position.
+    fn enter_edit_mode(&self) {
+        self.send(ServiceCommand::EnterEditMode);
+    }
+
+    fn clear_notifications(&self) {
+        self.send(ServiceCommand::ClearNotifications);
+    }
+
+    fn play_pause_media(&self) {
+        self.send(ServiceCommand::PlayPauseMedia);
+    }
+
+    fn next_media(&self) {
+        self.send(ServiceCommand::NextMedia);
+    }
+
+    fn previous_media(&self) {
+        self.send(ServiceCommand::PreviousMedia);
+    }
+
+    /// Exit the overlay process.
+    fn quit(&self) {
+        self.send(ServiceCommand::Quit);
+    }
+
+    #[zbus(property)]
+    fn cpu_usage(&self) -> f64 {
+        self.state.cpu_usage
+    }
+
+    #[zbus(property)]
+    fn memory_usage(&self) -> f64 {
+        self.state.memory_usage
+    }
+
+    #[zbus(property)]
+    fn gpu_usage(&self) -> f64 {
+        self.state.gpu_usage
+    }
+
+    /// Bytes per second received across all interfaces.
+    #[zbus(property)]
+    fn network_rx_rate(&self) -> f64 {
+        self.state.network_rx_rate
+    }
+
+    /// Bytes per second sent across all interfaces.
+    #[zbus(property)]
+    fn network_tx_rate(&self) -> f64 {
+        self.state.network_tx_rate
+    }
+
+    #[zbus(property)]
+    fn disk_read_rate(&self) -> f64 {
+        self.state.disk_read_rate
+    }
+
+    #[zbus(property)]
+    fn disk_write_rate(&self) -> f64 {
+        self.state.disk_write_rate
+    }
+
+    /// Degrees Celsius.
+    #[zbus(property)]
+    fn cpu_temperature(&self) -> f64 {
+        self.state.cpu_temperature
+    }
+
+    /// Degrees Celsius.
+    #[zbus(property)]
+    fn gpu_temperature(&self) -> f64 {
+        self.state.gpu_temperature
+    }
+
+    #[zbus(property)]
+    fn disks(&self) -> Vec<(String, String, f64, u64, u64)> {
+        self.state.disks.clone()
+    }
+
+    #[zbus(property)]
+    fn devices(&self) -> Vec<(String, i32, String, bool)> {
+        self.state.devices.clone()
+    }
+
+    #[zbus(property)]
+    fn notification_count(&self) -> u32 {
+        self.state.notification_count
+    }
+
+    #[zbus(property)]
+    fn media_player(&self) -> String {
+        self.state.media_player.clone()
+    }
+
+    #[zbus(property)]
+    fn media_title(&self) -> String {
+        self.state.media_title.clone()
+    }
+
+    #[zbus(property)]
+    fn media_artist(&self) -> String {
+        self.state.media_artist.clone()
+    }
+
+    #[zbus(property)]
+    fn media_status(&self) -> String {
+        self.state.media_status.clone()
+    }
+
+    #[zbus(property)]
+    fn visible(&self) -> bool {
+        self.state.visible
+    }
+
+    #[zbus(property)]
+    fn edit_mode(&self) -> bool {
+        self.state.edit_mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OverlayState;
+    use crate::battery::BatteryDevice;
+    use crate::iced_widget::stats::SystemSnapshot;
+
+    fn device(name: &str, level: Option<u8>, is_connected: bool) -> BatteryDevice {
+        BatteryDevice {
+            name: name.to_string(),
+            level,
+            status: None,
+            kind: None,
+            codename: None,
+            is_loading: false,
+            is_connected,
+        }
+    }
+
+    #[test]
+    fn state_reports_unknown_battery_levels_as_negative() {
+        let snapshot = SystemSnapshot {
+            cpu_usage: 42.5,
+            devices: vec![
+                device("Mouse", Some(80), true),
+                device("Headset", None, false),
+            ],
+            ..SystemSnapshot::default()
+        };
+
+        let state = OverlayState::new(&snapshot, true, false);
+
+        assert_eq!(state.cpu_usage, 42.5);
+        assert_eq!(
+            state.devices,
+            vec![
+                ("Mouse".to_string(), 80, String::new(), true),
+                ("Headset".to_string(), -1, String::new(), false),
+            ]
+        );
+        assert_eq!(state.media_status, "Stopped");
+        assert!(state.visible);
+    }
+}
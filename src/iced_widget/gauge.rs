@@ -386,7 +386,7 @@ fn fill_arc_cap(frame: &mut canvas::Frame<Renderer>, radius: f32, angle: f32, co
     frame.fill(&canvas::Path::circle(cap_center, TRACK_WIDTH / 2.0), color);
 }
 
-fn meter_style(theme: &Theme) -> cosmic::widget::progress_bar::style::Appearance {
+pub(super) fn meter_style(theme: &Theme) -> cosmic::widget::progress_bar::style::Appearance {
     <Theme as cosmic::widget::progress_bar::style::StyleSheet>::appearance(theme, &(), true, false)
 }
 
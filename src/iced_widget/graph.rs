@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Sparkline and area graphs drawn from sampled metric history.
+
+use super::history::MetricHistory;
+use crate::config::HistoryGraphStyle;
+use cosmic::iced::widget::canvas;
+use cosmic::iced::{Color, Length, Point, Rectangle, mouse};
+use cosmic::{Element, Renderer, Theme};
+use std::time::Duration;
+
+pub const GRAPH_HEIGHT: f32 = 40.0;
+const LINE_WIDTH: f32 = 1.5;
+const AREA_ALPHA: f32 = 0.25;
+const TRACK_ALPHA: f32 = 0.35;
+
+/// Value mapped to the top edge of a graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphScale {
+    /// A fixed ceiling, such as 100 for percentages.
+    Fixed(f32),
+    /// The largest visible sample, but never less than `floor`.
+    Auto { floor: f32 },
+}
+
+/// Graph the visible window of each history, newest samples on the right.
+///
+/// Series share one scale and are coloured accent, success, then warning.
+pub fn history_graph<'a>(
+    series: &[&MetricHistory],
+    window: Duration,
+    style: HistoryGraphStyle,
+    scale: GraphScale,
+) -> Element<'a, super::Message> {
+    let end = series
+        .iter()
+        .filter_map(|history| history.latest_at())
+        .max();
+    let points: Vec<Vec<(f32, f32)>> = end
+        .map(|end| {
+            series
+                .iter()
+                .map(|history| history.points(end, window))
+                .collect()
+        })
+        .unwrap_or_default();
+    let ceiling = scale_ceiling(&points, scale);
+
+    canvas::Canvas::new(HistoryGraph {
+        series: points,
+        ceiling,
+        style,
+    })
+    .width(Length::Fill)
+    .height(Length::Fixed(GRAPH_HEIGHT))
+    .into()
+}
+
+fn scale_ceiling(series: &[Vec<(f32, f32)>], scale: GraphScale) -> f32 {
+    match scale {
+        GraphScale::Fixed(ceiling) => ceiling,
+        GraphScale::Auto { floor } => series
+            .iter()
+            .flatten()
+            .map(|(_, value)| *value)
+            .fold(floor, f32::max),
+    }
+}
+
+struct HistoryGraph {
+    series: Vec<Vec<(f32, f32)>>,
+    ceiling: f32,
+    style: HistoryGraphStyle,
+}
+
+impl<Message> canvas::Program<Message, Theme, Renderer> for HistoryGraph {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry<Renderer>> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let meter_style = super::gauge::meter_style(theme);
+        let track = Color {
+            a: meter_style.track_color.a * TRACK_ALPHA,
+            ..meter_style.track_color
+        };
+        frame.fill(
+            &canvas::Path::rounded_rectangle(
+                Point::ORIGIN,
+                bounds.size(),
+                meter_style.border_radius.into(),
+            ),
+            track,
+        );
+
+        let cosmic = theme.cosmic();
+        let colors: [Color; 3] = [
+            cosmic.accent.base.into(),
+            cosmic.success.base.into(),
+            cosmic.warning.base.into(),
+        ];
+        let plot_height = bounds.height - LINE_WIDTH;
+        let to_point = |(position, value): (f32, f32)| {
+            let level = (value / self.ceiling.max(f32::EPSILON)).clamp(0.0, 1.0);
+            Point::new(
+                position * bounds.width,
+                LINE_WIDTH / 2.0 + plot_height * (1.0 - level),
+            )
+        };
+
+        for (points, color) in self.series.iter().zip(colors.into_iter().cycle()) {
+            let Some((first, rest)) = points.split_first() else {
+                continue;
+            };
+
+            let line = canvas::Path::new(|builder| {
+                builder.move_to(to_point(*first));
+                for point in rest {
+                    builder.line_to(to_point(*point));
+                }
+            });
+
+            if self.style == HistoryGraphStyle::Area {
+                let area = canvas::Path::new(|builder| {
+                    builder.move_to(Point::new(first.0 * bounds.width, bounds.height));
+                    builder.line_to(to_point(*first));
+                    for point in rest {
+                        builder.line_to(to_point(*point));
+                    }
+                    let last = points.last().unwrap_or(first);
+                    builder.line_to(Point::new(last.0 * bounds.width, bounds.height));
+                    builder.close();
+                });
+                frame.fill(
+                    &area,
+                    Color {
+                        a: AREA_ALPHA,
+                        ..color
+                    },
+                );
+            }
+
+            frame.stroke(
+                &line,
+                canvas::Stroke::default()
+                    .with_color(color)
+                    .with_width(LINE_WIDTH)
+                    .with_line_join(canvas::LineJoin::Round),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GraphScale, scale_ceiling};
+
+    #[test]
+    fn auto_scale_follows_the_largest_visible_sample() {
+        let series = vec![vec![(0.0, 10.0), (1.0, 40.0)], vec![(0.5, 25.0)]];
+
+        assert_eq!(
+            scale_ceiling(&series, GraphScale::Auto { floor: 1.0 }),
+            40.0
+        );
+        assert_eq!(scale_ceiling(&[], GraphScale::Auto { floor: 1.0 }), 1.0);
+        assert_eq!(scale_ceiling(&series, GraphScale::Fixed(100.0)), 100.0);
+    }
+}
@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Headless JSON output for debugging monitors and feeding other tools.
+//!
+//! `--once` prints a single [`SystemSnapshot`] and exits; `--json` prints one
+//! snapshot per line until stdout is closed. Both run the same sampler as the
+//! overlay, configured from the saved settings, without opening a Wayland
+//! surface, taking the overlay's instance lock or writing its cache files.
+
+use super::stats::{
+    MonitorSelection, NetworkSettings, PollIntervals, StatsSampler, StorageSettings, SystemSnapshot,
+};
+use crate::config::{Config, MAX_POLL_INTERVAL_MS, MIN_POLL_INTERVAL_MS, UPDATE_INTERVAL_MS};
+use std::io::{self, Write};
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: cosmic-widget [--json [--interval MS] | --once]
+
+Without options the desktop overlay is started.
+
+  --json          Print newline-delimited JSON snapshots to stdout
+  --interval MS   Time between --json snapshots (default 1000)
+  --once          Print a single JSON snapshot and exit
+  -h, --help      Show this help";
+
+/// Time given to the monitors before the first snapshot, so rates and CPU
+/// usage have a previous sample to compare against.
+const WARM_UP: Duration = Duration::from_millis(2 * UPDATE_INTERVAL_MS);
+
+/// How the binary was asked to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Overlay,
+    Help,
+    Json { once: bool, interval: Duration },
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Mode, String> {
+    let mut json = false;
+    let mut once = false;
+    let mut interval = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--once" => once = true,
+            "--interval" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--interval requires a value in milliseconds".to_string())?;
+                let ms = value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|ms| (MIN_POLL_INTERVAL_MS..=MAX_POLL_INTERVAL_MS).contains(ms))
+                    .ok_or_else(|| {
+                        format!(
+                            "--interval must be between {MIN_POLL_INTERVAL_MS} and \
+                             {MAX_POLL_INTERVAL_MS} ms, got {value}"
+                        )
+                    })?;
+                interval = Some(Duration::from_millis(ms));
+            }
+            "-h" | "--help" => return Ok(Mode::Help),
+            _ => return Err(format!("unrecognized option: {arg}")),
+        }
+    }
+
+    if interval.is_some() && (once || !json) {
+        return Err("--interval is only valid with --json".to_string());
+    }
+    if !json && !once {
+        return Ok(Mode::Overlay);
+    }
+
+    Ok(Mode::Json {
+        once,
+        interval: interval.unwrap_or(Duration::from_millis(UPDATE_INTERVAL_MS)),
+    })
+}
+
+/// Sample with the saved configuration and write snapshots to stdout.
+///
+/// A closed stdout, for example `| head -n 1`, ends the stream without error.
+/// Cache files are left to the overlay, which may be running at the same time.
+pub fn run(config: &Config, once: bool, interval: Duration) -> io::Result<()> {
+    crate::cache::disable_persistence();
+    let sampler = StatsSampler::spawn(
+        MonitorSelection::from_config(config),
+        PollIntervals::from_config(config),
+        config.show_weather,
+        config.enable_solaar_integration,
+        config.weather_location.clone(),
+        config.max_notifications,
+        config.cider_api_token.clone(),
+        if config.show_commands {
+            config.commands.clone()
+        } else {
+            Vec::new()
+        },
+    );
+    sampler.set_network_settings(NetworkSettings::from_config(config));
+    sampler.set_storage_settings(StorageSettings::from_config(config));
+    std::thread::sleep(WARM_UP);
+
+    let mut stdout = io::stdout().lock();
+    loop {
+        match write_snapshot(&mut stdout, &sampler.snapshot()) {
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+        if once {
+            return Ok(());
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+fn write_snapshot(output: &mut impl Write, snapshot: &SystemSnapshot) -> io::Result<()> {
+    serde_json::to_writer(&mut *output, snapshot)?;
+    output.write_all(b"\n")?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mode, parse_args};
+    use std::time::Duration;
+
+    fn parse(args: &[&str]) -> Result<Mode, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_headless_modes() {
+        assert_eq!(parse(&[]), Ok(Mode::Overlay));
+        assert_eq!(
+            parse(&["--once"]),
+            Ok(Mode::Json {
+                once: true,
+                interval: Duration::from_secs(1),
+            })
+        );
+        assert_eq!(
+            parse(&["--json", "--interval", "5000"]),
+            Ok(Mode::Json {
+                once: false,
+                interval: Duration::from_secs(5),
+            })
+        );
+        assert!(parse(&["--json", "--interval", "10"]).is_err());
+        assert!(parse(&["--interval", "5000"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+    }
+}
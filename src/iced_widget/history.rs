@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Bounded sample history backing the overlay's history graphs.
+//!
+//! Each metric keeps timestamped samples for at most
+//! [`MAX_HISTORY_WINDOW_SECS`](crate::config::MAX_HISTORY_WINDOW_SECS), so the
+//! memory used does not depend on how long the overlay has been running.
+
+use crate::config::{MAX_HISTORY_WINDOW_SECS, MIN_POLL_INTERVAL_MS};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Upper bound on stored samples per metric, reached at the fastest polling
+/// interval over the longest window.
+const MAX_SAMPLES: usize = (MAX_HISTORY_WINDOW_SECS * 1_000 / MIN_POLL_INTERVAL_MS) as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    at: Instant,
+    value: f32,
+}
+
+/// Ring buffer of recent readings for one metric.
+#[derive(Debug, Clone, Default)]
+pub struct MetricHistory {
+    samples: VecDeque<Sample>,
+}
+
+impl MetricHistory {
+    pub fn record(&mut self, value: f32) {
+        self.record_at(Instant::now(), value);
+    }
+
+    fn record_at(&mut self, at: Instant, value: f32) {
+        let retention = Duration::from_secs(MAX_HISTORY_WINDOW_SECS);
+        while self
+            .samples
+            .front()
+            .is_some_and(|oldest| at.saturating_duration_since(oldest.at) > retention)
+            || self.samples.len() >= MAX_SAMPLES
+        {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample { at, value });
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn latest_at(&self) -> Option<Instant> {
+        self.samples.back().map(|sample| sample.at)
+    }
+
+    /// Samples within `window` of `end` as `(position, value)` pairs, where
+    /// position runs from 0.0 at the start of the window to 1.0 at `end`.
+    pub fn points(&self, end: Instant, window: Duration) -> Vec<(f32, f32)> {
+        let window_secs = window.as_secs_f32().max(f32::EPSILON);
+        self.samples
+            .iter()
+            .filter_map(|sample| {
+                let age = end.checked_duration_since(sample.at)?;
+                (age <= window).then(|| (1.0 - age.as_secs_f32() / window_secs, sample.value))
+            })
+            .collect()
+    }
+}
+
+/// History of every metric shown by a graph-capable section.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotHistory {
+    pub cpu: MetricHistory,
+    pub memory: MetricHistory,
+    pub gpu: MetricHistory,
+    pub network_rx: MetricHistory,
+    pub network_tx: MetricHistory,
+    pub disk_read: MetricHistory,
+    pub disk_write: MetricHistory,
+    pub cpu_temp: MetricHistory,
+    pub gpu_temp: MetricHistory,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetricHistory;
+    use crate::config::MAX_HISTORY_WINDOW_SECS;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn drops_samples_older_than_the_retention_window() {
+        let start = Instant::now();
+        let mut history = MetricHistory::default();
+
+        history.record_at(start, 1.0);
+        history.record_at(
+            start + Duration::from_secs(MAX_HISTORY_WINDOW_SECS + 1),
+            2.0,
+        );
+
+        assert_eq!(history.samples.len(), 1);
+        assert_eq!(history.samples[0].value, 2.0);
+    }
+
+    #[test]
+    fn points_are_positioned_within_the_requested_window() {
+        let start = Instant::now();
+        let mut history = MetricHistory::default();
+        for (offset, value) in [(0, 10.0), (30, 20.0), (60, 30.0)] {
+            history.record_at(start + Duration::from_secs(offset), value);
+        }
+
+        let end = history.latest_at().unwrap();
+        let points = history.points(end, Duration::from_secs(60));
+        assert_eq!(points, vec![(0.0, 10.0), (0.5, 20.0), (1.0, 30.0)]);
+
+        let recent = history.points(end, Duration::from_secs(30));
+        assert_eq!(recent, vec![(0.0, 20.0), (1.0, 30.0)]);
+    }
+}
@@ -0,0 +1,370 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! OpenMetrics exporter for scraping the overlay's readings.
+//!
+//! A small HTTP listener serves `GET /metrics` from the sampler's latest
+//! snapshot, so Prometheus-compatible scrapers reuse the overlay's sampling
+//! instead of running a second exporter. Readings for disabled sections are
+//! omitted rather than reported as zero.
+
+use super::stats::{MonitorSelection, SnapshotReader, SystemSnapshot};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Connections served at once; further ones are closed unanswered.
+const MAX_CLIENTS: usize = 8;
+/// How long a new listener waits for a stopped one to release its port.
+const BIND_RETRY_LIMIT: Duration = Duration::from_secs(2);
+
+/// Listener thread serving `/metrics`; stops when dropped.
+///
+/// Dropping only signals the thread, which closes the listener within one
+/// accept poll, so the UI thread never waits on it or on a slow client.
+pub struct MetricsExporter {
+    running: Arc<AtomicBool>,
+}
+
+impl MetricsExporter {
+    pub fn spawn(address: SocketAddr, reader: SnapshotReader) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let running_clone = Arc::clone(&running);
+        std::thread::spawn(move || {
+            let Some(listener) = bind(address, &running_clone) else {
+                return;
+            };
+            log::info!("Serving OpenMetrics at http://{address}/metrics");
+
+            let clients = Arc::new(AtomicUsize::new(0));
+            while running_clone.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _peer)) => {
+                        if clients.fetch_add(1, Ordering::Relaxed) >= MAX_CLIENTS {
+                            clients.fetch_sub(1, Ordering::Relaxed);
+                            continue;
+                        }
+                        let clients = Arc::clone(&clients);
+                        let reader = reader.clone();
+                        std::thread::spawn(move || {
+                            if let Err(error) = handle_client(stream, &reader) {
+                                log::debug!("Metrics request failed: {error}");
+                            }
+                            clients.fetch_sub(1, Ordering::Relaxed);
+                        });
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                    Err(error) => {
+                        log::warn!("Metrics listener error: {error}");
+                        std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                }
+            }
+        });
+
+        Self { running }
+    }
+}
+
+impl Drop for MetricsExporter {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Bind the listener, waiting briefly for an exporter stopped by a
+/// configuration change to release the same port.
+fn bind(address: SocketAddr, running: &AtomicBool) -> Option<TcpListener> {
+    let started = Instant::now();
+    loop {
+        let result = TcpListener::bind(address).and_then(|listener| {
+            listener.set_nonblocking(true)?;
+            Ok(listener)
+        });
+        match result {
+            Ok(listener) => return Some(listener),
+            Err(error)
+                if error.kind() == io::ErrorKind::AddrInUse
+                    && started.elapsed() < BIND_RETRY_LIMIT
+                    && running.load(Ordering::Relaxed) =>
+            {
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+            Err(error) => {
+                log::error!("Failed to start the metrics exporter on {address}: {error}");
+                return None;
+            }
+        }
+    }
+}
+
+fn handle_client(mut stream: TcpStream, reader: &SnapshotReader) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut request = BufReader::new(&stream);
+    let mut request_line = String::new();
+    request.read_line(&mut request_line)?;
+    // Drain the headers; the exporter does not use any of them.
+    let mut header = String::new();
+    while request.read_line(&mut header)? > 0 && header != "\r\n" && header != "\n" {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            CONTENT_TYPE,
+            reader.read(|snapshot, selection| render(snapshot, selection)),
+        ),
+        (Some("GET"), Some(_)) => (
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            "Metrics are served at /metrics\n".to_string(),
+        ),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            String::new(),
+        ),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Render the snapshot as an OpenMetrics text exposition.
+fn render(snapshot: &SystemSnapshot, selection: MonitorSelection) -> String {
+    let mut output = String::new();
+
+    if selection.utilization {
+        gauge(
+            &mut output,
+            "cosmic_widget_cpu_usage_percent",
+            "CPU utilization across all cores.",
+            &[(NO_LABELS, f64::from(snapshot.cpu_usage))],
+        );
+        gauge(
+            &mut output,
+            "cosmic_widget_memory_usage_percent",
+            "Share of physical memory in use.",
+            &[(NO_LABELS, f64::from(snapshot.memory_usage))],
+        );
+    }
+    if selection.gpu {
+        gauge(
+            &mut output,
+            "cosmic_widget_gpu_usage_percent",
+            "GPU utilization.",
+            &[(NO_LABELS, f64::from(snapshot.gpu_usage))],
+        );
+    }
+    if selection.temperatures {
+        gauge(
+            &mut output,
+            "cosmic_widget_cpu_temperature_celsius",
+            "CPU package temperature.",
+            &[(NO_LABELS, f64::from(snapshot.cpu_temp))],
+        );
+        gauge(
+            &mut output,
+            "cosmic_widget_gpu_temperature_celsius",
+            "GPU temperature.",
+            &[(NO_LABELS, f64::from(snapshot.gpu_temp))],
+        );
+    }
+    if selection.network {
+        gauge(
+            &mut output,
+            "cosmic_widget_network_receive_bytes_per_second",
+            "Download rate across physical interfaces.",
+            &[(NO_LABELS, snapshot.network_rx_rate)],
+        );
+        gauge(
+            &mut output,
+            "cosmic_widget_network_transmit_bytes_per_second",
+            "Upload rate across physical interfaces.",
+            &[(NO_LABELS, snapshot.network_tx_rate)],
+        );
+    }
+    if selection.disk_io {
+        gauge(
+            &mut output,
+            "cosmic_widget_disk_read_bytes_per_second",
+            "Read rate across block devices.",
+            &[(NO_LABELS, snapshot.disk_read_rate)],
+        );
+        gauge(
+            &mut output,
+            "cosmic_widget_disk_write_bytes_per_second",
+            "Write rate across block devices.",
+            &[(NO_LABELS, snapshot.disk_write_rate)],
+        );
+    }
+    if selection.storage {
+        let disks: Vec<_> = snapshot
+            .disks
+            .iter()
+            .filter(|disk| !disk.is_loading)
+            .map(|disk| {
+                (
+                    [
+                        ("device", disk.name.as_str()),
+                        ("mountpoint", disk.mount_point.as_str()),
+                    ],
+                    disk,
+                )
+            })
+            .collect();
+        let samples = |value: fn(&crate::storage::DiskInfo) -> f64| {
+            disks
+                .iter()
+                .map(|(labels, disk)| (&labels[..], value(disk)))
+                .collect::<Vec<_>>()
+        };
+        gauge(
+            &mut output,
+            "cosmic_widget_filesystem_used_percent",
+            "Share of filesystem capacity in use.",
+            &samples(|disk| f64::from(disk.used_percentage)),
+        );
+        gauge(
+            &mut output,
+            "cosmic_widget_filesystem_size_bytes",
+            "Filesystem capacity.",
+            &samples(|disk| disk.total_space as f64),
+        );
+        gauge(
+            &mut output,
+            "cosmic_widget_filesystem_available_bytes",
+            "Filesystem space available to unprivileged users.",
+            &samples(|disk| disk.available_space as f64),
+        );
+    }
+    if selection.battery {
+        let devices: Vec<_> = snapshot
+            .devices
+            .iter()
+            .filter(|device| !device.is_loading)
+            .map(|device| {
+                (
+                    [
+                        ("device", device.name.as_str()),
+                        ("kind", device.kind.as_deref().unwrap_or("")),
+                    ],
+                    device,
+                )
+            })
+            .collect();
+        let levels: Vec<_> = devices
+            .iter()
+            .filter_map(|(labels, device)| Some((&labels[..], f64::from(device.level?))))
+            .collect();
+        let connected: Vec<_> = devices
+            .iter()
+            .map(|(labels, device)| (&labels[..], f64::from(u8::from(device.is_connected))))
+            .collect();
+        gauge(
+            &mut output,
+            "cosmic_widget_device_battery_percent",
+            "Peripheral battery level.",
+            &levels,
+        );
+        gauge(
+            &mut output,
+            "cosmic_widget_device_connected",
+            "Whether the peripheral is currently reachable.",
+            &connected,
+        );
+    }
+
+    output.push_str("# EOF\n");
+    output
+}
+
+type Labels<'a> = [(&'a str, &'a str)];
+
+const NO_LABELS: &Labels<'static> = &[];
+
+fn gauge(output: &mut String, name: &str, help: &str, samples: &[(&Labels<'_>, f64)]) {
+    let _ = writeln!(output, "# TYPE {name} gauge");
+    let _ = writeln!(output, "# HELP {name} {help}");
+    for (labels, value) in samples {
+        output.push_str(name);
+        if !labels.is_empty() {
+            output.push('{');
+            for (index, (label, label_value)) in labels.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                let _ = write!(output, "{label}=\"{}\"", escape_label_value(label_value));
+            }
+            output.push('}');
+        }
+        let _ = writeln!(output, " {value}");
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+    use crate::iced_widget::stats::{MonitorSelection, SystemSnapshot};
+    use crate::storage::DiskInfo;
+
+    #[test]
+    fn renders_enabled_sections_with_labels() {
+        let snapshot = SystemSnapshot {
+            cpu_usage: 12.5,
+            disks: vec![DiskInfo {
+                name: "Samsung \"990\"".to_string(),
+                mount_point: "/home".to_string(),
+                used_percentage: 50.0,
+                total_space: 1000,
+                available_space: 500,
+                is_loading: false,
+                device: None,
+                fs_type: "ext4".to_string(),
+                source: String::new(),
+                subvolumes: Vec::new(),
+            }],
+            ..SystemSnapshot::default()
+        };
+        let selection = MonitorSelection {
+            utilization: true,
+            storage: true,
+            ..MonitorSelection::default()
+        };
+
+        let output = render(&snapshot, selection);
+
+        assert!(output.contains("# TYPE cosmic_widget_cpu_usage_percent gauge\n"));
+        assert!(output.contains("cosmic_widget_cpu_usage_percent 12.5\n"));
+        assert!(output.contains(
+            "cosmic_widget_filesystem_size_bytes{device=\"Samsung \\\"990\\\"\",\
+             mountpoint=\"/home\"} 1000\n"
+        ));
+        assert!(!output.contains("gpu"));
+        assert!(output.ends_with("# EOF\n"));
+    }
+}
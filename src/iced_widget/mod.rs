@@ -1,37 +1,61 @@
 // SPDX-License-Identifier: MPL-2.0
 
+mod alerts;
+mod dbus;
 mod gauge;
+mod graph;
+pub mod headless;
+mod history;
 mod marquee;
+mod metrics;
 mod slide;
 mod stats;
 mod translate;
 mod view;
 
-use crate::config::{Config, UPDATE_INTERVAL_MS, WidgetSection};
+use crate::config::{
+    CONFIG_ID, Config, CustomSection, HistoryGraphStyle, OverlayAnchor, ShellCommand,
+    UPDATE_INTERVAL_MS, WidgetSection, instance_config_id,
+};
 use crate::media::{MultiPlayerState, PlaybackStatus, PlayerId};
+use crate::outputs::{OutputEvent, Outputs};
+use crate::template::{DEFAULT_FONT_SIZE, Template};
 use chrono::{DateTime, Local};
+use cosmic::cctk::sctk::reexports::client::protocol::wl_output::WlOutput;
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
 use cosmic::iced::platform_specific::runtime::wayland::{
     self, CornerRadius,
-    layer_surface::{IcedMargin, SctkLayerSurfaceSettings},
+    layer_surface::{IcedMargin, IcedOutput, SctkLayerSurfaceSettings},
 };
 use cosmic::iced::platform_specific::shell::commands::layer_surface::{
     self, Anchor, KeyboardInteractivity, Layer,
 };
 use cosmic::iced::platform_specific::shell::commands::{blur, corner_radius};
 use cosmic::iced::{self, Color, Point, Rectangle, Size, Subscription, Task, window};
+use dbus::{CommandReceiver, OverlayService, OverlayState, ServiceCommand};
 use futures_util::SinkExt;
-use stats::{StatsSampler, SystemSnapshot};
+use metrics::MetricsExporter;
+use stats::{
+    MonitorSelection, NetworkSettings, PollIntervals, StatsSampler, StorageSettings, SystemSnapshot,
+};
+use std::collections::HashMap;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 const APP_ID: &str = "com.github.zoliviragh.CosmicWidget.Iced";
 const SURFACE_WIDTH: u32 = 370;
 const BASE_SURFACE_HEIGHT: u32 = 556;
 const NETWORK_SECTION_HEIGHT: u32 = 120;
+const NETWORK_INTERFACE_ROW_HEIGHT: u32 = 26;
+const NETWORK_CONNECTION_ROW_HEIGHT: u32 = 26;
+const NETWORK_TRAFFIC_ROW_HEIGHT: u32 = 26;
 const DISK_IO_SECTION_HEIGHT: u32 = 120;
+const DISK_DEVICE_ROW_HEIGHT: u32 = 22;
+const HISTORY_GRAPH_HEIGHT: u32 = 48;
 const EMPTY_STORAGE_HEIGHT: u32 = 63;
 const STORAGE_SECTION_HEIGHT: u32 = 38;
 const STORAGE_ITEM_HEIGHT: u32 = 62;
+const DRIVE_HEALTH_ROW_HEIGHT: u32 = 22;
+const POOL_HEALTH_ROW_HEIGHT: u32 = 22;
 const EMPTY_DEVICES_HEIGHT: u32 = 83;
 const DEVICES_SECTION_HEIGHT: u32 = 54;
 const DEVICE_ITEM_HEIGHT: u32 = 33;
@@ -47,6 +71,28 @@ const NOTIFICATION_EXPANSION_DURATION: Duration = Duration::from_millis(220);
 const NOTIFICATION_GROUP_EXPANSION_DURATION: Duration = Duration::from_millis(320);
 const EMPTY_MEDIA_HEIGHT: u32 = 95;
 const MEDIA_SECTION_HEIGHT: u32 = 248;
+const EMPTY_COMMANDS_HEIGHT: u32 = 63;
+const COMMANDS_SECTION_HEIGHT: u32 = 38;
+const COMMAND_ITEM_HEIGHT: u32 = 26;
+const COMMAND_TABLE_ROW_HEIGHT: u32 = 20;
+const MAX_COMMAND_TABLE_ROWS: usize = 6;
+const PROCESSES_SECTION_HEIGHT: u32 = 38;
+const PROCESS_LIST_HEADING_HEIGHT: u32 = 24;
+const PROCESS_ROW_HEIGHT: u32 = 22;
+const PROCESS_SIGNAL_PROMPT_HEIGHT: u32 = 64;
+const MEMORY_DETAIL_ROW_HEIGHT: u32 = 26;
+const GPU_ROW_HEIGHT: u32 = 26;
+const GPU_ITEM_HEIGHT: u32 = 50;
+const GPU_DETAIL_ROW_HEIGHT: u32 = 26;
+const GPU_PROCESSES_HEADING_HEIGHT: u32 = 26;
+const GPU_PROCESS_ROW_HEIGHT: u32 = 26;
+const CPU_DETAILS_HEIGHT: u32 = 30;
+const CPU_CORE_HEADING_HEIGHT: u32 = 22;
+const CPU_CORE_ROW_HEIGHT: u32 = 52;
+const CUSTOM_SECTION_HEIGHT: u32 = 13;
+const CUSTOM_HEADING_HEIGHT: u32 = 28;
+const CUSTOM_LINE_SPACING: u32 = 4;
+const TEXT_LINE_HEIGHT: f32 = 1.3;
 const MEDIA_CONTROL_GRACE: Duration = Duration::from_secs(2);
 const UI_TICK_SETTLE_DELAY: Duration = Duration::from_millis(5);
 const CORNER_RADIUS_STARTUP_DELAY: Duration = Duration::from_secs(1);
@@ -64,6 +110,19 @@ struct NotificationKey {
     timestamp: u64,
 }
 
+/// Process from the top process list that a right click asked to end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessTarget {
+    pub pid: u32,
+    pub name: String,
+    /// Processes in the row, more than one for a grouped tree of which only
+    /// the root is signalled
+    pub count: u32,
+    /// Start time from `/proc/<pid>/stat` when the prompt opened, so a later
+    /// process reusing the PID is not signalled
+    pub start_time: Option<u64>,
+}
+
 #[derive(Debug, Clone)]
 struct DismissingNotification {
     key: NotificationKey,
@@ -252,16 +311,43 @@ pub fn run() -> iced::Result {
 }
 
 struct App {
-    config: Config,
-    config_handler: Option<cosmic_config::Config>,
+    /// The default overlay comes first and also holds the settings shared by
+    /// every instance; named instances follow it.
+    overlays: Vec<Overlay>,
     now: DateTime<Local>,
     snapshot: SystemSnapshot,
     sampler: StatsSampler,
+    outputs: Outputs,
+    frosted: bool,
+    pending_playback: Option<PendingPlayback>,
+    visible: bool,
+    service: OverlayService,
+    service_commands: CommandReceiver,
+    metrics_exporter: Option<MetricsExporter>,
+    alerts: AlertEvaluator,
+}
+
+/// One layer surface and the profile it is drawn from.
+struct Overlay {
+    /// Instance name, or `None` for the default overlay.
+    instance: Option<String>,
+    config: Config,
+    /// Templates of the profile's custom sections, parsed when it is loaded.
+    templates: HashMap<u32, Template>,
+    config_handler: Option<cosmic_config::Config>,
     surface_id: window::Id,
     surface_height: u32,
-    frosted: bool,
+    /// Connector name of the output the surface was requested on, or `None`
+    /// if the compositor chose it.
+    placed_output: Option<String>,
     corners: Option<CornerRadius>,
     corners_ready_at: Instant,
+    /// Whether the CPU bar is expanded to per-core details.
+    cpu_details_expanded: bool,
+    /// Whether the GPU rows are expanded to the busiest GPU processes.
+    gpu_processes_expanded: bool,
+    /// Process waiting for confirmation before it is sent a signal.
+    pending_process_signal: Option<ProcessTarget>,
     expanded_notification_group: Option<String>,
     expanded_notification: Option<NotificationKey>,
     notification_group_expansion: ExpansionAnimation,
@@ -270,7 +356,6 @@ struct App {
     notification_scroll: ScrollAnimation,
     media_seek_preview: Option<f64>,
     media_timeline_hovered: bool,
-    pending_playback: Option<PendingPlayback>,
     overlay_cursor: Point,
     overlay_drag_cursor: Option<Point>,
 }
@@ -279,10 +364,30 @@ struct App {
 pub enum Message {
     Tick,
     AnimationTick,
+    Output(OutputEvent, WlOutput),
+    /// A message from the view of the overlay drawn on this surface.
+    Surface(window::Id, Box<Message>),
+    ToggleCpuDetails,
+    ToggleGpuProcesses,
+    ConfirmProcessSignal(ProcessTarget),
+    /// Send SIGTERM, or SIGKILL if `force` is set.
+    SignalProcess {
+        pid: u32,
+        force: bool,
+    },
+    CancelProcessSignal,
     ClearNotifications,
-    ToggleNotificationGroup { source: String },
-    ToggleNotification { app_name: String, timestamp: u64 },
-    DismissNotification { app_name: String, timestamp: u64 },
+    ToggleNotificationGroup {
+        source: String,
+    },
+    ToggleNotification {
+        app_name: String,
+        timestamp: u64,
+    },
+    DismissNotification {
+        app_name: String,
+        timestamp: u64,
+    },
     PreviousMedia,
     PlayPauseMedia,
     NextMedia,
@@ -295,86 +400,360 @@ pub enum Message {
     BeginOverlayDrag,
     EndOverlayDrag,
     PinOverlay,
+    SetOverlayVisible(bool),
+    ToggleOverlay,
+    EnterEditMode,
+    Quit,
+}
+
+impl Overlay {
+    fn new(instance: Option<String>, snapshot: &SystemSnapshot) -> Self {
+        let config_id = instance
+            .as_deref()
+            .map_or_else(|| CONFIG_ID.to_string(), instance_config_id);
+        let config_handler = cosmic_config::Config::new(&config_id, Config::VERSION).ok();
+        let config = load_config(config_handler.as_ref());
+        let templates = view::parse_templates(&config);
+        let surface_height = desired_surface_height_with_animation(
+            &config, &templates, snapshot, None, None, 0.0, 0.0,
+        );
+
+        Self {
+            instance,
+            config,
+            templates,
+            config_handler,
+            surface_id: window::Id::unique(),
+            surface_height,
+            placed_output: None,
+            // The compositor validates radii against the committed buffer,
+            // so wait until the 1x1 bootstrap surface has been replaced.
+            corners: None,
+            corners_ready_at: Instant::now() + CORNER_RADIUS_STARTUP_DELAY,
+            cpu_details_expanded: false,
+            gpu_processes_expanded: false,
+            pending_process_signal: None,
+            expanded_notification_group: None,
+            expanded_notification: None,
+            notification_group_expansion: ExpansionAnimation::with_duration(
+                NOTIFICATION_GROUP_EXPANSION_DURATION,
+            ),
+            notification_expansion: ExpansionAnimation::default(),
+            dismissing_notifications: Vec::new(),
+            notification_scroll: ScrollAnimation::default(),
+            media_seek_preview: None,
+            media_timeline_hovered: false,
+            overlay_cursor: Point::ORIGIN,
+            overlay_drag_cursor: None,
+        }
+    }
+
+    /// Create the surface on the configured output, or on the one the
+    /// compositor picks while that output is not connected.
+    fn create_surface(&mut self, frosted: bool, outputs: &Outputs) -> Task<Message> {
+        let output = outputs.find(&self.config.overlay_output);
+        self.placed_output = output.map(|output| output.name.clone());
+        overlay_surface(
+            self.surface_id,
+            &self.config,
+            self.surface_height,
+            frosted,
+            output.map(|output| &output.output),
+        )
+    }
+
+    /// A destroyed layer surface cannot be mapped again, so showing the
+    /// overlay creates a fresh one at the configured position.
+    fn remap(
+        &mut self,
+        snapshot: &SystemSnapshot,
+        frosted: bool,
+        outputs: &Outputs,
+    ) -> Task<Message> {
+        self.surface_id = window::Id::unique();
+        self.surface_height = self.target_surface_height(snapshot);
+        self.corners = None;
+        self.corners_ready_at = Instant::now() + CORNER_RADIUS_STARTUP_DELAY;
+        self.create_surface(frosted, outputs)
+    }
+
+    /// Layer surfaces cannot change output, so moving the overlay to another
+    /// output or anchor replaces its surface.
+    fn replace_surface(
+        &mut self,
+        snapshot: &SystemSnapshot,
+        frosted: bool,
+        outputs: &Outputs,
+    ) -> Task<Message> {
+        let destroy = layer_surface::destroy_layer_surface(self.surface_id);
+        destroy.chain(self.remap(snapshot, frosted, outputs))
+    }
+
+    /// Whether the configured output's connection state differs from where
+    /// the surface was placed.
+    fn needs_output_change(&self, outputs: &Outputs) -> bool {
+        outputs
+            .find(&self.config.overlay_output)
+            .map(|output| output.name.as_str())
+            != self.placed_output.as_deref()
+    }
+
+    fn set_margin(&self) -> Task<Message> {
+        let (top, right, bottom, left) = self
+            .config
+            .overlay_anchor
+            .margins(self.config.widget_x, self.config.widget_y);
+        layer_surface::set_margin(self.surface_id, top, right, bottom, left)
+    }
+
+    /// Update the input and blur regions, and the surface size for overlays
+    /// anchored at the bottom, which are sized to their content.
+    fn set_regions(&self, height: u32, frosted: bool) -> Task<Message> {
+        let regions = set_surface_regions(self.surface_id, height, frosted);
+        if self.config.overlay_anchor.is_bottom() {
+            Task::batch([
+                layer_surface::set_size(self.surface_id, Some(SURFACE_WIDTH), Some(height)),
+                regions,
+            ])
+        } else {
+            regions
+        }
+    }
+
+    /// Re-read the profile, returning the surface update if it changed.
+    /// A hidden overlay is only recreated with the new placement once it is
+    /// shown again.
+    fn reload_config(
+        &mut self,
+        snapshot: &SystemSnapshot,
+        frosted: bool,
+        outputs: &Outputs,
+        visible: bool,
+    ) -> Option<Task<Message>> {
+        let handler = self.config_handler.as_ref()?;
+        let mut config = load_config(Some(handler));
+        if self.config.widget_movable && config.widget_movable {
+            config.widget_x = self.config.widget_x;
+            config.widget_y = self.config.widget_y;
+        }
+        if config == self.config {
+            return None;
+        }
+
+        let placement_changed = config.overlay_anchor != self.config.overlay_anchor
+            || config.overlay_output != self.config.overlay_output;
+        let position_changed =
+            config.widget_x != self.config.widget_x || config.widget_y != self.config.widget_y;
+        if config.custom_sections != self.config.custom_sections {
+            self.templates = view::parse_templates(&config);
+        }
+        self.config = config;
+        if !self.config.widget_movable {
+            self.overlay_drag_cursor = None;
+        }
+
+        Some(if placement_changed && visible {
+            self.replace_surface(snapshot, frosted, outputs)
+        } else if position_changed && visible {
+            self.set_margin()
+        } else {
+            Task::none()
+        })
+    }
+
+    fn save_config(&self, what: &str) {
+        if let Some(handler) = &self.config_handler
+            && let Err(error) = self.config.write_entry(handler)
+        {
+            log::error!("Failed to save {what}: {error}");
+        }
+    }
+
+    /// Drop expansion and dismissal state for notifications that are gone.
+    fn retain_notification_state(&mut self, snapshot: &SystemSnapshot) {
+        self.dismissing_notifications.retain(|dismissal| {
+            snapshot
+                .notifications
+                .iter()
+                .any(|notification| dismissal.matches(notification))
+        });
+        if self.expanded_notification.as_ref().is_some_and(|key| {
+            !snapshot
+                .notifications
+                .iter()
+                .any(|notification| key.matches(notification))
+        }) {
+            self.expanded_notification = None;
+            self.notification_expansion.reset();
+        }
+        if self
+            .expanded_notification_group
+            .as_ref()
+            .is_some_and(|source| notification_group_size(snapshot, source) < 2)
+        {
+            self.expanded_notification_group = None;
+            self.notification_group_expansion.reset();
+        }
+    }
+
+    fn forget_notification(
+        &mut self,
+        key: &NotificationKey,
+        source: &str,
+        snapshot: &SystemSnapshot,
+    ) {
+        if self.expanded_notification.as_ref() == Some(key) {
+            self.expanded_notification = None;
+            self.notification_expansion.reset();
+        }
+        if self.expanded_notification_group.as_deref() == Some(source)
+            && notification_group_size(snapshot, source) < 2
+        {
+            self.expanded_notification_group = None;
+            self.notification_group_expansion.reset();
+        }
+    }
+
+    fn clear_notification_state(&mut self) {
+        self.expanded_notification_group = None;
+        self.expanded_notification = None;
+        self.notification_group_expansion.reset();
+        self.notification_expansion.reset();
+        self.dismissing_notifications.clear();
+        self.notification_scroll = ScrollAnimation::default();
+    }
+
+    fn animations_active(&self) -> bool {
+        self.notification_expansion.is_animating()
+            || self.notification_group_expansion.is_animating()
+            || self
+                .dismissing_notifications
+                .iter()
+                .any(|dismissal| dismissal.animation.is_animating())
+            || self.notification_scroll.is_animating()
+    }
+
+    fn animated_surface_height(&self, snapshot: &SystemSnapshot) -> u32 {
+        desired_surface_height_with_animation(
+            &self.config,
+            &self.templates,
+            snapshot,
+            self.expanded_notification.as_ref(),
+            self.expanded_notification_group.as_deref(),
+            self.notification_expansion.progress,
+            self.notification_group_expansion.progress,
+        ) + self.cpu_details_height(snapshot)
+            + self.gpu_processes_height(snapshot)
+            + self.process_signal_prompt_height()
+    }
+
+    fn target_surface_height(&self, snapshot: &SystemSnapshot) -> u32 {
+        desired_surface_height_with_animation(
+            &self.config,
+            &self.templates,
+            snapshot,
+            self.expanded_notification.as_ref(),
+            self.expanded_notification_group.as_deref(),
+            self.notification_expansion.target,
+            self.notification_group_expansion.target,
+        ) + self.cpu_details_height(snapshot)
+            + self.gpu_processes_height(snapshot)
+            + self.process_signal_prompt_height()
+    }
+
+    fn cpu_details_height(&self, snapshot: &SystemSnapshot) -> u32 {
+        if self.cpu_details_expanded && self.config.show_cpu && self.config.show_cpu_details {
+            cpu_details_height(snapshot)
+        } else {
+            0
+        }
+    }
+
+    fn process_signal_prompt_height(&self) -> u32 {
+        if self.pending_process_signal.is_some() && self.processes_visible() {
+            PROCESS_SIGNAL_PROMPT_HEIGHT
+        } else {
+            0
+        }
+    }
+
+    fn processes_visible(&self) -> bool {
+        self.config.show_processes
+            && self
+                .config
+                .section_order
+                .iter()
+                .any(|section| matches!(section, WidgetSection::Processes))
+    }
+
+    fn gpu_processes_height(&self, snapshot: &SystemSnapshot) -> u32 {
+        if self.gpu_processes_expanded && self.config.show_gpu && self.config.show_gpu_processes {
+            gpu_processes_height(&self.config, snapshot)
+        } else {
+            0
+        }
+    }
 }
 
 impl App {
     fn new() -> (Self, Task<Message>) {
-        let config_handler =
-            cosmic_config::Config::new("com.github.zoliviragh.CosmicWidget", Config::VERSION).ok();
-        let mut config = config_handler
-            .as_ref()
-            .map(|handler| match Config::get_entry(handler) {
-                Ok(config) => config,
-                Err((_errors, config)) => config,
-            })
-            .unwrap_or_default();
-        config.ensure_all_sections();
+        let snapshot = SystemSnapshot::default();
+        let mut overlays = vec![Overlay::new(None, &snapshot)];
+        let instances = overlays[0].config.instance_names();
+        overlays.extend(
+            instances
+                .into_iter()
+                .map(|name| Overlay::new(Some(name), &snapshot)),
+        );
+
+        let shared = &overlays[0].config;
+        let mut alerts = AlertEvaluator::default();
+        alerts.set_rules(&shared.alert_rules);
         let sampler = StatsSampler::spawn(
-            config.show_weather,
-            config.enable_solaar_integration,
-            config.weather_location.clone(),
-            config.max_notifications,
-            config.cider_api_token.clone(),
+            MonitorSelection::from_configs(overlays.iter().map(|overlay| &overlay.config))
+                .with_alert_rules(alerts.rules()),
+            PollIntervals::from_config(shared),
+            overlays.iter().any(|overlay| overlay.config.show_weather),
+            shared.enable_solaar_integration,
+            shared.weather_location.clone(),
+            shared.max_notifications,
+            shared.cider_api_token.clone(),
+            active_commands(&overlays),
         );
-        let surface_id = window::Id::unique();
+        sampler.set_network_settings(NetworkSettings::from_config(shared));
+        sampler.set_storage_settings(StorageSettings::from_config(shared));
         let frosted = frosted_enabled();
-        let snapshot = SystemSnapshot::default();
-        let surface_height = desired_surface_height(&config, &snapshot);
-
-        let create_surface = layer_surface::get_layer_surface(SctkLayerSurfaceSettings {
-            id: surface_id,
-            layer: Layer::Bottom,
-            keyboard_interactivity: KeyboardInteractivity::OnDemand,
-            anchor: Anchor::TOP.union(Anchor::BOTTOM).union(Anchor::LEFT),
-            namespace: "cosmic-widget-iced".to_string(),
-            margin: IcedMargin {
-                top: config.widget_y,
-                left: config.widget_x,
-                ..IcedMargin::default()
-            },
-            // An unspecified size becomes 1x1 for a surface anchored to only
-            // one horizontal and vertical edge in the pinned Iced backend.
-            size: Some((Some(SURFACE_WIDTH), None)),
-            input_zone: Some(vec![surface_region(surface_height)]),
-            exclusive_zone: -1,
-            ..SctkLayerSurfaceSettings::default()
-        });
 
-        let create_surface = if frosted {
-            create_surface.chain(set_surface_blur(surface_id, true, surface_height))
-        } else {
-            create_surface
-        };
+        let (service, service_commands) = OverlayService::spawn();
+        let metrics_exporter = shared
+            .metrics_socket_address()
+            .map(|address| MetricsExporter::spawn(address, sampler.reader()));
+        // Outputs are announced after startup; overlays placed on a specific
+        // output move there once it is known.
+        let outputs = Outputs::default();
+        let create_surfaces = Task::batch(
+            overlays
+                .iter_mut()
+                .map(|overlay| overlay.create_surface(frosted, &outputs))
+                .collect::<Vec<_>>(),
+        );
 
         (
             Self {
-                config,
-                config_handler,
+                overlays,
                 now: Local::now(),
                 snapshot,
                 sampler,
-                surface_id,
-                surface_height,
+                outputs,
                 frosted,
-                // The compositor validates radii against the committed buffer,
-                // so wait until the 1x1 bootstrap surface has been replaced.
-                corners: None,
-                corners_ready_at: Instant::now() + CORNER_RADIUS_STARTUP_DELAY,
-                expanded_notification_group: None,
-                expanded_notification: None,
-                notification_group_expansion: ExpansionAnimation::with_duration(
-                    NOTIFICATION_GROUP_EXPANSION_DURATION,
-                ),
-                notification_expansion: ExpansionAnimation::default(),
-                dismissing_notifications: Vec::new(),
-                notification_scroll: ScrollAnimation::default(),
-                media_seek_preview: None,
-                media_timeline_hovered: false,
                 pending_playback: None,
-                overlay_cursor: Point::ORIGIN,
-                overlay_drag_cursor: None,
+                visible: true,
+                service,
+                service_commands,
+                metrics_exporter,
+                alerts,
             },
-            create_surface,
+            create_surfaces,
         )
     }
 
@@ -394,6 +773,12 @@ impl App {
         }
     }
 
+    /// The main configuration, which also holds the settings shared by all
+    /// overlay instances.
+    fn shared_config(&self) -> &Config {
+        &self.overlays[0].config
+    }
+
     fn update(&mut self, message: Message) -> Task<Message> {
         let mut tasks = Vec::new();
 
@@ -407,121 +792,90 @@ impl App {
                     Instant::now(),
                 );
                 self.snapshot = snapshot;
-                self.dismissing_notifications.retain(|dismissal| {
-                    self.snapshot
-                        .notifications
-                        .iter()
-                        .any(|notification| dismissal.matches(notification))
-                });
-                if self.expanded_notification.as_ref().is_some_and(|key| {
-                    !self
-                        .snapshot
-                        .notifications
-                        .iter()
-                        .any(|notification| key.matches(notification))
-                }) {
-                    self.expanded_notification = None;
-                    self.notification_expansion.reset();
-                }
-                if self
-                    .expanded_notification_group
-                    .as_ref()
-                    .is_some_and(|source| notification_group_size(&self.snapshot, source) < 2)
-                {
-                    self.expanded_notification_group = None;
-                    self.notification_group_expansion.reset();
+                let now = Instant::now();
+                for alert in self.alerts.evaluate(&self.snapshot, now) {
+                    log::info!("Alert: {} ({})", alert.summary, alert.body);
+                    self.sampler.send_notification(&alert.summary, &alert.body);
                 }
-                if Instant::now() >= self.corners_ready_at {
-                    let corners = overlay_corners();
-                    if self.corners != Some(corners) {
-                        self.corners = Some(corners);
-                        tasks.push(set_surface_corners(self.surface_id, corners));
+                let corners = self
+                    .overlays
+                    .iter()
+                    .any(|overlay| now >= overlay.corners_ready_at)
+                    .then(overlay_corners);
+                for overlay in &mut self.overlays {
+                    overlay.retain_notification_state(&self.snapshot);
+                    if let Some(corners) = corners
+                        && now >= overlay.corners_ready_at
+                        && overlay.corners != Some(corners)
+                    {
+                        overlay.corners = Some(corners);
+                        tasks.push(set_surface_corners(overlay.surface_id, corners));
                     }
                 }
 
-                if let Some(handler) = &self.config_handler {
-                    let mut config =
-                        Config::get_entry(handler).unwrap_or_else(|(_errors, config)| config);
-                    config.ensure_all_sections();
-                    if self.config.widget_movable && config.widget_movable {
-                        config.widget_x = self.config.widget_x;
-                        config.widget_y = self.config.widget_y;
-                    }
-                    if config != self.config {
-                        crate::widget_logging::set_enabled(config.enable_logging);
-                        let position_changed = config.widget_x != self.config.widget_x
-                            || config.widget_y != self.config.widget_y;
-                        self.sampler.set_weather_config(
-                            config.show_weather,
-                            config.weather_location.clone(),
-                        );
-                        self.sampler
-                            .set_solaar_enabled(config.enable_solaar_integration);
-                        if config.cider_api_token != self.config.cider_api_token {
-                            self.sampler.set_cider_token(config.cider_api_token.clone());
-                        }
-                        self.config = config;
-                        if !self.config.widget_movable {
-                            self.overlay_drag_cursor = None;
-                        }
-
-                        if position_changed {
-                            tasks.push(layer_surface::set_margin(
-                                self.surface_id,
-                                self.config.widget_y,
-                                0,
-                                0,
-                                self.config.widget_x,
-                            ));
-                        }
+                let previous_shared = self.shared_config().clone();
+                let mut config_changed = false;
+                for overlay in &mut self.overlays {
+                    if let Some(task) = overlay.reload_config(
+                        &self.snapshot,
+                        self.frosted,
+                        &self.outputs,
+                        self.visible,
+                    ) {
+                        config_changed = true;
+                        tasks.push(task);
                     }
                 }
+                if config_changed {
+                    tasks.push(self.sync_instances());
+                    self.apply_shared_config(&previous_shared);
+                }
 
                 let frosted = frosted_enabled();
-                let surface_height = desired_surface_height_with_animation(
-                    &self.config,
-                    &self.snapshot,
-                    self.expanded_notification.as_ref(),
-                    self.expanded_notification_group.as_deref(),
-                    self.notification_expansion.progress,
-                    self.notification_group_expansion.progress,
-                );
-                let size_changed = surface_height != self.surface_height;
                 let frosted_changed = frosted != self.frosted;
-                let animations_active = self.animations_active();
-
                 self.frosted = frosted;
-                if size_changed {
-                    self.surface_height = surface_height;
-                    if !animations_active {
-                        tasks.push(set_surface_regions(
-                            self.surface_id,
-                            surface_height,
+                for overlay in &mut self.overlays {
+                    let surface_height = overlay.animated_surface_height(&self.snapshot);
+                    let size_changed = surface_height != overlay.surface_height;
+                    if size_changed {
+                        overlay.surface_height = surface_height;
+                        if !overlay.animations_active() {
+                            tasks.push(overlay.set_regions(surface_height, frosted));
+                        }
+                    }
+                    if frosted_changed && !size_changed {
+                        tasks.push(set_surface_blur(
+                            overlay.surface_id,
                             frosted,
+                            surface_height,
                         ));
                     }
                 }
-                if frosted_changed && !size_changed {
-                    tasks.push(set_surface_blur(self.surface_id, frosted, surface_height));
-                }
+                self.publish_service_state();
             }
             Message::AnimationTick => {
                 let now = Instant::now();
-                let was_animating = self.animations_active();
-                self.notification_expansion.advance(now);
-                self.notification_group_expansion.advance(now);
-                self.notification_scroll.advance(now);
                 let mut completed_dismissals = Vec::new();
-                self.dismissing_notifications.retain_mut(|dismissal| {
-                    dismissal.animation.advance(now);
-                    let completed =
-                        !dismissal.animation.is_animating() && dismissal.animation.target == 1.0;
-                    if completed {
-                        completed_dismissals
-                            .push((dismissal.key.clone(), dismissal.source.clone()));
+                let mut finished_overlays = Vec::new();
+                for overlay in &mut self.overlays {
+                    let was_animating = overlay.animations_active();
+                    overlay.notification_expansion.advance(now);
+                    overlay.notification_group_expansion.advance(now);
+                    overlay.notification_scroll.advance(now);
+                    overlay.dismissing_notifications.retain_mut(|dismissal| {
+                        dismissal.animation.advance(now);
+                        let completed = !dismissal.animation.is_animating()
+                            && dismissal.animation.target == 1.0;
+                        if completed {
+                            completed_dismissals
+                                .push((dismissal.key.clone(), dismissal.source.clone()));
+                        }
+                        !completed
+                    });
+                    if was_animating {
+                        finished_overlays.push(overlay.surface_id);
                     }
-                    !completed
-                });
+                }
 
                 for (key, source) in completed_dismissals {
                     self.sampler
@@ -529,80 +883,248 @@ impl App {
                     self.snapshot
                         .notifications
                         .retain(|notification| !key.matches(notification));
-                    if self.expanded_notification.as_ref() == Some(&key) {
-                        self.expanded_notification = None;
-                        self.notification_expansion.reset();
+                    for overlay in &mut self.overlays {
+                        overlay.forget_notification(&key, &source, &self.snapshot);
+                    }
+                }
+
+                for overlay in &mut self.overlays {
+                    if overlay.notification_expansion.is_collapsed() {
+                        overlay.expanded_notification = None;
+                    }
+                    if overlay.notification_group_expansion.is_collapsed() {
+                        overlay.expanded_notification_group = None;
                     }
-                    if self.expanded_notification_group.as_deref() == Some(&source)
-                        && notification_group_size(&self.snapshot, &source) < 2
+
+                    overlay.surface_height = overlay.animated_surface_height(&self.snapshot);
+
+                    if finished_overlays.contains(&overlay.surface_id)
+                        && !overlay.animations_active()
                     {
-                        self.expanded_notification_group = None;
-                        self.notification_group_expansion.reset();
+                        tasks.push(overlay.set_regions(overlay.surface_height, self.frosted));
                     }
                 }
-
-                if self.notification_expansion.is_collapsed() {
-                    self.expanded_notification = None;
+            }
+            Message::Output(event, output) => {
+                if self.outputs.apply(event, output) {
+                    log::debug!(
+                        "Connected outputs: {}",
+                        self.outputs
+                            .iter()
+                            .map(|output| format!("{} ({})", output.name, output.description))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    for overlay in &mut self.overlays {
+                        if self.visible && overlay.needs_output_change(&self.outputs) {
+                            tasks.push(overlay.replace_surface(
+                                &self.snapshot,
+                                self.frosted,
+                                &self.outputs,
+                            ));
+                        }
+                    }
                 }
-                if self.notification_group_expansion.is_collapsed() {
-                    self.expanded_notification_group = None;
+            }
+            Message::Surface(id, message) => {
+                if let Some(index) = self
+                    .overlays
+                    .iter()
+                    .position(|overlay| overlay.surface_id == id)
+                {
+                    tasks.push(self.update_overlay(index, *message));
                 }
+            }
+            Message::ClearNotifications => {
+                self.sampler.clear_notifications();
+                self.snapshot.notifications.clear();
+                for overlay in &mut self.overlays {
+                    overlay.clear_notification_state();
+                    let target = overlay.target_surface_height(&self.snapshot);
+                    if target != overlay.surface_height {
+                        overlay.surface_height = target;
+                        tasks.push(overlay.set_regions(target, self.frosted));
+                    }
+                }
+            }
+            Message::PreviousMedia => {
+                self.clear_media_seek_previews();
+                self.pending_playback = None;
+                self.sampler.previous_media();
+            }
+            Message::PlayPauseMedia => {
+                self.pending_playback = self.snapshot.media.current_player().map(|(id, info)| {
+                    let status = match info.status {
+                        PlaybackStatus::Playing => PlaybackStatus::Paused,
+                        PlaybackStatus::Paused | PlaybackStatus::Stopped => PlaybackStatus::Playing,
+                    };
 
-                self.surface_height = desired_surface_height_with_animation(
-                    &self.config,
-                    &self.snapshot,
-                    self.expanded_notification.as_ref(),
-                    self.expanded_notification_group.as_deref(),
-                    self.notification_expansion.progress,
-                    self.notification_group_expansion.progress,
+                    PendingPlayback {
+                        player_id: id.clone(),
+                        status,
+                        expires_at: Instant::now() + MEDIA_CONTROL_GRACE,
+                    }
+                });
+                self.sampler.play_pause_media();
+                self.snapshot.media = self.sampler.media_state();
+                reconcile_media_state(
+                    &mut self.snapshot.media,
+                    &mut self.pending_playback,
+                    Instant::now(),
                 );
+            }
+            Message::NextMedia => {
+                self.clear_media_seek_previews();
+                self.pending_playback = None;
+                self.sampler.next_media();
+            }
+            Message::SelectMediaPlayer(player_id) => {
+                self.clear_media_seek_previews();
+                for overlay in &mut self.overlays {
+                    overlay.media_timeline_hovered = false;
+                }
+                self.pending_playback = None;
+                self.sampler.select_media_player(&player_id);
+                self.snapshot.media = self.sampler.media_state();
+                reconcile_media_state(
+                    &mut self.snapshot.media,
+                    &mut self.pending_playback,
+                    Instant::now(),
+                );
+            }
+            Message::SetOverlayVisible(visible) => {
+                if visible == self.visible {
+                    return Task::none();
+                }
+                self.visible = visible;
+                for overlay in &mut self.overlays {
+                    overlay.overlay_drag_cursor = None;
+                }
+                self.publish_service_state();
+                if !visible {
+                    return Task::batch(
+                        self.overlays
+                            .iter()
+                            .map(|overlay| layer_surface::destroy_layer_surface(overlay.surface_id))
+                            .collect::<Vec<_>>(),
+                    );
+                }
 
-                if was_animating && !self.animations_active() {
-                    tasks.push(set_surface_regions(
-                        self.surface_id,
-                        self.surface_height,
-                        self.frosted,
-                    ));
+                let (snapshot, frosted, outputs) = (&self.snapshot, self.frosted, &self.outputs);
+                return Task::batch(
+                    self.overlays
+                        .iter_mut()
+                        .map(|overlay| overlay.remap(snapshot, frosted, outputs))
+                        .collect::<Vec<_>>(),
+                );
+            }
+            Message::ToggleOverlay => {
+                return self.update(Message::SetOverlayVisible(!self.visible));
+            }
+            Message::EnterEditMode => {
+                for overlay in &mut self.overlays {
+                    if !overlay.config.widget_movable {
+                        overlay.config.widget_movable = true;
+                        overlay.save_config("the overlay edit mode");
+                    }
                 }
+                tasks.push(self.update(Message::SetOverlayVisible(true)));
+                self.publish_service_state();
             }
-            Message::ClearNotifications => {
-                self.sampler.clear_notifications();
-                self.snapshot.notifications.clear();
-                self.expanded_notification_group = None;
-                self.expanded_notification = None;
-                self.notification_group_expansion.reset();
-                self.notification_expansion.reset();
-                self.dismissing_notifications.clear();
-                self.notification_scroll = ScrollAnimation::default();
-                let target = desired_surface_height(&self.config, &self.snapshot);
-                if target != self.surface_height {
-                    self.surface_height = target;
-                    tasks.push(set_surface_regions(self.surface_id, target, self.frosted));
+            Message::Quit => return iced::exit(),
+            // Interaction with one surface arrives wrapped in `Surface`.
+            Message::ToggleNotificationGroup { .. }
+            | Message::ToggleNotification { .. }
+            | Message::DismissNotification { .. }
+            | Message::NotificationScrolled(_)
+            | Message::MediaTimelineHoverChanged(_)
+            | Message::MediaSeekChanged(_)
+            | Message::CommitMediaSeek
+            | Message::OverlayPointerMoved(_)
+            | Message::BeginOverlayDrag
+            | Message::EndOverlayDrag
+            | Message::PinOverlay
+            | Message::ToggleCpuDetails
+            | Message::ToggleGpuProcesses
+            | Message::ConfirmProcessSignal(_)
+            | Message::SignalProcess { .. }
+            | Message::CancelProcessSignal => {}
+        }
+
+        // Surface commands would target a destroyed surface while hidden; the
+        // next surface is created from the current state when shown again.
+        if !self.visible {
+            return Task::none();
+        }
+
+        Task::batch(tasks)
+    }
+
+    /// Handle a message from the view of `self.overlays[index]`.
+    fn update_overlay(&mut self, index: usize, message: Message) -> Task<Message> {
+        let overlay = &mut self.overlays[index];
+
+        match message {
+            Message::ToggleCpuDetails => {
+                overlay.cpu_details_expanded = !overlay.cpu_details_expanded;
+                overlay.surface_height = overlay.target_surface_height(&self.snapshot);
+                overlay.set_regions(overlay.surface_height, self.frosted)
+            }
+            Message::ToggleGpuProcesses => {
+                overlay.gpu_processes_expanded = !overlay.gpu_processes_expanded;
+                overlay.surface_height = overlay.target_surface_height(&self.snapshot);
+                overlay.set_regions(overlay.surface_height, self.frosted)
+            }
+            Message::ConfirmProcessSignal(mut target) => {
+                // A process that exited since the sample has nothing to end.
+                target.start_time = process_start_time(target.pid, &target.name);
+                overlay.pending_process_signal = target.start_time.is_some().then_some(target);
+                overlay.surface_height = overlay.target_surface_height(&self.snapshot);
+                overlay.set_regions(overlay.surface_height, self.frosted)
+            }
+            Message::SignalProcess { pid, force } => {
+                // Only a process the user confirmed is signalled.
+                if let Some(target) = overlay
+                    .pending_process_signal
+                    .take()
+                    .filter(|target| target.pid == pid)
+                {
+                    signal_process(&target, force);
                 }
+                overlay.surface_height = overlay.target_surface_height(&self.snapshot);
+                overlay.set_regions(overlay.surface_height, self.frosted)
+            }
+            Message::CancelProcessSignal => {
+                overlay.pending_process_signal = None;
+                overlay.surface_height = overlay.target_surface_height(&self.snapshot);
+                overlay.set_regions(overlay.surface_height, self.frosted)
             }
             Message::ToggleNotificationGroup { source } => {
                 let now = Instant::now();
-                let current_scroll_offset = self.notification_scroll.target;
-                self.notification_scroll.snap_to(current_scroll_offset);
-                if self.expanded_notification_group.as_deref() == Some(&source) {
-                    let target = if self.notification_group_expansion.target > 0.0 {
+                let current_scroll_offset = overlay.notification_scroll.target;
+                overlay.notification_scroll.snap_to(current_scroll_offset);
+                if overlay.expanded_notification_group.as_deref() == Some(&source) {
+                    let target = if overlay.notification_group_expansion.target > 0.0 {
                         0.0
                     } else {
                         1.0
                     };
-                    self.notification_group_expansion.transition_to(target, now);
+                    overlay
+                        .notification_group_expansion
+                        .transition_to(target, now);
                 } else {
-                    self.expanded_notification_group = Some(source);
-                    self.notification_group_expansion.reset();
-                    self.notification_group_expansion.transition_to(1.0, now);
+                    overlay.expanded_notification_group = Some(source);
+                    overlay.notification_group_expansion.reset();
+                    overlay.notification_group_expansion.transition_to(1.0, now);
                 }
-                self.expanded_notification = None;
-                self.notification_expansion.reset();
-                tasks.push(set_surface_regions(
-                    self.surface_id,
-                    self.surface_height.max(self.target_surface_height()),
+                overlay.expanded_notification = None;
+                overlay.notification_expansion.reset();
+                overlay.set_regions(
+                    overlay
+                        .surface_height
+                        .max(overlay.target_surface_height(&self.snapshot)),
                     self.frosted,
-                ));
+                )
             }
             Message::ToggleNotification {
                 app_name,
@@ -613,23 +1135,24 @@ impl App {
                     timestamp,
                 };
                 let now = Instant::now();
-                if self.expanded_notification.as_ref() == Some(&selected) {
-                    let target = if self.notification_expansion.target > 0.0 {
+                if overlay.expanded_notification.as_ref() == Some(&selected) {
+                    let target = if overlay.notification_expansion.target > 0.0 {
                         0.0
                     } else {
                         1.0
                     };
-                    self.notification_expansion.transition_to(target, now);
+                    overlay.notification_expansion.transition_to(target, now);
                 } else {
-                    self.expanded_notification = Some(selected);
-                    self.notification_expansion.reset();
-                    self.notification_expansion.transition_to(1.0, now);
+                    overlay.expanded_notification = Some(selected);
+                    overlay.notification_expansion.reset();
+                    overlay.notification_expansion.transition_to(1.0, now);
                 }
-                tasks.push(set_surface_regions(
-                    self.surface_id,
-                    self.surface_height.max(self.target_surface_height()),
+                overlay.set_regions(
+                    overlay
+                        .surface_height
+                        .max(overlay.target_surface_height(&self.snapshot)),
                     self.frosted,
-                ));
+                )
             }
             Message::DismissNotification {
                 app_name,
@@ -639,7 +1162,7 @@ impl App {
                     app_name,
                     timestamp,
                 };
-                if self
+                if overlay
                     .dismissing_notifications
                     .iter()
                     .any(|dismissal| dismissal.key == key)
@@ -658,177 +1181,269 @@ impl App {
                 };
                 let mut animation = ExpansionAnimation::default();
                 animation.transition_to(1.0, Instant::now());
-                self.dismissing_notifications.push(DismissingNotification {
-                    key,
-                    source,
-                    animation,
-                });
+                overlay
+                    .dismissing_notifications
+                    .push(DismissingNotification {
+                        key,
+                        source,
+                        animation,
+                    });
+                Task::none()
             }
             Message::NotificationScrolled(offset) => {
-                if self.notification_group_expansion.is_animating() {
-                    self.notification_scroll.snap_to(offset);
+                if overlay.notification_group_expansion.is_animating() {
+                    overlay.notification_scroll.snap_to(offset);
                 } else {
-                    self.notification_scroll
+                    overlay
+                        .notification_scroll
                         .transition_to(offset, Instant::now());
                 }
-            }
-            Message::PreviousMedia => {
-                self.media_seek_preview = None;
-                self.pending_playback = None;
-                self.sampler.previous_media();
-            }
-            Message::PlayPauseMedia => {
-                self.pending_playback = self.snapshot.media.current_player().map(|(id, info)| {
-                    let status = match info.status {
-                        PlaybackStatus::Playing => PlaybackStatus::Paused,
-                        PlaybackStatus::Paused | PlaybackStatus::Stopped => PlaybackStatus::Playing,
-                    };
-
-                    PendingPlayback {
-                        player_id: id.clone(),
-                        status,
-                        expires_at: Instant::now() + MEDIA_CONTROL_GRACE,
-                    }
-                });
-                self.sampler.play_pause_media();
-                self.snapshot.media = self.sampler.media_state();
-                reconcile_media_state(
-                    &mut self.snapshot.media,
-                    &mut self.pending_playback,
-                    Instant::now(),
-                );
-            }
-            Message::NextMedia => {
-                self.media_seek_preview = None;
-                self.pending_playback = None;
-                self.sampler.next_media();
-            }
-            Message::SelectMediaPlayer(player_id) => {
-                self.media_seek_preview = None;
-                self.media_timeline_hovered = false;
-                self.pending_playback = None;
-                self.sampler.select_media_player(&player_id);
-                self.snapshot.media = self.sampler.media_state();
-                reconcile_media_state(
-                    &mut self.snapshot.media,
-                    &mut self.pending_playback,
-                    Instant::now(),
-                );
+                Task::none()
             }
             Message::MediaTimelineHoverChanged(hovered) => {
-                self.media_timeline_hovered = hovered;
+                overlay.media_timeline_hovered = hovered;
+                Task::none()
             }
             Message::MediaSeekChanged(progress) => {
-                self.media_seek_preview = Some(progress.clamp(0.0, 1.0));
+                overlay.media_seek_preview = Some(progress.clamp(0.0, 1.0));
+                Task::none()
             }
             Message::CommitMediaSeek => {
-                if let Some(progress) = self.media_seek_preview.take() {
+                if let Some(progress) = overlay.media_seek_preview.take() {
                     self.sampler.seek_media(progress);
                     self.snapshot.media = self.sampler.media_state();
                 }
+                Task::none()
             }
             Message::OverlayPointerMoved(position) => {
-                self.overlay_cursor = position;
-                if self.config.widget_movable
-                    && let Some(drag_origin) = self.overlay_drag_cursor
+                overlay.overlay_cursor = position;
+                if overlay.config.widget_movable
+                    && let Some(drag_origin) = overlay.overlay_drag_cursor
                 {
                     let (x, y) = dragged_overlay_position(
-                        self.config.widget_x,
-                        self.config.widget_y,
+                        overlay.config.overlay_anchor,
+                        overlay.config.widget_x,
+                        overlay.config.widget_y,
                         drag_origin,
                         position,
                     );
-                    if x != self.config.widget_x || y != self.config.widget_y {
-                        self.config.widget_x = x;
-                        self.config.widget_y = y;
-                        tasks.push(layer_surface::set_margin(self.surface_id, y, 0, 0, x));
+                    if x != overlay.config.widget_x || y != overlay.config.widget_y {
+                        overlay.config.widget_x = x;
+                        overlay.config.widget_y = y;
+                        return overlay.set_margin();
                     }
                 }
+                Task::none()
             }
             Message::BeginOverlayDrag => {
-                if self.config.widget_movable {
-                    self.overlay_drag_cursor = Some(self.overlay_cursor);
+                if overlay.config.widget_movable {
+                    overlay.overlay_drag_cursor = Some(overlay.overlay_cursor);
                 }
+                Task::none()
             }
             Message::EndOverlayDrag => {
-                self.overlay_drag_cursor = None;
+                overlay.overlay_drag_cursor = None;
+                Task::none()
             }
             Message::PinOverlay => {
-                self.overlay_drag_cursor = None;
-                self.config.widget_movable = false;
-                if let Some(handler) = &self.config_handler
-                    && let Err(error) = self.config.write_entry(handler)
-                {
-                    log::error!("Failed to save the pinned overlay position: {error}");
-                }
+                overlay.overlay_drag_cursor = None;
+                overlay.config.widget_movable = false;
+                overlay.save_config("the pinned overlay position");
+                self.publish_service_state();
+                Task::none()
             }
+            message => self.update(message),
         }
-
-        Task::batch(tasks)
     }
 
-    fn view(&self, _window: window::Id) -> cosmic::Element<'_, Message> {
+    fn view(&self, window: window::Id) -> cosmic::Element<'_, Message> {
+        let Some(overlay) = self
+            .overlays
+            .iter()
+            .find(|overlay| overlay.surface_id == window)
+        else {
+            return cosmic::widget::space().into();
+        };
+
         view::widget_view(
-            &self.config,
+            &overlay.config,
+            &overlay.templates,
             self.now,
             &self.snapshot,
-            self.expanded_notification_group.as_deref(),
-            self.expanded_notification.as_ref(),
-            self.notification_group_expansion.progress,
-            self.notification_group_expansion.target > 0.0,
-            self.notification_expansion.progress,
-            &self.dismissing_notifications,
-            self.notification_scroll.translation(),
-            self.surface_height,
-            self.media_seek_preview,
-            self.media_timeline_hovered,
+            overlay.expanded_notification_group.as_deref(),
+            overlay.expanded_notification.as_ref(),
+            overlay.notification_group_expansion.progress,
+            overlay.notification_group_expansion.target > 0.0,
+            overlay.notification_expansion.progress,
+            &overlay.dismissing_notifications,
+            overlay.notification_scroll.translation(),
+            overlay.surface_height,
+            overlay.media_seek_preview,
+            overlay.media_timeline_hovered,
+            overlay.cpu_details_expanded,
+            overlay.gpu_processes_expanded,
+            overlay.pending_process_signal.as_ref(),
         )
+        .map(move |message| Message::Surface(window, Box::new(message)))
     }
 
     fn subscription(&self) -> Subscription<Message> {
         let stats = Subscription::run_with(self.ui_tick_interval(), aligned_tick_stream)
             .map(|_| Message::Tick);
+        let service = Subscription::run_with(self.service_commands.clone(), dbus::command_stream)
+            .map(service_message);
+        let outputs =
+            crate::outputs::events().map(|(event, output)| Message::Output(event, output));
 
         if self.animations_active() {
             Subscription::batch([
                 stats,
+                service,
+                outputs,
                 iced::window::frames().map(|_| Message::AnimationTick),
             ])
         } else {
-            stats
+            Subscription::batch([stats, service, outputs])
         }
     }
 
     fn animations_active(&self) -> bool {
-        self.notification_expansion.is_animating()
-            || self.notification_group_expansion.is_animating()
-            || self
-                .dismissing_notifications
-                .iter()
-                .any(|dismissal| dismissal.animation.is_animating())
-            || self.notification_scroll.is_animating()
+        self.overlays.iter().any(Overlay::animations_active)
     }
 
+    /// Tick at least once per second, and as often as the fastest enabled
+    /// monitor so its readings are shown as soon as they are published.
     fn ui_tick_interval(&self) -> Duration {
-        Duration::from_millis(UPDATE_INTERVAL_MS)
+        let default = Duration::from_millis(UPDATE_INTERVAL_MS);
+        PollIntervals::from_config(self.shared_config())
+            .fastest(self.monitor_selection())
+            .map_or(default, |fastest| fastest.min(default))
     }
 
-    fn target_surface_height(&self) -> u32 {
-        desired_surface_height_with_animation(
-            &self.config,
-            &self.snapshot,
-            self.expanded_notification.as_ref(),
-            self.expanded_notification_group.as_deref(),
-            self.notification_expansion.target,
-            self.notification_group_expansion.target,
-        )
+    fn monitor_selection(&self) -> MonitorSelection {
+        MonitorSelection::from_configs(self.overlays.iter().map(|overlay| &overlay.config))
+            .with_alert_rules(self.alerts.rules())
+    }
+
+    /// Push settings that apply to the whole process to the sampler and the
+    /// metrics exporter after any profile changed.
+    fn apply_shared_config(&mut self, previous: &Config) {
+        self.alerts.set_rules(&self.overlays[0].config.alert_rules);
+        let selection = self.monitor_selection();
+        let show_weather = self
+            .overlays
+            .iter()
+            .any(|overlay| overlay.config.show_weather);
+        let shared = &self.overlays[0].config;
+
+        crate::widget_logging::set_enabled(shared.enable_logging);
+        self.sampler.set_monitor_selection(selection);
+        self.sampler
+            .set_poll_intervals(PollIntervals::from_config(shared));
+        self.sampler
+            .set_weather_config(show_weather, shared.weather_location.clone());
+        self.sampler
+            .set_solaar_enabled(shared.enable_solaar_integration);
+        self.sampler
+            .set_network_settings(NetworkSettings::from_config(shared));
+        self.sampler
+            .set_storage_settings(StorageSettings::from_config(shared));
+        self.sampler.set_commands(active_commands(&self.overlays));
+        if shared.cider_api_token != previous.cider_api_token {
+            self.sampler.set_cider_token(shared.cider_api_token.clone());
+        }
+        let metrics_address = shared.metrics_socket_address();
+        if metrics_address != previous.metrics_socket_address() {
+            // The new listener waits for the old one to release the port.
+            self.metrics_exporter = None;
+            self.metrics_exporter = metrics_address
+                .map(|address| MetricsExporter::spawn(address, self.sampler.reader()));
+        }
+    }
+
+    /// Create and remove overlays to match the configured instance list.
+    fn sync_instances(&mut self) -> Task<Message> {
+        let names = self.shared_config().instance_names();
+        let mut tasks = Vec::new();
+
+        self.overlays.retain(|overlay| {
+            let keep = overlay
+                .instance
+                .as_ref()
+                .is_none_or(|name| names.contains(name));
+            if !keep {
+                tasks.push(layer_surface::destroy_layer_surface(overlay.surface_id));
+            }
+            keep
+        });
+
+        for name in names {
+            if self
+                .overlays
+                .iter()
+                .any(|overlay| overlay.instance.as_ref() == Some(&name))
+            {
+                continue;
+            }
+            let mut overlay = Overlay::new(Some(name), &self.snapshot);
+            if self.visible {
+                tasks.push(overlay.create_surface(self.frosted, &self.outputs));
+            }
+            self.overlays.push(overlay);
+        }
+
+        Task::batch(tasks)
+    }
+
+    fn clear_media_seek_previews(&mut self) {
+        for overlay in &mut self.overlays {
+            overlay.media_seek_preview = None;
+        }
+    }
+
+    fn publish_service_state(&mut self) {
+        let edit_mode = self
+            .overlays
+            .iter()
+            .any(|overlay| overlay.config.widget_movable);
+        self.service
+            .publish(OverlayState::new(&self.snapshot, self.visible, edit_mode));
     }
 }
 
-fn dragged_overlay_position(x: i32, y: i32, previous: Point, current: Point) -> (i32, i32) {
+fn load_config(handler: Option<&cosmic_config::Config>) -> Config {
+    let mut config = handler
+        .map(|handler| Config::get_entry(handler).unwrap_or_else(|(_errors, config)| config))
+        .unwrap_or_default();
+    config.ensure_all_sections();
+    config.migrate_legacy_position();
+    config
+}
+
+fn service_message(command: ServiceCommand) -> Message {
+    match command {
+        ServiceCommand::Show => Message::SetOverlayVisible(true),
+        ServiceCommand::Hide => Message::SetOverlayVisible(false),
+        ServiceCommand::Toggle => Message::ToggleOverlay,
+        ServiceCommand::EnterEditMode => Message::EnterEditMode,
+        ServiceCommand::ClearNotifications => Message::ClearNotifications,
+        ServiceCommand::PlayPauseMedia => Message::PlayPauseMedia,
+        ServiceCommand::NextMedia => Message::NextMedia,
+        ServiceCommand::PreviousMedia => Message::PreviousMedia,
+        ServiceCommand::Quit => Message::Quit,
+    }
+}
+
+fn dragged_overlay_position(
+    anchor: OverlayAnchor,
+    x: i32,
+    y: i32,
+    previous: Point,
+    current: Point,
+) -> (i32, i32) {
     let delta_x = (current.x - previous.x).round() as i32;
     let delta_y = (current.y - previous.y).round() as i32;
-    (x.saturating_add(delta_x), y.saturating_add(delta_y))
+    anchor.dragged_offsets(x, y, delta_x, delta_y)
 }
 
 fn aligned_tick_stream(interval: &Duration) -> impl iced::futures::Stream<Item = ()> + use<> {
@@ -976,6 +1591,60 @@ fn corner_inset(radius: u32, row_from_edge: u32) -> u32 {
     (radius - (radius * radius - distance * distance).sqrt()).ceil() as u32
 }
 
+fn overlay_surface(
+    surface_id: window::Id,
+    config: &Config,
+    surface_height: u32,
+    frosted: bool,
+    output: Option<&WlOutput>,
+) -> Task<Message> {
+    let placement = config.overlay_anchor;
+    let (top, right, bottom, left) = placement.margins(config.widget_x, config.widget_y);
+    let (anchor, height) = if placement.is_bottom() {
+        // The content is drawn from the top of the surface, so a surface
+        // anchored at the bottom is sized to the content and resized with it.
+        (Anchor::BOTTOM, Some(surface_height))
+    } else {
+        // An unspecified size becomes 1x1 for a surface anchored to only
+        // one horizontal and vertical edge in the pinned Iced backend.
+        (Anchor::TOP.union(Anchor::BOTTOM), None)
+    };
+    let anchor = if placement.is_centered() {
+        anchor
+    } else if placement.is_right() {
+        anchor.union(Anchor::RIGHT)
+    } else {
+        anchor.union(Anchor::LEFT)
+    };
+
+    let create_surface = layer_surface::get_layer_surface(SctkLayerSurfaceSettings {
+        id: surface_id,
+        layer: Layer::Bottom,
+        keyboard_interactivity: KeyboardInteractivity::OnDemand,
+        anchor,
+        output: output.map_or(IcedOutput::Active, |output| {
+            IcedOutput::Output(output.clone())
+        }),
+        namespace: "cosmic-widget-iced".to_string(),
+        margin: IcedMargin {
+            top,
+            right,
+            bottom,
+            left,
+        },
+        size: Some((Some(SURFACE_WIDTH), height)),
+        input_zone: Some(vec![surface_region(surface_height)]),
+        exclusive_zone: -1,
+        ..SctkLayerSurfaceSettings::default()
+    });
+
+    if frosted {
+        create_surface.chain(set_surface_blur(surface_id, true, surface_height))
+    } else {
+        create_surface
+    }
+}
+
 fn set_surface_regions(id: window::Id, height: u32, frosted: bool) -> Task<Message> {
     Task::batch([
         set_surface_input_zone(id, height),
@@ -1001,10 +1670,12 @@ fn set_surface_input_zone(id: window::Id, height: u32) -> Task<Message> {
     ))
 }
 
+#[cfg(test)]
 fn desired_surface_height(config: &Config, snapshot: &SystemSnapshot) -> u32 {
     desired_surface_height_with_expansion(config, snapshot, None, None)
 }
 
+#[cfg(test)]
 fn desired_surface_height_with_expansion(
     config: &Config,
     snapshot: &SystemSnapshot,
@@ -1013,6 +1684,7 @@ fn desired_surface_height_with_expansion(
 ) -> u32 {
     desired_surface_height_with_animation(
         config,
+        &view::parse_templates(config),
         snapshot,
         expanded_notification,
         expanded_notification_group,
@@ -1031,6 +1703,7 @@ fn desired_surface_height_with_expansion(
 
 fn desired_surface_height_with_animation(
     config: &Config,
+    templates: &HashMap<u32, Template>,
     snapshot: &SystemSnapshot,
     expanded_notification: Option<&NotificationKey>,
     expanded_notification_group: Option<&str>,
@@ -1038,6 +1711,31 @@ fn desired_surface_height_with_animation(
     group_progress: f32,
 ) -> u32 {
     let mut height = BASE_SURFACE_HEIGHT as f32;
+    height += history_graphs_height(config) as f32;
+    if config.show_gpu {
+        // The base height holds the combined GPU row that per-device rows
+        // replace.
+        let gpus = view::visible_gpus(config, snapshot);
+        if !gpus.is_empty() {
+            height += (GPU_ITEM_HEIGHT * gpus.len() as u32) as f32 - GPU_ROW_HEIGHT as f32;
+        }
+        if config.show_gpu_details {
+            let rows: usize = gpus
+                .iter()
+                .map(|gpu| view::gpu_detail_rows(gpu).len())
+                .sum();
+            height += (GPU_DETAIL_ROW_HEIGHT * rows as u32) as f32;
+        }
+    }
+    let utilization_visible = config
+        .section_order
+        .iter()
+        .any(|section| matches!(section, WidgetSection::Utilization));
+
+    if utilization_visible && config.show_memory && config.show_memory_details {
+        height +=
+            (MEMORY_DETAIL_ROW_HEIGHT * view::memory_detail_rows(snapshot).len() as u32) as f32;
+    }
     let network_visible = config.show_network
         && config
             .section_order
@@ -1046,6 +1744,19 @@ fn desired_surface_height_with_animation(
 
     if network_visible {
         height += NETWORK_SECTION_HEIGHT as f32;
+        height += (NETWORK_INTERFACE_ROW_HEIGHT
+            * view::visible_network_interfaces(config, snapshot).len() as u32)
+            as f32;
+        if config.show_network_connections {
+            height += (NETWORK_CONNECTION_ROW_HEIGHT
+                * view::network_connection_rows(&snapshot.network_connections).len() as u32)
+                as f32;
+        }
+        if config.show_network_traffic {
+            height += (NETWORK_TRAFFIC_ROW_HEIGHT
+                * view::network_traffic_rows(config, snapshot).len() as u32)
+                as f32;
+        }
     }
 
     let disk_io_visible = config.show_disk
@@ -1056,6 +1767,9 @@ fn desired_surface_height_with_animation(
 
     if disk_io_visible {
         height += DISK_IO_SECTION_HEIGHT as f32;
+        if config.show_disk_devices {
+            height += (DISK_DEVICE_ROW_HEIGHT * snapshot.disk_devices.len() as u32) as f32;
+        }
     }
 
     let storage_visible = config.show_storage
@@ -1065,10 +1779,24 @@ fn desired_surface_height_with_animation(
             .any(|section| matches!(section, WidgetSection::Storage));
 
     if storage_visible {
-        let storage_height = if snapshot.disks.is_empty() {
+        let disks = view::storage_rows(config, snapshot);
+        let health_rows = disks
+            .iter()
+            .filter(|disk| view::disk_drive_health(config, snapshot, disk).is_some())
+            .count() as u32;
+        let pools: Vec<_> = disks
+            .iter()
+            .filter_map(|disk| view::disk_pool_health(config, snapshot, disk))
+            .collect();
+        let degraded_rows = pools.iter().filter(|pool| pool.degraded).count() as u32;
+        let storage_height = if disks.is_empty() {
             EMPTY_STORAGE_HEIGHT
         } else {
-            STORAGE_SECTION_HEIGHT + STORAGE_ITEM_HEIGHT.saturating_mul(snapshot.disks.len() as u32)
+            STORAGE_SECTION_HEIGHT
+                + STORAGE_ITEM_HEIGHT.saturating_mul(disks.len() as u32)
+                + DRIVE_HEALTH_ROW_HEIGHT * health_rows
+                + POOL_HEALTH_ROW_HEIGHT * pools.len() as u32
+                + 2 * u32::from(view::DEGRADED_POOL_PADDING) * degraded_rows
         };
         height += storage_height as f32;
     }
@@ -1127,26 +1855,194 @@ fn desired_surface_height_with_animation(
         height += notifications_height as f32;
     }
 
-    let media_visible = config.show_media
-        && config
-            .section_order
+    let media_visible = config.show_media
+        && config
+            .section_order
+            .iter()
+            .any(|section| matches!(section, WidgetSection::Media));
+
+    if media_visible {
+        let media_height = if snapshot
+            .media
+            .current_player()
+            .is_some_and(|(_, info)| info.is_active())
+        {
+            MEDIA_SECTION_HEIGHT
+        } else {
+            EMPTY_MEDIA_HEIGHT
+        };
+        height += media_height as f32;
+    }
+
+    let commands_visible = config.show_commands
+        && config
+            .section_order
+            .iter()
+            .any(|section| matches!(section, WidgetSection::Commands));
+
+    if commands_visible {
+        let commands_height = if snapshot.commands.is_empty() {
+            EMPTY_COMMANDS_HEIGHT
+        } else {
+            COMMANDS_SECTION_HEIGHT
+                + snapshot
+                    .commands
+                    .iter()
+                    .map(|result| {
+                        let rows = if result.table {
+                            view::command_table(&result.stdout).len() as u32
+                        } else {
+                            0
+                        };
+                        COMMAND_ITEM_HEIGHT + COMMAND_TABLE_ROW_HEIGHT * rows
+                    })
+                    .sum::<u32>()
+        };
+        height += commands_height as f32;
+    }
+
+    let processes_visible = config.show_processes
+        && config
+            .section_order
+            .iter()
+            .any(|section| matches!(section, WidgetSection::Processes));
+
+    if processes_visible {
+        let processes_height = PROCESSES_SECTION_HEIGHT
+            + view::process_lists(config, snapshot)
+                .iter()
+                .map(|(_, list)| {
+                    PROCESS_LIST_HEADING_HEIGHT + PROCESS_ROW_HEIGHT * list.len() as u32
+                })
+                .sum::<u32>();
+        height += processes_height as f32;
+    }
+
+    for section in &config.section_order {
+        if let WidgetSection::Custom(id) = section
+            && let Some(custom) = config.custom_section(*id)
+            && let Some(template) = templates.get(id)
+        {
+            height += custom_section_height(custom, template);
+        }
+    }
+
+    height.round() as u32
+}
+
+/// Send SIGTERM, or SIGKILL if `force` is set, to a process of the top
+/// process list, unless it exited and its PID now names another process.
+fn signal_process(target: &ProcessTarget, force: bool) {
+    // PID 0 and negative PIDs would signal whole process groups.
+    let Some(pid) = libc::pid_t::try_from(target.pid)
+        .ok()
+        .filter(|pid| *pid > 0)
+    else {
+        return;
+    };
+    if target.start_time.is_none()
+        || process_start_time(target.pid, &target.name) != target.start_time
+    {
+        log::debug!("Process {pid} exited before it was signalled");
+        return;
+    }
+    let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+    // SAFETY: kill has no memory safety requirements.
+    if unsafe { libc::kill(pid, signal) } != 0 {
+        log::warn!(
+            "Failed to signal process {pid}: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Start time of a running process whose name starts with its command name,
+/// which the kernel truncates to 15 bytes.
+fn process_start_time(pid: u32, name: &str) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    parse_process_stat(&stat)
+        .filter(|(command, _)| !command.is_empty() && name.starts_with(command))
+        .map(|(_, start_time)| start_time)
+}
+
+/// Command name and start time in clock ticks after boot from
+/// `/proc/<pid>/stat`. The name is in parentheses and may contain spaces
+/// and parentheses itself.
+fn parse_process_stat(stat: &str) -> Option<(&str, u64)> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let command = stat.get(open + 1..close)?;
+    // Fields after the name start with the state, field 3; the start time
+    // is field 22.
+    let start_time = stat.get(close + 1..)?.split_whitespace().nth(19)?;
+    Some((command, start_time.parse().ok()?))
+}
+
+/// Load average row plus the per-core grid, one heading per core type on
+/// hybrid processors.
+fn cpu_details_height(snapshot: &SystemSnapshot) -> u32 {
+    CPU_DETAILS_HEIGHT
+        + view::cpu_core_groups(&snapshot.cpu_cores)
             .iter()
-            .any(|section| matches!(section, WidgetSection::Media));
+            .map(|(heading, cores)| {
+                let rows = cores.len().div_ceil(view::CPU_GRID_COLUMNS) as u32;
+                heading.map_or(0, |_| CPU_CORE_HEADING_HEIGHT) + CPU_CORE_ROW_HEIGHT * rows
+            })
+            .sum::<u32>()
+}
 
-    if media_visible {
-        let media_height = if snapshot
-            .media
-            .current_player()
-            .is_some_and(|(_, info)| info.is_active())
-        {
-            MEDIA_SECTION_HEIGHT
-        } else {
-            EMPTY_MEDIA_HEIGHT
-        };
-        height += media_height as f32;
+/// Heading plus one row per listed process, or for the empty-list note.
+fn gpu_processes_height(config: &Config, snapshot: &SystemSnapshot) -> u32 {
+    let rows = view::visible_gpu_processes(config, snapshot).len().max(1) as u32;
+    GPU_PROCESSES_HEADING_HEIGHT + GPU_PROCESS_ROW_HEIGHT * rows
+}
+
+/// Commands of the main profile while any overlay shows their section.
+fn active_commands(overlays: &[Overlay]) -> Vec<ShellCommand> {
+    if overlays.iter().any(|overlay| overlay.config.show_commands) {
+        overlays[0].config.commands.clone()
+    } else {
+        Vec::new()
     }
+}
 
-    height.round() as u32
+/// Heading plus one line per template line at its largest font size.
+fn custom_section_height(custom: &CustomSection, template: &Template) -> f32 {
+    let lines: f32 = template
+        .line_sizes()
+        .map(|size| {
+            f32::from(size.unwrap_or(DEFAULT_FONT_SIZE)) * TEXT_LINE_HEIGHT
+                + CUSTOM_LINE_SPACING as f32
+        })
+        .sum();
+    let heading = if custom.title.trim().is_empty() {
+        0
+    } else {
+        CUSTOM_HEADING_HEIGHT
+    };
+    (CUSTOM_SECTION_HEIGHT + heading) as f32 + lines
+}
+
+/// Extra height for the history graphs drawn below visible sections.
+fn history_graphs_height(config: &Config) -> u32 {
+    let graph_sections = config
+        .section_order
+        .iter()
+        .filter(|section| {
+            let visible = match section {
+                WidgetSection::Utilization => {
+                    config.show_cpu || config.show_memory || config.show_gpu
+                }
+                WidgetSection::Network => config.show_network,
+                WidgetSection::DiskIo => config.show_disk,
+                WidgetSection::Temperatures => config.show_cpu_temp || config.show_gpu_temp,
+                _ => false,
+            };
+            visible && config.history_graph_style(**section) != HistoryGraphStyle::Off
+        })
+        .count() as u32;
+
+    HISTORY_GRAPH_HEIGHT.saturating_mul(graph_sections)
 }
 
 fn notification_source(notification: &crate::notifications::Notification) -> &str {
@@ -1298,20 +2194,38 @@ fn estimated_wrapped_lines(text: &str, line_width: usize) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::{
-        BASE_SURFACE_HEIGHT, DISK_IO_SECTION_HEIGHT, ExpansionAnimation, NETWORK_SECTION_HEIGHT,
-        NOTIFICATION_EXPANSION_DURATION, NotificationKey, PendingPlayback, SURFACE_WIDTH,
-        ScrollAnimation, UI_TICK_SETTLE_DELAY, delay_until_next_tick, desired_surface_height,
-        desired_surface_height_with_expansion, dragged_overlay_position,
-        notification_viewport_height_with_animation, reconcile_media_state,
-        rounded_surface_regions,
+        BASE_SURFACE_HEIGHT, COMMAND_ITEM_HEIGHT, COMMAND_TABLE_ROW_HEIGHT,
+        COMMANDS_SECTION_HEIGHT, CPU_CORE_HEADING_HEIGHT, CPU_CORE_ROW_HEIGHT, CPU_DETAILS_HEIGHT,
+        DISK_DEVICE_ROW_HEIGHT, DISK_IO_SECTION_HEIGHT, DRIVE_HEALTH_ROW_HEIGHT,
+        EMPTY_COMMANDS_HEIGHT, ExpansionAnimation, GPU_DETAIL_ROW_HEIGHT, GPU_ITEM_HEIGHT,
+        GPU_PROCESS_ROW_HEIGHT, GPU_PROCESSES_HEADING_HEIGHT, GPU_ROW_HEIGHT, HISTORY_GRAPH_HEIGHT,
+        MEMORY_DETAIL_ROW_HEIGHT, NETWORK_CONNECTION_ROW_HEIGHT, NETWORK_INTERFACE_ROW_HEIGHT,
+        NETWORK_SECTION_HEIGHT, NETWORK_TRAFFIC_ROW_HEIGHT, NOTIFICATION_EXPANSION_DURATION,
+        NotificationKey, POOL_HEALTH_ROW_HEIGHT, PROCESS_LIST_HEADING_HEIGHT, PROCESS_ROW_HEIGHT,
+        PROCESSES_SECTION_HEIGHT, PendingPlayback, STORAGE_ITEM_HEIGHT, STORAGE_SECTION_HEIGHT,
+        SURFACE_WIDTH, ScrollAnimation, UI_TICK_SETTLE_DELAY, cpu_details_height,
+        delay_until_next_tick, desired_surface_height, desired_surface_height_with_expansion,
+        dragged_overlay_position, gpu_processes_height,
+        notification_viewport_height_with_animation, parse_process_stat, process_start_time,
+        reconcile_media_state, rounded_surface_regions,
     };
     use crate::battery::BatteryDevice;
-    use crate::config::{Config, WidgetSection};
+    use crate::commands::{CommandResult, CommandStatus};
+    use crate::config::{Config, HistoryGraphStyle, OverlayAnchor, WidgetSection};
+    use crate::disk_io::DiskDevice;
+    use crate::drive_health::DriveHealth;
+    use crate::gpu::{GpuDetails, GpuDevice, GpuFan, GpuVendor};
+    use crate::gpu_processes::GpuProcess;
     use crate::media::{MediaInfo, PlaybackStatus, PlayerId};
+    use crate::network::NetworkInterface;
+    use crate::network_manager::{ActiveConnection, NetworkConnections};
     use crate::notifications::Notification;
+    use crate::pool_health::{PoolHealth, PoolKind};
     use crate::storage::DiskInfo;
+    use crate::utilization::{CoreKind, CpuCore, ProcessUsage, TopProcesses};
     use crate::weather::WeatherData;
     use cosmic::iced::platform_specific::runtime::wayland::CornerRadius;
+    use std::collections::HashMap;
     use std::time::{Duration, Instant};
 
     #[test]
@@ -1355,6 +2269,7 @@ mod tests {
     fn overlay_dragging_uses_the_fixed_grab_point() {
         assert_eq!(
             dragged_overlay_position(
+                OverlayAnchor::TopLeft,
                 7250,
                 50,
                 cosmic::iced::Point::new(40.0, 30.0),
@@ -1367,12 +2282,17 @@ mod tests {
     #[test]
     fn compositor_catch_up_does_not_reverse_the_drag() {
         let origin = cosmic::iced::Point::new(80.0, 40.0);
-        let moved =
-            dragged_overlay_position(7250, 50, origin, cosmic::iced::Point::new(90.0, 45.0));
+        let moved = dragged_overlay_position(
+            OverlayAnchor::TopLeft,
+            7250,
+            50,
+            origin,
+            cosmic::iced::Point::new(90.0, 45.0),
+        );
         assert_eq!(moved, (7260, 55));
 
         assert_eq!(
-            dragged_overlay_position(moved.0, moved.1, origin, origin),
+            dragged_overlay_position(OverlayAnchor::TopLeft, moved.0, moved.1, origin, origin),
             moved
         );
     }
@@ -1385,12 +2305,44 @@ mod tests {
         let mut snapshot = super::SystemSnapshot::default();
 
         let empty_height = desired_surface_height(&config, &snapshot);
-        snapshot.disks = vec![disk(), disk(), disk()];
+        snapshot.storage_mounts = vec![disk(), disk(), disk()];
 
         assert!(empty_height > BASE_SURFACE_HEIGHT);
         assert_eq!(desired_surface_height(&config, &snapshot), 780);
     }
 
+    #[test]
+    fn surface_height_counts_storage_rows_after_rules_and_grouping() {
+        let mut config = Config::default();
+        config.section_order = vec![WidgetSection::Storage];
+        let btrfs = |mount_point: &str| DiskInfo {
+            mount_point: mount_point.to_string(),
+            fs_type: "btrfs".to_string(),
+            source: "/dev/nvme0n1p2".to_string(),
+            ..disk()
+        };
+        let snapshot = super::SystemSnapshot {
+            storage_mounts: vec![
+                btrfs("/"),
+                btrfs("/home"),
+                DiskInfo {
+                    mount_point: "/boot".to_string(),
+                    ..disk()
+                },
+            ],
+            ..Default::default()
+        };
+        let one_row = BASE_SURFACE_HEIGHT + STORAGE_SECTION_HEIGHT + STORAGE_ITEM_HEIGHT;
+
+        assert_eq!(desired_surface_height(&config, &snapshot), one_row);
+
+        config.set_storage_mount_shown("/boot", "ext4", &[], true);
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            one_row + STORAGE_ITEM_HEIGHT
+        );
+    }
+
     #[test]
     fn surface_height_tracks_network_visibility() {
         let mut config = Config::default();
@@ -1404,6 +2356,94 @@ mod tests {
         );
     }
 
+    #[test]
+    fn network_interfaces_add_a_row_per_shown_interface() {
+        let mut config = Config::default();
+        config.show_storage = false;
+        config.show_network = true;
+        config.section_order = vec![WidgetSection::Network];
+        let interface = |name: &str, physical| NetworkInterface {
+            name: name.to_string(),
+            physical,
+            rx_rate: 0.0,
+            tx_rate: 0.0,
+            speed_mbps: None,
+            operstate: "up".to_string(),
+        };
+        let snapshot = super::SystemSnapshot {
+            network_interfaces: vec![
+                interface("docker0", false),
+                interface("enp5s0", true),
+                interface("lo", false),
+                interface("wg0", false),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            BASE_SURFACE_HEIGHT + NETWORK_SECTION_HEIGHT + NETWORK_INTERFACE_ROW_HEIGHT
+        );
+
+        config.network_interfaces_included = vec!["wg0".to_string()];
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            BASE_SURFACE_HEIGHT + NETWORK_SECTION_HEIGHT + 2 * NETWORK_INTERFACE_ROW_HEIGHT
+        );
+    }
+
+    #[test]
+    fn network_connections_add_a_row_per_detail() {
+        let mut config = Config::default();
+        config.show_storage = false;
+        config.show_network = true;
+        config.section_order = vec![WidgetSection::Network];
+        let snapshot = super::SystemSnapshot {
+            network_connections: NetworkConnections {
+                primary: Some(ActiveConnection {
+                    name: "Wired connection 1".to_string(),
+                    ipv4: vec!["10.0.0.2/24".to_string()],
+                    gateway: Some("10.0.0.1".to_string()),
+                    ..ActiveConnection::default()
+                }),
+                vpns: Vec::new(),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            BASE_SURFACE_HEIGHT + NETWORK_SECTION_HEIGHT
+        );
+
+        config.show_network_connections = true;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            BASE_SURFACE_HEIGHT + NETWORK_SECTION_HEIGHT + 3 * NETWORK_CONNECTION_ROW_HEIGHT
+        );
+    }
+
+    #[test]
+    fn network_traffic_adds_a_row_per_period_and_the_quota() {
+        let mut config = Config::default();
+        config.show_storage = false;
+        config.show_network = true;
+        config.show_network_traffic = true;
+        config.section_order = vec![WidgetSection::Network];
+        let mut snapshot = super::SystemSnapshot::default();
+
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            BASE_SURFACE_HEIGHT + NETWORK_SECTION_HEIGHT + 2 * NETWORK_TRAFFIC_ROW_HEIGHT
+        );
+
+        snapshot.network_quota_bytes = 1024;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            BASE_SURFACE_HEIGHT + NETWORK_SECTION_HEIGHT + 3 * NETWORK_TRAFFIC_ROW_HEIGHT
+        );
+    }
+
     #[test]
     fn surface_height_tracks_disk_io_visibility() {
         let mut config = Config::default();
@@ -1417,6 +2457,104 @@ mod tests {
         );
     }
 
+    #[test]
+    fn drive_health_adds_a_row_per_filesystem_with_a_known_drive() {
+        let mut config = Config::default();
+        config.section_order = vec![WidgetSection::Storage];
+        let mut local = disk();
+        local.device = Some("nvme0n1p2".to_string());
+        let snapshot = super::SystemSnapshot {
+            storage_mounts: vec![local, disk()],
+            drive_health: HashMap::from([(
+                "nvme0n1p2".to_string(),
+                DriveHealth {
+                    drive: "nvme0n1".to_string(),
+                    ..DriveHealth::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let storage = BASE_SURFACE_HEIGHT + STORAGE_SECTION_HEIGHT + 2 * STORAGE_ITEM_HEIGHT;
+
+        assert_eq!(desired_surface_height(&config, &snapshot), storage);
+
+        config.show_drive_health = true;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            storage + DRIVE_HEALTH_ROW_HEIGHT
+        );
+    }
+
+    #[test]
+    fn pool_health_adds_a_row_per_filesystem_on_a_pool() {
+        let mut config = Config::default();
+        config.section_order = vec![WidgetSection::Storage];
+        let pool = PoolHealth {
+            kind: PoolKind::Md,
+            name: "md0".to_string(),
+            level: Some("raid1".to_string()),
+            state: Some("active".to_string()),
+            devices: Some((1, 2)),
+            degraded: true,
+            activity: None,
+            errors: None,
+            data: None,
+            metadata: None,
+        };
+        let snapshot = super::SystemSnapshot {
+            storage_mounts: vec![disk()],
+            pool_health: HashMap::from([("/".to_string(), pool)]),
+            ..Default::default()
+        };
+        let storage = BASE_SURFACE_HEIGHT + STORAGE_SECTION_HEIGHT + STORAGE_ITEM_HEIGHT;
+
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            storage + POOL_HEALTH_ROW_HEIGHT + 2 * u32::from(super::view::DEGRADED_POOL_PADDING)
+        );
+
+        config.show_pool_health = false;
+        assert_eq!(desired_surface_height(&config, &snapshot), storage);
+    }
+
+    #[test]
+    fn disk_devices_add_a_row_per_drive() {
+        let mut config = Config::default();
+        config.show_storage = false;
+        config.show_disk = true;
+        config.section_order = vec![WidgetSection::DiskIo];
+        let snapshot = super::SystemSnapshot {
+            disk_devices: vec![DiskDevice::default(), DiskDevice::default()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            BASE_SURFACE_HEIGHT + DISK_IO_SECTION_HEIGHT
+        );
+
+        config.show_disk_devices = true;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            BASE_SURFACE_HEIGHT + DISK_IO_SECTION_HEIGHT + 2 * DISK_DEVICE_ROW_HEIGHT
+        );
+    }
+
+    #[test]
+    fn surface_height_adds_history_graphs_for_visible_sections() {
+        let mut config = Config::default();
+        config.show_storage = false;
+        config.show_network = true;
+        config.network_graph_style = HistoryGraphStyle::Area;
+        config.disk_io_graph_style = HistoryGraphStyle::Sparkline;
+        config.section_order = vec![WidgetSection::Network, WidgetSection::DiskIo];
+
+        assert_eq!(
+            desired_surface_height(&config, &super::SystemSnapshot::default()),
+            BASE_SURFACE_HEIGHT + NETWORK_SECTION_HEIGHT + HISTORY_GRAPH_HEIGHT
+        );
+    }
+
     #[test]
     fn surface_height_tracks_visible_device_rows() {
         let mut config = Config::default();
@@ -1637,6 +2775,270 @@ mod tests {
         assert_eq!(desired_surface_height(&config, &snapshot), 804);
     }
 
+    #[test]
+    fn surface_height_grows_with_custom_template_lines() {
+        let mut config = Config::default();
+        config.show_storage = false;
+        let snapshot = super::SystemSnapshot::default();
+        let base_height = desired_surface_height(&config, &snapshot);
+
+        let custom = config.add_custom_section();
+        custom.template = "{cpu}\n{mem}".to_string();
+        // Heading and two 14 px lines.
+        assert_eq!(desired_surface_height(&config, &snapshot), base_height + 85);
+
+        config.custom_sections[0].template = "{size 30}{time}".to_string();
+        assert_eq!(desired_surface_height(&config, &snapshot), base_height + 84);
+    }
+
+    #[test]
+    fn surface_height_grows_with_command_output() {
+        let mut config = Config::default();
+        config.show_storage = false;
+        let mut snapshot = super::SystemSnapshot::default();
+        let base_height = desired_surface_height(&config, &snapshot);
+
+        config.show_commands = true;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            base_height + EMPTY_COMMANDS_HEIGHT
+        );
+
+        let line = CommandResult {
+            label: "Uptime".to_string(),
+            command: "uptime -p".to_string(),
+            table: false,
+            stdout: "up 3 days".to_string(),
+            stderr: String::new(),
+            status: CommandStatus::Exited(0),
+            truncated: false,
+            finished_at: 0,
+        };
+        let table = CommandResult {
+            table: true,
+            stdout: "a 1\nb 2\n\nc 3".to_string(),
+            ..line.clone()
+        };
+        snapshot.commands = vec![line, table];
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            base_height
+                + COMMANDS_SECTION_HEIGHT
+                + 2 * COMMAND_ITEM_HEIGHT
+                + 3 * COMMAND_TABLE_ROW_HEIGHT
+        );
+    }
+
+    #[test]
+    fn each_shown_gpu_gets_its_own_row() {
+        let mut config = Config {
+            show_gpu: true,
+            ..Config::default()
+        };
+        let mut snapshot = super::SystemSnapshot::default();
+        let combined_height = desired_surface_height(&config, &snapshot);
+
+        let gpu = |id: &str, vendor| GpuDevice {
+            id: id.to_string(),
+            name: format!("GPU {id}"),
+            vendor,
+            usage: Some(10.0),
+            temperature: Some(50.0),
+            details: GpuDetails::default(),
+        };
+        snapshot.gpus = vec![
+            gpu("0000:00:02.0", GpuVendor::Intel),
+            gpu("0000:01:00.0", GpuVendor::Nvidia),
+        ];
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            combined_height + 2 * GPU_ITEM_HEIGHT - GPU_ROW_HEIGHT
+        );
+
+        config.hidden_gpus = vec!["0000:00:02.0".to_string()];
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            combined_height + GPU_ITEM_HEIGHT - GPU_ROW_HEIGHT
+        );
+    }
+
+    #[test]
+    fn gpu_details_add_a_row_per_reported_reading() {
+        let mut config = Config {
+            show_gpu: true,
+            ..Config::default()
+        };
+        let mut snapshot = super::SystemSnapshot::default();
+        snapshot.gpus = vec![GpuDevice {
+            id: "0000:03:00.0".to_string(),
+            name: "AMD GPU (card1)".to_string(),
+            vendor: GpuVendor::Amd,
+            usage: Some(30.0),
+            temperature: None,
+            details: GpuDetails {
+                vram_used: Some(4 << 30),
+                vram_total: Some(16 << 30),
+                power_watts: Some(120.0),
+                fan: Some(GpuFan::Rpm(1400)),
+                ..GpuDetails::default()
+            },
+        }];
+        let rows_height = desired_surface_height(&config, &snapshot);
+
+        config.show_gpu_details = true;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            rows_height + 3 * GPU_DETAIL_ROW_HEIGHT
+        );
+    }
+
+    #[test]
+    fn gpu_processes_on_hidden_gpus_are_left_out() {
+        let mut config = Config {
+            show_gpu: true,
+            show_gpu_processes: true,
+            ..Config::default()
+        };
+        let mut snapshot = super::SystemSnapshot::default();
+        assert_eq!(
+            gpu_processes_height(&config, &snapshot),
+            GPU_PROCESSES_HEADING_HEIGHT + GPU_PROCESS_ROW_HEIGHT
+        );
+
+        let process = |pid, gpu_id: &str| GpuProcess {
+            pid,
+            name: "firefox".to_string(),
+            gpu_id: gpu_id.to_string(),
+            usage: Some(12.0),
+            vram: Some(256 << 20),
+        };
+        snapshot.gpu_processes = vec![
+            process(100, "0000:00:02.0"),
+            process(200, "0000:03:00.0"),
+            process(300, "0000:03:00.0"),
+        ];
+        assert_eq!(
+            gpu_processes_height(&config, &snapshot),
+            GPU_PROCESSES_HEADING_HEIGHT + 3 * GPU_PROCESS_ROW_HEIGHT
+        );
+
+        config.hidden_gpus = vec!["0000:03:00.0".to_string()];
+        assert_eq!(
+            gpu_processes_height(&config, &snapshot),
+            GPU_PROCESSES_HEADING_HEIGHT + GPU_PROCESS_ROW_HEIGHT
+        );
+    }
+
+    #[test]
+    fn top_processes_add_rows_up_to_the_configured_count() {
+        let mut config = Config {
+            process_count: 3,
+            ..Config::default()
+        };
+        let mut snapshot = super::SystemSnapshot::default();
+        let base_height = desired_surface_height(&config, &snapshot);
+
+        config.show_processes = true;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            base_height + PROCESSES_SECTION_HEIGHT + 2 * PROCESS_LIST_HEADING_HEIGHT
+        );
+
+        let process = |pid| ProcessUsage {
+            pid,
+            name: format!("process-{pid}"),
+            cpu_usage: 1.0,
+            memory: 1 << 20,
+            count: 1,
+        };
+        snapshot.top_processes = TopProcesses {
+            by_cpu: (1..=5).map(process).collect(),
+            by_memory: (1..=2).map(process).collect(),
+        };
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            base_height
+                + PROCESSES_SECTION_HEIGHT
+                + 2 * PROCESS_LIST_HEADING_HEIGHT
+                + 5 * PROCESS_ROW_HEIGHT
+        );
+
+        // Grouped trees are ranked separately and not sampled yet here.
+        config.group_process_trees = true;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            base_height + PROCESSES_SECTION_HEIGHT + 2 * PROCESS_LIST_HEADING_HEIGHT
+        );
+    }
+
+    #[test]
+    fn memory_details_add_a_row_per_reading() {
+        let mut config = Config::default();
+        let mut snapshot = super::SystemSnapshot::default();
+        let base_height = desired_surface_height(&config, &snapshot);
+
+        config.show_memory_details = true;
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            base_height + 3 * MEMORY_DETAIL_ROW_HEIGHT
+        );
+
+        snapshot.memory_details.pressure = Some(Default::default());
+        assert_eq!(
+            desired_surface_height(&config, &snapshot),
+            base_height + 5 * MEMORY_DETAIL_ROW_HEIGHT
+        );
+
+        config
+            .section_order
+            .retain(|section| !matches!(section, WidgetSection::Utilization));
+        let hidden_height = desired_surface_height(&config, &snapshot);
+        config.show_memory_details = false;
+        assert_eq!(desired_surface_height(&config, &snapshot), hidden_height);
+    }
+
+    #[test]
+    fn process_stat_reads_names_with_spaces_and_parentheses() {
+        let stat = "4242 (Web (Content)) S 4000 4000 4000 0 -1 4194560 6853 0 0 0 \
+                    120 35 0 0 20 0 31 0 987654 2950000000 61000";
+        assert_eq!(parse_process_stat(stat), Some(("Web (Content)", 987_654)));
+        assert_eq!(parse_process_stat("4242 (bash) S 1 2"), None);
+    }
+
+    #[test]
+    fn start_time_requires_a_matching_name() {
+        let pid = std::process::id();
+        let name = std::fs::read_to_string("/proc/self/comm").unwrap();
+        let name = name.trim_end();
+
+        assert!(process_start_time(pid, &format!("{name} with a longer name")).is_some());
+        assert_eq!(process_start_time(pid, "another process"), None);
+    }
+
+    #[test]
+    fn cpu_details_group_hybrid_cores() {
+        let core = |id, kind| CpuCore {
+            id,
+            kind,
+            ..CpuCore::default()
+        };
+        let mut snapshot = super::SystemSnapshot::default();
+        snapshot.cpu_cores = (0..4).map(|id| core(id, None)).collect();
+        assert_eq!(
+            cpu_details_height(&snapshot),
+            CPU_DETAILS_HEIGHT + 2 * CPU_CORE_ROW_HEIGHT
+        );
+
+        snapshot.cpu_cores = (0..4)
+            .map(|id| core(id, Some(CoreKind::Performance)))
+            .chain((4..8).map(|id| core(id, Some(CoreKind::Efficiency))))
+            .collect();
+        assert_eq!(
+            cpu_details_height(&snapshot),
+            CPU_DETAILS_HEIGHT + 2 * CPU_CORE_HEADING_HEIGHT + 4 * CPU_CORE_ROW_HEIGHT
+        );
+    }
+
     #[test]
     fn media_reconciliation_preserves_immediate_user_choices() {
         let firefox = PlayerId::Mpris("org.mpris.MediaPlayer2.firefox".to_string());
@@ -1691,6 +3093,10 @@ mod tests {
             total_space: 1_000,
             available_space: 500,
             is_loading: false,
+            device: None,
+            fs_type: "ext4".to_string(),
+            source: String::new(),
+            subvolumes: Vec::new(),
         }
     }
 
@@ -1,79 +1,395 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use super::history::SnapshotHistory;
 use crate::battery::{BatteryDevice, BatteryMonitor};
-use crate::config::UPDATE_INTERVAL_MS;
-use crate::disk_io::DiskIoMonitor;
+use crate::commands::{CommandMonitor, CommandResult};
+use crate::config::{
+    AlertMetric, AlertRule, Config, PolledMonitor, ShellCommand, StorageLabel, StorageRule,
+    StorageSort, UPDATE_INTERVAL_MS, WidgetSection, network_interface_counted,
+};
+use crate::disk_io::{DiskDevice, DiskIoMonitor};
+use crate::drive_health::{DriveHealth, DriveHealthMonitor};
+use crate::gpu::GpuDevice;
+use crate::gpu_processes::GpuProcess;
 use crate::media::{MediaMonitor, MultiPlayerState, PlayerId};
-use crate::network::NetworkMonitor;
+use crate::network::{NetworkInterface, NetworkMonitor};
+use crate::network_manager::{ConnectionMonitor, NetworkConnections};
 use crate::notifications::{Notification, NotificationMonitor};
-use crate::storage::{DiskInfo, StorageMonitor};
+use crate::pool_health::{PoolHealth, PoolHealthMonitor};
+use crate::storage::{DiskInfo, StorageMonitor, arrange_disks};
 use crate::temperature::TemperatureMonitor;
-use crate::utilization::UtilizationMonitor;
+use crate::template::Template;
+use crate::traffic::{TrafficLedger, TrafficSummary};
+use crate::utilization::{CpuCore, LoadAverage, MemoryDetails, TopProcesses, UtilizationMonitor};
 use crate::weather::{WeatherData, WeatherMonitor};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct SystemSnapshot {
     pub cpu_usage: f32,
+    pub cpu_cores: Vec<CpuCore>,
+    pub load_average: LoadAverage,
     pub memory_usage: f32,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    pub memory_details: MemoryDetails,
     pub gpu_usage: f32,
+    pub gpus: Vec<GpuDevice>,
+    pub gpu_processes: Vec<GpuProcess>,
+    /// Download and upload rates of the interfaces counted by the main
+    /// profile's interface filter.
     pub network_rx_rate: f64,
     pub network_tx_rate: f64,
+    pub network_interfaces: Vec<NetworkInterface>,
+    pub network_connections: NetworkConnections,
+    pub network_traffic: TrafficSummary,
+    /// Billing cycle traffic of the interfaces counted by the main profile,
+    /// and its quota, 0 without one.
+    pub network_cycle_bytes: u64,
+    pub network_quota_bytes: u64,
     pub disk_read_rate: f64,
     pub disk_write_rate: f64,
+    pub disk_devices: Vec<DiskDevice>,
     pub cpu_temp: f32,
     pub gpu_temp: f32,
+    /// Storage rows of the main profile, arranged by its storage rules.
     pub disks: Vec<DiskInfo>,
+    /// Every candidate mount, which other profiles arrange with their own
+    /// rules
+    pub storage_mounts: Vec<DiskInfo>,
+    /// Health of the drive below each filesystem, by [`DiskInfo::device`]
+    pub drive_health: HashMap<String, DriveHealth>,
+    /// Btrfs, ZFS or md pool below each filesystem, by mount point
+    pub pool_health: HashMap<String, PoolHealth>,
     pub devices: Vec<BatteryDevice>,
     pub weather: Option<WeatherData>,
     pub notifications: Vec<Notification>,
     pub media: MultiPlayerState,
+    pub commands: Vec<CommandResult>,
+    pub top_processes: TopProcesses,
+    pub top_process_trees: TopProcesses,
+    /// Shared with earlier snapshots until the sampler records into it
+    #[serde(skip)]
+    pub history: Arc<SnapshotHistory>,
+}
+
+/// Which device monitors the sampler keeps alive.
+///
+/// Derived from the section toggles in [`Config`]. A monitor that is not
+/// selected is dropped together with its background thread, and its snapshot
+/// fields fall back to their defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MonitorSelection {
+    pub utilization: bool,
+    pub gpu: bool,
+    /// Sample per-process GPU use, which scans the fdinfo of every process.
+    pub gpu_processes: bool,
+    /// Rank processes by CPU and memory, which refreshes every process.
+    pub processes: bool,
+    pub network: bool,
+    /// Listen to NetworkManager for connection details.
+    pub network_connections: bool,
+    pub disk_io: bool,
+    pub temperatures: bool,
+    pub storage: bool,
+    /// Read drive temperatures and SMART data of the mounted filesystems.
+    pub drive_health: bool,
+    /// Read Btrfs, ZFS and md state of the mounted filesystems.
+    pub pool_health: bool,
+    pub battery: bool,
+}
+
+impl MonitorSelection {
+    pub fn from_config(config: &Config) -> Self {
+        let selection = Self {
+            utilization: config.show_cpu
+                || config.show_memory
+                || config.show_gpu
+                || config.show_processes,
+            gpu: config.show_gpu,
+            gpu_processes: config.show_gpu && config.show_gpu_processes,
+            processes: config.show_processes,
+            network: config.show_network,
+            network_connections: config.show_network && config.show_network_connections,
+            disk_io: config.show_disk,
+            temperatures: config.show_cpu_temp || config.show_gpu_temp,
+            storage: config.show_storage,
+            drive_health: config.show_storage && config.show_drive_health,
+            pool_health: config.show_storage && config.show_pool_health,
+            battery: config.show_battery,
+        };
+
+        config
+            .section_order
+            .iter()
+            .filter_map(|section| match section {
+                WidgetSection::Custom(id) => config.custom_section(*id),
+                _ => None,
+            })
+            .fold(selection, |selection, custom| {
+                selection.with_template(&Template::parse(&custom.template))
+            })
+    }
+
+    /// Also keep the monitors read by the variables of a custom section.
+    fn with_template(mut self, template: &Template) -> Self {
+        for variable in template.variables() {
+            match variable {
+                "cpu" | "mem" | "mem_used" | "mem_total" => self.utilization = true,
+                "gpu" => {
+                    self.utilization = true;
+                    self.gpu = true;
+                }
+                "cpu_temp" | "gpu_temp" => self.temperatures = true,
+                "net_down" | "net_up" => self.network = true,
+                "disk_read" | "disk_write" => self.disk_io = true,
+                "disk" | "disk_free" => self.storage = true,
+                "battery" => self.battery = true,
+                _ => {}
+            }
+        }
+        self
+    }
+
+    /// Monitors needed by any of the overlays drawn from `configs`.
+    pub fn from_configs<'a>(configs: impl IntoIterator<Item = &'a Config>) -> Self {
+        configs
+            .into_iter()
+            .map(Self::from_config)
+            .fold(Self::default(), |selection, other| Self {
+                utilization: selection.utilization || other.utilization,
+                gpu: selection.gpu || other.gpu,
+                gpu_processes: selection.gpu_processes || other.gpu_processes,
+                processes: selection.processes || other.processes,
+                network: selection.network || other.network,
+                network_connections: selection.network_connections || other.network_connections,
+                disk_io: selection.disk_io || other.disk_io,
+                temperatures: selection.temperatures || other.temperatures,
+                storage: selection.storage || other.storage,
+                drive_health: selection.drive_health || other.drive_health,
+                pool_health: selection.pool_health || other.pool_health,
+                battery: selection.battery || other.battery,
+            })
+    }
+
+    /// Also keep the monitors that enabled alert rules read from, even if no
+    /// overlay shows their sections.
+    pub fn with_alert_rules(mut self, rules: &[AlertRule]) -> Self {
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            match rule.metric {
+                AlertMetric::CpuUsage | AlertMetric::MemoryUsage => self.utilization = true,
+                AlertMetric::GpuUsage => {
+                    self.utilization = true;
+                    self.gpu = true;
+                }
+                AlertMetric::CpuTemperature | AlertMetric::GpuTemperature => {
+                    self.temperatures = true;
+                }
+                AlertMetric::StorageUsage => self.storage = true,
+                AlertMetric::DeviceBattery => self.battery = true,
+                AlertMetric::NetworkQuota => self.network = true,
+            }
+        }
+        self
+    }
+
+    pub fn enabled(&self, monitor: PolledMonitor) -> bool {
+        match monitor {
+            PolledMonitor::Utilization => self.utilization,
+            PolledMonitor::Network => self.network,
+            PolledMonitor::DiskIo => self.disk_io,
+            PolledMonitor::Temperatures => self.temperatures,
+            PolledMonitor::Storage => self.storage,
+            PolledMonitor::Battery => self.battery,
+        }
+    }
+}
+
+/// Polling cadence of each device monitor, read from [`Config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollIntervals {
+    intervals: [Duration; PolledMonitor::ALL.len()],
+}
+
+impl PollIntervals {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            intervals: PolledMonitor::ALL
+                .map(|monitor| Duration::from_millis(config.poll_interval_ms(monitor))),
+        }
+    }
+
+    pub fn get(&self, monitor: PolledMonitor) -> Duration {
+        self.intervals[monitor as usize]
+    }
+
+    /// Shortest interval among the selected monitors.
+    pub fn fastest(&self, selection: MonitorSelection) -> Option<Duration> {
+        PolledMonitor::ALL
+            .into_iter()
+            .filter(|monitor| selection.enabled(*monitor))
+            .map(|monitor| self.get(monitor))
+            .min()
+    }
+}
+
+impl Default for PollIntervals {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+/// Network settings of the main profile, read from [`Config`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkSettings {
+    /// Included and excluded interfaces of the network totals
+    pub included: Vec<String>,
+    pub excluded: Vec<String>,
+    pub billing_day: u32,
+    pub quota_bytes: u64,
+}
+
+impl NetworkSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            included: config.network_interfaces_included.clone(),
+            excluded: config.network_interfaces_excluded.clone(),
+            billing_day: config.billing_day(),
+            quota_bytes: config.network_quota_bytes(),
+        }
+    }
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+/// Storage rows of the main profile, read from [`Config`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageSettings {
+    pub rules: Vec<StorageRule>,
+    pub labels: Vec<StorageLabel>,
+    pub sort: StorageSort,
+}
+
+impl StorageSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            rules: config.storage_rules.clone(),
+            labels: config.storage_labels.clone(),
+            sort: config.storage_sort,
+        }
+    }
+}
+
+/// Shared view of which monitors run and how often, read by every worker.
+#[derive(Clone)]
+struct MonitorSchedule {
+    selection: Arc<Mutex<MonitorSelection>>,
+    intervals: Arc<Mutex<PollIntervals>>,
+}
+
+impl MonitorSchedule {
+    fn selection(&self) -> MonitorSelection {
+        match self.selection.lock() {
+            Ok(selection) => *selection,
+            Err(poisoned) => *poisoned.into_inner(),
+        }
+    }
+
+    /// Returns the current selection and, if `monitor` is enabled, its interval.
+    fn current(&self, monitor: PolledMonitor) -> (MonitorSelection, Option<Duration>) {
+        let selection = self.selection();
+        let interval = match self.intervals.lock() {
+            Ok(intervals) => intervals.get(monitor),
+            Err(poisoned) => poisoned.into_inner().get(monitor),
+        };
+        (selection, selection.enabled(monitor).then_some(interval))
+    }
+}
+
+/// Read-only access to the published device readings for other threads.
+#[derive(Clone)]
+pub struct SnapshotReader {
+    latest: Arc<Mutex<SystemSnapshot>>,
+    schedule: MonitorSchedule,
+}
+
+impl SnapshotReader {
+    /// Run `read` against the latest snapshot without cloning it.
+    ///
+    /// Notifications and media are not refreshed here; use
+    /// [`StatsSampler::snapshot`] when those are needed.
+    pub fn read<R>(&self, read: impl FnOnce(&SystemSnapshot, MonitorSelection) -> R) -> R {
+        let selection = self.schedule.selection();
+        match self.latest.lock() {
+            Ok(snapshot) => read(&snapshot, selection),
+            Err(poisoned) => read(&poisoned.into_inner(), selection),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct StatsSampler {
     latest: Arc<Mutex<SystemSnapshot>>,
+    schedule: MonitorSchedule,
     weather_enabled: Arc<AtomicBool>,
     solaar_enabled: Arc<AtomicBool>,
     weather_location: Arc<Mutex<String>>,
+    network_settings: Arc<Mutex<NetworkSettings>>,
+    storage_settings: Arc<Mutex<StorageSettings>>,
+    connection_monitor: ConnectionMonitor,
     notification_monitor: NotificationMonitor,
     media_monitor: MediaMonitor,
+    command_monitor: CommandMonitor,
 }
 
 impl StatsSampler {
     pub fn spawn(
+        monitors: MonitorSelection,
+        intervals: PollIntervals,
         weather_enabled: bool,
         solaar_enabled: bool,
         weather_location: String,
         max_notifications: usize,
         cider_api_token: String,
+        commands: Vec<ShellCommand>,
     ) -> Self {
         let notification_monitor = NotificationMonitor::new(max_notifications);
         let media_monitor = MediaMonitor::new(Some(cider_api_token));
+        let command_monitor = CommandMonitor::new(commands);
         let sampler = Self {
             latest: Arc::new(Mutex::new(SystemSnapshot::default())),
+            schedule: MonitorSchedule {
+                selection: Arc::new(Mutex::new(monitors)),
+                intervals: Arc::new(Mutex::new(intervals)),
+            },
             weather_enabled: Arc::new(AtomicBool::new(weather_enabled)),
             solaar_enabled: Arc::new(AtomicBool::new(solaar_enabled)),
             weather_location: Arc::new(Mutex::new(weather_location)),
+            network_settings: Arc::default(),
+            storage_settings: Arc::default(),
+            connection_monitor: ConnectionMonitor::new(),
             notification_monitor: notification_monitor.clone(),
             media_monitor: media_monitor.clone(),
+            command_monitor: command_monitor.clone(),
         };
 
+        sampler.spawn_monitor_workers();
+
         let latest = Arc::clone(&sampler.latest);
         let weather_enabled = Arc::clone(&sampler.weather_enabled);
-        let solaar_enabled = Arc::clone(&sampler.solaar_enabled);
         let weather_location = Arc::clone(&sampler.weather_location);
         let media_monitor = sampler.media_monitor.clone();
+        let connection_monitor = sampler.connection_monitor.clone();
+        connection_monitor.set_enabled(monitors.network_connections);
         std::thread::spawn(move || {
-            let mut utilization = UtilizationMonitor::new();
-            let mut network = NetworkMonitor::new();
-            let mut disk_io = DiskIoMonitor::new();
-            let mut temperature = TemperatureMonitor::new();
-            let mut storage = StorageMonitor::new();
-            let mut battery =
-                BatteryMonitor::new_with_solaar(solaar_enabled.load(Ordering::Relaxed));
             let mut active_weather_location = match weather_location.lock() {
                 Ok(location) => location.clone(),
                 Err(poisoned) => poisoned.into_inner().clone(),
@@ -81,14 +397,6 @@ impl StatsSampler {
             let mut weather = WeatherMonitor::new(String::new(), active_weather_location.clone());
 
             loop {
-                utilization.update();
-                network.update();
-                disk_io.update();
-                temperature.update();
-                storage.update();
-                battery.set_solaar_enabled(solaar_enabled.load(Ordering::Relaxed));
-                battery.update();
-
                 let configured_location = match weather_location.lock() {
                     Ok(location) => location.clone(),
                     Err(poisoned) => poisoned.into_inner().clone(),
@@ -105,28 +413,18 @@ impl StatsSampler {
                     Ok(data) => data.clone(),
                     Err(poisoned) => poisoned.into_inner().clone(),
                 };
+                let notifications = notification_monitor.get_notifications();
+                let media = media_monitor.get_player_state();
+                let network_connections = connection_monitor.connections();
+                let commands = command_monitor.results();
 
-                let snapshot = SystemSnapshot {
-                    cpu_usage: utilization.cpu_usage,
-                    memory_usage: utilization.memory_usage,
-                    gpu_usage: utilization.get_gpu_usage(),
-                    network_rx_rate: network.network_rx_rate,
-                    network_tx_rate: network.network_tx_rate,
-                    disk_read_rate: disk_io.read_rate,
-                    disk_write_rate: disk_io.write_rate,
-                    cpu_temp: temperature.cpu_temp,
-                    gpu_temp: temperature.gpu_temp,
-                    disks: storage.disk_info.clone(),
-                    devices: battery.devices(),
-                    weather: weather_data,
-                    notifications: notification_monitor.get_notifications(),
-                    media: media_monitor.get_player_state(),
-                };
-
-                match latest.lock() {
-                    Ok(mut current) => *current = snapshot,
-                    Err(poisoned) => *poisoned.into_inner() = snapshot,
-                }
+                publish(&latest, |snapshot| {
+                    snapshot.weather = weather_data;
+                    snapshot.notifications = notifications;
+                    snapshot.media = media;
+                    snapshot.network_connections = network_connections;
+                    snapshot.commands = commands;
+                });
 
                 std::thread::sleep(Duration::from_millis(UPDATE_INTERVAL_MS));
             }
@@ -135,6 +433,177 @@ impl StatsSampler {
         sampler
     }
 
+    /// Start one polling thread per device monitor.
+    fn spawn_monitor_workers(&self) {
+        spawn_worker(
+            PolledMonitor::Utilization,
+            self.schedule.clone(),
+            Arc::clone(&self.latest),
+            |selection| UtilizationMonitor::new_with_gpu(selection.gpu),
+            |utilization, selection| {
+                utilization.set_gpu_enabled(selection.gpu);
+                utilization.set_gpu_processes_enabled(selection.gpu_processes);
+                utilization.set_processes_enabled(selection.processes);
+                utilization.update();
+            },
+            |utilization: Option<&UtilizationMonitor>, snapshot| {
+                snapshot.cpu_usage = utilization.map_or(0.0, |u| u.cpu_usage);
+                snapshot.cpu_cores = utilization.map(|u| u.cpu_cores.clone()).unwrap_or_default();
+                snapshot.load_average = utilization.map(|u| u.load_average).unwrap_or_default();
+                snapshot.memory_usage = utilization.map_or(0.0, |u| u.memory_usage);
+                snapshot.memory_used = utilization.map_or(0, |u| u.memory_used);
+                snapshot.memory_total = utilization.map_or(0, |u| u.memory_total);
+                snapshot.memory_details = utilization
+                    .map(|u| u.memory_details.clone())
+                    .unwrap_or_default();
+                snapshot.gpu_usage = utilization.map_or(0.0, UtilizationMonitor::get_gpu_usage);
+                snapshot.gpus = utilization
+                    .map(UtilizationMonitor::gpu_devices)
+                    .unwrap_or_default();
+                snapshot.gpu_processes = utilization
+                    .map(UtilizationMonitor::gpu_processes)
+                    .unwrap_or_default();
+                snapshot.top_processes = utilization
+                    .map(|u| u.top_processes.clone())
+                    .unwrap_or_default();
+                snapshot.top_process_trees = utilization
+                    .map(|u| u.top_process_trees.clone())
+                    .unwrap_or_default();
+                let history = Arc::make_mut(&mut snapshot.history);
+                if utilization.is_some() {
+                    history.cpu.record(snapshot.cpu_usage);
+                    history.memory.record(snapshot.memory_usage);
+                    history.gpu.record(snapshot.gpu_usage);
+                } else {
+                    history.cpu.clear();
+                    history.memory.clear();
+                    history.gpu.clear();
+                }
+            },
+        );
+        let network_settings = Arc::clone(&self.network_settings);
+        spawn_worker(
+            PolledMonitor::Network,
+            self.schedule.clone(),
+            Arc::clone(&self.latest),
+            |_| NetworkWorker {
+                monitor: NetworkMonitor::new(),
+                ledger: TrafficLedger::load(),
+                traffic: TrafficSummary::default(),
+                cycle_bytes: 0,
+                quota_bytes: 0,
+            },
+            move |worker, _| {
+                let settings = match network_settings.lock() {
+                    Ok(settings) => settings.clone(),
+                    Err(poisoned) => poisoned.into_inner().clone(),
+                };
+                worker.update(&settings);
+            },
+            |worker: Option<&NetworkWorker>, snapshot| {
+                let network = worker.map(|w| &w.monitor);
+                snapshot.network_rx_rate = network.map_or(0.0, |n| n.network_rx_rate);
+                snapshot.network_tx_rate = network.map_or(0.0, |n| n.network_tx_rate);
+                snapshot.network_interfaces =
+                    network.map(|n| n.interfaces.clone()).unwrap_or_default();
+                snapshot.network_traffic = worker.map(|w| w.traffic.clone()).unwrap_or_default();
+                snapshot.network_cycle_bytes = worker.map_or(0, |w| w.cycle_bytes);
+                snapshot.network_quota_bytes = worker.map_or(0, |w| w.quota_bytes);
+                let history = Arc::make_mut(&mut snapshot.history);
+                if network.is_some() {
+                    history.network_rx.record(snapshot.network_rx_rate as f32);
+                    history.network_tx.record(snapshot.network_tx_rate as f32);
+                } else {
+                    history.network_rx.clear();
+                    history.network_tx.clear();
+                }
+            },
+        );
+        spawn_worker(
+            PolledMonitor::DiskIo,
+            self.schedule.clone(),
+            Arc::clone(&self.latest),
+            |_| DiskIoMonitor::new(),
+            |disk_io, _| disk_io.update(),
+            |disk_io: Option<&DiskIoMonitor>, snapshot| {
+                snapshot.disk_read_rate = disk_io.map_or(0.0, |d| d.read_rate);
+                snapshot.disk_write_rate = disk_io.map_or(0.0, |d| d.write_rate);
+                snapshot.disk_devices = disk_io.map(|d| d.disks.clone()).unwrap_or_default();
+                let history = Arc::make_mut(&mut snapshot.history);
+                if disk_io.is_some() {
+                    history.disk_read.record(snapshot.disk_read_rate as f32);
+                    history.disk_write.record(snapshot.disk_write_rate as f32);
+                } else {
+                    history.disk_read.clear();
+                    history.disk_write.clear();
+                }
+            },
+        );
+        spawn_worker(
+            PolledMonitor::Temperatures,
+            self.schedule.clone(),
+            Arc::clone(&self.latest),
+            |_| TemperatureMonitor::new(),
+            |temperature, _| temperature.update(),
+            |temperature: Option<&TemperatureMonitor>, snapshot| {
+                snapshot.cpu_temp = temperature.map_or(0.0, |t| t.cpu_temp);
+                snapshot.gpu_temp = temperature.map_or(0.0, |t| t.gpu_temp);
+                let history = Arc::make_mut(&mut snapshot.history);
+                if temperature.is_some() {
+                    history.cpu_temp.record(snapshot.cpu_temp);
+                    history.gpu_temp.record(snapshot.gpu_temp);
+                } else {
+                    history.cpu_temp.clear();
+                    history.gpu_temp.clear();
+                }
+            },
+        );
+        let storage_settings = Arc::clone(&self.storage_settings);
+        spawn_worker(
+            PolledMonitor::Storage,
+            self.schedule.clone(),
+            Arc::clone(&self.latest),
+            |_| StorageWorker {
+                storage: StorageMonitor::new(),
+                drives: DriveHealthMonitor::new(),
+                pools: PoolHealthMonitor::new(),
+            },
+            StorageWorker::update,
+            move |worker: Option<&StorageWorker>, snapshot| {
+                snapshot.storage_mounts =
+                    worker.map(|w| w.storage.mounts.clone()).unwrap_or_default();
+                let settings = match storage_settings.lock() {
+                    Ok(settings) => settings.clone(),
+                    Err(poisoned) => poisoned.into_inner().clone(),
+                };
+                snapshot.disks = arrange_disks(
+                    &snapshot.storage_mounts,
+                    &settings.rules,
+                    &settings.labels,
+                    settings.sort,
+                );
+                snapshot.drive_health = worker.map(|w| w.drives.drives.clone()).unwrap_or_default();
+                snapshot.pool_health = worker.map(|w| w.pools.pools.clone()).unwrap_or_default();
+            },
+        );
+
+        let solaar_enabled = Arc::clone(&self.solaar_enabled);
+        let configured_solaar = Arc::clone(&self.solaar_enabled);
+        spawn_worker(
+            PolledMonitor::Battery,
+            self.schedule.clone(),
+            Arc::clone(&self.latest),
+            move |_| BatteryMonitor::new_with_solaar(solaar_enabled.load(Ordering::Relaxed)),
+            move |battery, _| {
+                battery.set_solaar_enabled(configured_solaar.load(Ordering::Relaxed));
+                battery.update();
+            },
+            |battery: Option<&BatteryMonitor>, snapshot| {
+                snapshot.devices = battery.map(BatteryMonitor::devices).unwrap_or_default();
+            },
+        );
+    }
+
     pub fn snapshot(&self) -> SystemSnapshot {
         let mut snapshot = match self.latest.lock() {
             Ok(snapshot) => snapshot.clone(),
@@ -145,6 +614,29 @@ impl StatsSampler {
         snapshot
     }
 
+    pub fn reader(&self) -> SnapshotReader {
+        SnapshotReader {
+            latest: Arc::clone(&self.latest),
+            schedule: self.schedule.clone(),
+        }
+    }
+
+    pub fn set_monitor_selection(&self, selection: MonitorSelection) {
+        self.connection_monitor
+            .set_enabled(selection.network_connections);
+        match self.schedule.selection.lock() {
+            Ok(mut current) => *current = selection,
+            Err(poisoned) => *poisoned.into_inner() = selection,
+        }
+    }
+
+    pub fn set_poll_intervals(&self, intervals: PollIntervals) {
+        match self.schedule.intervals.lock() {
+            Ok(mut current) => *current = intervals,
+            Err(poisoned) => *poisoned.into_inner() = intervals,
+        }
+    }
+
     pub fn set_weather_config(&self, enabled: bool, location: String) {
         self.weather_enabled.store(enabled, Ordering::Relaxed);
         match self.weather_location.lock() {
@@ -153,10 +645,33 @@ impl StatsSampler {
         }
     }
 
+    /// Replace the interface filter of the network totals and the billing
+    /// cycle of the traffic accounting.
+    pub fn set_network_settings(&self, settings: NetworkSettings) {
+        match self.network_settings.lock() {
+            Ok(mut current) => *current = settings,
+            Err(poisoned) => *poisoned.into_inner() = settings,
+        }
+    }
+
+    /// Replace the storage rules, labels and order of the main profile's
+    /// storage rows.
+    pub fn set_storage_settings(&self, settings: StorageSettings) {
+        match self.storage_settings.lock() {
+            Ok(mut current) => *current = settings,
+            Err(poisoned) => *poisoned.into_inner() = settings,
+        }
+    }
+
     pub fn set_solaar_enabled(&self, enabled: bool) {
         self.solaar_enabled.store(enabled, Ordering::Relaxed);
     }
 
+    /// Replace the shell commands. An empty list stops running commands.
+    pub fn set_commands(&self, commands: Vec<ShellCommand>) {
+        self.command_monitor.set_commands(commands);
+    }
+
     pub fn clear_notifications(&self) {
         self.notification_monitor.clear();
     }
@@ -166,6 +681,10 @@ impl StatsSampler {
             .remove_notification(app_name, timestamp);
     }
 
+    pub fn send_notification(&self, summary: &str, body: &str) {
+        self.notification_monitor.notify(summary, body);
+    }
+
     pub fn set_cider_token(&self, token: String) {
         self.media_monitor
             .set_cider_token((!token.is_empty()).then_some(token));
@@ -195,3 +714,253 @@ impl StatsSampler {
         self.media_monitor.seek_to_progress(progress);
     }
 }
+
+/// How often an idle or waiting worker re-reads its schedule.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Network rates and the traffic accounting fed by their byte counters.
+struct NetworkWorker {
+    monitor: NetworkMonitor,
+    ledger: TrafficLedger,
+    traffic: TrafficSummary,
+    cycle_bytes: u64,
+    quota_bytes: u64,
+}
+
+impl NetworkWorker {
+    fn update(&mut self, settings: &NetworkSettings) {
+        self.monitor
+            .set_interface_filter(&settings.included, &settings.excluded);
+        self.monitor.update();
+
+        let today = chrono::Local::now().date_naive();
+        self.ledger.record(today, self.monitor.byte_counters());
+        self.traffic = self.ledger.summary(today, settings.billing_day);
+        // Interfaces that are gone count as virtual unless included.
+        self.cycle_bytes = self
+            .traffic
+            .interfaces
+            .iter()
+            .filter(|traffic| {
+                let physical = self
+                    .monitor
+                    .interfaces
+                    .iter()
+                    .any(|interface| interface.name == traffic.name && interface.physical);
+                network_interface_counted(
+                    &settings.included,
+                    &settings.excluded,
+                    &traffic.name,
+                    physical,
+                )
+            })
+            .map(|traffic| traffic.cycle.total())
+            .sum();
+        self.quota_bytes = settings.quota_bytes;
+    }
+}
+
+/// Filesystem capacity and the health of the drives and pools below it.
+struct StorageWorker {
+    storage: StorageMonitor,
+    drives: DriveHealthMonitor,
+    pools: PoolHealthMonitor,
+}
+
+impl StorageWorker {
+    fn update(&mut self, selection: MonitorSelection) {
+        self.storage.update();
+        if selection.drive_health {
+            self.drives.update(
+                self.storage
+                    .mounts
+                    .iter()
+                    .filter_map(|disk| disk.device.as_deref()),
+            );
+        } else {
+            self.drives = DriveHealthMonitor::new();
+        }
+        if selection.pool_health {
+            self.pools.update(&self.storage.mounts);
+        } else {
+            self.pools = PoolHealthMonitor::new();
+        }
+    }
+}
+
+/// Poll one monitor on a dedicated thread and publish its readings.
+///
+/// The monitor is created on the worker thread while its section is enabled
+/// and dropped when the section is disabled, at which point `publish` receives
+/// `None` so the snapshot falls back to defaults.
+fn spawn_worker<M: 'static>(
+    kind: PolledMonitor,
+    schedule: MonitorSchedule,
+    latest: Arc<Mutex<SystemSnapshot>>,
+    create: impl Fn(MonitorSelection) -> M + Send + 'static,
+    poll: impl Fn(&mut M, MonitorSelection) + Send + 'static,
+    publish_fields: impl Fn(Option<&M>, &mut SystemSnapshot) + Send + 'static,
+) {
+    std::thread::spawn(move || {
+        let mut monitor: Option<M> = None;
+        loop {
+            let (selection, interval) = schedule.current(kind);
+            let Some(interval) = interval else {
+                if monitor.take().is_some() {
+                    publish(&latest, |snapshot| publish_fields(None, snapshot));
+                }
+                std::thread::sleep(SCHEDULE_CHECK_INTERVAL);
+                continue;
+            };
+
+            let started = Instant::now();
+            let active = monitor.get_or_insert_with(|| create(selection));
+            poll(active, selection);
+            publish(&latest, |snapshot| publish_fields(Some(&*active), snapshot));
+
+            // Sleep in short slices so toggles and shorter intervals apply
+            // without waiting out a long storage or battery cadence.
+            while let Some(remaining) = interval.checked_sub(started.elapsed()) {
+                std::thread::sleep(remaining.min(SCHEDULE_CHECK_INTERVAL));
+                if schedule.current(kind).1 != Some(interval) {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+fn publish(latest: &Mutex<SystemSnapshot>, update: impl FnOnce(&mut SystemSnapshot)) {
+    match latest.lock() {
+        Ok(mut current) => update(&mut current),
+        Err(poisoned) => update(&mut poisoned.into_inner()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monitor_selection_follows_section_toggles() {
+        let config = Config {
+            show_cpu: false,
+            show_memory: false,
+            show_gpu: false,
+            show_network: false,
+            show_cpu_temp: false,
+            show_gpu_temp: true,
+            show_battery: false,
+            ..Config::default()
+        };
+
+        let selection = MonitorSelection::from_config(&config);
+
+        assert!(!selection.utilization);
+        assert!(!selection.gpu);
+        assert!(!selection.network);
+        assert!(selection.temperatures);
+        assert_eq!(selection.storage, config.show_storage);
+        assert!(!selection.battery);
+    }
+
+    #[test]
+    fn instances_share_the_monitors_any_of_them_needs() {
+        let media = Config {
+            show_cpu: false,
+            show_memory: false,
+            show_gpu: false,
+            show_network: false,
+            show_disk: false,
+            show_cpu_temp: false,
+            show_gpu_temp: false,
+            show_storage: false,
+            show_battery: false,
+            ..Config::default()
+        };
+        let network = Config {
+            show_network: true,
+            ..media.clone()
+        };
+
+        let selection = MonitorSelection::from_configs([&media, &network]);
+
+        assert_eq!(
+            selection,
+            MonitorSelection {
+                network: true,
+                ..MonitorSelection::default()
+            }
+        );
+    }
+
+    #[test]
+    fn fastest_interval_ignores_disabled_monitors() {
+        let mut config = Config::default();
+        config.set_poll_interval_ms(PolledMonitor::Network, 500);
+        config.set_poll_interval_ms(PolledMonitor::Storage, 30_000);
+        let intervals = PollIntervals::from_config(&config);
+
+        let mut selection = MonitorSelection {
+            storage: true,
+            ..MonitorSelection::default()
+        };
+        assert_eq!(intervals.fastest(selection), Some(Duration::from_secs(30)));
+
+        selection.network = true;
+        assert_eq!(
+            intervals.fastest(selection),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(intervals.fastest(MonitorSelection::default()), None);
+    }
+
+    #[test]
+    fn alert_rules_keep_their_monitors_running() {
+        let storage = AlertRule {
+            metric: AlertMetric::StorageUsage,
+            ..AlertRule::default()
+        };
+        let disabled = AlertRule {
+            enabled: false,
+            metric: AlertMetric::DeviceBattery,
+            ..AlertRule::default()
+        };
+
+        let selection = MonitorSelection::default().with_alert_rules(&[storage, disabled]);
+
+        assert_eq!(
+            selection,
+            MonitorSelection {
+                storage: true,
+                ..MonitorSelection::default()
+            }
+        );
+    }
+
+    #[test]
+    fn custom_sections_select_the_monitors_their_variables_read() {
+        let mut config = Config {
+            show_cpu: false,
+            show_memory: false,
+            show_gpu: false,
+            show_network: false,
+            show_disk: false,
+            show_cpu_temp: false,
+            show_gpu_temp: false,
+            show_storage: false,
+            show_battery: false,
+            ..Config::default()
+        };
+        config.add_custom_section().template = "{time} {net_down} {disk:/home}".to_string();
+
+        assert_eq!(
+            MonitorSelection::from_config(&config),
+            MonitorSelection {
+                network: true,
+                storage: true,
+                ..MonitorSelection::default()
+            }
+        );
+    }
+}
@@ -1,21 +1,39 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::gauge;
+use super::graph::{self, GraphScale};
+use super::history::MetricHistory;
 use super::stats::SystemSnapshot;
 use crate::battery::BatteryDevice;
-use crate::config::{Config, WidgetSection};
+use crate::commands::{CommandResult, CommandStatus};
+use crate::config::{Config, CustomSection, HistoryGraphStyle, WidgetSection};
+use crate::disk_io::DiskDevice;
+use crate::drive_health::DriveHealth;
+use crate::gpu::{GpuDevice, GpuFan};
+use crate::gpu_processes::GpuProcess;
 use crate::media::{AlbumArt, MediaInfo, PlaybackStatus};
+use crate::network::NetworkInterface;
+use crate::network_manager::NetworkConnections;
 use crate::notifications::Notification;
-use crate::storage::DiskInfo;
+use crate::pool_health::{PoolHealth, PoolKind};
+use crate::storage::{DiskInfo, arrange_disks};
+use crate::template::{
+    self, ReadingValues, Readings, Template, format_network_rate, format_storage_bytes,
+};
+use crate::traffic::{InterfaceTraffic, TrafficBytes};
+use crate::utilization::{CoreKind, CpuCore, ProcessUsage};
 use crate::weather::WeatherData;
 use chrono::{DateTime, Local};
 use cosmic::iced::core::image::FilterMethod;
 use cosmic::iced::{Alignment, Background, Border, Color, ContentFit, Length, mouse};
 use cosmic::{Element, theme, widget};
+use std::collections::HashMap;
 use std::rc::Rc;
+use std::time::Duration;
 
 const METRIC_ICON_SIZE: u16 = 18;
 const METRIC_LABEL_WIDTH: f32 = 56.0;
+const RATE_GRAPH_FLOOR: f32 = 1024.0;
 const MEDIA_ACTIVE_SECTION_HEIGHT: f32 = 232.0;
 const MEDIA_CONTENT_HEIGHT: f32 = 204.0;
 const MEDIA_SOURCE_SELECTOR_HEIGHT: f32 = 34.0;
@@ -29,9 +47,23 @@ const MEDIA_CONTROL_ICON_SIZE: u16 = 20;
 const MEDIA_CONTROL_PADDING: u16 = 6;
 const MEDIA_CONTROL_SPACING: u16 = 8;
 const MEDIA_TIMELINE_FOOTER_GAP: f32 = 4.0;
+const MAX_COMMAND_TABLE_COLUMNS: usize = 4;
+const COMMAND_LABEL_CHARS: usize = 18;
+const COMMAND_OUTPUT_CHARS: usize = 28;
+const GPU_NAME_CHARS: usize = 30;
+const GPU_PROCESS_NAME_CHARS: usize = 26;
+const PROCESS_NAME_CHARS: usize = 24;
+const DISK_DEVICE_NAME_CHARS: usize = 18;
+const PROCESS_MEMORY_WIDTH: f32 = 64.0;
+/// Logical CPUs per row of the expanded CPU view.
+pub(super) const CPU_GRID_COLUMNS: usize = 3;
+/// Space above and below a degraded pool's storage row, which keeps the
+/// text clear of its 1 px border.
+pub(super) const DEGRADED_POOL_PADDING: u16 = 2;
 
 pub fn widget_view<'a>(
-    config: &Config,
+    config: &'a Config,
+    templates: &HashMap<u32, Template>,
     now: DateTime<Local>,
     stats: &'a SystemSnapshot,
     expanded_notification_group: Option<&'a str>,
@@ -44,6 +76,9 @@ pub fn widget_view<'a>(
     surface_height: u32,
     media_seek_preview: Option<f64>,
     media_timeline_hovered: bool,
+    cpu_details_expanded: bool,
+    gpu_processes_expanded: bool,
+    pending_process_signal: Option<&'a super::ProcessTarget>,
 ) -> Element<'a, super::Message> {
     let spacing = theme::system_preference().cosmic().spacing;
     let now_timestamp = now.timestamp().max(0) as u64;
@@ -61,17 +96,25 @@ pub fn widget_view<'a>(
     for section in &config.section_order {
         let migrated = match section {
             WidgetSection::Utilization if show_utilization(config) => Some(utilization_view(
+                config,
+                stats,
+                cpu_details_expanded,
+                gpu_processes_expanded,
+                spacing.space_xs,
+                spacing.space_xs,
+            )),
+            WidgetSection::Network if config.show_network => Some(network_view(
+                config,
+                stats,
+                spacing.space_xs,
+                spacing.space_xs,
+            )),
+            WidgetSection::DiskIo if config.show_disk => Some(disk_io_view(
                 config,
                 stats,
                 spacing.space_xs,
                 spacing.space_xs,
             )),
-            WidgetSection::Network if config.show_network => {
-                Some(network_view(stats, spacing.space_xs, spacing.space_xs))
-            }
-            WidgetSection::DiskIo if config.show_disk => {
-                Some(disk_io_view(stats, spacing.space_xs, spacing.space_xs))
-            }
             WidgetSection::Temperatures if show_temperatures(config) => Some(temperature_view(
                 config,
                 stats,
@@ -114,6 +157,31 @@ pub fn widget_view<'a>(
                 spacing.space_xxs,
                 media_timeline_hovered,
             )),
+            WidgetSection::Commands if config.show_commands => {
+                Some(commands_view(stats, spacing.space_xs, spacing.space_xs))
+            }
+            WidgetSection::Processes if config.show_processes => Some(processes_view(
+                config,
+                stats,
+                pending_process_signal,
+                spacing.space_xs,
+                spacing.space_xxs,
+            )),
+            WidgetSection::Custom(id) => {
+                config
+                    .custom_section(*id)
+                    .zip(templates.get(id))
+                    .map(|(custom, template)| {
+                        custom_view(
+                            custom,
+                            template,
+                            stats,
+                            now,
+                            spacing.space_xs,
+                            spacing.space_xxs,
+                        )
+                    })
+            }
             _ => None,
         };
 
@@ -209,6 +277,8 @@ fn clock_view<'a>(
 fn utilization_view<'a>(
     config: &Config,
     stats: &SystemSnapshot,
+    cpu_details_expanded: bool,
+    gpu_processes_expanded: bool,
     section_spacing: u16,
     metric_spacing: u16,
 ) -> Element<'a, super::Message> {
@@ -219,13 +289,25 @@ fn utilization_view<'a>(
     );
 
     if config.show_cpu {
-        section = section.push(metric(
+        let cpu = metric(
             MetricIcon::Cpu,
             "CPU",
             stats.cpu_usage,
             config.show_percentages,
             metric_spacing,
-        ));
+        );
+        if config.show_cpu_details {
+            section = section.push(
+                widget::mouse_area(cpu)
+                    .on_press(super::Message::ToggleCpuDetails)
+                    .interaction(mouse::Interaction::Pointer),
+            );
+            if cpu_details_expanded {
+                section = section.push(cpu_details_view(stats, metric_spacing));
+            }
+        } else {
+            section = section.push(cpu);
+        }
     }
     if config.show_memory {
         section = section.push(metric(
@@ -235,26 +317,405 @@ fn utilization_view<'a>(
             config.show_percentages,
             metric_spacing,
         ));
+        if config.show_memory_details {
+            section = section.push(detail_rows_view(memory_detail_rows(stats), metric_spacing));
+        }
     }
     if config.show_gpu {
-        section = section.push(metric(
-            MetricIcon::Gpu,
-            "GPU",
-            stats.gpu_usage,
-            config.show_percentages,
-            metric_spacing,
-        ));
+        let gpus = visible_gpus(config, stats);
+        let mut rows = Vec::with_capacity(gpus.len().max(1));
+        if gpus.is_empty() {
+            rows.push(metric(
+                MetricIcon::Gpu,
+                "GPU",
+                stats.gpu_usage,
+                config.show_percentages,
+                metric_spacing,
+            ));
+        }
+        for gpu in gpus {
+            rows.push(gpu_item(
+                gpu,
+                config.show_percentages,
+                config.show_gpu_details,
+                metric_spacing,
+            ));
+        }
+        for row in rows {
+            section = section.push(if config.show_gpu_processes {
+                widget::mouse_area(row)
+                    .on_press(super::Message::ToggleGpuProcesses)
+                    .interaction(mouse::Interaction::Pointer)
+                    .into()
+            } else {
+                row
+            });
+        }
+        if config.show_gpu_processes && gpu_processes_expanded {
+            section = section.push(gpu_processes_view(config, stats, metric_spacing));
+        }
+    }
+
+    let history = &stats.history;
+    let series: Vec<&MetricHistory> = [
+        (config.show_cpu, &history.cpu),
+        (config.show_memory, &history.memory),
+        (config.show_gpu, &history.gpu),
+    ]
+    .into_iter()
+    .filter_map(|(shown, history)| shown.then_some(history))
+    .collect();
+    if let Some(graph) = section_graph(
+        config,
+        WidgetSection::Utilization,
+        &series,
+        GraphScale::Fixed(100.0),
+    ) {
+        section = section.push(graph);
     }
 
     section.into()
 }
 
+/// GPUs with their own rows, leaving out those hidden in the settings.
+/// Empty until the first GPU poll, when the combined GPU row is shown instead.
+pub(super) fn visible_gpus<'a>(config: &Config, stats: &'a SystemSnapshot) -> Vec<&'a GpuDevice> {
+    stats
+        .gpus
+        .iter()
+        .filter(|gpu| !config.hidden_gpus.contains(&gpu.id))
+        .collect()
+}
+
+fn gpu_item<'a>(
+    gpu: &GpuDevice,
+    show_percentage: bool,
+    show_details: bool,
+    spacing: u16,
+) -> Element<'a, super::Message> {
+    let usage = gpu.usage.unwrap_or(0.0).clamp(0.0, 100.0);
+    let mut title = widget::row::with_capacity(3)
+        .align_y(Alignment::Center)
+        .spacing(spacing)
+        .push(metric_icon(MetricIcon::Gpu))
+        .push(
+            widget::text::caption_heading(compact_single_line(&gpu.name, GPU_NAME_CHARS))
+                .width(Length::Fill),
+        );
+    if let Some(temperature) = gpu.temperature.filter(|temperature| *temperature > 0.0) {
+        title = title.push(widget::text::caption(format!("{temperature:.0}°C")));
+    }
+
+    let mut bar = widget::row::with_capacity(2)
+        .align_y(Alignment::Center)
+        .spacing(spacing)
+        .push(gauge::indicator_bar(usage));
+    if show_percentage {
+        bar = bar.push(widget::text::monotext(match gpu.usage {
+            Some(_) => format!("{usage:>5.1}%"),
+            None => "    -".to_string(),
+        }));
+    }
+
+    let mut item = widget::column::with_capacity(3)
+        .spacing(4)
+        .push(title)
+        .push(bar);
+    if show_details {
+        item = item.push(detail_rows_view(gpu_detail_rows(gpu), spacing));
+    }
+    item.into()
+}
+
+/// Labelled VRAM, power, clock and fan readings of a GPU, leaving out those
+/// its driver does not report.
+pub(super) fn gpu_detail_rows(gpu: &GpuDevice) -> Vec<(&'static str, String)> {
+    let details = &gpu.details;
+    let mut rows = Vec::with_capacity(4);
+
+    match (details.vram_used, details.vram_total) {
+        (Some(used), Some(total)) if total > 0 => rows.push((
+            "VRAM",
+            format!(
+                "{} / {} ({:.0}%)",
+                format_storage_bytes(used),
+                format_storage_bytes(total),
+                used as f64 / total as f64 * 100.0
+            ),
+        )),
+        (Some(used), _) => rows.push(("VRAM", format_storage_bytes(used))),
+        _ => {}
+    }
+    if let Some(watts) = details.power_watts {
+        rows.push(("Power", format!("{watts:.1} W")));
+    }
+    match (details.core_clock_mhz, details.memory_clock_mhz) {
+        (Some(core), Some(memory)) => {
+            rows.push(("Clocks", format!("{core} MHz core, {memory} MHz memory")));
+        }
+        (Some(core), None) => rows.push(("Clocks", format!("{core} MHz core"))),
+        (None, Some(memory)) => rows.push(("Clocks", format!("{memory} MHz memory"))),
+        (None, None) => {}
+    }
+    match details.fan {
+        Some(GpuFan::Rpm(rpm)) => rows.push(("Fan", format!("{rpm} RPM"))),
+        Some(GpuFan::Percent(percent)) => rows.push(("Fan", format!("{percent}%"))),
+        None => {}
+    }
+
+    rows
+}
+
+/// The busiest GPU processes with their usage and video memory.
+fn gpu_processes_view<'a>(
+    config: &Config,
+    stats: &SystemSnapshot,
+    spacing: u16,
+) -> Element<'a, super::Message> {
+    let processes = visible_gpu_processes(config, stats);
+    let mut list = widget::column::with_capacity(processes.len() + 1)
+        .spacing(spacing)
+        .push(widget::text::caption_heading("Top GPU processes"));
+    if processes.is_empty() {
+        list = list.push(widget::text::caption("No processes are using the GPU"));
+    }
+    for process in processes {
+        let usage = match process.usage {
+            Some(usage) => format!("{usage:>5.1}%"),
+            None => "    -".to_string(),
+        };
+        let vram = process.vram.map(format_storage_bytes).unwrap_or_default();
+        list = list.push(
+            widget::row::with_capacity(4)
+                .align_y(Alignment::Center)
+                .spacing(spacing)
+                .push(
+                    widget::text::caption(compact_single_line(
+                        &format!("{} ({})", process.name, process.pid),
+                        GPU_PROCESS_NAME_CHARS,
+                    ))
+                    .width(Length::Fill),
+                )
+                .push(widget::text::caption(vram))
+                .push(widget::text::monotext(usage)),
+        );
+    }
+    list.into()
+}
+
+/// GPU processes on GPUs that are not hidden in the settings.
+pub(super) fn visible_gpu_processes<'a>(
+    config: &Config,
+    stats: &'a SystemSnapshot,
+) -> Vec<&'a GpuProcess> {
+    stats
+        .gpu_processes
+        .iter()
+        .filter(|process| !config.hidden_gpus.contains(&process.gpu_id))
+        .collect()
+}
+
+/// Load average and a usage and frequency grid of the logical CPUs.
+fn cpu_details_view<'a>(stats: &SystemSnapshot, spacing: u16) -> Element<'a, super::Message> {
+    let load = stats.load_average;
+    let groups = cpu_core_groups(&stats.cpu_cores);
+    let mut details = widget::column::with_capacity(groups.len() * 2 + 1)
+        .spacing(spacing)
+        .push(
+            widget::row::with_capacity(3)
+                .align_y(Alignment::Center)
+                .spacing(spacing)
+                .push(widget::text::caption_heading("Load average"))
+                .push(widget::space::horizontal())
+                .push(widget::text::monotext(format!(
+                    "{:.2} {:.2} {:.2}",
+                    load.one, load.five, load.fifteen
+                ))),
+        );
+
+    for (heading, cores) in groups {
+        if let Some(heading) = heading {
+            details = details.push(widget::text::caption_heading(heading));
+        }
+        for row in cores.chunks(CPU_GRID_COLUMNS) {
+            let mut cells = widget::row::with_capacity(CPU_GRID_COLUMNS).spacing(spacing);
+            for core in row {
+                cells = cells.push(cpu_core_cell(core));
+            }
+            // Keep cells of a short last row as wide as the others.
+            for _ in row.len()..CPU_GRID_COLUMNS {
+                cells = cells.push(widget::space::horizontal());
+            }
+            details = details.push(cells);
+        }
+    }
+
+    details.into()
+}
+
+fn cpu_core_cell<'a>(core: &CpuCore) -> Element<'a, super::Message> {
+    let usage = core.usage.clamp(0.0, 100.0);
+    widget::column::with_capacity(3)
+        .width(Length::Fill)
+        .spacing(2)
+        .push(
+            widget::row::with_capacity(3)
+                .push(widget::text::caption(format!("CPU {}", core.id)))
+                .push(widget::space::horizontal())
+                .push(widget::text::caption(format!("{usage:.0}%"))),
+        )
+        .push(gauge::indicator_bar(usage))
+        .push(widget::text::caption(format_core_frequency(core)))
+        .into()
+}
+
+/// Logical CPUs grouped by core type, with a heading for each group on
+/// hybrid processors.
+pub(super) fn cpu_core_groups(cores: &[CpuCore]) -> Vec<(Option<&'static str>, Vec<&CpuCore>)> {
+    if cores.iter().all(|core| core.kind.is_none()) {
+        return vec![(None, cores.iter().collect())];
+    }
+
+    [
+        ("P-cores", Some(CoreKind::Performance)),
+        ("E-cores", Some(CoreKind::Efficiency)),
+        ("Other cores", None),
+    ]
+    .into_iter()
+    .map(|(heading, kind)| {
+        (
+            Some(heading),
+            cores
+                .iter()
+                .filter(|core| core.kind == kind)
+                .collect::<Vec<_>>(),
+        )
+    })
+    .filter(|(_, cores)| !cores.is_empty())
+    .collect()
+}
+
+/// Current and maximum frequency, such as `3.4/5.0 GHz`.
+fn format_core_frequency(core: &CpuCore) -> String {
+    let ghz = |mhz: u64| mhz as f64 / 1000.0;
+    match (core.frequency_mhz, core.max_frequency_mhz) {
+        (0, _) => "-".to_string(),
+        (current, 0) => format!("{:.1} GHz", ghz(current)),
+        (current, max) => format!("{:.1}/{:.1} GHz", ghz(current), ghz(max)),
+    }
+}
+
+/// Label and value rows, such as the memory and GPU details.
+fn detail_rows_view<'a>(
+    rows: Vec<(&'static str, String)>,
+    spacing: u16,
+) -> Element<'a, super::Message> {
+    let mut details = widget::column::with_capacity(rows.len()).spacing(spacing);
+    for (label, value) in rows {
+        details = details.push(
+            widget::row::with_capacity(3)
+                .align_y(Alignment::Center)
+                .spacing(spacing)
+                .push(widget::text::caption_heading(label))
+                .push(widget::space::horizontal())
+                .push(widget::text::monotext(value)),
+        );
+    }
+    details.into()
+}
+
+/// Labelled memory readings below the memory bar. zram and pressure rows
+/// are left out where the system has none.
+pub(super) fn memory_detail_rows(stats: &SystemSnapshot) -> Vec<(&'static str, String)> {
+    let details = &stats.memory_details;
+    let mut rows = vec![(
+        "Used",
+        format!(
+            "{} / {}",
+            format_storage_bytes(stats.memory_used),
+            format_storage_bytes(stats.memory_total)
+        ),
+    )];
+
+    rows.push((
+        "Swap",
+        if details.swap_total == 0 {
+            "None".to_string()
+        } else {
+            format!(
+                "{} / {} ({:.0}%)",
+                format_storage_bytes(details.swap_used),
+                format_storage_bytes(details.swap_total),
+                details.swap_used as f64 / details.swap_total as f64 * 100.0
+            )
+        },
+    ));
+    if let Some(zram) = details.zram {
+        rows.push((
+            "zram",
+            format!(
+                "{} in {} ({:.1}x)",
+                format_storage_bytes(zram.original),
+                format_storage_bytes(zram.compressed),
+                zram.original as f64 / zram.compressed.max(1) as f64
+            ),
+        ));
+    }
+    rows.push((
+        "Cache",
+        format!(
+            "{} + {} buffers",
+            format_storage_bytes(details.cached),
+            format_storage_bytes(details.buffers)
+        ),
+    ));
+    if let Some(pressure) = details.pressure {
+        for (label, averages) in [
+            ("Pressure (some)", pressure.some),
+            ("Pressure (full)", pressure.full),
+        ] {
+            rows.push((
+                label,
+                format!(
+                    "{:.2} {:.2} {:.2}",
+                    averages.avg10, averages.avg60, averages.avg300
+                ),
+            ));
+        }
+    }
+
+    rows
+}
+
+/// History graph for a section, if one is enabled in the configuration.
+fn section_graph<'a>(
+    config: &Config,
+    section: WidgetSection,
+    series: &[&MetricHistory],
+    scale: GraphScale,
+) -> Option<Element<'a, super::Message>> {
+    let style = config.history_graph_style(section);
+    (style != HistoryGraphStyle::Off).then(|| {
+        graph::history_graph(
+            series,
+            Duration::from_secs(config.history_window_secs(section)),
+            style,
+            scale,
+        )
+    })
+}
+
 fn network_view<'a>(
+    config: &Config,
     stats: &SystemSnapshot,
     section_spacing: u16,
     row_spacing: u16,
 ) -> Element<'a, super::Message> {
-    section(
+    let interfaces = visible_network_interfaces(config, stats);
+    let (rx_rate, tx_rate) = interfaces.iter().fold((0.0, 0.0), |(rx, tx), interface| {
+        (rx + interface.rx_rate, tx + interface.tx_rate)
+    });
+    let mut network = section(
         "network-transmit-receive-symbolic",
         "Network",
         section_spacing,
@@ -262,16 +723,178 @@ fn network_view<'a>(
     .push(network_rate_row(
         "network-receive-symbolic",
         "Download",
-        stats.network_rx_rate,
+        rx_rate,
         row_spacing,
     ))
     .push(network_rate_row(
         "network-transmit-symbolic",
         "Upload",
-        stats.network_tx_rate,
+        tx_rate,
         row_spacing,
-    ))
-    .into()
+    ));
+
+    for interface in interfaces {
+        network = network.push(
+            widget::row::with_capacity(4)
+                .width(Length::Fill)
+                .align_y(Alignment::Center)
+                .spacing(row_spacing)
+                .push(widget::text::caption_heading(interface.name.clone()))
+                .push(widget::text::caption(network_link_label(interface)))
+                .push(widget::space::horizontal())
+                .push(widget::text::monotext(format!(
+                    "↓ {}  ↑ {}",
+                    format_network_rate(interface.rx_rate),
+                    format_network_rate(interface.tx_rate)
+                ))),
+        );
+    }
+    if config.show_network_connections {
+        network = network.push(detail_rows_view(
+            network_connection_rows(&stats.network_connections),
+            row_spacing,
+        ));
+    }
+    if config.show_network_traffic {
+        network = network.push(detail_rows_view(
+            network_traffic_rows(config, stats),
+            row_spacing,
+        ));
+    }
+
+    if let Some(graph) = section_graph(
+        config,
+        WidgetSection::Network,
+        &[&stats.history.network_rx, &stats.history.network_tx],
+        GraphScale::Auto {
+            floor: RATE_GRAPH_FLOOR,
+        },
+    ) {
+        network = network.push(graph);
+    }
+
+    network.into()
+}
+
+/// Interfaces the profile shows, which are also the ones its totals count.
+pub(super) fn visible_network_interfaces<'a>(
+    config: &Config,
+    stats: &'a SystemSnapshot,
+) -> Vec<&'a NetworkInterface> {
+    stats
+        .network_interfaces
+        .iter()
+        .filter(|interface| config.shows_network_interface(&interface.name, interface.physical))
+        .collect()
+}
+
+/// Traffic of the shown interfaces today, this month and, when it does not
+/// follow the calendar month, this billing cycle, then the quota's use.
+pub(super) fn network_traffic_rows(
+    config: &Config,
+    stats: &SystemSnapshot,
+) -> Vec<(&'static str, String)> {
+    let traffic = &stats.network_traffic;
+    let shown: Vec<_> = traffic
+        .interfaces
+        .iter()
+        .filter(|interface| {
+            let physical = stats
+                .network_interfaces
+                .iter()
+                .any(|current| current.name == interface.name && current.physical);
+            config.shows_network_interface(&interface.name, physical)
+        })
+        .collect();
+    let sum = |period: fn(&InterfaceTraffic) -> TrafficBytes| {
+        let (rx, tx) = shown.iter().fold((0, 0), |(rx, tx), interface| {
+            let bytes = period(interface);
+            (rx + bytes.rx, tx + bytes.tx)
+        });
+        format!(
+            "↓ {}  ↑ {}",
+            format_storage_bytes(rx),
+            format_storage_bytes(tx)
+        )
+    };
+
+    let mut rows = vec![
+        ("Today", sum(|interface| interface.today)),
+        ("This month", sum(|interface| interface.month)),
+    ];
+    if !traffic.cycle_start.is_empty() && !traffic.cycle_start.ends_with("-01") {
+        rows.push(("Billing cycle", sum(|interface| interface.cycle)));
+    }
+    if stats.network_quota_bytes > 0 {
+        rows.push((
+            "Quota",
+            format!(
+                "{} of {} · {:.0}%",
+                format_storage_bytes(stats.network_cycle_bytes),
+                format_storage_bytes(stats.network_quota_bytes),
+                stats.network_cycle_bytes as f64 / stats.network_quota_bytes as f64 * 100.0
+            ),
+        ));
+    }
+    rows
+}
+
+/// Labelled NetworkManager details: the primary connection with its first
+/// address of each family, then every saved VPN.
+pub(super) fn network_connection_rows(
+    connections: &NetworkConnections,
+) -> Vec<(&'static str, String)> {
+    let mut rows = Vec::new();
+    if let Some(primary) = &connections.primary {
+        rows.push((
+            "Connection",
+            match &primary.interface {
+                Some(interface) => format!("{} ({interface})", primary.name),
+                None => primary.name.clone(),
+            },
+        ));
+        if let Some(wifi) = &primary.wifi {
+            let mut value = format!("{} · {}%", wifi.ssid, wifi.strength);
+            if let Some(band) = wifi.band() {
+                value.push_str(&format!(" · {band}"));
+            }
+            rows.push(("Wi-Fi", value));
+        }
+        if let Some(address) = primary.ipv4.first() {
+            rows.push(("IPv4", address.clone()));
+        }
+        if let Some(address) = primary.ipv6.first() {
+            rows.push(("IPv6", address.clone()));
+        }
+        if let Some(gateway) = &primary.gateway {
+            rows.push(("Gateway", gateway.clone()));
+        }
+    }
+    for vpn in &connections.vpns {
+        rows.push((
+            if vpn.wireguard { "WireGuard" } else { "VPN" },
+            format!("{} · {}", vpn.name, vpn.state.label()),
+        ));
+    }
+    rows
+}
+
+/// Link speed and operational state, e.g. "1 Gb/s · up".
+fn network_link_label(interface: &NetworkInterface) -> String {
+    match interface.speed_mbps {
+        Some(speed) if speed >= 1_000 && speed % 1_000 == 0 => {
+            format!("{} Gb/s · {}", speed / 1_000, interface.operstate)
+        }
+        Some(speed) if speed >= 1_000 => {
+            format!(
+                "{:.1} Gb/s · {}",
+                f64::from(speed) / 1_000.0,
+                interface.operstate
+            )
+        }
+        Some(speed) => format!("{speed} Mb/s · {}", interface.operstate),
+        None => interface.operstate.clone(),
+    }
 }
 
 fn network_rate_row<'a>(
@@ -294,11 +917,12 @@ fn network_rate_row<'a>(
 }
 
 fn disk_io_view<'a>(
+    config: &Config,
     stats: &SystemSnapshot,
     section_spacing: u16,
     row_spacing: u16,
 ) -> Element<'a, super::Message> {
-    section(
+    let mut disk_io = section(
         "drive-harddisk-solidstate-symbolic",
         "Disk I/O",
         section_spacing,
@@ -314,8 +938,51 @@ fn disk_io_view<'a>(
         "Write",
         stats.disk_write_rate,
         row_spacing,
-    ))
-    .into()
+    ));
+
+    if config.show_disk_devices {
+        for device in &stats.disk_devices {
+            disk_io = disk_io.push(disk_device_row(device, row_spacing));
+        }
+    }
+
+    if let Some(graph) = section_graph(
+        config,
+        WidgetSection::DiskIo,
+        &[&stats.history.disk_read, &stats.history.disk_write],
+        GraphScale::Auto {
+            floor: RATE_GRAPH_FLOOR,
+        },
+    ) {
+        disk_io = disk_io.push(graph);
+    }
+
+    disk_io.into()
+}
+
+/// A drive's name, read and write IOPS, request latency and utilization.
+fn disk_device_row<'a>(device: &DiskDevice, spacing: u16) -> Element<'a, super::Message> {
+    widget::row::with_capacity(4)
+        .align_y(Alignment::Center)
+        .spacing(spacing)
+        .push(
+            widget::text::caption(compact_single_line(
+                device.display_name(),
+                DISK_DEVICE_NAME_CHARS,
+            ))
+            .width(Length::Fill),
+        )
+        .push(widget::text::caption(format!(
+            "{}/{} IOPS",
+            format_iops(device.read_iops),
+            format_iops(device.write_iops)
+        )))
+        .push(widget::text::monotext(format_latency(device.latency_ms)))
+        .push(widget::text::monotext(format!(
+            "{:>3.0}%",
+            device.utilization
+        )))
+        .into()
 }
 
 fn temperature_view<'a>(
@@ -324,6 +991,21 @@ fn temperature_view<'a>(
     section_spacing: u16,
     gauge_spacing: u16,
 ) -> Element<'a, super::Message> {
+    let history = &stats.history;
+    let series: Vec<&MetricHistory> = [
+        (config.show_cpu_temp, &history.cpu_temp),
+        (config.show_gpu_temp, &history.gpu_temp),
+    ]
+    .into_iter()
+    .filter_map(|(shown, history)| shown.then_some(history))
+    .collect();
+    let graph = section_graph(
+        config,
+        WidgetSection::Temperatures,
+        &series,
+        GraphScale::Fixed(100.0),
+    );
+
     if config.temperature_gauge_style == crate::config::TemperatureGaugeStyle::Text {
         let mut temperatures = section_with_icon(
             embedded_symbolic_icon(
@@ -349,6 +1031,9 @@ fn temperature_view<'a>(
                 gauge_spacing,
             ));
         }
+        if let Some(graph) = graph {
+            temperatures = temperatures.push(graph);
+        }
         return temperatures.into();
     }
 
@@ -371,7 +1056,7 @@ fn temperature_view<'a>(
         ));
     }
 
-    section_with_icon(
+    let mut temperatures = section_with_icon(
         embedded_symbolic_icon(
             include_bytes!("../../assets/icons/temperature-filled-symbolic.svg"),
             18,
@@ -379,8 +1064,12 @@ fn temperature_view<'a>(
         "Temperatures",
         section_spacing,
     )
-    .push(widget::container(gauges).center_x(Length::Fill))
-    .into()
+    .push(widget::container(gauges).center_x(Length::Fill));
+    if let Some(graph) = graph {
+        temperatures = temperatures.push(graph);
+    }
+
+    temperatures.into()
 }
 
 fn storage_view<'a>(
@@ -395,11 +1084,18 @@ fn storage_view<'a>(
         section_spacing,
     );
 
-    if stats.disks.is_empty() {
+    let disks = storage_rows(config, stats);
+    if disks.is_empty() {
         storage = storage.push(widget::text::caption("No mounted storage found"));
     } else {
-        for disk in &stats.disks {
-            storage = storage.push(storage_item(disk, config.show_percentages, item_spacing));
+        for disk in &disks {
+            storage = storage.push(storage_item(
+                disk,
+                disk_drive_health(config, stats, disk),
+                disk_pool_health(config, stats, disk),
+                config.show_percentages,
+                item_spacing,
+            ));
         }
     }
 
@@ -445,6 +1141,273 @@ fn weather_view<'a>(
     weather.into()
 }
 
+fn commands_view<'a>(
+    stats: &'a SystemSnapshot,
+    section_spacing: u16,
+    cell_spacing: u16,
+) -> Element<'a, super::Message> {
+    let mut commands = section("utilities-terminal-symbolic", "Commands", section_spacing);
+
+    if stats.commands.is_empty() {
+        commands = commands.push(widget::text::caption("No commands configured"));
+    } else {
+        for result in &stats.commands {
+            commands = commands.push(command_item(result, cell_spacing));
+        }
+    }
+
+    commands.into()
+}
+
+fn processes_view<'a>(
+    config: &Config,
+    stats: &'a SystemSnapshot,
+    pending_signal: Option<&'a super::ProcessTarget>,
+    section_spacing: u16,
+    row_spacing: u16,
+) -> Element<'a, super::Message> {
+    let mut processes = section("system-run-symbolic", "Top Processes", section_spacing);
+
+    if let Some(target) = pending_signal {
+        processes = processes.push(process_signal_prompt(target, row_spacing));
+    }
+    for (heading, list) in process_lists(config, stats) {
+        let mut rows = widget::column::with_capacity(list.len() + 1)
+            .spacing(row_spacing)
+            .push(widget::text::caption_heading(heading));
+        for process in list {
+            rows = rows.push(process_row(process, row_spacing));
+        }
+        processes = processes.push(rows);
+    }
+
+    processes.into()
+}
+
+/// The CPU and memory lists, cut to the configured length.
+pub(super) fn process_lists<'a>(
+    config: &Config,
+    stats: &'a SystemSnapshot,
+) -> [(&'static str, &'a [ProcessUsage]); 2] {
+    let top = if config.group_process_trees {
+        &stats.top_process_trees
+    } else {
+        &stats.top_processes
+    };
+    let count = config.top_process_count();
+    [
+        ("By CPU", &top.by_cpu[..top.by_cpu.len().min(count)]),
+        (
+            "By memory",
+            &top.by_memory[..top.by_memory.len().min(count)],
+        ),
+    ]
+}
+
+/// Name, PID, CPU usage and resident memory. A right click asks whether to
+/// end the process.
+fn process_row<'a>(process: &ProcessUsage, spacing: u16) -> Element<'a, super::Message> {
+    let name = if process.count > 1 {
+        format!("{} ×{}", process.name, process.count)
+    } else {
+        process.name.clone()
+    };
+    let row = widget::row::with_capacity(4)
+        .align_y(Alignment::Center)
+        .spacing(spacing)
+        .push(
+            widget::text::caption(compact_single_line(&name, PROCESS_NAME_CHARS))
+                .width(Length::Fill),
+        )
+        .push(widget::text::caption(process.pid.to_string()))
+        .push(widget::text::monotext(format!(
+            "{:>5.1}%",
+            process.cpu_usage
+        )))
+        .push(
+            widget::text::caption(format_storage_bytes(process.memory))
+                .width(Length::Fixed(PROCESS_MEMORY_WIDTH)),
+        );
+
+    widget::mouse_area(row)
+        .on_right_press(super::Message::ConfirmProcessSignal(super::ProcessTarget {
+            pid: process.pid,
+            name: process.name.clone(),
+            count: process.count,
+            start_time: None,
+        }))
+        .into()
+}
+
+fn process_signal_prompt<'a>(
+    target: &super::ProcessTarget,
+    spacing: u16,
+) -> Element<'a, super::Message> {
+    widget::column::with_capacity(2)
+        .spacing(spacing)
+        .push(widget::text::body(process_signal_question(target)))
+        .push(
+            widget::row::with_capacity(3)
+                .spacing(spacing)
+                .push(widget::button::standard("Terminate").on_press(
+                    super::Message::SignalProcess {
+                        pid: target.pid,
+                        force: false,
+                    },
+                ))
+                .push(
+                    widget::button::destructive("Kill").on_press(super::Message::SignalProcess {
+                        pid: target.pid,
+                        force: true,
+                    }),
+                )
+                .push(
+                    widget::button::standard("Cancel")
+                        .on_press(super::Message::CancelProcessSignal),
+                ),
+        )
+        .into()
+}
+
+/// Asks to end the process, naming the root of a grouped tree as such, as
+/// the other processes of the tree are not signalled.
+fn process_signal_question(target: &super::ProcessTarget) -> String {
+    let name = compact_single_line(&target.name, PROCESS_NAME_CHARS);
+    if target.count > 1 {
+        format!("End root process {name} ({})?", target.pid)
+    } else {
+        format!("End {name} ({})?", target.pid)
+    }
+}
+
+fn command_item<'a>(result: &'a CommandResult, spacing: u16) -> Element<'a, super::Message> {
+    let first_line = result.stdout.lines().find(|line| !line.trim().is_empty());
+    let summary = match (&result.status, first_line) {
+        (CommandStatus::Pending, _) => "Running...".to_string(),
+        (_, Some(line)) if !result.table => compact_single_line(line, COMMAND_OUTPUT_CHARS),
+        (status, _) if !status.succeeded() => status.description(),
+        (_, None) if !result.table => "No output".to_string(),
+        _ => String::new(),
+    };
+    let heading = widget::row::with_capacity(2)
+        .align_y(Alignment::Center)
+        .spacing(spacing)
+        .push(
+            widget::text::caption_heading(compact_single_line(&result.label, COMMAND_LABEL_CHARS))
+                .width(Length::Fill),
+        )
+        .push(widget::text::body(summary));
+
+    if !result.table {
+        return heading.into();
+    }
+
+    let rows = command_table(&result.stdout);
+    let mut table = widget::column::with_capacity(rows.len() + 1).push(heading);
+    for cells in rows {
+        table = table.push(cells.into_iter().fold(
+            widget::row::with_capacity(MAX_COMMAND_TABLE_COLUMNS).spacing(spacing),
+            |row, cell| {
+                row.push(
+                    widget::text::monotext(cell.to_string())
+                        .size(12)
+                        .width(Length::FillPortion(1)),
+                )
+            },
+        ));
+    }
+    table.into()
+}
+
+/// Cells of the first non-empty output lines, split at whitespace. The last
+/// column keeps the rest of its line.
+pub(super) fn command_table(output: &str) -> Vec<Vec<&str>> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(super::MAX_COMMAND_TABLE_ROWS)
+        .map(|line| {
+            let mut cells = Vec::with_capacity(MAX_COMMAND_TABLE_COLUMNS);
+            let mut rest = line.trim();
+            while !rest.is_empty() {
+                if cells.len() + 1 == MAX_COMMAND_TABLE_COLUMNS {
+                    cells.push(rest);
+                    break;
+                }
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                cells.push(&rest[..end]);
+                rest = rest[end..].trim_start();
+            }
+            cells
+        })
+        .collect()
+}
+
+/// Parsed templates of the profile's custom sections, by section ID.
+pub(super) fn parse_templates(config: &Config) -> HashMap<u32, Template> {
+    config
+        .custom_sections
+        .iter()
+        .map(|custom| (custom.id, Template::parse(&custom.template)))
+        .collect()
+}
+
+fn custom_view<'a>(
+    custom: &'a CustomSection,
+    template: &Template,
+    stats: &SystemSnapshot,
+    now: DateTime<Local>,
+    section_spacing: u16,
+    line_spacing: u16,
+) -> Element<'a, super::Message> {
+    let title = custom.title.trim();
+    let readings = template_readings(stats);
+    let values = ReadingValues {
+        readings: &readings,
+        now,
+    };
+    let lines = template::lines_view(template.render(&values), line_spacing);
+
+    if title.is_empty() {
+        return lines.into();
+    }
+    section("text-x-generic-symbolic", title, section_spacing)
+        .push(lines)
+        .into()
+}
+
+/// Template readings from the latest snapshot.
+fn template_readings(stats: &SystemSnapshot) -> Readings {
+    Readings {
+        cpu: f64::from(stats.cpu_usage),
+        memory: f64::from(stats.memory_usage),
+        memory_bytes: Some((stats.memory_used, stats.memory_total)),
+        gpu: f64::from(stats.gpu_usage),
+        cpu_temp: f64::from(stats.cpu_temp),
+        gpu_temp: f64::from(stats.gpu_temp),
+        network_rx_rate: stats.network_rx_rate,
+        network_tx_rate: stats.network_tx_rate,
+        disk_read_rate: stats.disk_read_rate,
+        disk_write_rate: stats.disk_write_rate,
+        disks: stats
+            .storage_mounts
+            .iter()
+            .map(|disk| {
+                (
+                    disk.mount_point.clone(),
+                    f64::from(disk.used_percentage),
+                    disk.available_space,
+                )
+            })
+            .collect(),
+        batteries: stats
+            .devices
+            .iter()
+            .map(|device| (device.name.clone(), device.level))
+            .collect(),
+    }
+}
+
 fn notifications_view<'a>(
     stats: &'a SystemSnapshot,
     expanded_notification_group: Option<&'a str>,
@@ -1315,22 +2278,170 @@ fn metric<'a>(
     metric_row.into()
 }
 
-fn storage_item<'a>(
-    disk: &'a DiskInfo,
+/// Storage rows of a profile, arranged by its storage rules.
+pub(super) fn storage_rows(config: &Config, stats: &SystemSnapshot) -> Vec<DiskInfo> {
+    arrange_disks(
+        &stats.storage_mounts,
+        &config.storage_rules,
+        &config.storage_labels,
+        config.storage_sort,
+    )
+}
+
+/// Health of the drive below `disk`, if the profile shows drive health.
+pub(super) fn disk_drive_health<'a>(
+    config: &Config,
+    stats: &'a SystemSnapshot,
+    disk: &DiskInfo,
+) -> Option<&'a DriveHealth> {
+    if !config.show_drive_health {
+        return None;
+    }
+    stats.drive_health.get(disk.device.as_deref()?)
+}
+
+/// Btrfs filesystem, ZFS pool or md array below `disk`, if the profile
+/// shows pool health.
+pub(super) fn disk_pool_health<'a>(
+    config: &Config,
+    stats: &'a SystemSnapshot,
+    disk: &DiskInfo,
+) -> Option<&'a PoolHealth> {
+    if !config.show_pool_health {
+        return None;
+    }
+    stats.pool_health.get(&disk.mount_point)
+}
+
+/// State and activity of a pool, e.g. "md0 raid1 · 1 of 2 devices ·
+/// recovery 8.5%" or "tank ONLINE · scrub 23.4%".
+fn pool_health_summary(pool: &PoolHealth) -> String {
+    let mut name = pool.name.clone();
+    for word in [&pool.level, &pool.state].into_iter().flatten() {
+        name.push(' ');
+        name.push_str(word);
+    }
+    let mut parts = vec![name];
+    if let Some((working, expected)) = pool.devices
+        && (expected > 1 || working < expected)
+    {
+        parts.push(format!("{working} of {expected} devices"));
+    }
+    if let Some(activity) = &pool.activity {
+        parts.push(match activity.percent {
+            Some(percent) => format!("{} {percent:.1}%", activity.operation),
+            None => activity.operation.clone(),
+        });
+    }
+    if pool.kind == PoolKind::Btrfs {
+        if let Some((used, allocated)) = pool.data {
+            parts.push(format!(
+                "data {} of {}",
+                format_storage_bytes(used),
+                format_storage_bytes(allocated)
+            ));
+        }
+        if let Some((used, allocated)) = pool.metadata {
+            parts.push(format!(
+                "metadata {} of {}",
+                format_storage_bytes(used),
+                format_storage_bytes(allocated)
+            ));
+        }
+        if let Some(errors) = pool.errors {
+            parts.push(format!("{errors} errors"));
+        }
+    }
+    parts.join(" · ")
+}
+
+/// Red tint and text for the row of a degraded pool.
+fn degraded_pool_class() -> theme::Container<'static> {
+    theme::Container::custom(|theme| {
+        let cosmic = theme.cosmic();
+        let destructive: Color = cosmic.destructive_color().into();
+        let mut background = destructive;
+        background.a = 0.12;
+
+        cosmic::iced::widget::container::Style {
+            icon_color: Some(destructive),
+            text_color: Some(destructive),
+            background: Some(Background::Color(background)),
+            border: Border {
+                color: destructive,
+                width: 1.0,
+                radius: cosmic.corner_radii.radius_s.into(),
+            },
+            ..Default::default()
+        }
+    })
+}
+
+/// Temperature, wear and error counts of a drive, e.g.
+/// "38 °C · 4% used · 100% spare · 0 errors · 12345 h".
+fn drive_health_summary(health: &DriveHealth) -> String {
+    let mut parts = Vec::new();
+    if let Some(temperature) = health.temperature {
+        parts.push(format!("{temperature:.0} °C"));
+    }
+    if let Some(used) = health.percentage_used {
+        parts.push(format!("{used}% used"));
+    }
+    if let Some(spare) = health.available_spare {
+        parts.push(format!("{spare}% spare"));
+    }
+    if let Some(errors) = health.media_errors {
+        parts.push(format!("{errors} errors"));
+    }
+    if let Some(sectors) = health.reallocated_sectors {
+        parts.push(format!("{sectors} reallocated"));
+    }
+    if let Some(sectors) = health.pending_sectors {
+        parts.push(format!("{sectors} pending"));
+    }
+    if let Some(hours) = health.power_on_hours {
+        parts.push(format!("{hours} h"));
+    }
+    if !health.smart_available() {
+        parts.push("SMART unavailable".to_string());
+    }
+    parts.join(" · ")
+}
+
+fn drive_warning_badge() -> Element<'static, super::Message> {
+    widget::icon::from_name("dialog-warning-symbolic")
+        .icon()
+        .size(METRIC_ICON_SIZE)
+        .class(theme::Svg::custom(|theme| {
+            cosmic::iced::widget::svg::Style {
+                color: Some(theme.cosmic().warning_color().into()),
+            }
+        }))
+        .into()
+}
+
+fn storage_item(
+    disk: &DiskInfo,
+    health: Option<&DriveHealth>,
+    pool: Option<&PoolHealth>,
     show_percentage: bool,
     spacing: u16,
-) -> Element<'a, super::Message> {
+) -> Element<'static, super::Message> {
     let percentage = disk.used_percentage.clamp(0.0, 100.0);
-    let mut title = widget::row::with_capacity(2)
+    let mut title = widget::row::with_capacity(3)
         .align_y(Alignment::Center)
         .spacing(spacing)
-        .push(widget::text::body(&disk.name).width(Length::Fill));
+        .push(widget::text::body(disk.name.clone()).width(Length::Fill));
+
+    if health.is_some_and(DriveHealth::degraded) {
+        title = title.push(drive_warning_badge());
+    }
 
     if show_percentage {
         title = title.push(widget::text::monotext(format!("{percentage:.1}%")));
     }
 
-    let details = if disk.is_loading || disk.total_space == 0 {
+    let mut details = if disk.is_loading || disk.total_space == 0 {
         "Loading...".to_string()
     } else {
         let used = disk.total_space.saturating_sub(disk.available_space);
@@ -1340,8 +2451,11 @@ fn storage_item<'a>(
             format_storage_bytes(disk.total_space)
         )
     };
+    if !disk.subvolumes.is_empty() {
+        details = format!("{} subvolumes · {details}", disk.subvolumes.len() + 1);
+    }
 
-    widget::column::with_capacity(3)
+    let mut item = widget::column::with_capacity(4)
         .spacing(spacing)
         .push(title)
         .push(gauge::indicator_bar(percentage))
@@ -1349,8 +2463,21 @@ fn storage_item<'a>(
             widget::row::with_capacity(2)
                 .push(widget::space::horizontal())
                 .push(widget::text::caption(details)),
-        )
-        .into()
+        );
+    if let Some(health) = health {
+        item = item.push(widget::text::caption(drive_health_summary(health)));
+    }
+    if let Some(pool) = pool {
+        item = item.push(widget::text::caption(pool_health_summary(pool)));
+    }
+
+    match pool {
+        Some(pool) if pool.degraded => widget::container(item)
+            .padding([DEGRADED_POOL_PADDING, 4])
+            .class(degraded_pool_class())
+            .into(),
+        _ => item.into(),
+    }
 }
 
 fn device_item<'a>(device: &'a BatteryDevice, spacing: u16) -> Element<'a, super::Message> {
@@ -1491,45 +2618,21 @@ fn is_charging(status: Option<&str>) -> bool {
     })
 }
 
-fn format_storage_bytes(bytes: u64) -> String {
-    const KB: f64 = 1_000.0;
-    const MB: f64 = KB * 1_000.0;
-    const GB: f64 = MB * 1_000.0;
-    const TB: f64 = GB * 1_000.0;
-
-    let bytes = bytes as f64;
-    if bytes >= TB {
-        format!("{:.1} TB", bytes / TB)
-    } else if bytes >= GB {
-        format!("{:.0} GB", bytes / GB)
-    } else if bytes >= MB {
-        format!("{:.0} MB", bytes / MB)
-    } else if bytes >= KB {
-        format!("{:.0} KB", bytes / KB)
+/// Requests per second, in thousands from 10,000 on.
+fn format_iops(iops: f64) -> String {
+    if iops >= 10_000.0 {
+        format!("{:.0}k", iops / 1_000.0)
     } else {
-        format!("{bytes:.0} B")
+        format!("{iops:.0}")
     }
 }
 
-fn format_network_rate(bytes_per_second: f64) -> String {
-    const KB: f64 = 1_024.0;
-    const MB: f64 = KB * 1_024.0;
-    const GB: f64 = MB * 1_024.0;
-
-    let rate = if bytes_per_second.is_finite() {
-        bytes_per_second.max(0.0)
+/// Average request latency, with a decimal below 10 ms.
+fn format_latency(milliseconds: f64) -> String {
+    if milliseconds >= 10.0 {
+        format!("{milliseconds:>4.0} ms")
     } else {
-        0.0
-    };
-
-    if rate >= GB {
-        format!("{:.1} GB/s", rate / GB)
-    } else if rate >= MB {
-        format!("{:.1} MB/s", rate / MB)
-    } else if rate >= KB {
-        format!("{:.1} KB/s", rate / KB)
-    } else {
-        format!("{rate:.0} B/s")
+        format!("{milliseconds:>4.1} ms")
     }
 }
 
@@ -1611,12 +2714,230 @@ fn format_time_parts(now: DateTime<Local>, use_24hour_time: bool) -> (String, St
 mod tests {
     use super::{
         BatteryBand, NotificationBand, battery_band, battery_icon_name, battery_visuals,
-        compact_single_line, format_media_time, format_network_rate, format_storage_bytes,
-        format_weather_temperature, is_charging, media_subtitle, notification_band,
-        relative_notification_time, weather_icon_name,
+        command_table, compact_single_line, drive_health_summary, format_core_frequency,
+        format_iops, format_latency, format_media_time, format_network_rate, format_storage_bytes,
+        format_weather_temperature, gpu_detail_rows, is_charging, media_subtitle,
+        memory_detail_rows, network_connection_rows, network_link_label, network_traffic_rows,
+        notification_band, pool_health_summary, relative_notification_time, weather_icon_name,
     };
     use crate::battery::BatteryDevice;
+    use crate::drive_health::DriveHealth;
+    use crate::gpu::{GpuDetails, GpuDevice, GpuFan, GpuVendor};
     use crate::media::MediaInfo;
+    use crate::network::NetworkInterface;
+    use crate::network_manager::{
+        AccessPoint, ActiveConnection, NetworkConnections, VpnConnection, VpnState,
+    };
+    use crate::pool_health::{PoolActivity, PoolHealth, PoolKind};
+    use crate::traffic::{InterfaceTraffic, TrafficBytes, TrafficSummary};
+    use crate::utilization::{CpuCore, MemoryDetails, ZramUsage};
+
+    #[test]
+    fn gpu_details_leave_out_unreported_readings() {
+        let mut gpu = GpuDevice {
+            id: "0000:01:00.0".to_string(),
+            name: "RTX 4090".to_string(),
+            vendor: GpuVendor::Nvidia,
+            usage: Some(97.0),
+            temperature: Some(71.0),
+            details: GpuDetails {
+                vram_used: Some(18_000_000_000),
+                vram_total: Some(24_000_000_000),
+                power_watts: Some(412.4),
+                core_clock_mhz: Some(2520),
+                memory_clock_mhz: Some(10501),
+                fan: Some(GpuFan::Percent(64)),
+            },
+        };
+
+        assert_eq!(
+            gpu_detail_rows(&gpu),
+            vec![
+                ("VRAM", "18 GB / 24 GB (75%)".to_string()),
+                ("Power", "412.4 W".to_string()),
+                ("Clocks", "2520 MHz core, 10501 MHz memory".to_string()),
+                ("Fan", "64%".to_string()),
+            ]
+        );
+
+        gpu.details = GpuDetails {
+            core_clock_mhz: Some(1300),
+            ..GpuDetails::default()
+        };
+        assert_eq!(
+            gpu_detail_rows(&gpu),
+            vec![("Clocks", "1300 MHz core".to_string())]
+        );
+    }
+
+    #[test]
+    fn network_link_labels_show_speed_and_state() {
+        let mut interface = NetworkInterface {
+            name: "enp5s0".to_string(),
+            physical: true,
+            rx_rate: 0.0,
+            tx_rate: 0.0,
+            speed_mbps: Some(2_500),
+            operstate: "up".to_string(),
+        };
+        assert_eq!(network_link_label(&interface), "2.5 Gb/s · up");
+
+        interface.speed_mbps = Some(1_000);
+        assert_eq!(network_link_label(&interface), "1 Gb/s · up");
+
+        interface.speed_mbps = Some(100);
+        assert_eq!(network_link_label(&interface), "100 Mb/s · up");
+
+        interface.speed_mbps = None;
+        interface.operstate = "down".to_string();
+        assert_eq!(network_link_label(&interface), "down");
+    }
+
+    #[test]
+    fn network_connection_rows_list_the_primary_connection_and_vpns() {
+        let mut connections = NetworkConnections {
+            primary: Some(ActiveConnection {
+                name: "Home".to_string(),
+                interface: Some("wlan0".to_string()),
+                wifi: Some(AccessPoint {
+                    ssid: "Home".to_string(),
+                    strength: 81,
+                    frequency_mhz: 2_437,
+                }),
+                ipv4: vec!["192.168.1.20/24".to_string()],
+                ipv6: Vec::new(),
+                gateway: Some("192.168.1.1".to_string()),
+            }),
+            vpns: vec![VpnConnection {
+                name: "Office".to_string(),
+                wireguard: true,
+                state: VpnState::Down,
+            }],
+        };
+
+        assert_eq!(
+            network_connection_rows(&connections),
+            vec![
+                ("Connection", "Home (wlan0)".to_string()),
+                ("Wi-Fi", "Home · 81% · 2.4 GHz".to_string()),
+                ("IPv4", "192.168.1.20/24".to_string()),
+                ("Gateway", "192.168.1.1".to_string()),
+                ("WireGuard", "Office · down".to_string()),
+            ]
+        );
+
+        connections.primary = None;
+        assert_eq!(network_connection_rows(&connections).len(), 1);
+    }
+
+    #[test]
+    fn network_traffic_rows_sum_the_shown_interfaces() {
+        const GB: u64 = 1_000_000_000;
+        let traffic = |name: &str, rx: u64| InterfaceTraffic {
+            name: name.to_string(),
+            today: TrafficBytes { rx, tx: 0 },
+            month: TrafficBytes { rx: 2 * rx, tx: 0 },
+            cycle: TrafficBytes { rx: 3 * rx, tx: 0 },
+        };
+        let mut stats = super::SystemSnapshot {
+            network_interfaces: vec![NetworkInterface {
+                name: "enp5s0".to_string(),
+                physical: true,
+                rx_rate: 0.0,
+                tx_rate: 0.0,
+                speed_mbps: None,
+                operstate: "up".to_string(),
+            }],
+            network_traffic: TrafficSummary {
+                interfaces: vec![traffic("enp5s0", GB), traffic("wg0", GB)],
+                cycle_start: "2026-03-01".to_string(),
+            },
+            ..Default::default()
+        };
+        let config = crate::config::Config::default();
+
+        assert_eq!(
+            network_traffic_rows(&config, &stats),
+            vec![
+                ("Today", "↓ 1 GB  ↑ 0 B".to_string()),
+                ("This month", "↓ 2 GB  ↑ 0 B".to_string()),
+            ]
+        );
+
+        stats.network_traffic.cycle_start = "2026-02-25".to_string();
+        stats.network_cycle_bytes = 3 * GB;
+        stats.network_quota_bytes = 12 * GB;
+        let rows = network_traffic_rows(&config, &stats);
+        assert_eq!(rows[2], ("Billing cycle", "↓ 3 GB  ↑ 0 B".to_string()));
+        assert_eq!(rows[3], ("Quota", "3 GB of 12 GB · 25%".to_string()));
+    }
+
+    #[test]
+    fn memory_details_list_swap_zram_and_cache_in_bytes() {
+        let mut stats = super::SystemSnapshot {
+            memory_used: 8_000_000_000,
+            memory_total: 16_000_000_000,
+            memory_details: MemoryDetails {
+                swap_total: 4_000_000_000,
+                swap_used: 1_000_000_000,
+                zram: Some(ZramUsage {
+                    original: 900_000_000,
+                    compressed: 300_000_000,
+                }),
+                cached: 3_000_000_000,
+                buffers: 200_000_000,
+                pressure: None,
+            },
+            ..super::SystemSnapshot::default()
+        };
+
+        assert_eq!(
+            memory_detail_rows(&stats),
+            [
+                ("Used", "8 GB / 16 GB".to_string()),
+                ("Swap", "1 GB / 4 GB (25%)".to_string()),
+                ("zram", "900 MB in 300 MB (3.0x)".to_string()),
+                ("Cache", "3 GB + 200 MB buffers".to_string()),
+            ]
+        );
+
+        stats.memory_details.swap_total = 0;
+        stats.memory_details.zram = None;
+        assert_eq!(memory_detail_rows(&stats)[1], ("Swap", "None".to_string()));
+        assert_eq!(memory_detail_rows(&stats).len(), 3);
+    }
+
+    #[test]
+    fn command_tables_keep_the_rest_of_long_lines_in_the_last_column() {
+        let output = "/dev/sda1  ext4  40G  /\n\n/dev/sdb1 btrfs 2T /mnt/backup drive\n";
+
+        assert_eq!(
+            command_table(output),
+            vec![
+                vec!["/dev/sda1", "ext4", "40G", "/"],
+                vec!["/dev/sdb1", "btrfs", "2T", "/mnt/backup drive"],
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_core_frequencies_in_gigahertz() {
+        let core = CpuCore {
+            frequency_mhz: 3_400,
+            max_frequency_mhz: 5_000,
+            ..CpuCore::default()
+        };
+
+        assert_eq!(format_core_frequency(&core), "3.4/5.0 GHz");
+        assert_eq!(
+            format_core_frequency(&CpuCore {
+                max_frequency_mhz: 0,
+                ..core.clone()
+            }),
+            "3.4 GHz"
+        );
+        assert_eq!(format_core_frequency(&CpuCore::default()), "-");
+    }
 
     #[test]
     fn formats_storage_capacities_for_compact_display() {
@@ -1634,6 +2955,95 @@ mod tests {
         assert_eq!(format_network_rate(f64::NAN), "0 B/s");
     }
 
+    #[test]
+    fn drive_health_summary_lists_readings_or_marks_smart_unavailable() {
+        let nvme = DriveHealth {
+            drive: "nvme0n1".to_string(),
+            temperature: Some(38.4),
+            critical_warning: Some(0),
+            percentage_used: Some(4),
+            available_spare: Some(100),
+            available_spare_threshold: Some(10),
+            media_errors: Some(0),
+            power_on_hours: Some(12_345),
+            ..DriveHealth::default()
+        };
+        assert_eq!(
+            drive_health_summary(&nvme),
+            "38 °C · 4% used · 100% spare · 0 errors · 12345 h"
+        );
+
+        let sata = DriveHealth {
+            drive: "sda".to_string(),
+            temperature: Some(41.0),
+            reallocated_sectors: Some(0),
+            pending_sectors: Some(2),
+            power_on_hours: Some(23_456),
+            ..DriveHealth::default()
+        };
+        assert_eq!(
+            drive_health_summary(&sata),
+            "41 °C · 0 reallocated · 2 pending · 23456 h"
+        );
+
+        let unprivileged = DriveHealth {
+            temperature: Some(35.0),
+            ..DriveHealth::default()
+        };
+        assert_eq!(
+            drive_health_summary(&unprivileged),
+            "35 °C · SMART unavailable"
+        );
+    }
+
+    #[test]
+    fn pool_health_summary_shows_members_progress_and_btrfs_chunks() {
+        let array = PoolHealth {
+            kind: PoolKind::Md,
+            name: "md1".to_string(),
+            level: Some("raid5".to_string()),
+            state: Some("active".to_string()),
+            devices: Some((2, 3)),
+            degraded: true,
+            activity: Some(PoolActivity {
+                operation: "recovery".to_string(),
+                percent: Some(8.46),
+            }),
+            errors: None,
+            data: None,
+            metadata: None,
+        };
+        assert_eq!(
+            pool_health_summary(&array),
+            "md1 raid5 active · 2 of 3 devices · recovery 8.5%"
+        );
+
+        let btrfs = PoolHealth {
+            kind: PoolKind::Btrfs,
+            name: "data".to_string(),
+            level: Some("single".to_string()),
+            state: None,
+            devices: Some((1, 1)),
+            degraded: false,
+            activity: None,
+            errors: Some(0),
+            data: Some((120_000_000_000, 150_000_000_000)),
+            metadata: Some((2_000_000_000, 4_000_000_000)),
+        };
+        assert_eq!(
+            pool_health_summary(&btrfs),
+            "data single · data 120 GB of 150 GB · metadata 2 GB of 4 GB · 0 errors"
+        );
+    }
+
+    #[test]
+    fn formats_disk_iops_and_latency() {
+        assert_eq!(format_iops(42.4), "42");
+        assert_eq!(format_iops(25_300.0), "25k");
+        assert_eq!(format_latency(0.42), " 0.4 ms");
+        assert_eq!(format_latency(17.6), "  18 ms");
+    }
+
     #[test]
     fn battery_visuals_follow_level_and_charging_state() {
         assert_eq!(battery_band(82), BatteryBand::Success);
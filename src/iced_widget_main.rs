@@ -6,22 +6,38 @@
 mod battery;
 #[path = "widget/cache.rs"]
 mod cache;
+#[path = "widget/commands.rs"]
+mod commands;
 mod config;
 #[path = "widget/disk_io.rs"]
 mod disk_io;
+#[path = "widget/drive_health.rs"]
+mod drive_health;
+#[path = "widget/gpu.rs"]
+mod gpu;
+#[path = "widget/gpu_processes.rs"]
+mod gpu_processes;
 mod iced_widget;
 #[path = "widget/media.rs"]
 mod media;
 #[path = "widget/network.rs"]
 mod network;
+#[path = "widget/network_manager.rs"]
+mod network_manager;
 #[path = "widget/notifications.rs"]
 mod notifications;
 #[path = "widget/nvidia.rs"]
 mod nvidia;
+mod outputs;
+#[path = "widget/pool_health.rs"]
+mod pool_health;
 #[path = "widget/storage.rs"]
 mod storage;
 #[path = "widget/temperature.rs"]
 mod temperature;
+mod template;
+#[path = "widget/traffic.rs"]
+mod traffic;
 #[path = "widget/utilization.rs"]
 mod utilization;
 #[path = "widget/weather.rs"]
@@ -30,23 +46,48 @@ mod widget_instance;
 mod widget_logging;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let _instance_guard = match widget_instance::try_acquire()? {
-        Some(guard) => guard,
-        None => {
-            eprintln!("cosmic-widget is already running; skipping duplicate launch");
-            return Ok(());
+    use iced_widget::headless::{self, Mode};
+
+    let mode = match headless::parse_args(std::env::args().skip(1)) {
+        Ok(mode) => mode,
+        Err(error) => {
+            eprintln!("{error}\n\n{}", headless::USAGE);
+            std::process::exit(2);
         }
     };
+    if mode == Mode::Help {
+        println!("{}", headless::USAGE);
+        return Ok(());
+    }
 
     use cosmic::cosmic_config::CosmicConfigEntry;
-    let logging_enabled = cosmic::cosmic_config::Config::new(
+    let mut config = cosmic::cosmic_config::Config::new(
         "com.github.zoliviragh.CosmicWidget",
         config::Config::VERSION,
     )
     .ok()
-    .and_then(|handler| config::Config::get_entry(&handler).ok())
-    .is_some_and(|config| config.enable_logging);
-    widget_logging::init(logging_enabled);
+    .map(|handler| config::Config::get_entry(&handler).unwrap_or_else(|(_errors, config)| config))
+    .unwrap_or_default();
+    config.ensure_all_sections();
+    config.migrate_legacy_position();
+
+    // Headless output runs alongside the overlay, so it does not take the
+    // instance lock.
+    if let Mode::Json { once, interval } = mode {
+        widget_logging::init(config.enable_logging);
+        headless::run(&config, once, interval)?;
+        return Ok(());
+    }
+
+    let _instance_guard = match widget_instance::try_acquire()? {
+        Some(guard) => guard,
+        None => {
+            eprintln!("cosmic-widget is already running; skipping duplicate launch");
+            return Ok(());
+        }
+    };
+
+    widget_logging::init(config.enable_logging);
     log::info!("Starting COSMIC Widget Iced overlay");
 
     iced_widget::run()?;
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Connected Wayland outputs, tracked from the compositor's output events.
+//!
+//! Overlays are placed on an output by its connector name (for example
+//! `DP-1`), which stays the same when resolutions or the arrangement of
+//! monitors change.
+
+use cosmic::cctk::sctk::reexports::client::protocol::wl_output::WlOutput;
+use cosmic::iced::event::{self, PlatformSpecific, wayland};
+use cosmic::iced::{Event, Subscription};
+
+pub use wayland::OutputEvent;
+
+/// An output the compositor has announced, with the names it is shown by.
+#[derive(Debug, Clone)]
+pub struct ConnectedOutput {
+    pub output: WlOutput,
+    /// Connector name, such as `DP-1` or `eDP-1`.
+    pub name: String,
+    /// Human-readable description, usually make and model.
+    pub description: String,
+}
+
+/// Outputs that are currently connected, in announcement order.
+#[derive(Debug, Clone, Default)]
+pub struct Outputs {
+    connected: Vec<ConnectedOutput>,
+}
+
+impl Outputs {
+    /// Apply an output event, returning whether any output was added,
+    /// renamed or removed.
+    pub fn apply(&mut self, event: OutputEvent, output: WlOutput) -> bool {
+        match event {
+            OutputEvent::Created(Some(info)) | OutputEvent::InfoUpdate(info) => {
+                let Some(name) = info.name.clone() else {
+                    return false;
+                };
+                let description = info
+                    .description
+                    .clone()
+                    .filter(|description| !description.trim().is_empty())
+                    .unwrap_or_else(|| format!("{} {}", info.make, info.model));
+                let connected = ConnectedOutput {
+                    output,
+                    name,
+                    description,
+                };
+
+                match self
+                    .connected
+                    .iter_mut()
+                    .find(|known| known.output == connected.output)
+                {
+                    Some(known) if known.name == connected.name => {
+                        known.description = connected.description;
+                        false
+                    }
+                    Some(known) => {
+                        *known = connected;
+                        true
+                    }
+                    None => {
+                        self.connected.push(connected);
+                        true
+                    }
+                }
+            }
+            OutputEvent::Created(None) => false,
+            OutputEvent::Removed => {
+                let count = self.connected.len();
+                self.connected.retain(|known| known.output != output);
+                self.connected.len() != count
+            }
+        }
+    }
+
+    /// The connected output with this connector name. An empty name never
+    /// matches, leaving the choice to the compositor.
+    pub fn find(&self, name: &str) -> Option<&ConnectedOutput> {
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        self.connected.iter().find(|known| known.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConnectedOutput> {
+        self.connected.iter()
+    }
+}
+
+/// Output events from the compositor.
+pub fn events() -> Subscription<(OutputEvent, WlOutput)> {
+    event::listen_with(|event, _status, _window| match event {
+        Event::PlatformSpecific(PlatformSpecific::Wayland(wayland::Event::Output(
+            event,
+            output,
+        ))) => Some((event, output)),
+        _ => None,
+    })
+}
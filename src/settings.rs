@@ -2,23 +2,85 @@
 
 //! Native COSMIC settings application for the desktop overlay.
 
-use crate::config::{Config, TemperatureGaugeStyle, WidgetSection};
+use crate::config::{
+    AlertComparison, AlertMetric, AlertRule, CONFIG_ID, Config, HISTORY_WINDOW_OPTIONS_SECS,
+    HistoryGraphStyle, MAX_BILLING_DAY, MAX_COMMAND_OUTPUT_BYTES, MAX_COMMAND_TIMEOUT_SECS,
+    MAX_POLL_INTERVAL_MS, MIN_POLL_INTERVAL_MS, OVERLAY_BUS_NAME, OVERLAY_INTERFACE,
+    OVERLAY_OBJECT_PATH, OverlayAnchor, PolledMonitor, ShellCommand, StorageSort,
+    TOP_PROCESS_COUNT_OPTIONS, TemperatureGaugeStyle, WidgetSection, instance_config_id,
+    is_valid_instance_name,
+};
+use crate::conky::{self, ImportReport};
+use crate::outputs::{OutputEvent, Outputs};
+use crate::template::{self, ReadingValues, Readings, Template, VARIABLES, Values};
+use chrono::Local;
+use cosmic::cctk::sctk::reexports::client::protocol::wl_output::WlOutput;
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
-use cosmic::iced::widget::canvas;
+use cosmic::iced::widget::{canvas, text_editor};
 use cosmic::iced::{
     Alignment, Color, Length, Point, Radians, Rectangle, Size, Subscription, mouse,
 };
 use cosmic::prelude::*;
 use cosmic::widget::{self, nav_bar};
 use cosmic::{Application, Element};
+use futures_util::SinkExt;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::f32::consts::PI;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use zbus::zvariant::OwnedValue;
 
-const CONFIG_APP_ID: &str = "com.github.zoliviragh.CosmicWidget";
 const PAGE_WIDTH: f32 = 720.0;
 const SHORT_INPUT_WIDTH: f32 = 140.0;
 const LONG_INPUT_WIDTH: f32 = 280.0;
+const TEMPLATE_EDITOR_HEIGHT: f32 = 140.0;
+const DEFAULT_CONKY_PATH: &str = "~/.config/conky/conky.conf";
+/// How often the Commands page re-reads the overlay's last command runs.
+const COMMAND_STATUS_REFRESH: Duration = Duration::from_secs(2);
+/// How often template previews read the running overlay's readings.
+const PREVIEW_READINGS_REFRESH: Duration = Duration::from_secs(2);
 const TEMPERATURE_STYLE_PREVIEW_HEIGHT: f32 = 104.0;
+const HISTORY_GRAPH_SECTIONS: [WidgetSection; 4] = [
+    WidgetSection::Utilization,
+    WidgetSection::Network,
+    WidgetSection::DiskIo,
+    WidgetSection::Temperatures,
+];
+const HISTORY_GRAPH_STYLE_LABELS: [&str; 3] = ["Off", "Sparkline", "Area"];
+const HISTORY_WINDOW_LABELS: [&str; 3] = ["5 minutes", "10 minutes", "15 minutes"];
+const TOP_PROCESS_COUNT_LABELS: [&str; TOP_PROCESS_COUNT_OPTIONS.len()] = ["3", "5", "8", "10"];
+const ALERT_METRIC_LABELS: [&str; AlertMetric::ALL.len()] = [
+    AlertMetric::CpuUsage.label(),
+    AlertMetric::MemoryUsage.label(),
+    AlertMetric::GpuUsage.label(),
+    AlertMetric::CpuTemperature.label(),
+    AlertMetric::GpuTemperature.label(),
+    AlertMetric::StorageUsage.label(),
+    AlertMetric::DeviceBattery.label(),
+    AlertMetric::NetworkQuota.label(),
+];
+const ALERT_COMPARISON_LABELS: [&str; AlertComparison::ALL.len()] = [
+    AlertComparison::Above.label(),
+    AlertComparison::Below.label(),
+];
+const STORAGE_SORT_LABELS: [&str; StorageSort::ALL.len()] = [
+    StorageSort::Discovered.label(),
+    StorageSort::Name.label(),
+    StorageSort::MountPoint.label(),
+    StorageSort::Usage.label(),
+    StorageSort::Size.label(),
+];
+const OVERLAY_ANCHOR_LABELS: [&str; OverlayAnchor::ALL.len()] = [
+    OverlayAnchor::TopLeft.label(),
+    OverlayAnchor::Top.label(),
+    OverlayAnchor::TopRight.label(),
+    OverlayAnchor::BottomLeft.label(),
+    OverlayAnchor::Bottom.label(),
+    OverlayAnchor::BottomRight.label(),
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct CachedBatteryDevice {
@@ -34,12 +96,90 @@ struct CachedBatteryDevice {
 struct CachedDiskInfo {
     name: String,
     mount_point: String,
+    #[serde(default)]
+    fs_type: String,
+    #[serde(default)]
+    source: String,
+    #[serde(default)]
+    device: Option<String>,
+}
+
+impl CachedDiskInfo {
+    fn device_names(&self) -> Vec<&str> {
+        [Some(self.source.as_str()), self.device.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct CachedGpu {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct CachedNetworkInterface {
+    name: String,
+    physical: bool,
+}
+
+/// How a command run ended, as written by the overlay to `commands.json`.
+#[derive(Debug, Clone, Deserialize)]
+enum CachedCommandStatus {
+    Pending,
+    Exited(i32),
+    Signaled(i32),
+    TimedOut,
+    Failed(String),
+}
+
+impl CachedCommandStatus {
+    fn description(&self) -> String {
+        match self {
+            Self::Pending => "Waiting for first run".to_string(),
+            Self::Exited(0) => "Succeeded".to_string(),
+            Self::Exited(code) => format!("Exit status {code}"),
+            Self::Signaled(signal) => format!("Killed by signal {signal}"),
+            Self::TimedOut => "Timed out".to_string(),
+            Self::Failed(error) => format!("Failed to start: {error}"),
+        }
+    }
+}
+
+/// Last run of a command in the overlay.
+#[derive(Debug, Clone, Deserialize)]
+struct CachedCommandResult {
+    command: String,
+    stderr: String,
+    status: CachedCommandStatus,
+    truncated: bool,
+}
+
+impl CachedCommandResult {
+    fn load_all() -> Vec<Self> {
+        let mut path = dirs::cache_dir().unwrap_or_else(|| std::path::PathBuf::from("/tmp"));
+        path.push("cosmic-widget-applet");
+        path.push("commands.json");
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct WidgetCache {
     disks: Vec<CachedDiskInfo>,
+    #[serde(default)]
+    storage_mounts: Vec<CachedDiskInfo>,
     battery_devices: Vec<CachedBatteryDevice>,
+    #[serde(default)]
+    gpus: Vec<CachedGpu>,
+    #[serde(default)]
+    network_interfaces: Vec<CachedNetworkInterface>,
 }
 
 impl WidgetCache {
@@ -58,11 +198,17 @@ impl WidgetCache {
             .unwrap_or_default()
     }
 
+    /// Replace the file through a temporary one, so the overlay never reads
+    /// a partial cache.
     fn save(&self) {
         let Ok(json) = serde_json::to_string_pretty(self) else {
             return;
         };
-        let _ = std::fs::write(Self::cache_path(), json);
+        let path = Self::cache_path();
+        let temporary = path.with_extension("json.settings.tmp");
+        if std::fs::write(&temporary, json).is_ok() && std::fs::rename(&temporary, &path).is_err() {
+            let _ = std::fs::remove_file(&temporary);
+        }
     }
 }
 
@@ -71,17 +217,28 @@ enum SettingsPage {
     Display,
     Layout,
     Services,
+    Alerts,
+    Commands,
     Behavior,
 }
 
 impl SettingsPage {
-    const ALL: [Self; 4] = [Self::Display, Self::Layout, Self::Services, Self::Behavior];
+    const ALL: [Self; 6] = [
+        Self::Display,
+        Self::Layout,
+        Self::Services,
+        Self::Alerts,
+        Self::Commands,
+        Self::Behavior,
+    ];
 
     const fn label(self) -> &'static str {
         match self {
             Self::Display => "Display",
             Self::Layout => "Layout",
             Self::Services => "Services",
+            Self::Alerts => "Alerts",
+            Self::Commands => "Commands",
             Self::Behavior => "Behavior",
         }
     }
@@ -91,6 +248,8 @@ impl SettingsPage {
             Self::Display => "preferences-appearance-symbolic",
             Self::Layout => "format-indent-more-symbolic",
             Self::Services => "preferences-system-symbolic",
+            Self::Alerts => "dialog-warning-symbolic",
+            Self::Commands => "utilities-terminal-symbolic",
             Self::Behavior => "preferences-startup-applications-symbolic",
         }
     }
@@ -101,27 +260,223 @@ pub struct SettingsApp {
     nav_model: nav_bar::Model,
     config: Config,
     config_handler: Option<cosmic_config::Config>,
+    /// Named overlay instance edited by the Display and Layout pages, or
+    /// `None` for the default overlay.
+    instance: Option<InstanceProfile>,
+    /// "Default" followed by the configured instance names.
+    instance_labels: Vec<String>,
+    instance_name_input: String,
+    outputs: Outputs,
+    /// "Automatic" followed by the connected outputs and, if it is not
+    /// connected, the output configured for the edited profile.
+    output_labels: Vec<String>,
+    /// Connector names matching `output_labels`, empty for "Automatic".
+    output_names: Vec<String>,
     x_input: String,
     y_input: String,
     weather_location_input: String,
     max_notifications_input: String,
+    metrics_bind_address_input: String,
+    metrics_port_input: String,
+    billing_day_input: String,
+    network_quota_input: String,
+    poll_interval_inputs: [String; PolledMonitor::ALL.len()],
+    alert_inputs: Vec<AlertRuleInputs>,
+    command_inputs: Vec<CommandInputs>,
+    /// Last runs reported by the overlay, refreshed while the Commands page
+    /// is open.
+    command_results: Vec<CachedCommandResult>,
+    /// Template editors of the edited profile's custom sections.
+    template_editors: Vec<text_editor::Content>,
+    /// Readings of the running overlay for the template previews, `None`
+    /// while it is not running.
+    overlay_readings: Option<Readings>,
+    conky_path_input: String,
+    /// Outcome of the last conky import, or why it failed.
+    conky_import: Option<Result<ImportReport, String>>,
     cider_api_token_input: String,
     cider_token_hidden: bool,
     cached_devices: Vec<CachedBatteryDevice>,
+    /// GPUs found by the overlay, for choosing which get a row.
+    cached_gpus: Vec<CachedGpu>,
+    /// Network interfaces found by the overlay, for choosing which are
+    /// shown and counted.
+    cached_network_interfaces: Vec<CachedNetworkInterface>,
+    /// Mounts found by the overlay, for choosing which are shown and what
+    /// they are called.
+    cached_storage_mounts: Vec<CachedDiskInfo>,
+}
+
+/// Text of the numeric and target inputs of one alert rule.
+#[derive(Debug, Clone, Default)]
+struct AlertRuleInputs {
+    threshold: String,
+    hysteresis: String,
+    duration: String,
+    cooldown: String,
+    target: String,
+}
+
+impl AlertRuleInputs {
+    fn new(rule: &AlertRule) -> Self {
+        Self {
+            threshold: rule.threshold.to_string(),
+            hysteresis: rule.hysteresis.to_string(),
+            duration: rule.duration_secs.to_string(),
+            cooldown: rule.cooldown_secs.to_string(),
+            target: rule.target.clone(),
+        }
+    }
+
+    fn get_mut(&mut self, field: AlertField) -> &mut String {
+        match field {
+            AlertField::Threshold => &mut self.threshold,
+            AlertField::Hysteresis => &mut self.hysteresis,
+            AlertField::Duration => &mut self.duration,
+            AlertField::Cooldown => &mut self.cooldown,
+            AlertField::Target => &mut self.target,
+        }
+    }
+}
+
+/// Editable text field of an alert rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertField {
+    Threshold,
+    Hysteresis,
+    Duration,
+    Cooldown,
+    Target,
+}
+
+/// Text of the inputs of one shell command.
+#[derive(Debug, Clone, Default)]
+struct CommandInputs {
+    label: String,
+    command: String,
+    interval: String,
+    timeout: String,
+    output_limit: String,
+}
+
+impl CommandInputs {
+    fn new(command: &ShellCommand) -> Self {
+        Self {
+            label: command.label.clone(),
+            command: command.command.clone(),
+            interval: command.interval_secs.to_string(),
+            timeout: command.timeout_secs.to_string(),
+            output_limit: command.max_output_bytes.to_string(),
+        }
+    }
+
+    fn get_mut(&mut self, field: CommandField) -> &mut String {
+        match field {
+            CommandField::Label => &mut self.label,
+            CommandField::Command => &mut self.command,
+            CommandField::Interval => &mut self.interval,
+            CommandField::Timeout => &mut self.timeout,
+            CommandField::OutputLimit => &mut self.output_limit,
+        }
+    }
+}
+
+/// Editable text field of a shell command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandField {
+    Label,
+    Command,
+    Interval,
+    Timeout,
+    OutputLimit,
+}
+
+/// Profile of a named overlay instance.
+struct InstanceProfile {
+    name: String,
+    config: Config,
+    handler: Option<cosmic_config::Config>,
+}
+
+impl InstanceProfile {
+    fn load(name: String) -> Self {
+        let handler = cosmic_config::Config::new(&instance_config_id(&name), Config::VERSION).ok();
+        let mut config = handler
+            .as_ref()
+            .map(|handler| Config::get_entry(handler).unwrap_or_else(|(_errors, config)| config))
+            .unwrap_or_default();
+        config.ensure_all_sections();
+        config.migrate_legacy_position();
+        config.ensure_position_defaults();
+
+        Self {
+            name,
+            config,
+            handler,
+        }
+    }
+
+    fn save(&self) {
+        let Some(handler) = &self.handler else {
+            return;
+        };
+        if let Err(error) = self.config.write_entry(handler) {
+            log::error!("Failed to save overlay instance {}: {error}", self.name);
+        }
+    }
+
+    /// Write the defaults over the stored profile, so an instance created
+    /// later under the same name does not bring back its settings.
+    fn reset(&self) {
+        let Some(handler) = &self.handler else {
+            return;
+        };
+        if let Err(error) = Config::default().write_entry(handler) {
+            log::error!("Failed to reset overlay instance {}: {error}", self.name);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     UpdateConfig(Config),
+    UpdateInstanceConfig(String, Config),
+    SelectInstance(usize),
+    UpdateInstanceName(String),
+    CreateInstance,
+    DuplicateInstance,
+    DeleteInstance,
+    Output(OutputEvent, WlOutput),
+    SelectOutput(usize),
+    SelectAnchor(usize),
     ToggleCpu(bool),
+    ToggleCpuDetails(bool),
     ToggleMemory(bool),
+    ToggleMemoryDetails(bool),
     ToggleNetwork(bool),
     ToggleDisk(bool),
+    ToggleDiskDevices(bool),
     ToggleStorage(bool),
+    ToggleDriveHealth(bool),
+    TogglePoolHealth(bool),
+    ToggleStorageMountShown(usize, bool),
+    UpdateStorageLabel(usize, String),
+    SetStorageSort(StorageSort),
     ToggleGpu(bool),
+    ToggleGpuShown(usize, bool),
+    ToggleNetworkInterfaceShown(usize, bool),
+    ToggleNetworkConnections(bool),
+    ToggleNetworkTraffic(bool),
+    ToggleGpuDetails(bool),
+    ToggleGpuProcesses(bool),
+    ToggleProcesses(bool),
+    SetProcessCount(u32),
+    ToggleGroupProcessTrees(bool),
     ToggleCpuTemp(bool),
     ToggleGpuTemp(bool),
     SetTemperatureGaugeStyle(TemperatureGaugeStyle),
+    SetHistoryGraphStyle(WidgetSection, HistoryGraphStyle),
+    SetHistoryWindow(WidgetSection, u64),
     ToggleClock(bool),
     ToggleDate(bool),
     Toggle24HourTime(bool),
@@ -130,10 +485,36 @@ pub enum Message {
     ToggleSolaarIntegration(bool),
     ToggleNotifications(bool),
     ToggleMedia(bool),
+    ToggleCommands(bool),
     ToggleWeather(bool),
     ToggleWidgetAutostart(bool),
     ToggleLogging(bool),
     UpdateMaxNotifications(String),
+    ToggleMetricsExporter(bool),
+    UpdateMetricsBindAddress(String),
+    UpdateMetricsPort(String),
+    UpdateBillingDay(String),
+    UpdateNetworkQuota(String),
+    UpdatePollInterval(PolledMonitor, String),
+    AddCommand,
+    RemoveCommand(usize),
+    ToggleCommand(usize, bool),
+    ToggleCommandTable(usize, bool),
+    UpdateCommandField(usize, CommandField, String),
+    RefreshCommandStatus,
+    OverlayReadings(Option<Readings>),
+    AddAlertRule,
+    RemoveAlertRule(usize),
+    ToggleAlertRule(usize, bool),
+    SetAlertMetric(usize, usize),
+    SetAlertComparison(usize, usize),
+    UpdateAlertField(usize, AlertField, String),
+    AddCustomSection,
+    RemoveCustomSection(usize),
+    UpdateCustomTitle(usize, String),
+    EditCustomTemplate(usize, text_editor::Action),
+    UpdateConkyPath(String),
+    ImportConky,
     UpdateCiderApiToken(String),
     ToggleCiderTokenVisibility,
     UpdateX(String),
@@ -155,13 +536,42 @@ impl SettingsApp {
         if let Err(error) = self.config.write_entry(handler) {
             log::error!("Failed to save widget settings: {error}");
         }
+        if let Some(instance) = &self.instance {
+            instance.save();
+        }
+    }
+
+    /// Profile edited by the Display and Layout pages. Settings on the other
+    /// pages are shared by all overlays and always come from `config`.
+    fn profile(&self) -> &Config {
+        self.instance
+            .as_ref()
+            .map_or(&self.config, |instance| &instance.config)
+    }
+
+    fn profile_mut(&mut self) -> &mut Config {
+        match &mut self.instance {
+            Some(instance) => &mut instance.config,
+            None => &mut self.config,
+        }
     }
 
     fn sync_inputs(&mut self) {
-        self.x_input = self.config.widget_x.to_string();
-        self.y_input = self.config.widget_y.to_string();
+        self.instance_labels = instance_labels(&self.config);
+        (self.output_labels, self.output_names) =
+            output_choices(&self.outputs, &self.profile().overlay_output);
+        self.x_input = self.profile().widget_x.to_string();
+        self.y_input = self.profile().widget_y.to_string();
         self.weather_location_input = self.config.weather_location.clone();
         self.max_notifications_input = self.config.max_notifications.to_string();
+        self.metrics_bind_address_input = self.config.metrics_bind_address.clone();
+        self.metrics_port_input = self.config.metrics_port.to_string();
+        self.billing_day_input = self.config.network_billing_day.to_string();
+        self.network_quota_input = self.config.network_quota_gib.to_string();
+        self.poll_interval_inputs = poll_interval_inputs(&self.config);
+        self.alert_inputs = alert_inputs(&self.config);
+        self.command_inputs = command_inputs(&self.config);
+        self.template_editors = template_editors(self.profile());
         self.cider_api_token_input = self.config.cider_api_token.clone();
     }
 
@@ -194,98 +604,470 @@ impl SettingsApp {
             .into()
     }
 
+    fn instance_selector(&self) -> Element<'_, Message> {
+        let selected = self.instance.as_ref().map_or(Some(0), |instance| {
+            self.instance_labels[1..]
+                .iter()
+                .position(|name| *name == instance.name)
+                .map(|index| index + 1)
+        });
+
+        widget::settings::section()
+            .title("Overlay")
+            .add(
+                widget::settings::item::builder("Editing")
+                    .description("Sections, position and styling are set per overlay")
+                    .control(widget::dropdown(
+                        &self.instance_labels,
+                        selected,
+                        Message::SelectInstance,
+                    )),
+            )
+            .into()
+    }
+
+    fn instances_section(&self) -> Element<'_, Message> {
+        let name = self.instance_name_input.trim();
+        let name_available =
+            is_valid_instance_name(name) && !self.instance_labels.iter().any(|label| label == name);
+        let create = widget::button::standard("Create")
+            .leading_icon(widget::icon::from_name("list-add-symbolic"))
+            .on_press_maybe(name_available.then_some(Message::CreateInstance));
+        let duplicate = widget::button::standard("Duplicate")
+            .leading_icon(widget::icon::from_name("edit-copy-symbolic"))
+            .on_press_maybe(name_available.then_some(Message::DuplicateInstance));
+        let new_controls = widget::row::with_capacity(3)
+            .spacing(8)
+            .align_y(Alignment::Center)
+            .push(
+                widget::text_input("Name", &self.instance_name_input)
+                    .on_input(Message::UpdateInstanceName)
+                    .width(Length::Fixed(SHORT_INPUT_WIDTH)),
+            )
+            .push(create)
+            .push(duplicate);
+
+        let mut manage = widget::settings::section().title("Overlay instances").add(
+            widget::settings::item::builder("New overlay")
+                .description("Letters, digits, - and _; duplicate copies the selected overlay")
+                .control(new_controls),
+        );
+        if let Some(instance) = &self.instance {
+            let delete = widget::button::destructive("Delete")
+                .leading_icon(widget::icon::from_name("user-trash-symbolic"))
+                .on_press(Message::DeleteInstance);
+            manage = manage.add(
+                widget::settings::item::builder(format!("Delete {}", instance.name))
+                    .description("Removes the overlay; the default overlay is kept")
+                    .control(delete),
+            );
+        }
+
+        widget::column::with_capacity(2)
+            .spacing(24)
+            .push(self.instance_selector())
+            .push(manage)
+            .into()
+    }
+
     fn display_page(&self) -> Element<'_, Message> {
         let clock = widget::settings::section()
             .title("Clock and date")
             .add(
                 widget::settings::item::builder("Clock")
                     .description("Show the current time")
-                    .toggler(self.config.show_clock, Message::ToggleClock),
+                    .toggler(self.profile().show_clock, Message::ToggleClock),
             )
             .add(
                 widget::settings::item::builder("Date")
                     .description("Show the date below the clock")
-                    .toggler(self.config.show_date, Message::ToggleDate),
+                    .toggler(self.profile().show_date, Message::ToggleDate),
             )
             .add(
                 widget::settings::item::builder("24-hour time")
                     .description("Use 23:15 instead of 11:15 PM")
-                    .toggler(self.config.use_24hour_time, Message::Toggle24HourTime),
+                    .toggler(self.profile().use_24hour_time, Message::Toggle24HourTime),
             );
 
         let metrics = widget::settings::section()
             .title("System metrics")
             .add(
                 widget::settings::item::builder("CPU utilization")
-                    .toggler(self.config.show_cpu, Message::ToggleCpu),
+                    .toggler(self.profile().show_cpu, Message::ToggleCpu),
+            )
+            .add(
+                widget::settings::item::builder("CPU details")
+                    .description(
+                        "Click the CPU bar to show per-core usage, frequencies and load average",
+                    )
+                    .toggler(self.profile().show_cpu_details, Message::ToggleCpuDetails),
             )
             .add(
                 widget::settings::item::builder("Memory utilization")
-                    .toggler(self.config.show_memory, Message::ToggleMemory),
+                    .toggler(self.profile().show_memory, Message::ToggleMemory),
+            )
+            .add(
+                widget::settings::item::builder("Memory details")
+                    .description("Show swap, zram, page cache and memory pressure")
+                    .toggler(
+                        self.profile().show_memory_details,
+                        Message::ToggleMemoryDetails,
+                    ),
             )
             .add(
                 widget::settings::item::builder("GPU utilization")
-                    .toggler(self.config.show_gpu, Message::ToggleGpu),
+                    .toggler(self.profile().show_gpu, Message::ToggleGpu),
+            )
+            .add(
+                widget::settings::item::builder("GPU details")
+                    .description("Show VRAM, power draw, clocks and fan speed of each GPU")
+                    .toggler(self.profile().show_gpu_details, Message::ToggleGpuDetails),
+            )
+            .add(
+                widget::settings::item::builder("GPU processes")
+                    .description("Click a GPU row to list the processes using the GPU most")
+                    .toggler(
+                        self.profile().show_gpu_processes,
+                        Message::ToggleGpuProcesses,
+                    ),
             )
             .add(
                 widget::settings::item::builder("Network activity")
-                    .toggler(self.config.show_network, Message::ToggleNetwork),
+                    .toggler(self.profile().show_network, Message::ToggleNetwork),
+            )
+            .add(
+                widget::settings::item::builder("Connection details")
+                    .description(
+                        "Show the active connection, Wi-Fi signal, addresses, gateway and VPN \
+                         state from NetworkManager",
+                    )
+                    .toggler(
+                        self.profile().show_network_connections,
+                        Message::ToggleNetworkConnections,
+                    ),
+            )
+            .add(
+                widget::settings::item::builder("Traffic totals")
+                    .description("Show today's, this month's and this billing cycle's traffic")
+                    .toggler(
+                        self.profile().show_network_traffic,
+                        Message::ToggleNetworkTraffic,
+                    ),
             )
             .add(
                 widget::settings::item::builder("Disk I/O")
-                    .toggler(self.config.show_disk, Message::ToggleDisk),
+                    .toggler(self.profile().show_disk, Message::ToggleDisk),
+            )
+            .add(
+                widget::settings::item::builder("Per-drive activity")
+                    .description("Show IOPS, request latency and utilization of each drive")
+                    .toggler(self.profile().show_disk_devices, Message::ToggleDiskDevices),
             )
             .add(
                 widget::settings::item::builder("Percentage labels")
                     .description("Show exact values beside utilization and storage bars")
-                    .toggler(self.config.show_percentages, Message::TogglePercentages),
+                    .toggler(self.profile().show_percentages, Message::TogglePercentages),
             );
 
         let temperatures = widget::settings::section()
             .title("Temperatures")
             .add(
                 widget::settings::item::builder("CPU temperature")
-                    .toggler(self.config.show_cpu_temp, Message::ToggleCpuTemp),
+                    .toggler(self.profile().show_cpu_temp, Message::ToggleCpuTemp),
             )
             .add(
                 widget::settings::item::builder("GPU temperature")
-                    .toggler(self.config.show_gpu_temp, Message::ToggleGpuTemp),
+                    .toggler(self.profile().show_gpu_temp, Message::ToggleGpuTemp),
+            );
+
+        let mut gpus = widget::settings::section().title("GPUs");
+        if self.cached_gpus.is_empty() {
+            gpus = gpus.add(
+                widget::settings::item::builder("No GPUs found yet")
+                    .description("GPUs are listed once the overlay has shown GPU utilization"),
+            );
+        }
+        for (index, gpu) in self.cached_gpus.iter().enumerate() {
+            gpus = gpus.add(
+                widget::settings::item::builder(&gpu.name)
+                    .description(&gpu.id)
+                    .toggler(
+                        !self.profile().hidden_gpus.contains(&gpu.id),
+                        move |shown| Message::ToggleGpuShown(index, shown),
+                    ),
+            );
+        }
+
+        let mut interfaces = widget::settings::section().title("Network interfaces");
+        if self.cached_network_interfaces.is_empty() {
+            interfaces = interfaces.add(
+                widget::settings::item::builder("No interfaces found yet").description(
+                    "Interfaces are listed once the overlay has shown network activity",
+                ),
+            );
+        }
+        for (index, interface) in self.cached_network_interfaces.iter().enumerate() {
+            interfaces = interfaces.add(
+                widget::settings::item::builder(&interface.name)
+                    .description(if interface.physical {
+                        "Physical"
+                    } else {
+                        "Virtual, such as a bridge, container link or VPN tunnel"
+                    })
+                    .toggler(
+                        self.profile()
+                            .shows_network_interface(&interface.name, interface.physical),
+                        move |shown| Message::ToggleNetworkInterfaceShown(index, shown),
+                    ),
+            );
+        }
+
+        let sort_index = StorageSort::ALL
+            .iter()
+            .position(|sort| *sort == self.profile().storage_sort);
+        let mut mounts = widget::settings::section().title("Storage mounts").add(
+            widget::settings::item::builder("Order").control(widget::dropdown(
+                &STORAGE_SORT_LABELS,
+                sort_index,
+                |index| Message::SetStorageSort(StorageSort::ALL[index]),
+            )),
+        );
+        if self.cached_storage_mounts.is_empty() {
+            mounts = mounts.add(
+                widget::settings::item::builder("No mounts found yet")
+                    .description("Mounts are listed once the overlay has shown storage"),
+            );
+        }
+        for (index, mount) in self.cached_storage_mounts.iter().enumerate() {
+            let description = if mount.source.is_empty() {
+                mount.fs_type.clone()
+            } else {
+                format!("{} on {}", mount.fs_type, mount.source)
+            };
+            let label = self
+                .profile()
+                .storage_labels
+                .iter()
+                .find(|label| label.mount_point == mount.mount_point)
+                .map_or("", |label| label.name.as_str());
+            let controls = widget::row::with_capacity(2)
+                .spacing(8)
+                .align_y(Alignment::Center)
+                .push(
+                    widget::text_input(mount.name.as_str(), label)
+                        .on_input(move |value| Message::UpdateStorageLabel(index, value))
+                        .width(Length::Fixed(SHORT_INPUT_WIDTH)),
+                )
+                .push(
+                    widget::toggler(self.profile().shows_storage_mount(
+                        &mount.mount_point,
+                        &mount.fs_type,
+                        &mount.device_names(),
+                    ))
+                    .on_toggle(move |shown| Message::ToggleStorageMountShown(index, shown)),
+                );
+            mounts = mounts.add(
+                widget::settings::item::builder(&mount.mount_point)
+                    .description(description)
+                    .control(controls),
             );
+        }
 
         let temperature_style = self.temperature_style_selector();
 
+        let mut history = widget::settings::section().title("History graphs");
+        for section in HISTORY_GRAPH_SECTIONS {
+            let style = self.profile().history_graph_style(section);
+            let style_index = HistoryGraphStyle::ALL
+                .iter()
+                .position(|candidate| *candidate == style);
+            let window = self.profile().history_window_secs(section);
+            let window_index = HISTORY_WINDOW_OPTIONS_SECS
+                .iter()
+                .position(|candidate| *candidate == window);
+            let controls = widget::row::with_capacity(2)
+                .spacing(8)
+                .align_y(Alignment::Center)
+                .push(widget::dropdown(
+                    &HISTORY_GRAPH_STYLE_LABELS,
+                    style_index,
+                    move |index| {
+                        Message::SetHistoryGraphStyle(section, HistoryGraphStyle::ALL[index])
+                    },
+                ))
+                .push(widget::dropdown(
+                    &HISTORY_WINDOW_LABELS,
+                    window_index,
+                    move |index| {
+                        Message::SetHistoryWindow(section, HISTORY_WINDOW_OPTIONS_SECS[index])
+                    },
+                ));
+            history =
+                history.add(widget::settings::item::builder(section.label()).control(controls));
+        }
+
         let sections = widget::settings::section()
             .title("Sections")
             .add(
                 widget::settings::item::builder("Storage")
-                    .toggler(self.config.show_storage, Message::ToggleStorage),
+                    .toggler(self.profile().show_storage, Message::ToggleStorage),
+            )
+            .add(
+                widget::settings::item::builder("Drive health")
+                    .description(
+                        "Show drive temperature, wear and SMART errors below each filesystem. \
+                         SMART data needs read access to the drive's device node",
+                    )
+                    .toggler(self.profile().show_drive_health, Message::ToggleDriveHealth),
+            )
+            .add(
+                widget::settings::item::builder("Pool health")
+                    .description(
+                        "Show Btrfs, ZFS and RAID array state and resync or scrub progress \
+                         below each filesystem",
+                    )
+                    .toggler(self.profile().show_pool_health, Message::TogglePoolHealth),
             )
             .add(
                 widget::settings::item::builder("Devices")
-                    .toggler(self.config.show_battery, Message::ToggleDevices),
+                    .toggler(self.profile().show_battery, Message::ToggleDevices),
             )
             .add(
                 widget::settings::item::builder("Weather")
-                    .toggler(self.config.show_weather, Message::ToggleWeather),
+                    .toggler(self.profile().show_weather, Message::ToggleWeather),
             )
+            .add(widget::settings::item::builder("Notifications").toggler(
+                self.profile().show_notifications,
+                Message::ToggleNotifications,
+            ))
             .add(
-                widget::settings::item::builder("Notifications")
-                    .toggler(self.config.show_notifications, Message::ToggleNotifications),
+                widget::settings::item::builder("Now Playing")
+                    .toggler(self.profile().show_media, Message::ToggleMedia),
             )
             .add(
-                widget::settings::item::builder("Now Playing")
-                    .toggler(self.config.show_media, Message::ToggleMedia),
+                widget::settings::item::builder("Commands")
+                    .description("Output of the commands on the Commands page")
+                    .toggler(self.profile().show_commands, Message::ToggleCommands),
+            )
+            .add(
+                widget::settings::item::builder("Top Processes")
+                    .description("Right-click a process to end it")
+                    .toggler(self.profile().show_processes, Message::ToggleProcesses),
+            );
+
+        let process_count_index = TOP_PROCESS_COUNT_OPTIONS
+            .iter()
+            .position(|count| *count == self.profile().process_count);
+        let processes = widget::settings::section()
+            .title("Top processes")
+            .add(
+                widget::settings::item::builder("Processes per list").control(widget::dropdown(
+                    &TOP_PROCESS_COUNT_LABELS,
+                    process_count_index,
+                    |index| Message::SetProcessCount(TOP_PROCESS_COUNT_OPTIONS[index]),
+                )),
+            )
+            .add(
+                widget::settings::item::builder("Group process trees")
+                    .description(
+                        "Count a browser or Electron app and its child processes as one row",
+                    )
+                    .toggler(
+                        self.profile().group_process_trees,
+                        Message::ToggleGroupProcessTrees,
+                    ),
             );
 
         self.page(widget::settings::view_column(vec![
+            self.instance_selector(),
             clock.into(),
             metrics.into(),
+            gpus.into(),
+            interfaces.into(),
+            mounts.into(),
             temperatures.into(),
             temperature_style,
+            history.into(),
             sections.into(),
+            processes.into(),
+            self.custom_sections_editor(),
         ]))
     }
 
+    fn custom_sections_editor(&self) -> Element<'_, Message> {
+        let mut editor = widget::column::with_capacity(self.template_editors.len() + 1).spacing(24);
+
+        for (index, (custom, content)) in self
+            .profile()
+            .custom_sections
+            .iter()
+            .zip(&self.template_editors)
+            .enumerate()
+        {
+            let template = text_editor(content)
+                .on_action(move |action| Message::EditCustomTemplate(index, action))
+                .font(cosmic::font::mono())
+                .height(Length::Fixed(TEMPLATE_EDITOR_HEIGHT));
+            let preview = widget::container(template::lines_view(
+                Template::parse(&custom.template).render(&PreviewValues {
+                    readings: self.overlay_readings.as_ref(),
+                }),
+                4,
+            ))
+            .padding(12)
+            .width(Length::Fill)
+            .class(cosmic::theme::Container::Card);
+            let remove = widget::button::destructive("Remove")
+                .leading_icon(widget::icon::from_name("user-trash-symbolic"))
+                .on_press(Message::RemoveCustomSection(index));
+
+            let section = widget::settings::section()
+                .title(
+                    self.profile()
+                        .section_label(WidgetSection::Custom(custom.id))
+                        .to_string(),
+                )
+                .add(
+                    widget::settings::item::builder("Title")
+                        .description("Leave empty to hide the heading")
+                        .control(
+                            widget::text_input("Custom", &custom.title)
+                                .on_input(move |value| Message::UpdateCustomTitle(index, value))
+                                .width(Length::Fixed(LONG_INPUT_WIDTH)),
+                        ),
+                )
+                .add(widget::settings::item_row(vec![template.into()]))
+                .add(
+                    widget::column::with_capacity(2)
+                        .spacing(8)
+                        .push(widget::text::caption(if self.overlay_readings.is_some() {
+                            "Preview with the overlay's readings"
+                        } else {
+                            "Preview with example readings"
+                        }))
+                        .push(preview),
+                )
+                .add(widget::settings::item::builder("Remove section").control(remove));
+            editor = editor.push(section);
+        }
+
+        let add = widget::button::standard("Add section")
+            .leading_icon(widget::icon::from_name("list-add-symbolic"))
+            .on_press(Message::AddCustomSection);
+        editor
+            .push(
+                widget::settings::section()
+                    .title("Custom sections")
+                    .add(
+                        widget::settings::item::builder("New custom section")
+                            .description("Text drawn from a template with live readings")
+                            .control(add),
+                    )
+                    .add(widget::text::caption(template_help())),
+            )
+            .into()
+    }
+
     fn temperature_style_selector(&self) -> Element<'_, Message> {
         let options = widget::row::with_capacity(3)
             .spacing(16)
@@ -309,7 +1091,7 @@ impl SettingsApp {
         let preview = cosmic::iced::widget::Canvas::new(TemperatureStylePreview { style })
             .width(Length::Fill)
             .height(Length::Fixed(TEMPERATURE_STYLE_PREVIEW_HEIGHT));
-        let selected = self.config.temperature_gauge_style == style;
+        let selected = self.profile().temperature_gauge_style == style;
         let button = widget::button::custom_image_button(preview, None::<Message>)
             .class(cosmic::theme::Button::Image)
             .selected(selected)
@@ -328,13 +1110,13 @@ impl SettingsApp {
 
     fn layout_page(&self) -> Element<'_, Message> {
         let mut order = widget::settings::section().title("Section order");
-        let enabled_sections = self
-            .config
+        let profile = self.profile();
+        let enabled_sections = profile
             .section_order
             .iter()
             .copied()
             .enumerate()
-            .filter(|(_, section)| section_enabled(&self.config, *section))
+            .filter(|(_, section)| section_enabled(profile, *section))
             .collect::<Vec<_>>();
         let last_visible_index = enabled_sections.len().saturating_sub(1);
 
@@ -353,38 +1135,76 @@ impl SettingsApp {
                 .push(up)
                 .push(down);
 
-            order = order.add(widget::settings::item::builder(section.label()).control(controls));
+            order = order.add(
+                widget::settings::item::builder(profile.section_label(section).to_string())
+                    .control(controls),
+            );
         }
 
         let reset = widget::button::standard("Reset to default")
             .leading_icon(widget::icon::from_name("view-refresh-symbolic"))
             .on_press(Message::ResetPosition);
-        let edit = widget::button::suggested(if self.config.widget_movable {
+        let edit = widget::button::suggested(if profile.widget_movable {
             "Editing"
         } else {
             "Edit"
         })
         .leading_icon(widget::icon::from_name("edit-symbolic"))
-        .on_press_maybe((!self.config.widget_movable).then_some(Message::EditPosition));
+        .on_press_maybe((!profile.widget_movable).then_some(Message::EditPosition));
         let position_controls = widget::row::with_capacity(2)
             .spacing(8)
             .align_y(Alignment::Center)
             .push(reset)
             .push(edit);
 
+        let anchor = profile.overlay_anchor;
+        let horizontal_edge = if anchor.is_right() { "right" } else { "left" };
+        let vertical_edge = if anchor.is_bottom() { "bottom" } else { "top" };
+        let selected_output = self
+            .output_names
+            .iter()
+            .position(|name| *name == profile.overlay_output.trim());
+        let selected_anchor = OverlayAnchor::ALL.iter().position(|known| *known == anchor);
+
         let position = widget::settings::section()
             .title("Position")
+            .add(
+                widget::settings::item::builder("Display")
+                    .description("Output the overlay is shown on when it is connected")
+                    .control(widget::dropdown(
+                        &self.output_labels,
+                        selected_output,
+                        Message::SelectOutput,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder("Anchor")
+                    .description("Corner or edge the offsets are measured from")
+                    .control(widget::dropdown(
+                        &OVERLAY_ANCHOR_LABELS,
+                        selected_anchor,
+                        Message::SelectAnchor,
+                    )),
+            )
             .add(
                 widget::settings::item::builder("Overlay position")
-                    .description(format!(
-                        "{} px from left, {} px from top",
-                        self.config.widget_x, self.config.widget_y
-                    ))
+                    .description(if anchor.is_centered() {
+                        format!("Centered, {} px from {vertical_edge}", profile.widget_y)
+                    } else {
+                        format!(
+                            "{} px from {horizontal_edge}, {} px from {vertical_edge}",
+                            profile.widget_x, profile.widget_y
+                        )
+                    })
                     .control(position_controls),
             )
             .add(
                 widget::settings::item::builder("Horizontal offset")
-                    .description("Pixels from the left edge")
+                    .description(if anchor.is_centered() {
+                        "Unused while the overlay is centered".to_string()
+                    } else {
+                        format!("Pixels from the {horizontal_edge} edge")
+                    })
                     .control(
                         widget::text_input("0", &self.x_input)
                             .on_input(Message::UpdateX)
@@ -393,7 +1213,7 @@ impl SettingsApp {
             )
             .add(
                 widget::settings::item::builder("Vertical offset")
-                    .description("Pixels from the top edge")
+                    .description(format!("Pixels from the {vertical_edge} edge"))
                     .control(
                         widget::text_input("0", &self.y_input)
                             .on_input(Message::UpdateY)
@@ -402,11 +1222,61 @@ impl SettingsApp {
             );
 
         self.page(widget::settings::view_column(vec![
+            self.instances_section(),
             order.into(),
             position.into(),
+            self.conky_import_section(),
         ]))
     }
 
+    fn conky_import_section(&self) -> Element<'_, Message> {
+        let import = widget::button::standard("Import")
+            .leading_icon(widget::icon::from_name("document-open-symbolic"))
+            .on_press_maybe(
+                (!self.conky_path_input.trim().is_empty()).then_some(Message::ImportConky),
+            );
+        let mut section = widget::settings::section()
+            .title("Import conky config")
+            .add(
+                widget::settings::item::builder("Config file")
+                    .description("Replaces the sections and position of this overlay")
+                    .control(
+                        widget::row::with_capacity(2)
+                            .spacing(8)
+                            .align_y(Alignment::Center)
+                            .push(
+                                widget::text_input(DEFAULT_CONKY_PATH, &self.conky_path_input)
+                                    .on_input(Message::UpdateConkyPath)
+                                    .width(Length::Fixed(LONG_INPUT_WIDTH)),
+                            )
+                            .push(import),
+                    ),
+            );
+
+        match &self.conky_import {
+            Some(Ok(report)) if report.untranslated.is_empty() => {
+                section = section.add(widget::text::caption("Imported everything"));
+            }
+            Some(Ok(report)) => {
+                let untranslated = report.untranslated.iter().fold(
+                    widget::column::with_capacity(report.untranslated.len() + 1)
+                        .spacing(4)
+                        .push(widget::text::caption_heading(
+                            "Imported, except for the following",
+                        )),
+                    |column, note| column.push(widget::text::caption(note.as_str())),
+                );
+                section = section.add(untranslated);
+            }
+            Some(Err(error)) => {
+                section = section.add(widget::text::caption(error.as_str()));
+            }
+            None => {}
+        }
+
+        section.into()
+    }
+
     fn services_page(&self) -> Element<'_, Message> {
         let devices = widget::settings::section().title("Devices").add(
             widget::settings::item::builder("Solaar compatibility fallback")
@@ -452,11 +1322,64 @@ impl SettingsApp {
                 ),
         );
 
+        let metrics = widget::settings::section()
+            .title("Metrics exporter")
+            .add(
+                widget::settings::item::builder("Serve OpenMetrics")
+                    .description("Expose readings for Prometheus at /metrics")
+                    .toggler(
+                        self.config.enable_metrics_exporter,
+                        Message::ToggleMetricsExporter,
+                    ),
+            )
+            .add(
+                widget::settings::item::builder("Bind address")
+                    .description("Use a loopback address to keep readings local")
+                    .control(
+                        widget::text_input("127.0.0.1", &self.metrics_bind_address_input)
+                            .on_input(Message::UpdateMetricsBindAddress)
+                            .width(Length::Fixed(LONG_INPUT_WIDTH)),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder("Port").control(
+                    widget::text_input("9863", &self.metrics_port_input)
+                        .on_input(Message::UpdateMetricsPort)
+                        .width(Length::Fixed(SHORT_INPUT_WIDTH)),
+                ),
+            );
+
+        let traffic = widget::settings::section()
+            .title("Network traffic")
+            .add(
+                widget::settings::item::builder("Billing day")
+                    .description("Day of the month on which the billing cycle starts")
+                    .control(
+                        widget::text_input("1", &self.billing_day_input)
+                            .on_input(Message::UpdateBillingDay)
+                            .width(Length::Fixed(SHORT_INPUT_WIDTH)),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder("Quota (GiB)")
+                    .description(
+                        "Traffic allowed per billing cycle; 0 for none. Alert rules can warn \
+                         as it fills up",
+                    )
+                    .control(
+                        widget::text_input("0", &self.network_quota_input)
+                            .on_input(Message::UpdateNetworkQuota)
+                            .width(Length::Fixed(SHORT_INPUT_WIDTH)),
+                    ),
+            );
+
         let mut sections: Vec<Element<'_, Message>> = vec![
             devices.into(),
             weather.into(),
             notifications.into(),
             media.into(),
+            metrics.into(),
+            traffic.into(),
         ];
 
         if !self.cached_devices.is_empty() {
@@ -478,6 +1401,263 @@ impl SettingsApp {
         self.page(widget::settings::view_column(sections))
     }
 
+    fn alerts_page(&self) -> Element<'_, Message> {
+        let mut sections = Vec::with_capacity(self.config.alert_rules.len() + 1);
+
+        for (index, (rule, inputs)) in self
+            .config
+            .alert_rules
+            .iter()
+            .zip(&self.alert_inputs)
+            .enumerate()
+        {
+            let unit = rule.metric.unit().trim();
+            let condition = widget::row::with_capacity(2)
+                .spacing(8)
+                .align_y(Alignment::Center)
+                .push(widget::dropdown(
+                    &ALERT_COMPARISON_LABELS,
+                    AlertComparison::ALL
+                        .iter()
+                        .position(|comparison| *comparison == rule.comparison),
+                    move |selected| Message::SetAlertComparison(index, selected),
+                ))
+                .push(alert_input(
+                    index,
+                    AlertField::Threshold,
+                    "90",
+                    &inputs.threshold,
+                ));
+            let remove = widget::button::destructive("Remove")
+                .leading_icon(widget::icon::from_name("user-trash-symbolic"))
+                .on_press(Message::RemoveAlertRule(index));
+
+            let mut section = widget::settings::section()
+                .title(format!("Rule {}", index + 1))
+                .add(
+                    widget::settings::item::builder("Enabled")
+                        .toggler(rule.enabled, move |enabled| {
+                            Message::ToggleAlertRule(index, enabled)
+                        }),
+                )
+                .add(
+                    widget::settings::item::builder("Reading").control(widget::dropdown(
+                        &ALERT_METRIC_LABELS,
+                        AlertMetric::ALL
+                            .iter()
+                            .position(|metric| *metric == rule.metric),
+                        move |selected| Message::SetAlertMetric(index, selected),
+                    )),
+                )
+                .add(
+                    widget::settings::item::builder("Condition")
+                        .description(format!("Threshold in {unit}"))
+                        .control(condition),
+                );
+            if rule.metric.has_targets() {
+                section = section.add(
+                    widget::settings::item::builder(if rule.metric == AlertMetric::StorageUsage {
+                        "Mount point"
+                    } else {
+                        "Device name"
+                    })
+                    .description("Leave empty to watch all of them")
+                    .control(
+                        widget::text_input("All", &inputs.target)
+                            .on_input(move |value| {
+                                Message::UpdateAlertField(index, AlertField::Target, value)
+                            })
+                            .width(Length::Fixed(LONG_INPUT_WIDTH)),
+                    ),
+                );
+            }
+            section = section
+                .add(
+                    widget::settings::item::builder("Duration")
+                        .description("Seconds the threshold must stay crossed")
+                        .control(alert_input(
+                            index,
+                            AlertField::Duration,
+                            "30",
+                            &inputs.duration,
+                        )),
+                )
+                .add(
+                    widget::settings::item::builder("Hysteresis")
+                        .description(format!(
+                            "How far, in {unit}, the reading must recover before alerting again"
+                        ))
+                        .control(alert_input(
+                            index,
+                            AlertField::Hysteresis,
+                            "5",
+                            &inputs.hysteresis,
+                        )),
+                )
+                .add(
+                    widget::settings::item::builder("Cooldown")
+                        .description("Minimum seconds between notifications")
+                        .control(alert_input(
+                            index,
+                            AlertField::Cooldown,
+                            "300",
+                            &inputs.cooldown,
+                        )),
+                )
+                .add(widget::settings::item::builder("Remove rule").control(remove));
+            sections.push(section.into());
+        }
+
+        let add = widget::button::standard("Add rule")
+            .leading_icon(widget::icon::from_name("list-add-symbolic"))
+            .on_press(Message::AddAlertRule);
+        sections.push(
+            widget::settings::section()
+                .title("Alerts")
+                .add(
+                    widget::settings::item::builder("New alert rule")
+                        .description(
+                            "Raise a desktop notification when a reading crosses a threshold",
+                        )
+                        .control(add),
+                )
+                .into(),
+        );
+
+        self.page(widget::settings::view_column(sections))
+    }
+
+    fn commands_page(&self) -> Element<'_, Message> {
+        let mut sections = Vec::with_capacity(self.config.commands.len() + 1);
+
+        for (index, (command, inputs)) in self
+            .config
+            .commands
+            .iter()
+            .zip(&self.command_inputs)
+            .enumerate()
+        {
+            let result = self
+                .command_results
+                .iter()
+                .find(|result| result.command == command.command);
+            let last_run = match result {
+                Some(result) if result.truncated => {
+                    format!("{}, output truncated", result.status.description())
+                }
+                Some(result) => result.status.description(),
+                None if command.enabled && self.profile().show_commands => {
+                    "Waiting for first run".to_string()
+                }
+                None => "Not running".to_string(),
+            };
+            let remove = widget::button::destructive("Remove")
+                .leading_icon(widget::icon::from_name("user-trash-symbolic"))
+                .on_press(Message::RemoveCommand(index));
+
+            let mut section = widget::settings::section()
+                .title(command.display_label().to_string())
+                .add(
+                    widget::settings::item::builder("Enabled")
+                        .toggler(command.enabled, move |enabled| {
+                            Message::ToggleCommand(index, enabled)
+                        }),
+                )
+                .add(
+                    widget::settings::item::builder("Label")
+                        .description("Leave empty to show the command")
+                        .control(command_input(
+                            index,
+                            CommandField::Label,
+                            "Uptime",
+                            &inputs.label,
+                            LONG_INPUT_WIDTH,
+                        )),
+                )
+                .add(
+                    widget::settings::item::builder("Command")
+                        .description("Run with sh in your home directory")
+                        .control(command_input(
+                            index,
+                            CommandField::Command,
+                            "uptime -p",
+                            &inputs.command,
+                            LONG_INPUT_WIDTH,
+                        )),
+                )
+                .add(
+                    widget::settings::item::builder("Interval")
+                        .description("Seconds between runs")
+                        .control(command_input(
+                            index,
+                            CommandField::Interval,
+                            "60",
+                            &inputs.interval,
+                            SHORT_INPUT_WIDTH,
+                        )),
+                )
+                .add(
+                    widget::settings::item::builder("Timeout")
+                        .description(format!(
+                            "Seconds before the command is killed, at most {MAX_COMMAND_TIMEOUT_SECS}"
+                        ))
+                        .control(command_input(
+                            index,
+                            CommandField::Timeout,
+                            "5",
+                            &inputs.timeout,
+                            SHORT_INPUT_WIDTH,
+                        )),
+                )
+                .add(
+                    widget::settings::item::builder("Output limit")
+                        .description(format!(
+                            "Bytes of output kept, at most {MAX_COMMAND_OUTPUT_BYTES}"
+                        ))
+                        .control(command_input(
+                            index,
+                            CommandField::OutputLimit,
+                            "4096",
+                            &inputs.output_limit,
+                            SHORT_INPUT_WIDTH,
+                        )),
+                )
+                .add(
+                    widget::settings::item::builder("Show as table")
+                        .description("Split lines into columns at whitespace")
+                        .toggler(command.table, move |table| {
+                            Message::ToggleCommandTable(index, table)
+                        }),
+                )
+                .add(widget::settings::item::builder("Last run").control(widget::text::body(last_run)));
+            if let Some(result) = result.filter(|result| !result.stderr.is_empty()) {
+                section = section.add(
+                    widget::settings::item::builder("Error output")
+                        .control(widget::text::caption(result.stderr.clone())),
+                );
+            }
+            section =
+                section.add(widget::settings::item::builder("Remove command").control(remove));
+            sections.push(section.into());
+        }
+
+        let add = widget::button::standard("Add command")
+            .leading_icon(widget::icon::from_name("list-add-symbolic"))
+            .on_press(Message::AddCommand);
+        sections.push(
+            widget::settings::section()
+                .title("Commands")
+                .add(
+                    widget::settings::item::builder("New command")
+                        .description("Show the output of a shell command in the Commands section")
+                        .control(add),
+                )
+                .into(),
+        );
+
+        self.page(widget::settings::view_column(sections))
+    }
+
     fn behavior_page(&self) -> Element<'_, Message> {
         let general = widget::settings::section()
             .title("General")
@@ -492,7 +1672,25 @@ impl SettingsApp {
                     .toggler(self.config.enable_logging, Message::ToggleLogging),
             );
 
-        self.page(widget::settings::view_column(vec![general.into()]))
+        let mut polling = widget::settings::section().title("Polling intervals");
+        for monitor in PolledMonitor::ALL {
+            polling = polling.add(
+                widget::settings::item::builder(monitor.label())
+                    .description(format!(
+                        "Milliseconds, {MIN_POLL_INTERVAL_MS} to {MAX_POLL_INTERVAL_MS}"
+                    ))
+                    .control(
+                        widget::text_input("1000", &self.poll_interval_inputs[monitor as usize])
+                            .on_input(move |value| Message::UpdatePollInterval(monitor, value))
+                            .width(Length::Fixed(SHORT_INPUT_WIDTH)),
+                    ),
+            );
+        }
+
+        self.page(widget::settings::view_column(vec![
+            general.into(),
+            polling.into(),
+        ]))
     }
 }
 
@@ -653,7 +1851,7 @@ impl Application for SettingsApp {
         core: cosmic::app::Core,
         _flags: Self::Flags,
     ) -> (Self, Task<cosmic::Action<Self::Message>>) {
-        let config_handler = cosmic_config::Config::new(CONFIG_APP_ID, Config::VERSION).ok();
+        let config_handler = cosmic_config::Config::new(CONFIG_ID, Config::VERSION).ok();
         let (mut config, incomplete_schema) = config_handler
             .as_ref()
             .map(|handler| match Config::get_entry(handler) {
@@ -663,6 +1861,7 @@ impl Application for SettingsApp {
             .unwrap_or_else(|| (Config::default(), false));
 
         let mut migrated = config.ensure_all_sections();
+        migrated |= config.migrate_legacy_position();
         migrated |= config.ensure_position_defaults();
         if migrated || incomplete_schema {
             if let Some(handler) = &config_handler {
@@ -686,14 +1885,36 @@ impl Application for SettingsApp {
             nav_model,
             x_input: config.widget_x.to_string(),
             y_input: config.widget_y.to_string(),
+            instance: None,
+            instance_labels: instance_labels(&config),
+            instance_name_input: String::new(),
+            outputs: Outputs::default(),
+            output_labels: Vec::new(),
+            output_names: Vec::new(),
             weather_location_input: config.weather_location.clone(),
             max_notifications_input: config.max_notifications.to_string(),
+            metrics_bind_address_input: config.metrics_bind_address.clone(),
+            metrics_port_input: config.metrics_port.to_string(),
+            billing_day_input: config.network_billing_day.to_string(),
+            network_quota_input: config.network_quota_gib.to_string(),
+            poll_interval_inputs: poll_interval_inputs(&config),
+            alert_inputs: alert_inputs(&config),
+            command_inputs: command_inputs(&config),
+            command_results: CachedCommandResult::load_all(),
+            template_editors: template_editors(&config),
+            overlay_readings: None,
+            conky_path_input: DEFAULT_CONKY_PATH.to_string(),
+            conky_import: None,
             cider_api_token_input: config.cider_api_token.clone(),
             cider_token_hidden: true,
             cached_devices: cache.battery_devices,
+            cached_gpus: cache.gpus,
+            cached_network_interfaces: cache.network_interfaces,
+            cached_storage_mounts: cache.storage_mounts,
             config,
             config_handler,
         };
+        app.sync_inputs();
         let task = app.update_title();
 
         (app, task)
@@ -713,9 +1934,43 @@ impl Application for SettingsApp {
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        self.core()
-            .watch_config::<Config>(CONFIG_APP_ID)
-            .map(|update| Message::UpdateConfig(update.config))
+        let main = self
+            .core()
+            .watch_config::<Config>(CONFIG_ID)
+            .map(|update| Message::UpdateConfig(update.config));
+        let outputs =
+            crate::outputs::events().map(|(event, output)| Message::Output(event, output));
+        let command_status = if self.active_page() == SettingsPage::Commands {
+            cosmic::iced::time::every(COMMAND_STATUS_REFRESH).map(|_| Message::RefreshCommandStatus)
+        } else {
+            Subscription::none()
+        };
+        let readings = if self.active_page() == SettingsPage::Display
+            && !self.profile().custom_sections.is_empty()
+        {
+            Subscription::run_with(PREVIEW_READINGS_REFRESH, overlay_readings_stream)
+                .map(Message::OverlayReadings)
+        } else {
+            Subscription::none()
+        };
+        let Some(instance) = &self.instance else {
+            return Subscription::batch([main, outputs, command_status, readings]);
+        };
+
+        // Overlays save their own position when pinned, so watch the edited
+        // profile too. Subscriptions are identified by a hash of its ID.
+        let config_id = instance_config_id(&instance.name);
+        let mut hasher = DefaultHasher::new();
+        config_id.hash(&mut hasher);
+        let name = instance.name.clone();
+        let profile =
+            cosmic_config::config_subscription(hasher.finish(), config_id.into(), Config::VERSION)
+                .with(name)
+                .map(|(name, update): (String, cosmic_config::Update<Config>)| {
+                    Message::UpdateInstanceConfig(name, update.config)
+                });
+
+        Subscription::batch([main, outputs, command_status, readings, profile])
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
@@ -723,6 +1978,8 @@ impl Application for SettingsApp {
             SettingsPage::Display => self.display_page(),
             SettingsPage::Layout => self.layout_page(),
             SettingsPage::Services => self.services_page(),
+            SettingsPage::Alerts => self.alerts_page(),
+            SettingsPage::Commands => self.commands_page(),
             SettingsPage::Behavior => self.behavior_page(),
         }
     }
@@ -731,8 +1988,16 @@ impl Application for SettingsApp {
         match message {
             Message::UpdateConfig(mut config) => {
                 config.ensure_all_sections();
+                config.migrate_legacy_position();
                 config.ensure_position_defaults();
                 if config != self.config {
+                    if self
+                        .instance
+                        .as_ref()
+                        .is_some_and(|instance| !config.instance_names().contains(&instance.name))
+                    {
+                        self.instance = None;
+                    }
                     self.config = config;
                     self.sync_inputs();
                 }
@@ -741,28 +2006,185 @@ impl Application for SettingsApp {
                 // into a feedback loop (most visibly, position reset oscillation).
                 return Task::none();
             }
-            Message::ToggleCpu(value) => self.config.show_cpu = value,
-            Message::ToggleMemory(value) => self.config.show_memory = value,
-            Message::ToggleNetwork(value) => self.config.show_network = value,
-            Message::ToggleDisk(value) => self.config.show_disk = value,
-            Message::ToggleStorage(value) => self.config.show_storage = value,
-            Message::ToggleGpu(value) => self.config.show_gpu = value,
-            Message::ToggleCpuTemp(value) => self.config.show_cpu_temp = value,
-            Message::ToggleGpuTemp(value) => self.config.show_gpu_temp = value,
+            Message::UpdateInstanceConfig(name, mut config) => {
+                config.ensure_all_sections();
+                if let Some(instance) = &mut self.instance
+                    && instance.name == name
+                    && instance.config != config
+                {
+                    instance.config = config;
+                    self.sync_inputs();
+                }
+                return Task::none();
+            }
+            Message::SelectInstance(index) => {
+                self.instance = index
+                    .checked_sub(1)
+                    .and_then(|index| self.instance_labels[1..].get(index))
+                    .cloned()
+                    .map(InstanceProfile::load);
+                self.sync_inputs();
+                return Task::none();
+            }
+            Message::Output(event, output) => {
+                if self.outputs.apply(event, output) {
+                    (self.output_labels, self.output_names) =
+                        output_choices(&self.outputs, &self.profile().overlay_output);
+                }
+                return Task::none();
+            }
+            Message::SelectOutput(index) => {
+                let Some(name) = self.output_names.get(index).cloned() else {
+                    return Task::none();
+                };
+                self.profile_mut().overlay_output = name;
+            }
+            Message::SelectAnchor(index) => {
+                let Some(anchor) = OverlayAnchor::ALL.get(index).copied() else {
+                    return Task::none();
+                };
+                self.profile_mut().overlay_anchor = anchor;
+            }
+            Message::UpdateInstanceName(value) => {
+                self.instance_name_input = value;
+                return Task::none();
+            }
+            Message::CreateInstance | Message::DuplicateInstance => {
+                let name = self.instance_name_input.trim().to_string();
+                if !is_valid_instance_name(&name) || self.instance_labels.contains(&name) {
+                    return Task::none();
+                }
+                let mut profile = if matches!(message, Message::DuplicateInstance) {
+                    self.profile().clone()
+                } else {
+                    let mut config = Config::default();
+                    config.ensure_position_defaults();
+                    config
+                };
+                // Start in edit mode so the new overlay can be dragged off the
+                // one it was created over.
+                profile.widget_movable = true;
+                let mut instance = InstanceProfile::load(name.clone());
+                instance.config = profile;
+                self.config.overlay_instances.push(name);
+                self.instance = Some(instance);
+                self.instance_name_input.clear();
+                self.sync_inputs();
+            }
+            Message::DeleteInstance => {
+                let Some(instance) = self.instance.take() else {
+                    return Task::none();
+                };
+                self.config
+                    .overlay_instances
+                    .retain(|name| *name != instance.name);
+                instance.reset();
+                self.sync_inputs();
+            }
+            Message::ToggleCpu(value) => self.profile_mut().show_cpu = value,
+            Message::ToggleCpuDetails(value) => self.profile_mut().show_cpu_details = value,
+            Message::ToggleMemory(value) => self.profile_mut().show_memory = value,
+            Message::ToggleMemoryDetails(value) => {
+                self.profile_mut().show_memory_details = value;
+            }
+            Message::ToggleNetwork(value) => self.profile_mut().show_network = value,
+            Message::ToggleNetworkConnections(value) => {
+                self.profile_mut().show_network_connections = value;
+            }
+            Message::ToggleNetworkTraffic(value) => {
+                self.profile_mut().show_network_traffic = value;
+            }
+            Message::ToggleDisk(value) => self.profile_mut().show_disk = value,
+            Message::ToggleDiskDevices(value) => {
+                self.profile_mut().show_disk_devices = value;
+            }
+            Message::ToggleStorage(value) => self.profile_mut().show_storage = value,
+            Message::ToggleDriveHealth(value) => {
+                self.profile_mut().show_drive_health = value;
+            }
+            Message::TogglePoolHealth(value) => self.profile_mut().show_pool_health = value,
+            Message::ToggleGpu(value) => self.profile_mut().show_gpu = value,
+            Message::ToggleGpuDetails(value) => self.profile_mut().show_gpu_details = value,
+            Message::ToggleGpuProcesses(value) => {
+                self.profile_mut().show_gpu_processes = value;
+            }
+            Message::ToggleProcesses(value) => self.profile_mut().show_processes = value,
+            Message::SetProcessCount(count) => self.profile_mut().process_count = count,
+            Message::ToggleGroupProcessTrees(value) => {
+                self.profile_mut().group_process_trees = value;
+            }
+            Message::ToggleGpuShown(index, shown) => {
+                let Some(id) = self.cached_gpus.get(index).map(|gpu| gpu.id.clone()) else {
+                    return Task::none();
+                };
+                let hidden = &mut self.profile_mut().hidden_gpus;
+                hidden.retain(|hidden| *hidden != id);
+                if !shown {
+                    hidden.push(id);
+                }
+            }
+            Message::ToggleStorageMountShown(index, shown) => {
+                let Some(mount) = self.cached_storage_mounts.get(index).cloned() else {
+                    return Task::none();
+                };
+                self.profile_mut().set_storage_mount_shown(
+                    &mount.mount_point,
+                    &mount.fs_type,
+                    &mount.device_names(),
+                    shown,
+                );
+            }
+            Message::UpdateStorageLabel(index, name) => {
+                let Some(mount_point) = self
+                    .cached_storage_mounts
+                    .get(index)
+                    .map(|mount| mount.mount_point.clone())
+                else {
+                    return Task::none();
+                };
+                self.profile_mut().set_storage_label(&mount_point, name);
+            }
+            Message::SetStorageSort(sort) => self.profile_mut().storage_sort = sort,
+            Message::ToggleNetworkInterfaceShown(index, shown) => {
+                let Some(interface) = self.cached_network_interfaces.get(index).cloned() else {
+                    return Task::none();
+                };
+                let profile = self.profile_mut();
+                profile
+                    .network_interfaces_included
+                    .retain(|name| *name != interface.name);
+                profile
+                    .network_interfaces_excluded
+                    .retain(|name| *name != interface.name);
+                if shown && !interface.physical {
+                    profile.network_interfaces_included.push(interface.name);
+                } else if !shown && interface.physical {
+                    profile.network_interfaces_excluded.push(interface.name);
+                }
+            }
+            Message::ToggleCpuTemp(value) => self.profile_mut().show_cpu_temp = value,
+            Message::ToggleGpuTemp(value) => self.profile_mut().show_gpu_temp = value,
             Message::SetTemperatureGaugeStyle(style) => {
-                self.config.temperature_gauge_style = style;
+                self.profile_mut().temperature_gauge_style = style;
             }
-            Message::ToggleClock(value) => self.config.show_clock = value,
-            Message::ToggleDate(value) => self.config.show_date = value,
-            Message::Toggle24HourTime(value) => self.config.use_24hour_time = value,
-            Message::TogglePercentages(value) => self.config.show_percentages = value,
-            Message::ToggleDevices(value) => self.config.show_battery = value,
+            Message::SetHistoryGraphStyle(section, style) => {
+                self.profile_mut().set_history_graph_style(section, style);
+            }
+            Message::SetHistoryWindow(section, window) => {
+                self.profile_mut().set_history_window_secs(section, window);
+            }
+            Message::ToggleClock(value) => self.profile_mut().show_clock = value,
+            Message::ToggleDate(value) => self.profile_mut().show_date = value,
+            Message::Toggle24HourTime(value) => self.profile_mut().use_24hour_time = value,
+            Message::TogglePercentages(value) => self.profile_mut().show_percentages = value,
+            Message::ToggleDevices(value) => self.profile_mut().show_battery = value,
             Message::ToggleSolaarIntegration(value) => {
                 self.config.enable_solaar_integration = value;
             }
-            Message::ToggleNotifications(value) => self.config.show_notifications = value,
-            Message::ToggleMedia(value) => self.config.show_media = value,
-            Message::ToggleWeather(value) => self.config.show_weather = value,
+            Message::ToggleNotifications(value) => self.profile_mut().show_notifications = value,
+            Message::ToggleMedia(value) => self.profile_mut().show_media = value,
+            Message::ToggleCommands(value) => self.profile_mut().show_commands = value,
+            Message::ToggleWeather(value) => self.profile_mut().show_weather = value,
             Message::ToggleWidgetAutostart(value) => self.config.widget_autostart = value,
             Message::ToggleLogging(value) => self.config.enable_logging = value,
             Message::UpdateMaxNotifications(value) => {
@@ -773,6 +2195,258 @@ impl Application for SettingsApp {
                     return Task::none();
                 }
             }
+            Message::ToggleMetricsExporter(value) => self.config.enable_metrics_exporter = value,
+            Message::UpdateMetricsBindAddress(value) => {
+                self.metrics_bind_address_input = value;
+                if self.metrics_bind_address_input.parse::<IpAddr>().is_ok() {
+                    self.config
+                        .metrics_bind_address
+                        .clone_from(&self.metrics_bind_address_input);
+                } else {
+                    return Task::none();
+                }
+            }
+            Message::UpdateMetricsPort(value) => {
+                self.metrics_port_input = value;
+                if let Some(port) = parse_bounded_usize(&self.metrics_port_input, 1, 65_535) {
+                    self.config.metrics_port = port as u16;
+                } else {
+                    return Task::none();
+                }
+            }
+            Message::UpdateBillingDay(value) => {
+                self.billing_day_input = value;
+                if let Some(day) =
+                    parse_bounded_usize(&self.billing_day_input, 1, MAX_BILLING_DAY as usize)
+                {
+                    self.config.network_billing_day = day as u32;
+                } else {
+                    return Task::none();
+                }
+            }
+            Message::UpdateNetworkQuota(value) => {
+                self.network_quota_input = value;
+                if let Some(quota) =
+                    parse_bounded_usize(&self.network_quota_input, 0, u32::MAX as usize)
+                {
+                    self.config.network_quota_gib = quota as u32;
+                } else {
+                    return Task::none();
+                }
+            }
+            Message::UpdatePollInterval(monitor, value) => {
+                let input = &mut self.poll_interval_inputs[monitor as usize];
+                *input = value;
+                if let Some(interval) = parse_bounded_usize(
+                    input,
+                    MIN_POLL_INTERVAL_MS as usize,
+                    MAX_POLL_INTERVAL_MS as usize,
+                ) {
+                    self.config.set_poll_interval_ms(monitor, interval as u64);
+                } else {
+                    return Task::none();
+                }
+            }
+            Message::AddCommand => {
+                let command = ShellCommand::default();
+                self.command_inputs.push(CommandInputs::new(&command));
+                self.config.commands.push(command);
+            }
+            Message::RemoveCommand(index) => {
+                if index >= self.config.commands.len() {
+                    return Task::none();
+                }
+                self.config.commands.remove(index);
+                self.command_inputs = command_inputs(&self.config);
+            }
+            Message::ToggleCommand(index, enabled) => {
+                let Some(command) = self.config.commands.get_mut(index) else {
+                    return Task::none();
+                };
+                command.enabled = enabled;
+            }
+            Message::ToggleCommandTable(index, table) => {
+                let Some(command) = self.config.commands.get_mut(index) else {
+                    return Task::none();
+                };
+                command.table = table;
+            }
+            Message::UpdateCommandField(index, field, value) => {
+                let (Some(command), Some(inputs)) = (
+                    self.config.commands.get_mut(index),
+                    self.command_inputs.get_mut(index),
+                ) else {
+                    return Task::none();
+                };
+                *inputs.get_mut(field) = value.clone();
+                let parsed = match field {
+                    CommandField::Label => {
+                        command.label = value;
+                        Ok(())
+                    }
+                    CommandField::Command => {
+                        command.command = value;
+                        Ok(())
+                    }
+                    CommandField::Interval => value
+                        .trim()
+                        .parse()
+                        .map(|secs| command.interval_secs = secs),
+                    CommandField::Timeout => {
+                        value.trim().parse().map(|secs| command.timeout_secs = secs)
+                    }
+                    CommandField::OutputLimit => value
+                        .trim()
+                        .parse()
+                        .map(|bytes| command.max_output_bytes = bytes),
+                };
+                if parsed.is_err() {
+                    return Task::none();
+                }
+            }
+            Message::RefreshCommandStatus => {
+                self.command_results = CachedCommandResult::load_all();
+                return Task::none();
+            }
+            Message::OverlayReadings(readings) => {
+                self.overlay_readings = readings;
+                return Task::none();
+            }
+            Message::AddAlertRule => {
+                let rule = AlertRule::default();
+                self.alert_inputs.push(AlertRuleInputs::new(&rule));
+                self.config.alert_rules.push(rule);
+            }
+            Message::RemoveAlertRule(index) => {
+                if index >= self.config.alert_rules.len() {
+                    return Task::none();
+                }
+                self.config.alert_rules.remove(index);
+                self.alert_inputs = alert_inputs(&self.config);
+            }
+            Message::ToggleAlertRule(index, enabled) => {
+                let Some(rule) = self.config.alert_rules.get_mut(index) else {
+                    return Task::none();
+                };
+                rule.enabled = enabled;
+            }
+            Message::SetAlertMetric(index, selected) => {
+                let (Some(rule), Some(metric)) = (
+                    self.config.alert_rules.get_mut(index),
+                    AlertMetric::ALL.get(selected),
+                ) else {
+                    return Task::none();
+                };
+                rule.metric = *metric;
+            }
+            Message::SetAlertComparison(index, selected) => {
+                let (Some(rule), Some(comparison)) = (
+                    self.config.alert_rules.get_mut(index),
+                    AlertComparison::ALL.get(selected),
+                ) else {
+                    return Task::none();
+                };
+                rule.comparison = *comparison;
+            }
+            Message::UpdateAlertField(index, field, value) => {
+                let (Some(rule), Some(inputs)) = (
+                    self.config.alert_rules.get_mut(index),
+                    self.alert_inputs.get_mut(index),
+                ) else {
+                    return Task::none();
+                };
+                *inputs.get_mut(field) = value.clone();
+                let value = value.trim();
+                let parsed = match field {
+                    AlertField::Threshold => {
+                        value.parse().map(|threshold| rule.threshold = threshold)
+                    }
+                    AlertField::Hysteresis => {
+                        value.parse().map(|hysteresis| rule.hysteresis = hysteresis)
+                    }
+                    AlertField::Duration => value.parse().map(|secs| rule.duration_secs = secs),
+                    AlertField::Cooldown => value.parse().map(|secs| rule.cooldown_secs = secs),
+                    AlertField::Target => {
+                        rule.target = value.to_string();
+                        Ok(())
+                    }
+                };
+                if parsed.is_err() {
+                    return Task::none();
+                }
+            }
+            Message::AddCustomSection => {
+                self.profile_mut().add_custom_section();
+                self.template_editors = template_editors(self.profile());
+            }
+            Message::RemoveCustomSection(index) => {
+                let profile = self.profile_mut();
+                if index >= profile.custom_sections.len() {
+                    return Task::none();
+                }
+                profile.custom_sections.remove(index);
+                profile.ensure_all_sections();
+                self.template_editors = template_editors(self.profile());
+            }
+            Message::UpdateCustomTitle(index, value) => {
+                let Some(custom) = self.profile_mut().custom_sections.get_mut(index) else {
+                    return Task::none();
+                };
+                custom.title = value;
+            }
+            Message::EditCustomTemplate(index, action) => {
+                let Some(content) = self.template_editors.get_mut(index) else {
+                    return Task::none();
+                };
+                let edited = action.is_edit();
+                content.perform(action);
+                if !edited {
+                    return Task::none();
+                }
+                let text = content.text();
+                let Some(custom) = self.profile_mut().custom_sections.get_mut(index) else {
+                    return Task::none();
+                };
+                custom.template = text.strip_suffix('\n').unwrap_or(&text).to_string();
+            }
+            Message::UpdateConkyPath(value) => {
+                self.conky_path_input = value;
+                return Task::none();
+            }
+            Message::ImportConky => {
+                let path = expand_home(self.conky_path_input.trim());
+                let mut result = std::fs::read_to_string(&path)
+                    .map_err(|error| format!("Could not read {}: {error}", path.display()))
+                    .and_then(|source| {
+                        conky::import(&source, self.profile_mut())
+                            .map_err(|error| error.to_string())
+                    });
+                match &mut result {
+                    Ok(report) => {
+                        if let Some(interval) = report.update_interval_ms {
+                            let profile = self.profile_mut();
+                            for monitor in PolledMonitor::ALL {
+                                profile.set_poll_interval_ms(monitor, interval);
+                            }
+                            // The sampler is shared and polls at the main
+                            // profile's intervals.
+                            if self.instance.is_some() {
+                                report.untranslated.push(
+                                    "update_interval: polling follows the default overlay"
+                                        .to_string(),
+                                );
+                            }
+                        }
+                        self.sync_inputs();
+                    }
+                    Err(error) => log::warn!("Failed to import conky config: {error}"),
+                }
+                let failed = result.is_err();
+                self.conky_import = Some(result);
+                if failed {
+                    return Task::none();
+                }
+            }
             Message::UpdateCiderApiToken(value) => {
                 self.cider_api_token_input = value.clone();
                 self.config.cider_api_token = value;
@@ -784,7 +2458,7 @@ impl Application for SettingsApp {
             Message::UpdateX(value) => {
                 self.x_input = value;
                 if let Ok(position) = self.x_input.parse::<i32>() {
-                    self.config.widget_x = position;
+                    self.profile_mut().widget_x = position;
                 } else {
                     return Task::none();
                 }
@@ -792,17 +2466,17 @@ impl Application for SettingsApp {
             Message::UpdateY(value) => {
                 self.y_input = value;
                 if let Ok(position) = self.y_input.parse::<i32>() {
-                    self.config.widget_y = position;
+                    self.profile_mut().widget_y = position;
                 } else {
                     return Task::none();
                 }
             }
             Message::ResetPosition => {
-                self.config.reset_widget_position();
+                self.profile_mut().reset_widget_position();
                 self.sync_inputs();
             }
             Message::EditPosition => {
-                self.config.widget_movable = true;
+                self.profile_mut().widget_movable = true;
             }
             Message::UpdateWeatherLocation(value) => {
                 self.weather_location_input = value.clone();
@@ -818,12 +2492,12 @@ impl Application for SettingsApp {
                 return Task::none();
             }
             Message::MoveSectionUp(index) => {
-                if !move_enabled_section(&mut self.config, index, -1) {
+                if !move_enabled_section(self.profile_mut(), index, -1) {
                     return Task::none();
                 }
             }
             Message::MoveSectionDown(index) => {
-                if !move_enabled_section(&mut self.config, index, 1) {
+                if !move_enabled_section(self.profile_mut(), index, 1) {
                     return Task::none();
                 }
             }
@@ -849,6 +2523,9 @@ fn section_enabled(config: &Config, section: WidgetSection) -> bool {
         WidgetSection::Weather => config.show_weather,
         WidgetSection::Notifications => config.show_notifications,
         WidgetSection::Media => config.show_media,
+        WidgetSection::Commands => config.show_commands,
+        WidgetSection::Processes => config.show_processes,
+        WidgetSection::Custom(id) => config.custom_section(id).is_some(),
     }
 }
 
@@ -879,6 +2556,216 @@ fn move_enabled_section(config: &mut Config, index: usize, direction: i8) -> boo
     }
 }
 
+fn instance_labels(config: &Config) -> Vec<String> {
+    let mut labels = vec!["Default".to_string()];
+    labels.extend(config.instance_names());
+    labels
+}
+
+/// Output dropdown labels and the connector names they select.
+fn output_choices(outputs: &Outputs, configured: &str) -> (Vec<String>, Vec<String>) {
+    let mut labels = vec!["Automatic".to_string()];
+    let mut names = vec![String::new()];
+    for output in outputs.iter() {
+        labels.push(format!("{} ({})", output.description, output.name));
+        names.push(output.name.clone());
+    }
+
+    let configured = configured.trim();
+    if !configured.is_empty() && outputs.find(configured).is_none() {
+        labels.push(format!("{configured} (disconnected)"));
+        names.push(configured.to_string());
+    }
+    (labels, names)
+}
+
+/// Expand a leading `~/` to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(relative), Some(home)) => home.join(relative),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// The running overlay's readings, with example values for the variables
+/// it has no reading for or while it is not running.
+struct PreviewValues<'a> {
+    readings: Option<&'a Readings>,
+}
+
+impl Values for PreviewValues<'_> {
+    fn value(&self, name: &str, argument: Option<&str>) -> Option<String> {
+        if name == "time" {
+            return template::format_time(Local::now(), argument);
+        }
+        self.readings
+            .and_then(|readings| {
+                ReadingValues {
+                    readings,
+                    now: Local::now(),
+                }
+                .value(name, argument)
+            })
+            .or_else(|| {
+                VARIABLES
+                    .iter()
+                    .find(|(variable, ..)| *variable == name)
+                    .map(|(_, _, example)| example.to_string())
+            })
+    }
+}
+
+/// Readings of the running overlay every `interval`, read from its D-Bus
+/// properties.
+fn overlay_readings_stream(
+    interval: &Duration,
+) -> impl cosmic::iced::futures::Stream<Item = Option<Readings>> + use<> {
+    let interval = *interval;
+
+    cosmic::iced::stream::channel(1, async move |mut output| {
+        let connection = match zbus::Connection::session().await {
+            Ok(connection) => Some(connection),
+            Err(error) => {
+                log::warn!("Failed to connect to the session bus for template previews: {error}");
+                None
+            }
+        };
+        loop {
+            let readings = match &connection {
+                Some(connection) => overlay_readings(connection).await,
+                None => None,
+            };
+            if output.send(readings).await.is_err() {
+                break;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    })
+}
+
+/// The overlay's readings, or `None` if it is not running.
+async fn overlay_readings(connection: &zbus::Connection) -> Option<Readings> {
+    let properties = zbus::Proxy::new(
+        connection,
+        OVERLAY_BUS_NAME,
+        OVERLAY_OBJECT_PATH,
+        "org.freedesktop.DBus.Properties",
+    )
+    .await
+    .ok()?;
+    let values: HashMap<String, OwnedValue> = properties
+        .call("GetAll", &(OVERLAY_INTERFACE,))
+        .await
+        .ok()?;
+    Some(readings_from_properties(&values))
+}
+
+fn readings_from_properties(values: &HashMap<String, OwnedValue>) -> Readings {
+    let number = |key: &str| {
+        values
+            .get(key)
+            .and_then(|value| f64::try_from(value).ok())
+            .unwrap_or_default()
+    };
+    let list = |key: &str| values.get(key).and_then(|value| value.try_clone().ok());
+    // (name, mount point, used percentage, total bytes, available bytes)
+    let disks = list("Disks")
+        .and_then(|value| Vec::<(String, String, f64, u64, u64)>::try_from(value).ok())
+        .unwrap_or_default();
+    // (name, battery level or -1, status, connected)
+    let devices = list("Devices")
+        .and_then(|value| Vec::<(String, i32, String, bool)>::try_from(value).ok())
+        .unwrap_or_default();
+
+    Readings {
+        cpu: number("CpuUsage"),
+        memory: number("MemoryUsage"),
+        memory_bytes: None,
+        gpu: number("GpuUsage"),
+        cpu_temp: number("CpuTemperature"),
+        gpu_temp: number("GpuTemperature"),
+        network_rx_rate: number("NetworkRxRate"),
+        network_tx_rate: number("NetworkTxRate"),
+        disk_read_rate: number("DiskReadRate"),
+        disk_write_rate: number("DiskWriteRate"),
+        disks: disks
+            .into_iter()
+            .map(|(_, mount_point, used, _, available)| (mount_point, used, available))
+            .collect(),
+        batteries: devices
+            .into_iter()
+            .map(|(name, level, ..)| (name, u8::try_from(level).ok()))
+            .collect(),
+    }
+}
+
+fn template_editors(config: &Config) -> Vec<text_editor::Content> {
+    config
+        .custom_sections
+        .iter()
+        .map(|custom| text_editor::Content::with_text(&custom.template))
+        .collect()
+}
+
+/// Variables and markup accepted by custom section templates.
+fn template_help() -> String {
+    let variables = VARIABLES
+        .iter()
+        .map(|(name, description, _)| match *name {
+            "disk" | "disk_free" => format!("{{{name}:/mount}} {description}"),
+            "battery" => format!("{{{name}:Device}} {description}"),
+            "time" => format!("{{{name}:%H:%M}} {description}"),
+            _ => format!("{{{name}}} {description}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "Variables: {variables}. Markup: {{size 18}} and {{color #rrggbb}} change the \
+         text until {{size}} or {{color}}; {{{{ and }}}} are literal braces."
+    )
+}
+
+fn alert_input<'a>(
+    index: usize,
+    field: AlertField,
+    placeholder: &'a str,
+    value: &'a str,
+) -> Element<'a, Message> {
+    widget::text_input(placeholder, value)
+        .on_input(move |value| Message::UpdateAlertField(index, field, value))
+        .width(Length::Fixed(SHORT_INPUT_WIDTH))
+        .into()
+}
+
+fn command_inputs(config: &Config) -> Vec<CommandInputs> {
+    config.commands.iter().map(CommandInputs::new).collect()
+}
+
+fn command_input<'a>(
+    index: usize,
+    field: CommandField,
+    placeholder: &'a str,
+    value: &'a str,
+    width: f32,
+) -> Element<'a, Message> {
+    widget::text_input(placeholder, value)
+        .on_input(move |value| Message::UpdateCommandField(index, field, value))
+        .width(Length::Fixed(width))
+        .into()
+}
+
+fn alert_inputs(config: &Config) -> Vec<AlertRuleInputs> {
+    config
+        .alert_rules
+        .iter()
+        .map(AlertRuleInputs::new)
+        .collect()
+}
+
+fn poll_interval_inputs(config: &Config) -> [String; PolledMonitor::ALL.len()] {
+    PolledMonitor::ALL.map(|monitor| config.poll_interval_ms(monitor).to_string())
+}
+
 fn parse_bounded_usize(value: &str, minimum: usize, maximum: usize) -> Option<usize> {
     value
         .parse::<usize>()
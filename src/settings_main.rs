@@ -30,8 +30,11 @@
 //! the shared cosmic-config and immediately visible to the widget.
 
 mod config;
+mod conky;
 mod i18n;
+mod outputs;
 mod settings;
+mod template;
 
 /// Settings application entry point.
 ///
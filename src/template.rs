@@ -0,0 +1,534 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Conky-style text templates drawn by custom overlay sections.
+//!
+//! A template is plain text with variables and markup in braces:
+//!
+//! ```text
+//! {size 20}{time:%H:%M}{size}
+//! CPU {cpu} at {cpu_temp}, /home {color #e5a50a}{disk:/home}{color} used
+//! ```
+//!
+//! - `{name}` or `{name:argument}` is replaced with a reading, see
+//!   [`VARIABLES`]. Unknown variables are kept as written so typos show up.
+//! - `{size N}` sets the font size in pixels and `{size}` restores the default.
+//! - `{color #rrggbb}` sets the text colour and `{color}` restores the default.
+//! - `{{` and `}}` are literal braces.
+//!
+//! Markup stays in effect across line breaks until it is reset.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use cosmic::iced::{Alignment, Color};
+use cosmic::{Element, theme, widget};
+
+/// Smallest and largest accepted `{size}` values.
+const FONT_SIZES: std::ops::RangeInclusive<u16> = 6..=72;
+
+/// Font size of text without `{size}` markup, matching body text.
+pub const DEFAULT_FONT_SIZE: u16 = 14;
+
+/// Format used by `{time}` without an argument.
+const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+/// Variables a template can use, with a description and an example value.
+/// `disk`, `disk_free` and `battery` take the mount point or device name as
+/// argument, `time` an optional strftime format.
+pub const VARIABLES: [(&str, &str, &str); 15] = [
+    ("cpu", "CPU usage", "12%"),
+    ("mem", "Memory usage", "48%"),
+    ("mem_used", "Used memory", "7.6 GB"),
+    ("mem_total", "Total memory", "16 GB"),
+    ("gpu", "GPU usage", "5%"),
+    ("cpu_temp", "CPU temperature", "54°C"),
+    ("gpu_temp", "GPU temperature", "47°C"),
+    ("net_down", "Download rate", "1.2 MB/s"),
+    ("net_up", "Upload rate", "86.0 KB/s"),
+    ("disk_read", "Disk read rate", "4.1 MB/s"),
+    ("disk_write", "Disk write rate", "512.0 KB/s"),
+    ("disk", "Used space of a mount point", "63%"),
+    ("disk_free", "Free space of a mount point", "210 GB"),
+    ("battery", "Battery level of a named device", "80%"),
+    ("time", "Local time in a strftime format", "14:30"),
+];
+
+/// Resolves template variables to text.
+pub trait Values {
+    /// Text for `{name}` or `{name:argument}`, or `None` if the variable is
+    /// unknown or has no reading.
+    fn value(&self, name: &str, argument: Option<&str>) -> Option<String>;
+}
+
+/// Text style set by markup.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Style {
+    /// Font size in pixels, or `None` for the default body size.
+    pub size: Option<u16>,
+    /// Text colour, or `None` for the theme's text colour.
+    pub color: Option<Color>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Variable {
+        name: String,
+        argument: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    piece: Piece,
+    style: Style,
+}
+
+/// Styled run of text in a rendered line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+/// Parsed template.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Template {
+    lines: Vec<Vec<Segment>>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Self {
+        let mut style = Style::default();
+        let lines = source
+            .lines()
+            .map(|line| parse_line(line, &mut style))
+            .collect();
+        Self { lines }
+    }
+
+    /// Names of the variables the template uses.
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.lines
+            .iter()
+            .flatten()
+            .filter_map(|segment| match &segment.piece {
+                Piece::Variable { name, .. } => Some(name.as_str()),
+                Piece::Text(_) => None,
+            })
+    }
+
+    /// Largest font size on each line, or `None` for lines in the default
+    /// size only.
+    pub fn line_sizes(&self) -> impl Iterator<Item = Option<u16>> + '_ {
+        self.lines
+            .iter()
+            .map(|line| line.iter().filter_map(|segment| segment.style.size).max())
+    }
+
+    /// Lines of styled text with the variables replaced by `values`.
+    pub fn render(&self, values: &impl Values) -> Vec<Vec<Span>> {
+        self.lines
+            .iter()
+            .map(|line| {
+                let mut spans: Vec<Span> = Vec::with_capacity(line.len());
+                for segment in line {
+                    let text = match &segment.piece {
+                        Piece::Text(text) => text.clone(),
+                        Piece::Variable { name, argument } => values
+                            .value(name, argument.as_deref())
+                            .unwrap_or_else(|| match argument {
+                                Some(argument) => format!("{{{name}:{argument}}}"),
+                                None => format!("{{{name}}}"),
+                            }),
+                    };
+                    match spans.last_mut() {
+                        Some(last) if last.style == segment.style => last.text.push_str(&text),
+                        _ => spans.push(Span {
+                            text,
+                            style: segment.style,
+                        }),
+                    }
+                }
+                spans
+            })
+            .collect()
+    }
+}
+
+/// Rendered lines as a column of text rows.
+pub fn lines_view<'a, Message: 'a>(lines: Vec<Vec<Span>>, spacing: u16) -> Element<'a, Message> {
+    let mut column = widget::column::with_capacity(lines.len()).spacing(spacing);
+    for spans in lines {
+        let mut line = widget::row::with_capacity(spans.len().max(1)).align_y(Alignment::End);
+        if spans.is_empty() {
+            line = line.push(widget::text::body(""));
+        }
+        for span in spans {
+            let mut text =
+                widget::text(span.text).size(span.style.size.unwrap_or(DEFAULT_FONT_SIZE));
+            if let Some(color) = span.style.color {
+                text = text.class(theme::Text::Color(color));
+            }
+            line = line.push(text);
+        }
+        column = column.push(line);
+    }
+    column.into()
+}
+
+/// Local time in a user-supplied strftime format, or `None` if the format
+/// is invalid.
+pub fn format_time(now: DateTime<Local>, format: Option<&str>) -> Option<String> {
+    let items = StrftimeItems::new(format.unwrap_or(DEFAULT_TIME_FORMAT))
+        .map(|item| (!matches!(item, Item::Error)).then_some(item))
+        .collect::<Option<Vec<_>>>()?;
+    Some(now.format_with_items(items.into_iter()).to_string())
+}
+
+/// Readings the variables stand for. The overlay fills them from its latest
+/// snapshot and the settings preview from the overlay's D-Bus properties.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Readings {
+    pub cpu: f64,
+    pub memory: f64,
+    /// Used and total memory in bytes, if known
+    pub memory_bytes: Option<(u64, u64)>,
+    pub gpu: f64,
+    /// Degrees Celsius, zero without a sensor
+    pub cpu_temp: f64,
+    pub gpu_temp: f64,
+    /// Bytes per second
+    pub network_rx_rate: f64,
+    pub network_tx_rate: f64,
+    pub disk_read_rate: f64,
+    pub disk_write_rate: f64,
+    /// Mount point, used percentage and available bytes of each filesystem
+    pub disks: Vec<(String, f64, u64)>,
+    /// Name and battery level of each device
+    pub batteries: Vec<(String, Option<u8>)>,
+}
+
+/// [`Readings`] with `{time}` resolved to `now`.
+pub struct ReadingValues<'a> {
+    pub readings: &'a Readings,
+    pub now: DateTime<Local>,
+}
+
+impl Values for ReadingValues<'_> {
+    fn value(&self, name: &str, argument: Option<&str>) -> Option<String> {
+        let readings = self.readings;
+        let percent = |value: f64| format!("{value:.0}%");
+        let temperature = |value: f64| (value > 0.0).then(|| format!("{value:.0}°C"));
+        let disk = |mount_point: &str| {
+            readings
+                .disks
+                .iter()
+                .find(|(known, ..)| known == mount_point.trim())
+        };
+
+        match (name, argument) {
+            ("cpu", None) => Some(percent(readings.cpu)),
+            ("mem", None) => Some(percent(readings.memory)),
+            ("mem_used", None) => readings
+                .memory_bytes
+                .map(|(used, _)| format_storage_bytes(used)),
+            ("mem_total", None) => readings
+                .memory_bytes
+                .map(|(_, total)| format_storage_bytes(total)),
+            ("gpu", None) => Some(percent(readings.gpu)),
+            ("cpu_temp", None) => temperature(readings.cpu_temp),
+            ("gpu_temp", None) => temperature(readings.gpu_temp),
+            ("net_down", None) => Some(format_network_rate(readings.network_rx_rate)),
+            ("net_up", None) => Some(format_network_rate(readings.network_tx_rate)),
+            ("disk_read", None) => Some(format_network_rate(readings.disk_read_rate)),
+            ("disk_write", None) => Some(format_network_rate(readings.disk_write_rate)),
+            ("disk", Some(mount_point)) => disk(mount_point).map(|(_, used, _)| percent(*used)),
+            ("disk_free", Some(mount_point)) => {
+                disk(mount_point).map(|(_, _, available)| format_storage_bytes(*available))
+            }
+            ("battery", Some(device)) => readings
+                .batteries
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(device.trim()))
+                .and_then(|(_, level)| *level)
+                .map(|level| format!("{level}%")),
+            ("time", format) => format_time(self.now, format),
+            _ => None,
+        }
+    }
+}
+
+/// Decimal size, such as "210 GB".
+pub fn format_storage_bytes(bytes: u64) -> String {
+    const KB: f64 = 1_000.0;
+    const MB: f64 = KB * 1_000.0;
+    const GB: f64 = MB * 1_000.0;
+    const TB: f64 = GB * 1_000.0;
+
+    let bytes = bytes as f64;
+    if bytes >= TB {
+        format!("{:.1} TB", bytes / TB)
+    } else if bytes >= GB {
+        format!("{:.0} GB", bytes / GB)
+    } else if bytes >= MB {
+        format!("{:.0} MB", bytes / MB)
+    } else if bytes >= KB {
+        format!("{:.0} KB", bytes / KB)
+    } else {
+        format!("{bytes:.0} B")
+    }
+}
+
+/// Binary rate, such as "1.2 MB/s".
+pub fn format_network_rate(bytes_per_second: f64) -> String {
+    const KB: f64 = 1_024.0;
+    const MB: f64 = KB * 1_024.0;
+    const GB: f64 = MB * 1_024.0;
+
+    let rate = if bytes_per_second.is_finite() {
+        bytes_per_second.max(0.0)
+    } else {
+        0.0
+    };
+
+    if rate >= GB {
+        format!("{:.1} GB/s", rate / GB)
+    } else if rate >= MB {
+        format!("{:.1} MB/s", rate / MB)
+    } else if rate >= KB {
+        format!("{:.1} KB/s", rate / KB)
+    } else {
+        format!("{rate:.0} B/s")
+    }
+}
+
+fn parse_line(line: &str, style: &mut Style) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = line;
+
+    while let Some(character) = rest.chars().next() {
+        let after = &rest[character.len_utf8()..];
+        match character {
+            '{' if after.starts_with('{') => {
+                text.push('{');
+                rest = &after[1..];
+            }
+            '}' if after.starts_with('}') => {
+                text.push('}');
+                rest = &after[1..];
+            }
+            '{' => {
+                let Some(end) = after.find('}') else {
+                    text.push_str(rest);
+                    break;
+                };
+                let tag = &after[..end];
+                rest = &after[end + 1..];
+
+                if let Some(markup) = parse_markup(tag) {
+                    push_text(&mut segments, &mut text, *style);
+                    markup.apply(style);
+                    continue;
+                }
+
+                let (name, argument) = match tag.split_once(':') {
+                    Some((name, argument)) => (name.trim(), Some(argument.to_string())),
+                    None => (tag.trim(), None),
+                };
+                if name.is_empty() {
+                    text.push('{');
+                    text.push_str(tag);
+                    text.push('}');
+                    continue;
+                }
+                push_text(&mut segments, &mut text, *style);
+                segments.push(Segment {
+                    piece: Piece::Variable {
+                        name: name.to_string(),
+                        argument,
+                    },
+                    style: *style,
+                });
+            }
+            _ => {
+                text.push(character);
+                rest = after;
+            }
+        }
+    }
+
+    push_text(&mut segments, &mut text, *style);
+    segments
+}
+
+fn push_text(segments: &mut Vec<Segment>, text: &mut String, style: Style) {
+    if !text.is_empty() {
+        segments.push(Segment {
+            piece: Piece::Text(std::mem::take(text)),
+            style,
+        });
+    }
+}
+
+enum Markup {
+    Size(Option<u16>),
+    Color(Option<Color>),
+}
+
+impl Markup {
+    fn apply(self, style: &mut Style) {
+        match self {
+            Markup::Size(size) => style.size = size,
+            Markup::Color(color) => style.color = color,
+        }
+    }
+}
+
+/// `{size}`, `{size N}`, `{color}` or `{color #rrggbb}`. Malformed values
+/// reset the style rather than being drawn as text.
+fn parse_markup(tag: &str) -> Option<Markup> {
+    let (keyword, value) = match tag.trim().split_once(char::is_whitespace) {
+        Some((keyword, value)) => (keyword, Some(value.trim())),
+        None => (tag.trim(), None),
+    };
+
+    match keyword {
+        "size" => Some(Markup::Size(value.and_then(|value| {
+            value
+                .parse::<u16>()
+                .ok()
+                .map(|size| size.clamp(*FONT_SIZES.start(), *FONT_SIZES.end()))
+        }))),
+        "color" => Some(Markup::Color(value.and_then(parse_hex_color))),
+        _ => None,
+    }
+}
+
+fn parse_hex_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReadingValues, Readings, Span, Style, Template, Values, format_time};
+    use chrono::{Local, TimeZone};
+    use cosmic::iced::Color;
+
+    struct Sample;
+
+    impl Values for Sample {
+        fn value(&self, name: &str, argument: Option<&str>) -> Option<String> {
+            match (name, argument) {
+                ("cpu", None) => Some("12%".to_string()),
+                ("disk", Some("/home")) => Some("63%".to_string()),
+                _ => None,
+            }
+        }
+    }
+
+    fn plain(text: &str) -> Span {
+        Span {
+            text: text.to_string(),
+            style: Style::default(),
+        }
+    }
+
+    #[test]
+    fn replaces_variables_and_keeps_unknown_ones_visible() {
+        let template = Template::parse("CPU {cpu}, /home {disk:/home}, {nope} {{literal}}");
+
+        assert_eq!(
+            template.render(&Sample),
+            vec![vec![plain("CPU 12%, /home 63%, {nope} {literal}")]]
+        );
+        assert_eq!(
+            template.variables().collect::<Vec<_>>(),
+            ["cpu", "disk", "nope"]
+        );
+    }
+
+    #[test]
+    fn markup_styles_text_until_reset_across_lines() {
+        let template =
+            Template::parse("{size 20}{color #ff8800}Big\nstill{color} plain{size}\nend");
+        let orange = Color::from_rgb8(0xff, 0x88, 0x00);
+        let big = Style {
+            size: Some(20),
+            color: Some(orange),
+        };
+
+        assert_eq!(
+            template.render(&Sample),
+            vec![
+                vec![Span {
+                    text: "Big".to_string(),
+                    style: big,
+                }],
+                vec![
+                    Span {
+                        text: "still".to_string(),
+                        style: big,
+                    },
+                    Span {
+                        text: " plain".to_string(),
+                        style: Style {
+                            size: Some(20),
+                            color: None,
+                        },
+                    },
+                ],
+                vec![plain("end")],
+            ]
+        );
+        assert_eq!(
+            template.line_sizes().collect::<Vec<_>>(),
+            [Some(20), Some(20), None]
+        );
+    }
+
+    #[test]
+    fn invalid_time_formats_are_rejected() {
+        let now = Local.with_ymd_and_hms(2026, 3, 14, 9, 5, 0).unwrap();
+
+        assert_eq!(format_time(now, None).as_deref(), Some("09:05"));
+        assert_eq!(format_time(now, Some("%d.%m.")).as_deref(), Some("14.03."));
+        assert_eq!(format_time(now, Some("%Q")), None);
+    }
+
+    #[test]
+    fn readings_resolve_with_units_and_leave_missing_ones_unset() {
+        let readings = Readings {
+            cpu: 12.4,
+            network_rx_rate: 1_536.0,
+            disks: vec![("/home".to_string(), 63.2, 210_000_000_000)],
+            batteries: vec![("MX Master 3".to_string(), Some(80))],
+            ..Readings::default()
+        };
+        let values = ReadingValues {
+            readings: &readings,
+            now: Local.with_ymd_and_hms(2026, 3, 14, 9, 5, 0).unwrap(),
+        };
+
+        assert_eq!(values.value("cpu", None).as_deref(), Some("12%"));
+        assert_eq!(values.value("net_down", None).as_deref(), Some("1.5 KB/s"));
+        assert_eq!(values.value("disk", Some("/home")).as_deref(), Some("63%"));
+        assert_eq!(
+            values.value("disk_free", Some(" /home ")).as_deref(),
+            Some("210 GB")
+        );
+        assert_eq!(
+            values.value("battery", Some("mx master 3")).as_deref(),
+            Some("80%")
+        );
+        assert_eq!(values.value("time", None).as_deref(), Some("09:05"));
+        assert_eq!(values.value("cpu_temp", None), None);
+        assert_eq!(values.value("mem_used", None), None);
+        assert_eq!(values.value("disk", Some("/srv")), None);
+    }
+}
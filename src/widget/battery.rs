@@ -48,6 +48,7 @@
 //! - Parse failure → keep previous snapshot
 //! - Device disconnected → device is omitted from the visible snapshot
 
+use serde::Serialize;
 use std::collections::HashSet;
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -91,7 +92,7 @@ const EXTERNAL_DISCOVERY_INTERVAL: Duration = Duration::from_secs(5 * 60);
 /// - `codename`: Short device codename for deduplication (e.g., "MX MCHNCL M")
 /// - `is_loading`: True while waiting for first real data (showing cached)
 /// - `is_connected`: False if device is paired but powered off/out of range
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct BatteryDevice {
     /// Device product name from Solaar/HeadsetControl
     pub name: String,
@@ -176,6 +177,8 @@ pub struct BatteryMonitor {
     update_requested: Arc<Mutex<bool>>,
     /// Whether the Solaar compatibility fallback may be queried
     solaar_enabled: Arc<AtomicBool>,
+    /// Cleared on drop so the background polling thread exits
+    running: Arc<AtomicBool>,
 }
 
 impl BatteryMonitor {
@@ -197,6 +200,7 @@ impl BatteryMonitor {
     /// - Only queries active fallback backends during 30-second updates
     /// - Rechecks inactive external backends every five minutes
     /// - On error, keeps previous device snapshot
+    /// - Exits after the monitor is dropped
     pub fn new() -> Self {
         Self::new_with_solaar(true)
     }
@@ -208,10 +212,12 @@ impl BatteryMonitor {
 
         // Cache provides provisional values only after live discovery confirms
         // that the corresponding device is connected.
-        let mut cache = WidgetCache::load();
-        if cache.normalize_battery_devices() {
-            cache.save();
-        }
+        let cache = WidgetCache::update(|cache| {
+            if cache.normalize_battery_devices() {
+                cache.save();
+            }
+            cache.clone()
+        });
         let cached_devices: Vec<BatteryDevice> = cache
             .battery_devices
             .iter()
@@ -242,12 +248,14 @@ impl BatteryMonitor {
         let devices = Arc::new(Mutex::new(startup_devices));
         let update_requested = Arc::new(Mutex::new(true)); // Request initial update immediately
         let solaar_enabled = Arc::new(AtomicBool::new(enable_solaar));
+        let running = Arc::new(AtomicBool::new(true));
 
         // Spawn background thread for battery updates
         // This avoids blocking the main render loop on slow CLI tools
         let devices_clone = Arc::clone(&devices);
         let update_requested_clone = Arc::clone(&update_requested);
         let solaar_enabled_clone = Arc::clone(&solaar_enabled);
+        let running_clone = Arc::clone(&running);
 
         std::thread::spawn(move || {
             let initial_probe_started = Instant::now();
@@ -406,6 +414,9 @@ impl BatteryMonitor {
                     )
                 };
                 std::thread::sleep(poll_interval);
+                if !running_clone.load(Ordering::Relaxed) {
+                    break;
+                }
 
                 let standard_native_poll_due = fast_initial_poll
                     || last_standard_native_poll.elapsed() >= NATIVE_POLL_INTERVAL;
@@ -543,6 +554,7 @@ impl BatteryMonitor {
             refresh_interval: EXTERNAL_FALLBACK_REFRESH_INTERVAL,
             update_requested,
             solaar_enabled,
+            running,
         }
     }
 
@@ -584,6 +596,12 @@ impl BatteryMonitor {
     }
 }
 
+impl Drop for BatteryMonitor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
 // ============================================================================
 // Native Device Queries
 // ============================================================================
@@ -821,12 +839,13 @@ fn persist_battery_readings(
         return;
     }
 
-    let mut cache = WidgetCache::load();
-    let readings_changed = cache.merge_battery_devices(devices);
-    let connections_changed = cache.update_last_connected_battery_devices(devices);
-    if readings_changed || connections_changed {
-        cache.save();
-    }
+    WidgetCache::update(|cache| {
+        let readings_changed = cache.merge_battery_devices(devices);
+        let connections_changed = cache.update_last_connected_battery_devices(devices);
+        if readings_changed || connections_changed {
+            cache.save();
+        }
+    });
     *previous_snapshot = Some(snapshot);
 }
 
@@ -16,17 +16,56 @@
 //! # Data Stored
 //!
 //! - **Disk information**: Name and mount point of discovered disks
+//! - **Storage mounts**: Every candidate mount with its filesystem type and
+//!   device, for choosing the shown ones
 //! - **Battery devices**: Name, type, and last confirmed battery reading
+//! - **GPUs**: PCI address and name of each GPU, for choosing the shown ones
+//! - **Network interfaces**: Name of each interface and whether it is physical
 //!
 //! # Thread Safety
 //!
-//! The cache uses simple file I/O without locking. In practice, the widget
-//! writes and the settings app reads, so conflicts are rare. If data races
-//! occur, the worst case is displaying slightly stale data.
+//! Worker threads change the cache through [`WidgetCache::update`], which
+//! holds a process-wide lock from load to save so one writer cannot drop
+//! another's changes. Saves replace the file atomically, so readers in other
+//! processes, such as the settings app, never see a partial file.
 
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Cleared by processes that sample alongside the overlay, such as headless
+/// JSON output, so they never overwrite the overlay's cache files.
+static PERSISTENCE_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Stop this process from writing cache files.
+#[allow(dead_code)]
+pub fn disable_persistence() {
+    PERSISTENCE_ENABLED.store(false, Ordering::Relaxed);
+}
+
+/// Whether this process may write cache files.
+pub fn persistence_enabled() -> bool {
+    PERSISTENCE_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Held by [`WidgetCache::update`] from load to save.
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Replace `path` with `contents` through a temporary file, so readers see
+/// either the old or the new file.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+    let temporary = PathBuf::from(temporary);
+    fs::write(&temporary, contents)
+        .and_then(|()| fs::rename(&temporary, path))
+        .inspect_err(|_| {
+            fs::remove_file(&temporary).ok();
+        })
+}
 
 // ============================================================================
 // Cache Data Structures
@@ -35,12 +74,21 @@ use std::path::PathBuf;
 /// Cached information about a mounted disk.
 ///
 /// This is a simplified version of `DiskInfo` for serialization.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 pub struct CachedDiskInfo {
     /// Disk device name (e.g., "nvme0n1p2")
     pub name: String,
     /// Mount point path (e.g., "/home")
     pub mount_point: String,
+    /// Filesystem type (e.g., "btrfs")
+    #[serde(default)]
+    pub fs_type: String,
+    /// Mounted device path (e.g., "/dev/nvme0n1p2")
+    #[serde(default)]
+    pub source: String,
+    /// Kernel name of the block device (e.g., "dm-0")
+    #[serde(default)]
+    pub device: Option<String>,
 }
 
 /// Cached information about a battery device.
@@ -60,6 +108,24 @@ pub struct CachedBatteryDevice {
     pub status: Option<String>,
 }
 
+/// Cached identity of a GPU.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct CachedGpu {
+    /// PCI address (e.g., "0000:01:00.0")
+    pub id: String,
+    /// Display name (e.g., "NVIDIA GeForce RTX 4070")
+    pub name: String,
+}
+
+/// Cached identity of a network interface.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct CachedNetworkInterface {
+    /// Kernel name (e.g., "enp5s0")
+    pub name: String,
+    /// Backed by a device rather than virtual
+    pub physical: bool,
+}
+
 /// Main cache structure containing all cached device information.
 ///
 /// Serialized to JSON and stored in the user's cache directory.
@@ -67,11 +133,20 @@ pub struct CachedBatteryDevice {
 pub struct WidgetCache {
     /// All discovered mounted disks
     pub disks: Vec<CachedDiskInfo>,
+    /// Every mount the storage section can show, including hidden ones
+    #[serde(default)]
+    pub storage_mounts: Vec<CachedDiskInfo>,
     /// All discovered battery sources
     pub battery_devices: Vec<CachedBatteryDevice>,
     /// Device names confirmed connected by the latest live battery snapshot.
     #[serde(default)]
     pub last_connected_battery_devices: Vec<String>,
+    /// GPUs found by the latest GPU poll
+    #[serde(default)]
+    pub gpus: Vec<CachedGpu>,
+    /// Network interfaces found by the latest interface discovery
+    #[serde(default)]
+    pub network_interfaces: Vec<CachedNetworkInterface>,
 }
 
 // ============================================================================
@@ -104,14 +179,30 @@ impl WidgetCache {
         }
     }
 
+    /// Load the cache, let `change` modify and save it, and return its
+    /// result.
+    ///
+    /// Other threads of this process wait until `change` returns, so
+    /// concurrent writers of different fields keep each other's changes.
+    pub fn update<T>(change: impl FnOnce(&mut Self) -> T) -> T {
+        let _lock = match UPDATE_LOCK.lock() {
+            Ok(lock) => lock,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        change(&mut Self::load())
+    }
+
     /// Save the cache to disk.
     ///
     /// Uses pretty-printed JSON for easier debugging.
     /// Silently ignores write errors (cache is non-critical).
     pub fn save(&self) {
+        if !persistence_enabled() {
+            return;
+        }
         let path = Self::cache_path();
         if let Ok(json) = serde_json::to_string_pretty(self) {
-            fs::write(&path, json).ok();
+            write_atomically(&path, &json).ok();
         }
     }
 
@@ -119,14 +210,39 @@ impl WidgetCache {
     ///
     /// Replaces all cached disks and saves immediately.
     pub fn update_disks(&mut self, disks: &[super::storage::DiskInfo]) {
-        self.disks = disks
+        self.disks = cached_disks(disks);
+        self.save();
+    }
+
+    /// Replace the cached candidate mounts and save if they changed.
+    pub fn update_storage_mounts(&mut self, mounts: Vec<CachedDiskInfo>) {
+        if mounts != self.storage_mounts {
+            self.storage_mounts = mounts;
+            self.save();
+        }
+    }
+
+    /// Replace the cached GPUs and save if they changed.
+    pub fn update_gpus(&mut self, gpus: &[super::gpu::GpuDevice]) {
+        let gpus: Vec<_> = gpus
             .iter()
-            .map(|d| CachedDiskInfo {
-                name: d.name.clone(),
-                mount_point: d.mount_point.clone(),
+            .map(|gpu| CachedGpu {
+                id: gpu.id.clone(),
+                name: gpu.name.clone(),
             })
             .collect();
-        self.save();
+        if gpus != self.gpus {
+            self.gpus = gpus;
+            self.save();
+        }
+    }
+
+    /// Replace the cached network interfaces and save if they changed.
+    pub fn update_network_interfaces(&mut self, interfaces: Vec<CachedNetworkInterface>) {
+        if interfaces != self.network_interfaces {
+            self.network_interfaces = interfaces;
+            self.save();
+        }
     }
 
     /// Merge confirmed battery readings without replacing them with transient
@@ -271,6 +387,20 @@ impl WidgetCache {
     }
 }
 
+/// Cache entries of mounted disks.
+pub fn cached_disks(disks: &[super::storage::DiskInfo]) -> Vec<CachedDiskInfo> {
+    disks
+        .iter()
+        .map(|d| CachedDiskInfo {
+            name: d.name.clone(),
+            mount_point: d.mount_point.clone(),
+            fs_type: d.fs_type.clone(),
+            source: d.source.clone(),
+            device: d.device.clone(),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::battery::BatteryDevice;
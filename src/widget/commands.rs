@@ -0,0 +1,461 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! User-configured shell commands shown by the Commands section.
+//!
+//! Commands run one after another on a worker thread, never on the UI
+//! thread, so a slow command delays the others until it exits or its
+//! timeout expires. The thread starts with the first configured command,
+//! sleeps until the next one is due and stops with the monitor. Each run is
+//! contained:
+//!
+//! - `sh -c` starts in its own process group, with no stdin, the home
+//!   directory as working directory and only a few environment variables;
+//! - output beyond the configured limit is read and discarded, so a chatty
+//!   command can neither block on a full pipe nor grow the overlay's memory;
+//! - the whole process group is killed once the command finishes or its
+//!   timeout expires, so background children do not outlive the run.
+//!
+//! The latest results are also written to `commands.json` in the cache
+//! directory, where the settings application shows exit statuses and stderr.
+
+use crate::config::ShellCommand;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{ErrorKind, Read};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Environment variables passed on to commands.
+const PASSED_ENVIRONMENT: [&str; 7] = [
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "LC_ALL",
+    "XDG_RUNTIME_DIR",
+    "DBUS_SESSION_BUS_ADDRESS",
+];
+
+/// How often a running command is checked for completion.
+const WAIT_SLICE: Duration = Duration::from_millis(50);
+
+/// How long output readers may take after the process group was killed.
+/// Only a process that left the group can keep a pipe open that long.
+const READER_GRACE: Duration = Duration::from_millis(500);
+
+/// How a command run ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommandStatus {
+    /// The command has not finished its first run yet.
+    Pending,
+    /// Exited with this status code.
+    Exited(i32),
+    /// Killed by this signal before the timeout.
+    Signaled(i32),
+    /// Killed after running longer than its timeout.
+    TimedOut,
+    /// The shell could not be started.
+    Failed(String),
+}
+
+impl CommandStatus {
+    pub fn succeeded(&self) -> bool {
+        matches!(self, CommandStatus::Exited(0))
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            CommandStatus::Pending => "Waiting for first run".to_string(),
+            CommandStatus::Exited(code) => format!("Exit status {code}"),
+            CommandStatus::Signaled(signal) => format!("Killed by signal {signal}"),
+            CommandStatus::TimedOut => "Timed out".to_string(),
+            CommandStatus::Failed(error) => format!("Failed to start: {error}"),
+        }
+    }
+}
+
+/// Latest run of a configured command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandResult {
+    pub label: String,
+    /// Command line the result belongs to.
+    pub command: String,
+    pub table: bool,
+    /// Standard output without trailing whitespace.
+    pub stdout: String,
+    pub stderr: String,
+    pub status: CommandStatus,
+    /// Whether stdout or stderr was cut at the output limit.
+    pub truncated: bool,
+    /// Unix time the run finished, or 0 while pending.
+    pub finished_at: u64,
+}
+
+impl CommandResult {
+    fn pending(command: &ShellCommand) -> Self {
+        Self {
+            label: command.display_label().to_string(),
+            command: command.command.clone(),
+            table: command.table,
+            stdout: String::new(),
+            stderr: String::new(),
+            status: CommandStatus::Pending,
+            truncated: false,
+            finished_at: 0,
+        }
+    }
+}
+
+/// State shared by the monitor handles and the worker thread.
+struct Shared {
+    commands: Mutex<Vec<ShellCommand>>,
+    /// Signalled when the commands change or the last handle is dropped.
+    changed: Condvar,
+    results: Mutex<Vec<CommandResult>>,
+    stopped: AtomicBool,
+}
+
+impl Shared {
+    fn commands(&self) -> MutexGuard<'_, Vec<ShellCommand>> {
+        match self.commands.lock() {
+            Ok(commands) => commands,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Stops the worker once the last monitor handle is dropped.
+struct StopOnDrop(Arc<Shared>);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        // Holding the lock orders this with the worker's check before it
+        // waits, so the wake-up cannot be missed.
+        let _commands = self.0.commands();
+        self.0.stopped.store(true, Ordering::Relaxed);
+        self.0.changed.notify_all();
+    }
+}
+
+/// Runs the configured commands on a background thread, started once there
+/// is a command to run.
+#[derive(Clone)]
+pub struct CommandMonitor {
+    shared: Arc<Shared>,
+    worker: Arc<Once>,
+    _stop: Arc<StopOnDrop>,
+}
+
+impl CommandMonitor {
+    pub fn new(commands: Vec<ShellCommand>) -> Self {
+        let shared = Arc::new(Shared {
+            commands: Mutex::new(commands),
+            changed: Condvar::new(),
+            results: Mutex::new(Vec::new()),
+            stopped: AtomicBool::new(false),
+        });
+        let monitor = Self {
+            _stop: Arc::new(StopOnDrop(Arc::clone(&shared))),
+            shared,
+            worker: Arc::new(Once::new()),
+        };
+        if !monitor.shared.commands().is_empty() {
+            monitor.start_worker();
+        }
+        monitor
+    }
+
+    /// Replace the commands. An empty list stops running commands.
+    pub fn set_commands(&self, commands: Vec<ShellCommand>) {
+        let start = !commands.is_empty();
+        *self.shared.commands() = commands;
+        self.shared.changed.notify_all();
+        if start {
+            self.start_worker();
+        }
+    }
+
+    /// Latest result of each enabled command, in configured order.
+    pub fn results(&self) -> Vec<CommandResult> {
+        match self.shared.results.lock() {
+            Ok(results) => results.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn start_worker(&self) {
+        self.worker.call_once(|| {
+            let shared = Arc::clone(&self.shared);
+            std::thread::spawn(move || work(&shared));
+        });
+    }
+}
+
+/// Run due commands one after another until the monitor is dropped.
+fn work(shared: &Shared) {
+    // Last run of each configured command. Editing a command starts it
+    // over, as its old result no longer applies.
+    let mut runs: Vec<(ShellCommand, Instant, CommandResult)> = Vec::new();
+
+    while !shared.stopped.load(Ordering::Relaxed) {
+        let all = shared.commands().clone();
+        let configured = all
+            .iter()
+            .filter(|command| command.enabled && !command.command.trim().is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+        runs.retain(|(command, ..)| configured.contains(command));
+
+        let due = configured.iter().find(|command| {
+            runs.iter()
+                .find(|(run, ..)| run == *command)
+                .is_none_or(|(_, started, _)| started.elapsed() >= command.interval())
+        });
+        if let Some(command) = due {
+            let started = Instant::now();
+            let result = run(command);
+            if !result.status.succeeded() {
+                log::debug!(
+                    "Command '{}' ended with {}",
+                    command.command,
+                    result.status.description()
+                );
+            }
+            runs.retain(|(run, ..)| run != command);
+            runs.push((command.clone(), started, result));
+        }
+
+        let latest = configured
+            .iter()
+            .map(|command| {
+                runs.iter().find(|(run, ..)| run == command).map_or_else(
+                    || CommandResult::pending(command),
+                    |(.., result)| result.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let changed = {
+            let mut current = match shared.results.lock() {
+                Ok(current) => current,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            let changed = *current != latest;
+            if changed {
+                current.clone_from(&latest);
+            }
+            changed
+        };
+        if changed {
+            save_status(&latest);
+        }
+
+        if due.is_some() {
+            continue;
+        }
+        // Sleep until the next command is due, or without commands until
+        // they change.
+        let next_due = runs
+            .iter()
+            .map(|(command, started, _)| command.interval().saturating_sub(started.elapsed()))
+            .min();
+        let commands = shared.commands();
+        if shared.stopped.load(Ordering::Relaxed) || *commands != all {
+            continue;
+        }
+        match next_due {
+            Some(wait) => drop(shared.changed.wait_timeout(commands, wait)),
+            None => drop(shared.changed.wait(commands)),
+        }
+    }
+}
+
+/// Run a command to completion or until its timeout.
+fn run(command: &ShellCommand) -> CommandResult {
+    let mut shell = Command::new("sh");
+    shell
+        .arg("-c")
+        .arg(&command.command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .env_clear()
+        .process_group(0);
+    for name in PASSED_ENVIRONMENT {
+        if let Some(value) = std::env::var_os(name) {
+            shell.env(name, value);
+        }
+    }
+    if let Some(home) = dirs::home_dir() {
+        shell.current_dir(home);
+    }
+
+    let mut result = CommandResult::pending(command);
+    let mut child = match shell.spawn() {
+        Ok(child) => child,
+        Err(error) => {
+            result.status = CommandStatus::Failed(error.to_string());
+            result.finished_at = unix_time();
+            return result;
+        }
+    };
+    let limit = command.output_limit();
+    let stdout = child.stdout.take().map(|pipe| read_limited(pipe, limit));
+    let stderr = child.stderr.take().map(|pipe| read_limited(pipe, limit));
+
+    let deadline = Instant::now() + command.timeout();
+    result.status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break exit_status(status),
+            Ok(None) if Instant::now() >= deadline => {
+                kill_group(child.id());
+                let _ = child.wait();
+                break CommandStatus::TimedOut;
+            }
+            Ok(None) => std::thread::sleep(WAIT_SLICE),
+            Err(error) => {
+                kill_group(child.id());
+                let _ = child.wait();
+                break CommandStatus::Failed(error.to_string());
+            }
+        }
+    };
+    // Background children would otherwise keep the pipes open.
+    kill_group(child.id());
+
+    let (stdout, stdout_truncated) = collect(stdout);
+    let (stderr, stderr_truncated) = collect(stderr);
+    result.stdout = stdout.trim_end().to_string();
+    result.stderr = stderr.trim_end().to_string();
+    result.truncated = stdout_truncated || stderr_truncated;
+    result.finished_at = unix_time();
+    result
+}
+
+fn exit_status(status: ExitStatus) -> CommandStatus {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => CommandStatus::Exited(code),
+        (None, Some(signal)) => CommandStatus::Signaled(signal),
+        (None, None) => CommandStatus::Exited(-1),
+    }
+}
+
+fn kill_group(pid: u32) {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return;
+    };
+    // SAFETY: kill has no memory safety requirements. The group was created
+    // for this run, and an already empty group only yields ESRCH.
+    unsafe {
+        libc::kill(-pid, libc::SIGKILL);
+    }
+}
+
+/// Read a pipe to its end on a separate thread, keeping at most `limit` bytes.
+fn read_limited(mut pipe: impl Read + Send + 'static, limit: usize) -> Receiver<(Vec<u8>, bool)> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut kept = Vec::new();
+        let mut truncated = false;
+        let mut buffer = [0; 4096];
+        loop {
+            match pipe.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => {
+                    let room = limit.saturating_sub(kept.len());
+                    kept.extend_from_slice(&buffer[..read.min(room)]);
+                    truncated |= read > room;
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+        let _ = sender.send((kept, truncated));
+    });
+    receiver
+}
+
+fn collect(output: Option<Receiver<(Vec<u8>, bool)>>) -> (String, bool) {
+    output
+        .and_then(|output| output.recv_timeout(READER_GRACE).ok())
+        .map(|(bytes, truncated)| (String::from_utf8_lossy(&bytes).into_owned(), truncated))
+        .unwrap_or_default()
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn status_path() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("/tmp"));
+    path.push("cosmic-widget-applet");
+    fs::create_dir_all(&path).ok();
+    path.push("commands.json");
+    path
+}
+
+/// Write the latest results for the settings application, replacing the
+/// file at once so it never reads a partial list. Failures are only
+/// logged, as the file is informational.
+fn save_status(results: &[CommandResult]) {
+    if !super::cache::persistence_enabled() {
+        return;
+    }
+    match serde_json::to_string_pretty(results) {
+        Ok(json) => {
+            if let Err(error) = super::cache::write_atomically(&status_path(), &json) {
+                log::warn!("Failed to save command status: {error}");
+            }
+        }
+        Err(error) => log::warn!("Failed to serialize command status: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandStatus, run};
+    use crate::config::ShellCommand;
+
+    fn shell(command: &str) -> ShellCommand {
+        ShellCommand {
+            command: command.to_string(),
+            timeout_secs: 1,
+            ..ShellCommand::default()
+        }
+    }
+
+    #[test]
+    fn captures_output_and_exit_status() {
+        let result = run(&shell("echo 'up 3 days'; echo oops >&2; exit 3"));
+
+        assert_eq!(result.stdout, "up 3 days");
+        assert_eq!(result.stderr, "oops");
+        assert_eq!(result.status, CommandStatus::Exited(3));
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn output_is_cut_at_the_limit() {
+        let result = run(&ShellCommand {
+            max_output_bytes: 5,
+            ..shell("printf 'abcdefghij'")
+        });
+
+        assert_eq!(result.stdout, "abcde");
+        assert!(result.truncated);
+        assert!(result.status.succeeded());
+    }
+
+    #[test]
+    fn slow_commands_and_their_children_are_killed() {
+        let result = run(&shell("sleep 30 & sleep 30"));
+
+        assert_eq!(result.status, CommandStatus::TimedOut);
+    }
+}
@@ -1,38 +1,82 @@
 // SPDX-License-Identifier: MPL-2.0
 
-//! Aggregate physical-disk throughput from Linux block statistics.
+//! Physical-disk throughput from Linux block statistics.
+//!
+//! The read and write rates sum every physical block device. Each device also
+//! gets its IOPS, average request latency and utilization, as `iostat -x`
+//! reports them: latency is the time spent on requests completed since the
+//! last sample over their count, and utilization the share of that time the
+//! device had requests in flight.
 
-use std::collections::HashSet;
+use crate::storage::StorageMonitor;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::time::{Duration, Instant};
 
 const DEVICE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
 const KERNEL_SECTOR_SIZE: u64 = 512;
 
+/// Activity of one physical block device since the previous sample.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DiskDevice {
+    /// Kernel name, e.g. "nvme0n1"
+    pub name: String,
+    /// Vendor and model from sysfs, as the storage section names drives
+    pub model: Option<String>,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    /// Average time per completed request in milliseconds
+    pub latency_ms: f64,
+    /// Share of the time with requests in flight (0-100)
+    pub utilization: f32,
+}
+
+impl DiskDevice {
+    pub fn display_name(&self) -> &str {
+        self.model.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Cumulative counters of one device from `/proc/diskstats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct DeviceCounters {
+    reads: u64,
+    sectors_read: u64,
+    read_ms: u64,
+    writes: u64,
+    sectors_written: u64,
+    write_ms: u64,
+    busy_ms: u64,
+}
+
 pub struct DiskIoMonitor {
     devices: HashSet<String>,
-    read_bytes: u64,
-    written_bytes: u64,
+    models: HashMap<String, String>,
+    counters: HashMap<String, DeviceCounters>,
     last_update: Instant,
     last_device_refresh: Instant,
     pub read_rate: f64,
     pub write_rate: f64,
+    /// Physical devices by name
+    pub disks: Vec<DiskDevice>,
 }
 
 impl DiskIoMonitor {
     pub fn new() -> Self {
         let devices = physical_block_devices();
-        let (read_bytes, written_bytes) = read_totals(&devices).unwrap_or_default();
+        let counters = read_counters(&devices).unwrap_or_default();
         let now = Instant::now();
 
         Self {
             devices,
-            read_bytes,
-            written_bytes,
+            models: StorageMonitor::fetch_disk_models().unwrap_or_default(),
+            counters,
             last_update: now,
             last_device_refresh: now,
             read_rate: 0.0,
             write_rate: 0.0,
+            disks: Vec::new(),
         }
     }
 
@@ -41,13 +85,14 @@ impl DiskIoMonitor {
         let elapsed = now.duration_since(self.last_update).as_secs_f64();
 
         if now.duration_since(self.last_device_refresh) >= DEVICE_REFRESH_INTERVAL {
+            self.models = StorageMonitor::fetch_disk_models().unwrap_or_default();
             let devices = physical_block_devices();
             if devices != self.devices {
                 self.devices = devices;
-                (self.read_bytes, self.written_bytes) =
-                    read_totals(&self.devices).unwrap_or_default();
+                self.counters = read_counters(&self.devices).unwrap_or_default();
                 self.read_rate = 0.0;
                 self.write_rate = 0.0;
+                self.disks.clear();
                 self.last_update = now;
                 self.last_device_refresh = now;
                 return;
@@ -55,21 +100,67 @@ impl DiskIoMonitor {
             self.last_device_refresh = now;
         }
 
-        let Some((read_bytes, written_bytes)) = read_totals(&self.devices) else {
+        let Some(counters) = read_counters(&self.devices) else {
             self.read_rate = 0.0;
             self.write_rate = 0.0;
+            self.disks.clear();
             self.last_update = now;
             return;
         };
 
-        self.read_rate = rate_from_totals(self.read_bytes, read_bytes, elapsed);
-        self.write_rate = rate_from_totals(self.written_bytes, written_bytes, elapsed);
-        self.read_bytes = read_bytes;
-        self.written_bytes = written_bytes;
+        let (read_bytes, written_bytes) = byte_totals(&self.counters);
+        let (current_read, current_written) = byte_totals(&counters);
+        self.read_rate = rate_from_totals(read_bytes, current_read, elapsed);
+        self.write_rate = rate_from_totals(written_bytes, current_written, elapsed);
+
+        let mut disks: Vec<_> = counters
+            .iter()
+            .map(|(name, current)| {
+                let previous = self.counters.get(name).copied().unwrap_or(*current);
+                device_activity(name, previous, *current, elapsed, self.models.get(name))
+            })
+            .collect();
+        disks.sort_by(|a, b| a.name.cmp(&b.name));
+        self.disks = disks;
+
+        self.counters = counters;
         self.last_update = now;
     }
 }
 
+/// IOPS, latency and utilization of a device between two samples taken
+/// `elapsed_seconds` apart.
+fn device_activity(
+    name: &str,
+    previous: DeviceCounters,
+    current: DeviceCounters,
+    elapsed_seconds: f64,
+    model: Option<&String>,
+) -> DiskDevice {
+    let reads = current.reads.saturating_sub(previous.reads);
+    let writes = current.writes.saturating_sub(previous.writes);
+    let request_ms = current.read_ms.saturating_sub(previous.read_ms)
+        + current.write_ms.saturating_sub(previous.write_ms);
+    let busy_ms = current.busy_ms.saturating_sub(previous.busy_ms);
+
+    DiskDevice {
+        name: name.to_string(),
+        model: model.cloned(),
+        read_iops: rate_from_totals(previous.reads, current.reads, elapsed_seconds),
+        write_iops: rate_from_totals(previous.writes, current.writes, elapsed_seconds),
+        latency_ms: if reads + writes > 0 {
+            request_ms as f64 / (reads + writes) as f64
+        } else {
+            0.0
+        },
+        utilization: if elapsed_seconds > 0.0 {
+            (busy_ms as f64 / (elapsed_seconds * 10.0)).clamp(0.0, 100.0) as f32
+        } else {
+            0.0
+        },
+    }
+}
+
 fn physical_block_devices() -> HashSet<String> {
     let Ok(entries) = fs::read_dir("/sys/class/block") else {
         return HashSet::new();
@@ -83,27 +174,58 @@ fn physical_block_devices() -> HashSet<String> {
         .collect()
 }
 
-fn read_totals(devices: &HashSet<String>) -> Option<(u64, u64)> {
+fn read_counters(devices: &HashSet<String>) -> Option<HashMap<String, DeviceCounters>> {
     let diskstats = fs::read_to_string("/proc/diskstats").ok()?;
-    Some(parse_diskstats(&diskstats, devices))
+    Some(parse_device_counters(&diskstats, devices))
 }
 
-fn parse_diskstats(diskstats: &str, devices: &HashSet<String>) -> (u64, u64) {
-    diskstats.lines().fold((0_u64, 0_u64), |totals, line| {
-        let fields = line.split_whitespace().collect::<Vec<_>>();
-        if fields.len() < 10 || !devices.contains(fields[2]) {
-            return totals;
-        }
+/// Counters of the selected devices. Fields after the device name are reads,
+/// merged reads, sectors read and milliseconds reading, the same four for
+/// writes, requests in flight and milliseconds with requests in flight.
+fn parse_device_counters(
+    diskstats: &str,
+    devices: &HashSet<String>,
+) -> HashMap<String, DeviceCounters> {
+    diskstats
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.len() < 10 || !devices.contains(fields[2]) {
+                return None;
+            }
+
+            let field = |index: usize| {
+                fields
+                    .get(index)
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .unwrap_or(0)
+            };
+            Some((
+                fields[2].to_string(),
+                DeviceCounters {
+                    reads: field(3),
+                    sectors_read: field(5),
+                    read_ms: field(6),
+                    writes: field(7),
+                    sectors_written: field(9),
+                    write_ms: field(10),
+                    busy_ms: field(12),
+                },
+            ))
+        })
+        .collect()
+}
 
-        let sectors_read = fields[5].parse::<u64>().unwrap_or(0);
-        let sectors_written = fields[9].parse::<u64>().unwrap_or(0);
+/// Bytes read and written, summed over devices.
+fn byte_totals(counters: &HashMap<String, DeviceCounters>) -> (u64, u64) {
+    counters.values().fold((0_u64, 0_u64), |totals, device| {
         (
             totals
                 .0
-                .saturating_add(sectors_read.saturating_mul(KERNEL_SECTOR_SIZE)),
+                .saturating_add(device.sectors_read.saturating_mul(KERNEL_SECTOR_SIZE)),
             totals
                 .1
-                .saturating_add(sectors_written.saturating_mul(KERNEL_SECTOR_SIZE)),
+                .saturating_add(device.sectors_written.saturating_mul(KERNEL_SECTOR_SIZE)),
         )
     })
 }
@@ -120,7 +242,9 @@ fn rate_from_totals(previous: u64, current: u64, elapsed_seconds: f64) -> f64 {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_diskstats, rate_from_totals};
+    use super::{
+        DeviceCounters, byte_totals, device_activity, parse_device_counters, rate_from_totals,
+    };
     use std::collections::HashSet;
 
     #[test]
@@ -131,7 +255,48 @@ mod tests {
 259 1 nvme0n1p1 1 0 75 0 2 0 30 0 0 0 0 0 0 0 0 0\n\
 8 0 sda 1 0 20 0 2 0 10 0 0 0 0 0 0 0 0 0\n";
 
-        assert_eq!(parse_diskstats(fixture, &devices), (120 * 512, 60 * 512));
+        assert_eq!(
+            byte_totals(&parse_device_counters(fixture, &devices)),
+            (120 * 512, 60 * 512)
+        );
+    }
+
+    #[test]
+    fn device_activity_reports_iops_latency_and_utilization() {
+        let devices = HashSet::from(["sda".to_string()]);
+        let before = parse_device_counters(
+            "8 0 sda 100 0 800 400 50 0 400 600 0 1000 1000 0 0 0 0\n",
+            &devices,
+        )["sda"];
+        let after = parse_device_counters(
+            "8 0 sda 300 0 2400 1400 150 0 1200 1100 2 1500 2500 0 0 0 0\n",
+            &devices,
+        )["sda"];
+        assert_eq!(
+            after,
+            DeviceCounters {
+                reads: 300,
+                sectors_read: 2400,
+                read_ms: 1400,
+                writes: 150,
+                sectors_written: 1200,
+                write_ms: 1100,
+                busy_ms: 1500,
+            }
+        );
+
+        let model = "Samsung SSD 870".to_string();
+        let device = device_activity("sda", before, after, 2.0, Some(&model));
+        assert_eq!(device.display_name(), "Samsung SSD 870");
+        assert_eq!(device.read_iops, 100.0);
+        assert_eq!(device.write_iops, 50.0);
+        // 1500 ms spent on 300 requests
+        assert_eq!(device.latency_ms, 5.0);
+        assert_eq!(device.utilization, 25.0);
+
+        let idle = device_activity("sda", after, after, 2.0, None);
+        assert_eq!(idle.display_name(), "sda");
+        assert_eq!((idle.latency_ms, idle.utilization), (0.0, 0.0));
     }
 
     #[test]
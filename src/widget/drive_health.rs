@@ -0,0 +1,501 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Health of the physical drives behind mounted filesystems.
+//!
+//! - **Temperature**: the drive's hwmon sensor, registered by the nvme driver
+//!   or by `drivetemp` for SATA drives
+//! - **NVMe**: the SMART / Health Information log page, read with an admin
+//!   command ioctl on the controller's character device (`/dev/nvme0`)
+//! - **SATA**: the SMART attribute table, read with `HDIO_DRIVE_CMD` on the
+//!   disk's block device. Like `smartctl -n standby`, a drive that CHECK
+//!   POWER MODE finds in standby or sleep is not woken: it keeps its last
+//!   readings and is tried again on the next update.
+//!
+//! Both ioctls need read access to the device node, and usually
+//! `CAP_SYS_ADMIN` or `CAP_SYS_RAWIO`. Without them only the temperature is
+//! known and the SMART readings stay `None`.
+//!
+//! Filesystems on partitions, LUKS or LVM volumes are traced through sysfs
+//! to the first drive below them.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const SYS_CLASS_BLOCK: &str = "/sys/class/block";
+/// SMART data changes slowly, and reading it can keep a disk from spinning
+/// down.
+const SMART_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+/// NVMe's estimate of the rated endurance used, in percent, from which the
+/// drive counts as worn.
+const WORN_PERCENTAGE_USED: u8 = 90;
+
+const NVME_ADMIN_GET_LOG_PAGE: u8 = 0x02;
+const NVME_LOG_SMART: u32 = 0x02;
+const NVME_SMART_LOG_SIZE: usize = 512;
+const ATA_SMART_CMD: u8 = 0xb0;
+const ATA_SMART_READ_VALUES: u8 = 0xd0;
+const ATA_CHECK_POWER_MODE: u8 = 0xe5;
+/// Sector counts CHECK POWER MODE returns for a spinning drive, idle and
+/// active or idle. Every other value, such as standby (0x00, 0x01) or the
+/// NV cache power modes (0x40, 0x41), means reading SMART would spin it up.
+const ATA_POWER_MODES_ACTIVE: [u8; 2] = [0x80, 0xff];
+const ATA_SECTOR_SIZE: usize = 512;
+const HDIO_DRIVE_CMD: libc::c_ulong = 0x031f;
+
+/// SMART attribute IDs read from SATA drives.
+const ATA_REALLOCATED_SECTORS: u8 = 5;
+const ATA_POWER_ON_HOURS: u8 = 9;
+const ATA_TEMPERATURE: u8 = 194;
+const ATA_PENDING_SECTORS: u8 = 197;
+
+/// Health readings of one drive. Readings the drive does not report, or that
+/// need more privileges, are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DriveHealth {
+    /// Kernel name of the drive, e.g. "nvme0n1" or "sda"
+    pub drive: String,
+    pub temperature: Option<f32>,
+    /// NVMe critical warning flags, 0 when healthy
+    pub critical_warning: Option<u8>,
+    /// NVMe estimate of the rated endurance used (may exceed 100)
+    pub percentage_used: Option<u8>,
+    /// NVMe spare capacity left, and the level below which it warns
+    pub available_spare: Option<u8>,
+    pub available_spare_threshold: Option<u8>,
+    pub media_errors: Option<u64>,
+    pub power_on_hours: Option<u64>,
+    /// SATA reallocated and pending sector counts
+    pub reallocated_sectors: Option<u64>,
+    pub pending_sectors: Option<u64>,
+}
+
+impl DriveHealth {
+    /// Whether SMART data could be read.
+    pub fn smart_available(&self) -> bool {
+        self.percentage_used.is_some()
+            || self.reallocated_sectors.is_some()
+            || self.pending_sectors.is_some()
+            || self.power_on_hours.is_some()
+    }
+
+    /// Whether any reading shows wear, failing media or a drive warning.
+    pub fn degraded(&self) -> bool {
+        let spare_low = matches!(
+            (self.available_spare, self.available_spare_threshold),
+            (Some(spare), Some(threshold)) if spare < threshold
+        );
+        self.critical_warning.is_some_and(|flags| flags != 0)
+            || self
+                .percentage_used
+                .is_some_and(|used| used >= WORN_PERCENTAGE_USED)
+            || spare_low
+            || self.media_errors.is_some_and(|errors| errors > 0)
+            || self.reallocated_sectors.is_some_and(|sectors| sectors > 0)
+            || self.pending_sectors.is_some_and(|sectors| sectors > 0)
+    }
+}
+
+/// SMART readings of one drive and when they were read, `None` while the
+/// drive has slept through every attempt.
+struct SmartSample {
+    read_at: Option<Instant>,
+    health: DriveHealth,
+}
+
+#[derive(Default)]
+pub struct DriveHealthMonitor {
+    smart: HashMap<String, SmartSample>,
+    /// Health of the drive below each filesystem's block device
+    pub drives: HashMap<String, DriveHealth>,
+}
+
+impl DriveHealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refresh the health of the drives below `devices`, the kernel names of
+    /// the mounted filesystems' block devices such as "nvme0n1p2" or "dm-0".
+    pub fn update<'a>(&mut self, devices: impl IntoIterator<Item = &'a str>) {
+        let root = Path::new(SYS_CLASS_BLOCK);
+        let now = Instant::now();
+        let mut drives = HashMap::new();
+        let mut smart = HashMap::new();
+
+        for device in devices {
+            let Some(drive) = physical_drive(root, device) else {
+                continue;
+            };
+            let sample = smart.entry(drive.clone()).or_insert_with(|| {
+                match self.smart.remove(&drive) {
+                    Some(sample)
+                        if sample.read_at.is_some_and(|read_at| {
+                            now.duration_since(read_at) < SMART_REFRESH_INTERVAL
+                        }) =>
+                    {
+                        sample
+                    }
+                    previous => match read_smart(root, &drive) {
+                        Some(health) => SmartSample {
+                            read_at: Some(now),
+                            health,
+                        },
+                        // A sleeping drive keeps its last readings, as its
+                        // read time stays due.
+                        None => previous.unwrap_or_else(|| SmartSample {
+                            read_at: None,
+                            health: DriveHealth {
+                                drive: drive.clone(),
+                                ..DriveHealth::default()
+                            },
+                        }),
+                    },
+                }
+            });
+
+            let mut health = sample.health.clone();
+            health.temperature =
+                hwmon_temperature(&root.join(&drive).join("device")).or(sample.health.temperature);
+            drives.insert(device.to_string(), health);
+        }
+
+        // Drives that are no longer mounted are read afresh when they return.
+        self.smart = smart;
+        self.drives = drives;
+    }
+}
+
+/// The drive holding `device`: itself for a whole drive, the parent of a
+/// partition, or the first device below a device-mapper or md volume.
+fn physical_drive(root: &Path, device: &str) -> Option<String> {
+    let mut current = device.to_string();
+    // Each step goes one layer down, e.g. LVM on LUKS on a partition.
+    for _ in 0..8 {
+        let dir = root.join(&current);
+        if dir.join("partition").exists() {
+            current = fs::canonicalize(&dir)
+                .ok()?
+                .parent()?
+                .file_name()?
+                .to_string_lossy()
+                .into_owned();
+        } else if dir.join("device").exists() {
+            return Some(current);
+        } else {
+            let mut slaves: Vec<_> = fs::read_dir(dir.join("slaves"))
+                .ok()?
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect();
+            slaves.sort();
+            current = slaves.into_iter().next()?;
+        }
+    }
+    None
+}
+
+/// First temperature of the drive's hwmon device, in °C. NVMe controllers
+/// hold it directly and SCSI disks in a `hwmon` subdirectory.
+fn hwmon_temperature(device_dir: &Path) -> Option<f32> {
+    let direct = fs::read_dir(device_dir).ok()?.flatten();
+    let nested = fs::read_dir(device_dir.join("hwmon"))
+        .into_iter()
+        .flatten()
+        .flatten();
+    direct
+        .chain(nested)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("hwmon"))
+        .find_map(|entry| {
+            let millidegrees: i64 = fs::read_to_string(entry.path().join("temp1_input"))
+                .ok()?
+                .trim()
+                .parse()
+                .ok()?;
+            Some(millidegrees as f32 / 1000.0)
+        })
+}
+
+/// SMART readings of `drive`, empty if the drive does not support them or
+/// the overlay may not read them, and `None` for a SATA drive in standby or
+/// sleep, which reading them would spin up.
+fn read_smart(root: &Path, drive: &str) -> Option<DriveHealth> {
+    let health = if drive.starts_with("nvme") {
+        nvme_controller(root, drive).and_then(|controller| {
+            let log = nvme_smart_log(&Path::new("/dev").join(controller))?;
+            Some(parse_nvme_smart_log(&log))
+        })
+    } else if drive.starts_with("sd") {
+        let disk = open_read_only(&Path::new("/dev").join(drive));
+        if disk.as_ref().is_some_and(|disk| !ata_drive_active(disk)) {
+            return None;
+        }
+        disk.and_then(|disk| ata_smart_values(&disk))
+            .map(|values| parse_ata_smart(&values))
+    } else {
+        None
+    };
+
+    Some(DriveHealth {
+        drive: drive.to_string(),
+        ..health.unwrap_or_default()
+    })
+}
+
+/// Character device name of the controller of an NVMe namespace, e.g.
+/// "nvme0" for "nvme0n1".
+fn nvme_controller(root: &Path, drive: &str) -> Option<String> {
+    let controller = fs::canonicalize(root.join(drive).join("device")).ok()?;
+    Some(controller.file_name()?.to_string_lossy().into_owned())
+}
+
+/// `struct nvme_passthru_cmd` from `linux/nvme_ioctl.h`.
+#[repr(C)]
+#[derive(Default)]
+struct NvmePassthruCommand {
+    opcode: u8,
+    flags: u8,
+    rsvd1: u16,
+    nsid: u32,
+    cdw2: u32,
+    cdw3: u32,
+    metadata: u64,
+    addr: u64,
+    metadata_len: u32,
+    data_len: u32,
+    cdw10: u32,
+    cdw11: u32,
+    cdw12: u32,
+    cdw13: u32,
+    cdw14: u32,
+    cdw15: u32,
+    timeout_ms: u32,
+    result: u32,
+}
+
+/// `NVME_IOCTL_ADMIN_CMD`, `_IOWR('N', 0x41, struct nvme_passthru_cmd)`.
+const fn nvme_ioctl_admin_cmd() -> libc::c_ulong {
+    const IOC_READ_WRITE: libc::c_ulong = 3;
+    (IOC_READ_WRITE << 30)
+        | ((std::mem::size_of::<NvmePassthruCommand>() as libc::c_ulong) << 16)
+        | ((b'N' as libc::c_ulong) << 8)
+        | 0x41
+}
+
+fn open_read_only(path: &Path) -> Option<File> {
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+        .ok()
+}
+
+fn nvme_smart_log(controller: &Path) -> Option<[u8; NVME_SMART_LOG_SIZE]> {
+    let file = open_read_only(controller)?;
+    let mut log = [0_u8; NVME_SMART_LOG_SIZE];
+    let dwords = (NVME_SMART_LOG_SIZE / 4) as u32;
+    let mut command = NvmePassthruCommand {
+        opcode: NVME_ADMIN_GET_LOG_PAGE,
+        // The controller-wide log.
+        nsid: u32::MAX,
+        addr: log.as_mut_ptr() as u64,
+        data_len: NVME_SMART_LOG_SIZE as u32,
+        cdw10: ((dwords - 1) << 16) | NVME_LOG_SMART,
+        ..NvmePassthruCommand::default()
+    };
+    // SAFETY: `command` matches the kernel's layout and points at `log`,
+    // which outlives the call and holds `data_len` bytes.
+    let status = unsafe {
+        libc::ioctl(
+            file.as_raw_fd(),
+            nvme_ioctl_admin_cmd(),
+            &mut command as *mut NvmePassthruCommand,
+        )
+    };
+    (status == 0).then_some(log)
+}
+
+/// Whether CHECK POWER MODE finds the drive active or idle. A sleeping
+/// drive fails the command until it is reset, so a failure counts as asleep.
+fn ata_drive_active(disk: &File) -> bool {
+    // Command, sector number, feature and sector count; the sector count
+    // comes back with the power mode.
+    let mut args = [ATA_CHECK_POWER_MODE, 0, 0, 0];
+    // SAFETY: HDIO_DRIVE_CMD without a sector count reads and writes the
+    // four bytes of `args`.
+    let status = unsafe { libc::ioctl(disk.as_raw_fd(), HDIO_DRIVE_CMD, args.as_mut_ptr()) };
+    status == 0 && power_mode_active(args[2])
+}
+
+/// Whether a CHECK POWER MODE sector count means the drive is spinning.
+fn power_mode_active(sector_count: u8) -> bool {
+    ATA_POWER_MODES_ACTIVE.contains(&sector_count)
+}
+
+fn ata_smart_values(disk: &File) -> Option<[u8; ATA_SECTOR_SIZE]> {
+    // Command, sector number, feature and sector count, then the data.
+    let mut args = [0_u8; 4 + ATA_SECTOR_SIZE];
+    args[..4].copy_from_slice(&[ATA_SMART_CMD, 0, ATA_SMART_READ_VALUES, 1]);
+    // SAFETY: HDIO_DRIVE_CMD reads four bytes of arguments and writes one
+    // sector after them, which `args` has room for.
+    let status = unsafe { libc::ioctl(disk.as_raw_fd(), HDIO_DRIVE_CMD, args.as_mut_ptr()) };
+    if status != 0 {
+        return None;
+    }
+    let mut values = [0_u8; ATA_SECTOR_SIZE];
+    values.copy_from_slice(&args[4..]);
+    Some(values)
+}
+
+/// Little-endian counter of `bytes`, saturating at `u64::MAX`.
+fn le_counter(bytes: &[u8]) -> u64 {
+    let value = bytes
+        .iter()
+        .rev()
+        .fold(0_u128, |value, byte| (value << 8) | u128::from(*byte));
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+fn parse_nvme_smart_log(log: &[u8; NVME_SMART_LOG_SIZE]) -> DriveHealth {
+    let kelvin = u16::from_le_bytes([log[1], log[2]]);
+    DriveHealth {
+        temperature: (kelvin > 0).then(|| f32::from(kelvin) - 273.15),
+        critical_warning: Some(log[0]),
+        available_spare: Some(log[3]),
+        available_spare_threshold: Some(log[4]),
+        percentage_used: Some(log[5]),
+        power_on_hours: Some(le_counter(&log[128..144])),
+        media_errors: Some(le_counter(&log[160..176])),
+        ..DriveHealth::default()
+    }
+}
+
+/// Reads the attribute table of an ATA SMART data sector: 30 entries of
+/// ID, flags, normalized and worst values, and a six-byte raw value.
+fn parse_ata_smart(values: &[u8; ATA_SECTOR_SIZE]) -> DriveHealth {
+    let raw = |id: u8| {
+        values[2..2 + 30 * 12]
+            .chunks_exact(12)
+            .find(|attribute| attribute[0] == id)
+            .map(|attribute| &attribute[5..11])
+    };
+    // Vendors pack extra fields into the upper raw bytes of these counters.
+    let low_dword = |id: u8| raw(id).map(|raw| le_counter(&raw[..4]));
+
+    DriveHealth {
+        temperature: raw(ATA_TEMPERATURE).map(|raw| f32::from(raw[0])),
+        power_on_hours: low_dword(ATA_POWER_ON_HOURS),
+        reallocated_sectors: low_dword(ATA_REALLOCATED_SECTORS),
+        pending_sectors: low_dword(ATA_PENDING_SECTORS),
+        ..DriveHealth::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ATA_SECTOR_SIZE, DriveHealth, NVME_SMART_LOG_SIZE, NvmePassthruCommand, hwmon_temperature,
+        parse_ata_smart, parse_nvme_smart_log, physical_drive, power_mode_active,
+    };
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn filesystems_trace_back_to_their_drive() {
+        let root = std::env::temp_dir().join(format!("drive-health-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let devices = root.join("devices/pci0000:00/nvme/nvme0/nvme0n1");
+        fs::create_dir_all(devices.join("device")).unwrap();
+        fs::create_dir_all(devices.join("nvme0n1p2")).unwrap();
+        fs::write(devices.join("nvme0n1p2/partition"), "2").unwrap();
+        let block = root.join("class/block");
+        fs::create_dir_all(block.join("dm-0/slaves")).unwrap();
+        fs::write(block.join("dm-0/slaves/nvme0n1p2"), "").unwrap();
+        symlink(&devices, block.join("nvme0n1")).unwrap();
+        symlink(devices.join("nvme0n1p2"), block.join("nvme0n1p2")).unwrap();
+
+        assert_eq!(
+            physical_drive(&block, "nvme0n1").as_deref(),
+            Some("nvme0n1")
+        );
+        assert_eq!(
+            physical_drive(&block, "nvme0n1p2").as_deref(),
+            Some("nvme0n1")
+        );
+        assert_eq!(physical_drive(&block, "dm-0").as_deref(), Some("nvme0n1"));
+        assert_eq!(physical_drive(&block, "loop0"), None);
+
+        fs::create_dir_all(devices.join("device/hwmon3")).unwrap();
+        fs::write(devices.join("device/hwmon3/temp1_input"), "38850\n").unwrap();
+        assert_eq!(hwmon_temperature(&devices.join("device")), Some(38.85));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn nvme_smart_log_reports_wear_spare_and_errors() {
+        assert_eq!(std::mem::size_of::<NvmePassthruCommand>(), 72);
+
+        let mut log = [0_u8; NVME_SMART_LOG_SIZE];
+        log[1..3].copy_from_slice(&313_u16.to_le_bytes());
+        log[3] = 100;
+        log[4] = 10;
+        log[5] = 4;
+        log[128..130].copy_from_slice(&12_345_u16.to_le_bytes());
+
+        let health = parse_nvme_smart_log(&log);
+        assert_eq!(health.temperature.map(f32::round), Some(40.0));
+        assert_eq!(health.percentage_used, Some(4));
+        assert_eq!(health.power_on_hours, Some(12_345));
+        assert_eq!(health.media_errors, Some(0));
+        assert!(health.smart_available());
+        assert!(!health.degraded());
+
+        log[160] = 2;
+        assert!(parse_nvme_smart_log(&log).degraded());
+        log[160] = 0;
+        log[3] = 5;
+        assert!(parse_nvme_smart_log(&log).degraded());
+    }
+
+    #[test]
+    fn ata_smart_attributes_report_sectors_and_hours() {
+        let mut values = [0_u8; ATA_SECTOR_SIZE];
+        let mut attribute = |slot: usize, id: u8, raw: &[u8]| {
+            let offset = 2 + slot * 12;
+            values[offset] = id;
+            values[offset + 5..offset + 5 + raw.len()].copy_from_slice(raw);
+        };
+        attribute(0, 5, &[0, 0]);
+        attribute(1, 9, &[0x39, 0x30, 0, 0, 0x12, 0x34]);
+        attribute(2, 194, &[36, 0, 18, 0, 45, 0]);
+        attribute(3, 197, &[8]);
+
+        let health = parse_ata_smart(&values);
+        assert_eq!(
+            health,
+            DriveHealth {
+                temperature: Some(36.0),
+                power_on_hours: Some(12_345),
+                reallocated_sectors: Some(0),
+                pending_sectors: Some(8),
+                ..DriveHealth::default()
+            }
+        );
+        assert!(health.degraded());
+        assert!(!DriveHealth::default().smart_available());
+    }
+
+    #[test]
+    fn only_idle_and_active_drives_count_as_spinning() {
+        assert!(power_mode_active(0x80));
+        assert!(power_mode_active(0xff));
+        for standby in [0x00, 0x01, 0x40, 0x41] {
+            assert!(!power_mode_active(standby));
+        }
+    }
+}
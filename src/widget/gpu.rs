@@ -0,0 +1,429 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Per-device GPU readings.
+//!
+//! Every DRM card in `/sys/class/drm` from a supported vendor becomes one
+//! [`GpuDevice`], identified by its PCI address:
+//!
+//! - **NVIDIA**: name, utilization, temperature, VRAM, power, clocks and fan
+//!   speed from NVML
+//! - **AMD**: `gpu_busy_percent`, `mem_info_vram_*`, the active `pp_dpm_sclk`
+//!   and `pp_dpm_mclk` levels, and the card's hwmon temperature, power and fan
+//! - **Intel**: current/maximum frequency ratio (i915 or xe), and the card's
+//!   hwmon temperature, power and fan, where the driver provides them
+//!
+//! NVML devices without a DRM card are listed as well, so systems without
+//! the NVIDIA DRM driver still get their rows.
+//!
+//! i915 and xe only report an energy counter, so their power draw is the
+//! energy used between two readings.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::Instant;
+
+const DRM_ROOT: &str = "/sys/class/drm";
+
+/// Last energy counter reading in microjoules, per PCI address.
+static ENERGY_READINGS: LazyLock<Mutex<HashMap<String, (Instant, u64)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// GPU vendors with utilization support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GpuVendor {
+    /// NVIDIA GPU (uses NVML)
+    Nvidia,
+    /// AMD GPU (uses sysfs)
+    Amd,
+    /// Intel integrated/discrete GPU (uses sysfs)
+    Intel,
+}
+
+impl GpuVendor {
+    /// Vendor of a PCI vendor ID such as `0x1002`.
+    pub fn from_pci_id(id: &str) -> Option<Self> {
+        match id.trim().to_ascii_lowercase().as_str() {
+            "0x10de" => Some(Self::Nvidia),
+            "0x1002" => Some(Self::Amd),
+            "0x8086" => Some(Self::Intel),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Nvidia => "NVIDIA",
+            Self::Amd => "AMD",
+            Self::Intel => "Intel",
+        }
+    }
+}
+
+/// Readings of one GPU.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuDevice {
+    /// PCI address such as `0000:01:00.0`, stable across restarts
+    pub id: String,
+    /// Model name from NVML, or the vendor and DRM card otherwise
+    pub name: String,
+    pub vendor: GpuVendor,
+    /// Utilization percentage (0-100), `None` if the driver reports none
+    pub usage: Option<f32>,
+    /// Temperature in Celsius, `None` without a sensor
+    pub temperature: Option<f32>,
+    pub details: GpuDetails,
+}
+
+/// Memory, power, clock and fan readings of a GPU. Each is `None` where the
+/// driver does not report it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GpuDetails {
+    /// Used video memory in bytes
+    pub vram_used: Option<u64>,
+    /// Total video memory in bytes
+    pub vram_total: Option<u64>,
+    /// Board power draw in watts
+    pub power_watts: Option<f32>,
+    /// Shader/graphics clock in MHz
+    pub core_clock_mhz: Option<u32>,
+    /// Memory clock in MHz
+    pub memory_clock_mhz: Option<u32>,
+    pub fan: Option<GpuFan>,
+}
+
+/// Fan speed, in whichever unit the driver reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GpuFan {
+    Rpm(u32),
+    Percent(u32),
+}
+
+/// Read every GPU, ordered by DRM card number.
+pub fn read_devices() -> Vec<GpuDevice> {
+    let mut nvidia = if super::nvidia::hardware_present() {
+        super::nvidia::devices().unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let mut cards = drm_cards(Path::new(DRM_ROOT));
+    cards.sort_by_key(|(number, ..)| *number);
+
+    let mut devices = Vec::with_capacity(cards.len());
+    for (number, path, vendor) in cards {
+        let Some(id) = std::fs::canonicalize(path.join("device"))
+            .ok()
+            .and_then(|device| Some(device.file_name()?.to_string_lossy().into_owned()))
+        else {
+            continue;
+        };
+        if devices.iter().any(|device: &GpuDevice| device.id == id) {
+            continue;
+        }
+
+        let nvml = nvidia
+            .iter()
+            .position(|device| device.pci_address == id)
+            .map(|index| nvidia.remove(index));
+        let device = match (vendor, nvml) {
+            (GpuVendor::Nvidia, Some(nvml)) => GpuDevice {
+                id,
+                name: nvml.name,
+                vendor,
+                usage: nvml.utilization,
+                temperature: nvml.temperature,
+                details: nvml.details,
+            },
+            (GpuVendor::Nvidia, None) => GpuDevice {
+                id,
+                name: format!("NVIDIA GPU (card{number})"),
+                vendor,
+                usage: None,
+                temperature: None,
+                details: GpuDetails::default(),
+            },
+            (GpuVendor::Amd | GpuVendor::Intel, _) => {
+                let (usage, details) = if vendor == GpuVendor::Amd {
+                    (amd_usage(&path), amd_details(&path, &id))
+                } else {
+                    (intel_usage(&path), intel_details(&path, &id))
+                };
+                GpuDevice {
+                    id,
+                    name: format!("{} GPU (card{number})", vendor.label()),
+                    vendor,
+                    usage,
+                    temperature: hwmon_temperature(&path),
+                    details,
+                }
+            }
+        };
+        devices.push(device);
+    }
+
+    devices.extend(nvidia.into_iter().map(|nvml| GpuDevice {
+        id: nvml.pci_address,
+        name: nvml.name,
+        vendor: GpuVendor::Nvidia,
+        usage: nvml.utilization,
+        temperature: nvml.temperature,
+        details: nvml.details,
+    }));
+    distinguish_names(&mut devices);
+    devices
+}
+
+/// Combined utilization of the `vendor` GPUs: the busiest NVIDIA device, or
+/// the first AMD or Intel card with a reading.
+pub fn combined_usage(vendor: GpuVendor, devices: &[GpuDevice]) -> Option<f32> {
+    let mut usages = devices
+        .iter()
+        .filter(|device| device.vendor == vendor)
+        .filter_map(|device| device.usage);
+    match vendor {
+        GpuVendor::Nvidia => usages.max_by(f32::total_cmp),
+        GpuVendor::Amd | GpuVendor::Intel => usages.next(),
+    }
+}
+
+/// Number, path and vendor of each DRM card (not connector) of a supported
+/// vendor.
+fn drm_cards(root: &Path) -> Vec<(u32, PathBuf, GpuVendor)> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let number = name.to_str()?.strip_prefix("card")?.parse().ok()?;
+            let vendor = std::fs::read_to_string(entry.path().join("device/vendor")).ok()?;
+            Some((number, entry.path(), GpuVendor::from_pci_id(&vendor)?))
+        })
+        .collect()
+}
+
+fn read_number(path: &Path) -> Option<f32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn amd_usage(card: &Path) -> Option<f32> {
+    read_number(&card.join("device/gpu_busy_percent"))
+}
+
+fn amd_details(card: &Path, id: &str) -> GpuDetails {
+    let read_bytes = |name: &str| {
+        std::fs::read_to_string(card.join("device").join(name))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    let active_level = |name: &str| {
+        std::fs::read_to_string(card.join("device").join(name))
+            .ok()
+            .and_then(|levels| active_dpm_level(&levels))
+    };
+
+    GpuDetails {
+        vram_used: read_bytes("mem_info_vram_used"),
+        vram_total: read_bytes("mem_info_vram_total"),
+        power_watts: hwmon_power(card, id),
+        core_clock_mhz: active_level("pp_dpm_sclk"),
+        memory_clock_mhz: active_level("pp_dpm_mclk"),
+        fan: hwmon_fan(card),
+    }
+}
+
+/// Intel GPUs report no video memory use in sysfs, and no memory clock.
+fn intel_details(card: &Path, id: &str) -> GpuDetails {
+    GpuDetails {
+        power_watts: hwmon_power(card, id),
+        core_clock_mhz: INTEL_FREQUENCIES
+            .iter()
+            .find_map(|(current, _)| read_number(&card.join(current)))
+            .map(|mhz| mhz as u32),
+        fan: hwmon_fan(card),
+        ..GpuDetails::default()
+    }
+}
+
+/// Current and maximum frequency files in MHz, for i915 and xe.
+const INTEL_FREQUENCIES: [(&str, &str); 2] = [
+    ("gt/gt0/rps_cur_freq_mhz", "gt/gt0/rps_max_freq_mhz"),
+    (
+        "device/tile0/gt0/freq0/cur_freq",
+        "device/tile0/gt0/freq0/max_freq",
+    ),
+];
+
+/// Current to maximum frequency ratio, from i915 or xe sysfs.
+fn intel_usage(card: &Path) -> Option<f32> {
+    INTEL_FREQUENCIES.iter().find_map(|(current, max)| {
+        let current = read_number(&card.join(current))?;
+        let max = read_number(&card.join(max))?;
+        (max > 0.0).then(|| (current / max * 100.0).clamp(0.0, 100.0))
+    })
+}
+
+/// Clock of the active level in a `pp_dpm_*` table, where the active line
+/// is marked with `*`, such as `1: 1800Mhz *`.
+fn active_dpm_level(levels: &str) -> Option<u32> {
+    let line = levels.lines().find(|line| line.trim_end().ends_with('*'))?;
+    let (_, clock) = line.split_once(':')?;
+    let clock = clock.trim().trim_end_matches('*').trim_end();
+    let digits = clock.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    digits.trim().parse().ok()
+}
+
+/// First value of `file` among the card's hwmon devices.
+fn hwmon_value(card: &Path, file: &str) -> Option<f32> {
+    std::fs::read_dir(card.join("device/hwmon"))
+        .ok()?
+        .flatten()
+        .find_map(|hwmon| read_number(&hwmon.path().join(file)))
+}
+
+/// First temperature of the card's hwmon device, in Celsius.
+fn hwmon_temperature(card: &Path) -> Option<f32> {
+    hwmon_value(card, "temp1_input").map(|millidegrees| millidegrees / 1000.0)
+}
+
+/// Board power in watts: the averaged or instantaneous reading where the
+/// driver has one, otherwise the energy used since the previous reading.
+fn hwmon_power(card: &Path, id: &str) -> Option<f32> {
+    if let Some(microwatts) =
+        hwmon_value(card, "power1_average").or_else(|| hwmon_value(card, "power1_input"))
+    {
+        return Some(microwatts / 1_000_000.0);
+    }
+
+    let energy = std::fs::read_dir(card.join("device/hwmon"))
+        .ok()?
+        .flatten()
+        .find_map(|hwmon| {
+            std::fs::read_to_string(hwmon.path().join("energy1_input"))
+                .ok()?
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })?;
+    let now = Instant::now();
+    let previous = ENERGY_READINGS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(id.to_string(), (now, energy))?;
+    power_between(previous, (now, energy))
+}
+
+/// Average power in watts between two energy counter readings in
+/// microjoules, `None` if the counter wrapped or no time passed.
+fn power_between(previous: (Instant, u64), current: (Instant, u64)) -> Option<f32> {
+    let seconds = current.0.duration_since(previous.0).as_secs_f64();
+    let microjoules = current.1.checked_sub(previous.1)?;
+    (seconds > 0.0).then(|| (microjoules as f64 / seconds / 1_000_000.0) as f32)
+}
+
+/// Fan speed in RPM, or the PWM duty cycle where the card has no tachometer.
+fn hwmon_fan(card: &Path) -> Option<GpuFan> {
+    if let Some(rpm) = hwmon_value(card, "fan1_input") {
+        return Some(GpuFan::Rpm(rpm as u32));
+    }
+    hwmon_value(card, "pwm1").map(|pwm| GpuFan::Percent((pwm / 255.0 * 100.0).round() as u32))
+}
+
+/// Add the PCI address to names shared by several GPUs, such as two
+/// identical cards.
+fn distinguish_names(devices: &mut [GpuDevice]) {
+    let duplicated: Vec<bool> = devices
+        .iter()
+        .map(|device| {
+            devices
+                .iter()
+                .filter(|other| other.name == device.name)
+                .count()
+                > 1
+        })
+        .collect();
+    for (device, duplicated) in devices.iter_mut().zip(duplicated) {
+        if duplicated {
+            device.name = format!("{} ({})", device.name, device.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GpuDetails, GpuDevice, GpuVendor, active_dpm_level, combined_usage, distinguish_names,
+        power_between,
+    };
+    use std::time::{Duration, Instant};
+
+    fn gpu(id: &str, name: &str, vendor: GpuVendor, usage: Option<f32>) -> GpuDevice {
+        GpuDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            vendor,
+            usage,
+            temperature: None,
+            details: GpuDetails::default(),
+        }
+    }
+
+    #[test]
+    fn combined_usage_follows_the_vendor() {
+        let devices = [
+            gpu(
+                "0000:00:02.0",
+                "Intel GPU (card0)",
+                GpuVendor::Intel,
+                Some(12.0),
+            ),
+            gpu("0000:01:00.0", "RTX A4000", GpuVendor::Nvidia, Some(40.0)),
+            gpu("0000:02:00.0", "RTX A4000", GpuVendor::Nvidia, Some(75.0)),
+        ];
+
+        assert_eq!(combined_usage(GpuVendor::Nvidia, &devices), Some(75.0));
+        assert_eq!(combined_usage(GpuVendor::Intel, &devices), Some(12.0));
+        assert_eq!(combined_usage(GpuVendor::Amd, &devices), None);
+    }
+
+    #[test]
+    fn identical_cards_get_their_address_in_the_name() {
+        let mut devices = [
+            gpu("0000:00:02.0", "Intel GPU (card0)", GpuVendor::Intel, None),
+            gpu("0000:01:00.0", "RTX A4000", GpuVendor::Nvidia, None),
+            gpu("0000:02:00.0", "RTX A4000", GpuVendor::Nvidia, None),
+        ];
+
+        distinguish_names(&mut devices);
+
+        assert_eq!(devices[0].name, "Intel GPU (card0)");
+        assert_eq!(devices[1].name, "RTX A4000 (0000:01:00.0)");
+        assert_eq!(devices[2].name, "RTX A4000 (0000:02:00.0)");
+    }
+
+    #[test]
+    fn active_dpm_level_is_the_starred_line() {
+        let sclk = "0: 500Mhz\n1: 1800Mhz *\n2: 2500Mhz\n";
+        assert_eq!(active_dpm_level(sclk), Some(1800));
+        assert_eq!(active_dpm_level("0: 96Mhz\n1: 1000Mhz\n"), None);
+    }
+
+    #[test]
+    fn power_comes_from_the_energy_used_between_readings() {
+        let start = Instant::now();
+        let later = start + Duration::from_secs(2);
+
+        assert_eq!(
+            power_between((start, 1_000_000), (later, 31_000_000)),
+            Some(15.0)
+        );
+        assert_eq!(power_between((start, 31_000_000), (later, 1_000_000)), None);
+        assert_eq!(power_between((start, 0), (start, 1_000)), None);
+    }
+}
@@ -0,0 +1,335 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Per-process GPU usage.
+//!
+//! - **NVIDIA**: running graphics and compute processes and their SM
+//!   utilization from NVML
+//! - **AMD and Intel**: DRM client statistics in `/proc/<pid>/fdinfo`, as
+//!   documented in the kernel's `drm-usage-stats`
+//!
+//! A process's usage is that of its busiest engine between two samples.
+//! Engines report busy time in nanoseconds (`drm-engine-<engine>`), or on xe
+//! busy and total GPU cycles (`drm-cycles-<engine>`,
+//! `drm-total-cycles-<engine>`). Only processes whose fdinfo is readable are
+//! listed, which without root are those of the current user.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+
+/// Number of processes kept, busiest first.
+pub const TOP_GPU_PROCESSES: usize = 5;
+
+/// GPU use of one process on one GPU.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuProcess {
+    pub pid: u32,
+    /// Command name from `/proc/<pid>/comm`
+    pub name: String,
+    /// PCI address of the GPU, as in [`super::gpu::GpuDevice::id`]
+    pub gpu_id: String,
+    /// Busiest engine's utilization (0-100), `None` until a second sample
+    pub usage: Option<f32>,
+    /// Video memory in bytes, `None` if the driver reports none
+    pub vram: Option<u64>,
+}
+
+/// Busy counter of one engine of a DRM client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EngineTime {
+    Nanoseconds(u64),
+    /// Busy and total GPU cycles
+    Cycles(u64, u64),
+}
+
+/// Statistics of one DRM client, as read from fdinfo.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct DrmClient {
+    driver: String,
+    pdev: String,
+    client_id: u64,
+    engines: HashMap<String, EngineTime>,
+    vram: Option<u64>,
+}
+
+/// Keeps the previous engine counters of each DRM client to turn them into
+/// utilization.
+#[derive(Default)]
+pub struct GpuProcessSampler {
+    previous: HashMap<(String, u64), (Instant, HashMap<String, EngineTime>)>,
+}
+
+impl GpuProcessSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The busiest GPU processes, by utilization and then video memory.
+    pub fn sample(&mut self) -> Vec<GpuProcess> {
+        let now = Instant::now();
+        let mut processes = self.drm_processes(now);
+        if super::nvidia::hardware_present() {
+            processes.extend(
+                super::nvidia::processes()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|process| GpuProcess {
+                        name: process_name(process.pid),
+                        pid: process.pid,
+                        gpu_id: process.pci_address,
+                        usage: process.utilization,
+                        vram: process.vram,
+                    }),
+            );
+        }
+
+        processes.sort_by(|a, b| {
+            b.usage
+                .unwrap_or(0.0)
+                .total_cmp(&a.usage.unwrap_or(0.0))
+                .then(b.vram.unwrap_or(0).cmp(&a.vram.unwrap_or(0)))
+        });
+        processes.truncate(TOP_GPU_PROCESSES);
+        processes
+    }
+
+    /// Processes with open DRM clients, one per process and GPU.
+    fn drm_processes(&mut self, now: Instant) -> Vec<GpuProcess> {
+        let mut previous = std::mem::take(&mut self.previous);
+        let mut seen = HashMap::new();
+        let mut processes: HashMap<(u32, String), (HashMap<String, f32>, Option<u64>)> =
+            HashMap::new();
+
+        for (pid, client) in drm_clients(Path::new("/proc")) {
+            let key = (client.pdev.clone(), client.client_id);
+            // A client shows up once per duplicated file descriptor.
+            if seen.contains_key(&key) {
+                continue;
+            }
+
+            let process = processes.entry((pid, client.pdev.clone())).or_default();
+            if let Some((then, engines)) = previous.remove(&key) {
+                let seconds = now.duration_since(then).as_secs_f64();
+                for (engine, usage) in engine_usage(&engines, &client.engines, seconds) {
+                    *process.0.entry(engine).or_default() += usage;
+                }
+            }
+            if let Some(vram) = client.vram {
+                *process.1.get_or_insert(0) += vram;
+            }
+            seen.insert(key, (now, client.engines));
+        }
+        // Clients that closed since the last sample are forgotten.
+        self.previous = seen;
+
+        processes
+            .into_iter()
+            .map(|((pid, gpu_id), (engines, vram))| GpuProcess {
+                pid,
+                name: process_name(pid),
+                gpu_id,
+                usage: engines
+                    .into_values()
+                    .max_by(f32::total_cmp)
+                    .map(|usage| usage.clamp(0.0, 100.0)),
+                vram,
+            })
+            .collect()
+    }
+}
+
+/// Utilization in percent of each engine between two readings taken
+/// `seconds` apart. Engines whose counter went backwards are left out.
+fn engine_usage(
+    previous: &HashMap<String, EngineTime>,
+    current: &HashMap<String, EngineTime>,
+    seconds: f64,
+) -> Vec<(String, f32)> {
+    current
+        .iter()
+        .filter_map(|(engine, time)| {
+            let usage = match (previous.get(engine)?, time) {
+                (EngineTime::Nanoseconds(before), EngineTime::Nanoseconds(after)) => {
+                    let busy = after.checked_sub(*before)? as f64;
+                    (seconds > 0.0).then(|| busy / (seconds * 1e9))?
+                }
+                (
+                    EngineTime::Cycles(busy_before, total_before),
+                    EngineTime::Cycles(busy, total),
+                ) => {
+                    let total = total.checked_sub(*total_before)?;
+                    let busy = busy.checked_sub(*busy_before)?;
+                    (total > 0).then(|| busy as f64 / total as f64)?
+                }
+                _ => return None,
+            };
+            Some((engine.clone(), (usage * 100.0) as f32))
+        })
+        .collect()
+}
+
+/// Every DRM client in the fdinfo of every readable process, with the
+/// process's PID. NVIDIA clients are left out as NVML reports them.
+fn drm_clients(proc_root: &Path) -> Vec<(u32, DrmClient)> {
+    let Ok(entries) = std::fs::read_dir(proc_root) else {
+        return Vec::new();
+    };
+
+    let mut clients = Vec::new();
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fdinfo")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            // fdinfo of other file types does not carry drm keys.
+            let Ok(text) = std::fs::read_to_string(fd.path()) else {
+                continue;
+            };
+            if let Some(client) = parse_fdinfo(&text)
+                && !client.driver.starts_with("nvidia")
+            {
+                clients.push((pid, client));
+            }
+        }
+    }
+    clients
+}
+
+/// DRM client statistics of one fdinfo file, `None` for other files.
+fn parse_fdinfo(text: &str) -> Option<DrmClient> {
+    let mut client = DrmClient::default();
+    let mut cycles: HashMap<String, (Option<u64>, Option<u64>)> = HashMap::new();
+    let mut resident_vram = None;
+    let mut legacy_vram = None;
+
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let Some(key) = key.trim().strip_prefix("drm-") else {
+            continue;
+        };
+
+        match key {
+            "driver" => client.driver = value.to_string(),
+            "pdev" => client.pdev = value.to_string(),
+            "client-id" => client.client_id = value.parse().ok()?,
+            _ => {
+                if let Some(engine) = key.strip_prefix("engine-") {
+                    // `drm-engine-capacity-<engine>` counts engine instances.
+                    if !engine.starts_with("capacity-")
+                        && let Some(nanoseconds) = leading_number(value)
+                    {
+                        client
+                            .engines
+                            .insert(engine.to_string(), EngineTime::Nanoseconds(nanoseconds));
+                    }
+                } else if let Some(engine) = key.strip_prefix("total-cycles-") {
+                    cycles.entry(engine.to_string()).or_default().1 = leading_number(value);
+                } else if let Some(engine) = key.strip_prefix("cycles-") {
+                    cycles.entry(engine.to_string()).or_default().0 = leading_number(value);
+                } else if let Some(region) = key.strip_prefix("resident-") {
+                    if (region.starts_with("vram") || region.starts_with("local"))
+                        && let Some(bytes) = memory_bytes(value)
+                    {
+                        *resident_vram.get_or_insert(0) += bytes;
+                    }
+                } else if key == "memory-vram" {
+                    legacy_vram = memory_bytes(value);
+                }
+            }
+        }
+    }
+
+    for (engine, (busy, total)) in cycles {
+        if let (Some(busy), Some(total)) = (busy, total) {
+            client
+                .engines
+                .insert(engine, EngineTime::Cycles(busy, total));
+        }
+    }
+    client.vram = resident_vram.or(legacy_vram);
+
+    (!client.driver.is_empty() && !client.pdev.is_empty()).then_some(client)
+}
+
+fn leading_number(value: &str) -> Option<u64> {
+    value.split_whitespace().next()?.parse().ok()
+}
+
+/// Memory size with an optional `KiB` or `MiB` unit, in bytes.
+fn memory_bytes(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let amount: u64 = parts.next()?.parse().ok()?;
+    Some(match parts.next() {
+        Some("KiB") => amount * 1024,
+        Some("MiB") => amount * 1024 * 1024,
+        _ => amount,
+    })
+}
+
+fn process_name(pid: u32) -> String {
+    std::fs::read_to_string(format!("/proc/{pid}/comm"))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| pid.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EngineTime, engine_usage, parse_fdinfo};
+    use std::collections::HashMap;
+
+    #[test]
+    fn fdinfo_lists_engines_and_vram() {
+        let amdgpu = "pos:\t0\nflags:\t02100002\ndrm-driver:\tamdgpu\n\
+            drm-pdev:\t0000:03:00.0\ndrm-client-id:\t42\n\
+            drm-memory-vram:\t524288 KiB\ndrm-memory-gtt:\t2048 KiB\n\
+            drm-engine-gfx:\t1500000 ns\ndrm-engine-compute:\t0 ns\n";
+        let client = parse_fdinfo(amdgpu).unwrap();
+        assert_eq!(client.pdev, "0000:03:00.0");
+        assert_eq!(client.client_id, 42);
+        assert_eq!(client.vram, Some(512 * 1024 * 1024));
+        assert_eq!(
+            client.engines.get("gfx"),
+            Some(&EngineTime::Nanoseconds(1_500_000))
+        );
+
+        let xe = "drm-driver:\txe\ndrm-pdev:\t0000:00:02.0\ndrm-client-id:\t7\n\
+            drm-resident-vram0:\t64 MiB\ndrm-cycles-rcs:\t300\n\
+            drm-total-cycles-rcs:\t1000\ndrm-engine-capacity-vcs:\t2\n";
+        let client = parse_fdinfo(xe).unwrap();
+        assert_eq!(client.vram, Some(64 * 1024 * 1024));
+        assert_eq!(
+            client.engines,
+            HashMap::from([("rcs".to_string(), EngineTime::Cycles(300, 1000))])
+        );
+
+        assert_eq!(parse_fdinfo("pos:\t0\nflags:\t0100000\n"), None);
+    }
+
+    #[test]
+    fn engine_usage_is_busy_time_over_elapsed_time() {
+        let before = HashMap::from([
+            ("gfx".to_string(), EngineTime::Nanoseconds(1_000_000_000)),
+            ("rcs".to_string(), EngineTime::Cycles(100, 1_000)),
+        ]);
+        let after = HashMap::from([
+            ("gfx".to_string(), EngineTime::Nanoseconds(1_500_000_000)),
+            ("rcs".to_string(), EngineTime::Cycles(400, 2_000)),
+            ("new".to_string(), EngineTime::Nanoseconds(10)),
+        ]);
+
+        let mut usage = engine_usage(&before, &after, 2.0);
+        usage.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            usage,
+            vec![("gfx".to_string(), 25.0), ("rcs".to_string(), 30.0)]
+        );
+    }
+}
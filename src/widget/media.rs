@@ -32,7 +32,7 @@
 //! are maintained by a persistent native D-Bus connection and refreshed from
 //! player, property, and seek signals, with a slow reconciliation fallback.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -201,7 +201,7 @@ impl ArtworkCache {
 // ============================================================================
 
 /// Media player playback state.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum PlaybackStatus {
     /// Track is currently playing
     Playing,
@@ -222,7 +222,7 @@ impl Default for PlaybackStatus {
 // ============================================================================
 
 /// Identifies a specific media player instance.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub enum PlayerId {
     /// Cider Apple Music client (REST API)
     Cider,
@@ -547,7 +547,7 @@ struct EmbyPlayState {
 ///
 /// Contains track metadata, playback position, and capability flags
 /// for the media controls.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MediaInfo {
     /// Name of the media player (e.g., "Cider")
     pub player_name: String,
@@ -562,6 +562,7 @@ pub struct MediaInfo {
     /// URI of the media itself, used to identify tracks and derive video art.
     pub media_url: Option<String>,
     /// Decoded album artwork ready for rendering
+    #[serde(skip)]
     pub album_art: Option<AlbumArt>,
     /// Current playback status
     pub status: PlaybackStatus,
@@ -875,7 +876,7 @@ fn merge_mpris_proxy_timeline(primary: &mut MediaInfo, proxy: &MediaInfo) {
 // ============================================================================
 
 /// State for all detected media players.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MultiPlayerState {
     /// All detected players with their current info
     pub players: Vec<(PlayerId, MediaInfo)>,
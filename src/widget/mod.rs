@@ -12,6 +12,8 @@
 //! These modules collect system information:
 //!
 //! - [`utilization`]: CPU, Memory, and GPU usage monitoring via sysinfo/NVML/sysfs
+//! - [`gpu`]: Per-device GPU utilization, temperature, VRAM, power, clocks and fans
+//! - [`gpu_processes`]: Busiest GPU processes from NVML and DRM fdinfo
 //! - [`temperature`]: CPU and GPU temperature readings from hwmon sensors
 //! - [`network`]: Network interface bandwidth monitoring
 //! - [`storage`]: Disk space usage for mounted filesystems
@@ -39,6 +41,8 @@
 
 // === Monitoring Module Declarations ===
 pub mod battery;
+pub mod gpu;
+pub mod gpu_processes;
 pub mod media;
 pub mod network;
 pub mod notifications;
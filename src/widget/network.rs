@@ -2,8 +2,8 @@
 
 //! # Network Monitoring Module
 //!
-//! This module tracks network throughput (upload/download speeds) across all
-//! network interfaces using the `sysinfo` crate.
+//! This module tracks network throughput (upload/download speeds) of each
+//! network interface using the `sysinfo` crate.
 //!
 //! ## Measurement Approach
 //!
@@ -14,13 +14,17 @@
 //! Rate (bytes/sec) = (current_bytes - previous_bytes) / elapsed_time
 //! ```
 //!
-//! The module aggregates traffic from ALL network interfaces (eth0, wlan0,
-//! docker0, lo, etc.) to give a system-wide throughput view.
+//! Each interface gets its own rate. The totals only add up the counted
+//! interfaces: by default those backed by a device (`/sys/class/net/<if>/device`),
+//! which leaves out `lo`, bridges, veth pairs and tunnels whose traffic
+//! physical interfaces already carry. An include and an exclude list adjust
+//! the default.
 //!
 //! ## Data Sources
 //!
 //! - **sysinfo crate**: Reads from `/proc/net/dev` or equivalent
 //! - **Byte counters**: Cumulative since boot (wraps at 2^64)
+//! - **sysfs**: `device` link, `speed` (Mb/s) and `operstate` of each interface
 //!
 //! ## Display Format
 //!
@@ -34,16 +38,39 @@
 //! - **First update**: No previous data, so rate starts at 0
 //! - **Interface changes**: The interface list is rediscovered every 30 seconds
 
+use super::cache::{CachedNetworkInterface, WidgetCache};
+use crate::config::network_interface_counted;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
 use std::time::{Duration, Instant};
 use sysinfo::Networks;
 
 const INTERFACE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+const SYS_CLASS_NET: &str = "/sys/class/net";
+
+/// Throughput and link state of one network interface.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetworkInterface {
+    /// Kernel name (e.g., "enp5s0", "wlan0")
+    pub name: String,
+    /// Backed by a device, unlike loopback, bridges, veth pairs and tunnels
+    pub physical: bool,
+    /// Download rate in bytes per second
+    pub rx_rate: f64,
+    /// Upload rate in bytes per second
+    pub tx_rate: f64,
+    /// Negotiated link speed in Mb/s, `None` when the driver reports none
+    pub speed_mbps: Option<u32>,
+    /// Operational state from sysfs (e.g., "up", "down", "dormant")
+    pub operstate: String,
+}
 
 // ============================================================================
 // Network Monitor Struct
 // ============================================================================
 
-/// Monitors network throughput across all interfaces.
+/// Monitors network throughput of each interface.
 ///
 /// Calculates download (RX) and upload (TX) speeds in bytes per second by
 /// tracking the change in cumulative byte counters over time.
@@ -51,10 +78,10 @@ const INTERFACE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
 /// # Fields
 ///
 /// - `networks`: sysinfo's network interface list
-/// - `network_rx_bytes`: Previous total received bytes (for delta calculation)
-/// - `network_tx_bytes`: Previous total transmitted bytes (for delta calculation)
-/// - `network_rx_rate`: Current download speed in bytes/second
-/// - `network_tx_rate`: Current upload speed in bytes/second
+/// - `previous_bytes`: Previous received and transmitted bytes of each interface
+/// - `interfaces`: Current rates and link state of each interface
+/// - `network_rx_rate`: Current download speed of the counted interfaces
+/// - `network_tx_rate`: Current upload speed of the counted interfaces
 /// - `last_update`: Timestamp of last update (for elapsed time calculation)
 ///
 /// # Rate Calculation
@@ -66,13 +93,19 @@ const INTERFACE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
 pub struct NetworkMonitor {
     /// sysinfo's network interface list (refreshed on update)
     networks: Networks,
-    /// Previous total received bytes across all interfaces
-    network_rx_bytes: u64,
-    /// Previous total transmitted bytes across all interfaces
-    network_tx_bytes: u64,
-    /// Current download rate in bytes per second
+    /// Previous received and transmitted bytes of each interface
+    previous_bytes: HashMap<String, (u64, u64)>,
+    /// Whether each known interface is backed by a device
+    physical: HashMap<String, bool>,
+    /// Interfaces counted even though they are virtual
+    included: Vec<String>,
+    /// Physical interfaces left out of the totals
+    excluded: Vec<String>,
+    /// Rates and link state of each interface, sorted by name
+    pub interfaces: Vec<NetworkInterface>,
+    /// Current download rate of the counted interfaces in bytes per second
     pub network_rx_rate: f64,
-    /// Current upload rate in bytes per second
+    /// Current upload rate of the counted interfaces in bytes per second
     pub network_tx_rate: f64,
     /// Timestamp of last update for elapsed time calculation
     last_update: Instant,
@@ -85,16 +118,22 @@ impl NetworkMonitor {
     ///
     /// Initializes sysinfo's network list with immediate discovery of all
     /// interfaces. Initial rates are 0.0 until the first update provides a
-    /// delta from the baseline captured here.
+    /// delta from the baseline captured here. Only physical interfaces are
+    /// counted until [`Self::set_interface_filter`] says otherwise.
     pub fn new() -> Self {
         let networks = Networks::new_with_refreshed_list();
-        let (network_rx_bytes, network_tx_bytes) = total_bytes(&networks);
+        let previous_bytes = interface_bytes(&networks);
+        let physical = physical_interfaces(&networks);
+        cache_interfaces(&physical);
         let now = Instant::now();
 
         Self {
             networks,
-            network_rx_bytes,
-            network_tx_bytes,
+            previous_bytes,
+            physical,
+            included: Vec::new(),
+            excluded: Vec::new(),
+            interfaces: Vec::new(),
             network_rx_rate: 0.0,
             network_tx_rate: 0.0,
             last_update: now,
@@ -102,17 +141,30 @@ impl NetworkMonitor {
         }
     }
 
+    /// Choose the interfaces counted in the totals, see
+    /// [`network_interface_counted`].
+    pub fn set_interface_filter(&mut self, included: &[String], excluded: &[String]) {
+        self.included = included.to_vec();
+        self.excluded = excluded.to_vec();
+    }
+
+    /// Cumulative received and transmitted bytes of each interface as of the
+    /// last update.
+    pub fn byte_counters(&self) -> &HashMap<String, (u64, u64)> {
+        &self.previous_bytes
+    }
+
     /// Update network throughput calculations.
     ///
-    /// Refreshes sysinfo's network data, sums bytes across all interfaces,
-    /// then calculates the rate based on time elapsed since last update.
+    /// Refreshes sysinfo's network data, then calculates the rate of each
+    /// interface based on time elapsed since last update.
     ///
     /// # Algorithm
     ///
     /// 1. Calculate elapsed time since last update
     /// 2. Refresh network interface data
-    /// 3. Sum RX and TX bytes across ALL interfaces
-    /// 4. Calculate rates: `(new_bytes - old_bytes) / elapsed_seconds`
+    /// 3. Calculate each interface's rates: `(new_bytes - old_bytes) / elapsed_seconds`
+    /// 4. Sum the rates of the counted interfaces
     /// 5. Store new byte counts for next delta calculation
     ///
     /// # Counter Reset Handling
@@ -126,30 +178,109 @@ impl NetworkMonitor {
 
         if now.duration_since(self.last_interface_refresh) >= INTERFACE_REFRESH_INTERVAL {
             self.networks.refresh_list();
+            let physical = physical_interfaces(&self.networks);
+            if physical != self.physical {
+                cache_interfaces(&physical);
+                self.physical = physical;
+            }
             self.last_interface_refresh = now;
         } else {
             self.networks.refresh();
         }
 
-        let (total_rx, total_tx) = total_bytes(&self.networks);
+        let current_bytes = interface_bytes(&self.networks);
+        let root = Path::new(SYS_CLASS_NET);
+        let mut interfaces: Vec<_> = current_bytes
+            .iter()
+            .map(|(name, &(rx, tx))| {
+                let (previous_rx, previous_tx) =
+                    self.previous_bytes.get(name).copied().unwrap_or((rx, tx));
+                NetworkInterface {
+                    name: name.clone(),
+                    physical: self.physical.get(name).copied().unwrap_or(false),
+                    rx_rate: rate_from_totals(previous_rx, rx, elapsed),
+                    tx_rate: rate_from_totals(previous_tx, tx, elapsed),
+                    speed_mbps: link_speed(root, name),
+                    operstate: operstate(root, name),
+                }
+            })
+            .collect();
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
 
-        self.network_rx_rate = rate_from_totals(self.network_rx_bytes, total_rx, elapsed);
-        self.network_tx_rate = rate_from_totals(self.network_tx_bytes, total_tx, elapsed);
+        let (rx_rate, tx_rate) = counted_rates(&interfaces, &self.included, &self.excluded);
+        self.network_rx_rate = rx_rate;
+        self.network_tx_rate = tx_rate;
+        self.interfaces = interfaces;
 
         // Store current values for next update's delta calculation
-        self.network_rx_bytes = total_rx;
-        self.network_tx_bytes = total_tx;
+        self.previous_bytes = current_bytes;
         self.last_update = now;
     }
 }
 
-fn total_bytes(networks: &Networks) -> (u64, u64) {
-    networks.values().fold((0, 0), |(rx, tx), network| {
-        (
-            rx.saturating_add(network.total_received()),
-            tx.saturating_add(network.total_transmitted()),
-        )
-    })
+/// Summed download and upload rates of the counted interfaces.
+fn counted_rates<'a>(
+    interfaces: impl IntoIterator<Item = &'a NetworkInterface>,
+    included: &[String],
+    excluded: &[String],
+) -> (f64, f64) {
+    interfaces
+        .into_iter()
+        .filter(|interface| {
+            network_interface_counted(included, excluded, &interface.name, interface.physical)
+        })
+        .fold((0.0, 0.0), |(rx, tx), interface| {
+            (rx + interface.rx_rate, tx + interface.tx_rate)
+        })
+}
+
+fn interface_bytes(networks: &Networks) -> HashMap<String, (u64, u64)> {
+    networks
+        .iter()
+        .map(|(name, network)| {
+            (
+                name.clone(),
+                (network.total_received(), network.total_transmitted()),
+            )
+        })
+        .collect()
+}
+
+fn physical_interfaces(networks: &Networks) -> HashMap<String, bool> {
+    let root = Path::new(SYS_CLASS_NET);
+    networks
+        .keys()
+        .map(|name| (name.clone(), root.join(name).join("device").exists()))
+        .collect()
+}
+
+/// Let the settings app list the interfaces to choose from.
+fn cache_interfaces(physical: &HashMap<String, bool>) {
+    let mut interfaces: Vec<_> = physical
+        .iter()
+        .map(|(name, &physical)| CachedNetworkInterface {
+            name: name.clone(),
+            physical,
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    WidgetCache::update(|cache| cache.update_network_interfaces(interfaces));
+}
+
+/// Link speed in Mb/s. Drivers report -1, or fail the read, while the link
+/// is down or has no fixed speed.
+fn link_speed(root: &Path, name: &str) -> Option<u32> {
+    std::fs::read_to_string(root.join(name).join("speed"))
+        .ok()
+        .and_then(|speed| speed.trim().parse::<i64>().ok())
+        .and_then(|speed| u32::try_from(speed).ok())
+        .filter(|speed| *speed > 0)
+}
+
+fn operstate(root: &Path, name: &str) -> String {
+    std::fs::read_to_string(root.join(name).join("operstate"))
+        .map(|state| state.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
 }
 
 fn rate_from_totals(previous: u64, current: u64, elapsed_seconds: f64) -> f64 {
@@ -164,7 +295,7 @@ fn rate_from_totals(previous: u64, current: u64, elapsed_seconds: f64) -> f64 {
 
 #[cfg(test)]
 mod tests {
-    use super::rate_from_totals;
+    use super::{NetworkInterface, counted_rates, link_speed, rate_from_totals};
 
     #[test]
     fn calculates_bytes_per_second_from_cumulative_totals() {
@@ -181,4 +312,42 @@ mod tests {
         assert_eq!(rate_from_totals(100, 200, 0.0), 0.0);
         assert_eq!(rate_from_totals(100, 200, f64::NAN), 0.0);
     }
+
+    #[test]
+    fn only_physical_interfaces_are_counted_by_default() {
+        let interface = |name: &str, physical, rate| NetworkInterface {
+            name: name.to_string(),
+            physical,
+            rx_rate: rate,
+            tx_rate: rate / 2.0,
+            speed_mbps: None,
+            operstate: "up".to_string(),
+        };
+        let interfaces = [
+            interface("enp5s0", true, 1_000.0),
+            interface("wlan0", true, 200.0),
+            interface("docker0", false, 1_000.0),
+            interface("wg0", false, 50.0),
+        ];
+
+        assert_eq!(counted_rates(&interfaces, &[], &[]), (1_200.0, 600.0));
+        assert_eq!(
+            counted_rates(&interfaces, &["wg0".to_string()], &["wlan0".to_string()]),
+            (1_050.0, 525.0)
+        );
+    }
+
+    #[test]
+    fn unknown_link_speeds_are_left_out() {
+        let root = std::env::temp_dir().join(format!("network-speed-{}", std::process::id()));
+        for (name, speed) in [("eth0", "1000\n"), ("eth1", "-1\n")] {
+            std::fs::create_dir_all(root.join(name)).unwrap();
+            std::fs::write(root.join(name).join("speed"), speed).unwrap();
+        }
+
+        assert_eq!(link_speed(&root, "eth0"), Some(1000));
+        assert_eq!(link_speed(&root, "eth1"), None);
+        assert_eq!(link_speed(&root, "missing"), None);
+        std::fs::remove_dir_all(root).unwrap();
+    }
 }
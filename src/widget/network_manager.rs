@@ -0,0 +1,640 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Connection details from NetworkManager on the system bus.
+//!
+//! The monitor reads the primary connection (name, Wi-Fi access point,
+//! addresses and gateway) and the state of every saved VPN and WireGuard
+//! connection. Signals from NetworkManager drive every refresh; nothing is
+//! polled. The saved VPN profiles are read once per bus connection and again
+//! only when a saved connection is added, removed or updated. Strength
+//! updates of access points other than the connected ones are ignored, as
+//! NetworkManager sends them for every network in range.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use zbus::MatchRule;
+use zbus::blocking::{Connection, MessageIterator, Proxy};
+use zbus::message::Type as MessageType;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+const NM_BUS_NAME: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const NM_SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const ACCESS_POINT_PATH: &str = "/org/freedesktop/NetworkManager/AccessPoint/";
+const NM_INTERFACE: &str = "org.freedesktop.NetworkManager";
+const ACTIVE_INTERFACE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const DEVICE_INTERFACE: &str = "org.freedesktop.NetworkManager.Device";
+const WIRELESS_INTERFACE: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const ACCESS_POINT_INTERFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const IP4_CONFIG_INTERFACE: &str = "org.freedesktop.NetworkManager.IP4Config";
+const IP6_CONFIG_INTERFACE: &str = "org.freedesktop.NetworkManager.IP6Config";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
+const SETTINGS_CONNECTION_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const DISABLED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `NMActiveConnectionState` values.
+const ACTIVATING: u32 = 1;
+const ACTIVATED: u32 = 2;
+
+/// Connection context shown below the network rates.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NetworkConnections {
+    /// The connection carrying the default route, VPNs aside
+    pub primary: Option<ActiveConnection>,
+    /// Saved VPN and WireGuard connections, by name
+    pub vpns: Vec<VpnConnection>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ActiveConnection {
+    /// Connection profile name (e.g., "Home Wi-Fi", "Wired connection 1")
+    pub name: String,
+    /// Kernel name of the connection's first device
+    pub interface: Option<String>,
+    pub wifi: Option<AccessPoint>,
+    /// Addresses with prefix length (e.g., "192.168.1.20/24")
+    pub ipv4: Vec<String>,
+    /// Global IPv6 addresses with prefix length; link-local ones are left out
+    pub ipv6: Vec<String>,
+    /// IPv4 default gateway, or the IPv6 one without IPv4
+    pub gateway: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccessPoint {
+    pub ssid: String,
+    /// Signal quality in percent
+    pub strength: u8,
+    pub frequency_mhz: u32,
+}
+
+impl AccessPoint {
+    /// Wi-Fi band of the access point's channel.
+    pub fn band(&self) -> Option<&'static str> {
+        match self.frequency_mhz {
+            2_400..=2_500 => Some("2.4 GHz"),
+            4_900..=5_900 => Some("5 GHz"),
+            5_925..=7_125 => Some("6 GHz"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VpnConnection {
+    pub name: String,
+    pub wireguard: bool,
+    pub state: VpnState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum VpnState {
+    Up,
+    Connecting,
+    Down,
+}
+
+impl VpnState {
+    pub fn label(self) -> &'static str {
+        match self {
+            VpnState::Up => "up",
+            VpnState::Connecting => "connecting",
+            VpnState::Down => "down",
+        }
+    }
+}
+
+/// A saved VPN or WireGuard connection profile.
+#[derive(Debug, Clone, PartialEq)]
+struct VpnProfile {
+    name: String,
+    uuid: Option<String>,
+    wireguard: bool,
+}
+
+impl VpnProfile {
+    /// The profile with the state of its activation, if any.
+    fn connection(&self, active_by_uuid: &HashMap<String, u32>) -> VpnConnection {
+        let state = match self.uuid.as_ref().and_then(|uuid| active_by_uuid.get(uuid)) {
+            Some(&ACTIVATED) => VpnState::Up,
+            Some(&ACTIVATING) => VpnState::Connecting,
+            _ => VpnState::Down,
+        };
+        VpnConnection {
+            name: self.name.clone(),
+            wireguard: self.wireguard,
+            state,
+        }
+    }
+}
+
+/// What the primary connection is picked from.
+#[derive(Debug, Clone, Default)]
+struct ActiveCandidate {
+    path: String,
+    kind: String,
+    state: u32,
+    default_route: bool,
+}
+
+impl ActiveCandidate {
+    fn is_vpn(&self) -> bool {
+        matches!(self.kind.as_str(), "vpn" | "wireguard")
+    }
+}
+
+/// Listens to NetworkManager while enabled and keeps the latest details.
+#[derive(Clone)]
+pub struct ConnectionMonitor {
+    connections: Arc<Mutex<NetworkConnections>>,
+    enabled: Arc<AtomicBool>,
+    /// Object paths of the access points of active Wi-Fi devices
+    active_access_points: Arc<Mutex<HashSet<String>>>,
+    listener: Arc<Once>,
+}
+
+impl ConnectionMonitor {
+    /// Create a monitor that connects to the system bus once enabled.
+    pub fn new() -> Self {
+        Self {
+            connections: Arc::default(),
+            enabled: Arc::new(AtomicBool::new(false)),
+            active_access_points: Arc::default(),
+            listener: Arc::new(Once::new()),
+        }
+    }
+
+    /// Start or stop listening. Stopping forgets the details, and the bus
+    /// connection closes with the next signal.
+    pub fn set_enabled(&self, enabled: bool) {
+        if enabled {
+            self.enabled.store(true, Ordering::Relaxed);
+            let listener = self.clone();
+            self.listener.call_once(|| {
+                std::thread::spawn(move || listener.supervise_connection());
+            });
+        } else {
+            // Under the lock a refresh in progress either publishes before
+            // the details are cleared or sees the flag and drops its result.
+            let mut connections = self
+                .connections
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            self.enabled.store(false, Ordering::Relaxed);
+            *connections = NetworkConnections::default();
+        }
+    }
+
+    pub fn connections(&self) -> NetworkConnections {
+        self.connections
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn supervise_connection(&self) {
+        loop {
+            if !self.enabled.load(Ordering::Relaxed) {
+                std::thread::sleep(DISABLED_CHECK_INTERVAL);
+                continue;
+            }
+
+            match Connection::system() {
+                Ok(connection) => {
+                    if let Err(error) = self.monitor_connection(connection) {
+                        log::warn!("NetworkManager connection ended: {error}");
+                    }
+                }
+                Err(error) => log::warn!("System bus unavailable for NetworkManager: {error}"),
+            }
+            if self.enabled.load(Ordering::Relaxed) {
+                std::thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    }
+
+    fn monitor_connection(
+        &self,
+        signal_connection: Connection,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Method replies go over their own connection so the signal iterator
+        // cannot consume them.
+        let command_connection = Connection::system()?;
+        let bus = zbus::blocking::fdo::DBusProxy::new(&signal_connection)?;
+        bus.add_match_rule(
+            MatchRule::builder()
+                .msg_type(MessageType::Signal)
+                .sender(NM_BUS_NAME)?
+                .build(),
+        )?;
+
+        let mut messages = MessageIterator::from(&signal_connection);
+        let mut vpn_profiles = None;
+        self.refresh(&command_connection, &mut vpn_profiles);
+
+        for message in &mut messages {
+            if !self.enabled.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let message = message?;
+            let header = message.header();
+            if changes_saved_connections(
+                header.interface().map(|interface| interface.as_str()),
+                header.member().map(|member| member.as_str()),
+            ) {
+                vpn_profiles = None;
+            }
+            let path = header.path().map(|path| path.as_str()).unwrap_or_default();
+            if path.starts_with(ACCESS_POINT_PATH)
+                && !self
+                    .active_access_points
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .contains(path)
+            {
+                continue;
+            }
+            self.refresh(&command_connection, &mut vpn_profiles);
+        }
+        Err("NetworkManager signal stream closed".into())
+    }
+
+    /// Read the details, and the saved VPN profiles unless `vpn_profiles`
+    /// holds them, and publish them while the monitor is enabled.
+    fn refresh(&self, connection: &Connection, vpn_profiles: &mut Option<Vec<VpnProfile>>) {
+        let profiles = match vpn_profiles {
+            Some(profiles) => profiles,
+            None => match query_vpn_profiles(connection) {
+                Ok(profiles) => vpn_profiles.insert(profiles),
+                Err(error) => {
+                    log::warn!("Failed to read NetworkManager VPN profiles: {error}");
+                    return;
+                }
+            },
+        };
+        let mut access_points = HashSet::new();
+        match query_connections(connection, profiles, &mut access_points) {
+            Ok(connections) => {
+                let mut current = self
+                    .connections
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                // Disabling cleared the details while this refresh ran.
+                if !self.enabled.load(Ordering::Relaxed) {
+                    return;
+                }
+                *current = connections;
+                *self
+                    .active_access_points
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = access_points;
+            }
+            Err(error) => log::warn!("Failed to read NetworkManager connections: {error}"),
+        }
+    }
+}
+
+/// Read the primary connection and the state of the saved VPNs, collecting
+/// the paths of the access points in use into `access_points`.
+fn query_connections(
+    connection: &Connection,
+    vpn_profiles: &[VpnProfile],
+    access_points: &mut HashSet<String>,
+) -> zbus::Result<NetworkConnections> {
+    let manager = get_all(connection, NM_PATH, NM_INTERFACE)?;
+    let primary_path = property_path(&manager, "PrimaryConnection");
+
+    let mut candidates = Vec::new();
+    let mut active_by_uuid = HashMap::new();
+    for path in property_paths(&manager, "ActiveConnections") {
+        // A connection can go away between listing and reading it.
+        let Ok(active) = get_all(connection, &path, ACTIVE_INTERFACE) else {
+            continue;
+        };
+        let state = property_u32(&active, "State").unwrap_or_default();
+        if let Some(uuid) = property_string(&active, "Uuid") {
+            active_by_uuid.insert(uuid, state);
+        }
+        candidates.push(ActiveCandidate {
+            path,
+            kind: property_string(&active, "Type").unwrap_or_default(),
+            state,
+            default_route: property_bool(&active, "Default").unwrap_or(false)
+                || property_bool(&active, "Default6").unwrap_or(false),
+        });
+    }
+
+    let primary = match primary_candidate(&candidates, primary_path.as_deref()) {
+        Some(path) => Some(query_active(connection, path, access_points)?),
+        None => None,
+    };
+
+    Ok(NetworkConnections {
+        primary,
+        vpns: vpn_profiles
+            .iter()
+            .map(|profile| profile.connection(&active_by_uuid))
+            .collect(),
+    })
+}
+
+/// NetworkManager's primary connection unless it is a VPN, in which case
+/// the activated connection with a default route that carries the VPN.
+fn primary_candidate<'a>(
+    candidates: &'a [ActiveCandidate],
+    primary_path: Option<&str>,
+) -> Option<&'a str> {
+    let usable = |candidate: &&ActiveCandidate| {
+        candidate.state == ACTIVATED && !candidate.is_vpn() && candidate.kind != "loopback"
+    };
+    candidates
+        .iter()
+        .filter(usable)
+        .find(|candidate| Some(candidate.path.as_str()) == primary_path)
+        .or_else(|| {
+            candidates
+                .iter()
+                .filter(usable)
+                .find(|candidate| candidate.default_route)
+        })
+        .map(|candidate| candidate.path.as_str())
+}
+
+fn query_active(
+    connection: &Connection,
+    path: &str,
+    access_points: &mut HashSet<String>,
+) -> zbus::Result<ActiveConnection> {
+    let active = get_all(connection, path, ACTIVE_INTERFACE)?;
+    let mut details = ActiveConnection {
+        name: property_string(&active, "Id").unwrap_or_default(),
+        ..ActiveConnection::default()
+    };
+
+    if let Some(device) = property_paths(&active, "Devices").into_iter().next() {
+        let properties = get_all(connection, &device, DEVICE_INTERFACE)?;
+        details.interface = property_string(&properties, "Interface");
+        if let Ok(wireless) = get_all(connection, &device, WIRELESS_INTERFACE)
+            && let Some(access_point) = property_path(&wireless, "ActiveAccessPoint")
+        {
+            let properties = get_all(connection, &access_point, ACCESS_POINT_INTERFACE)?;
+            details.wifi = Some(access_point_from_properties(&properties));
+            access_points.insert(access_point);
+        }
+    }
+
+    let mut ipv6_gateway = None;
+    if let Some(config) = property_path(&active, "Ip4Config") {
+        let properties = get_all(connection, &config, IP4_CONFIG_INTERFACE)?;
+        details.ipv4 = addresses(&properties);
+        details.gateway = property_string(&properties, "Gateway").filter(|gw| !gw.is_empty());
+    }
+    if let Some(config) = property_path(&active, "Ip6Config") {
+        let properties = get_all(connection, &config, IP6_CONFIG_INTERFACE)?;
+        details.ipv6 = addresses(&properties)
+            .into_iter()
+            .filter(|address| !address.starts_with("fe80:"))
+            .collect();
+        ipv6_gateway = property_string(&properties, "Gateway").filter(|gw| !gw.is_empty());
+    }
+    details.gateway = details.gateway.or(ipv6_gateway);
+    Ok(details)
+}
+
+/// Whether a signal adds, removes or changes a saved connection, which may
+/// be a VPN profile.
+fn changes_saved_connections(interface: Option<&str>, member: Option<&str>) -> bool {
+    matches!(
+        (interface, member),
+        (
+            Some(SETTINGS_INTERFACE),
+            Some("NewConnection" | "ConnectionRemoved")
+        ) | (
+            Some(SETTINGS_CONNECTION_INTERFACE),
+            Some("Updated" | "Removed")
+        )
+    )
+}
+
+/// Saved VPN and WireGuard connection profiles, by name.
+fn query_vpn_profiles(connection: &Connection) -> zbus::Result<Vec<VpnProfile>> {
+    let settings = Proxy::new(
+        connection,
+        NM_BUS_NAME,
+        NM_SETTINGS_PATH,
+        SETTINGS_INTERFACE,
+    )?;
+    let paths: Vec<OwnedObjectPath> = settings.call("ListConnections", &())?;
+
+    let mut vpns = Vec::new();
+    for path in paths {
+        let Ok(saved) = Proxy::new(
+            connection,
+            NM_BUS_NAME,
+            path.as_str(),
+            SETTINGS_CONNECTION_INTERFACE,
+        )
+        .and_then(|proxy| {
+            proxy.call::<_, _, HashMap<String, HashMap<String, OwnedValue>>>("GetSettings", &())
+        }) else {
+            continue;
+        };
+        if let Some(vpn) = vpn_profile(&saved) {
+            vpns.push(vpn);
+        }
+    }
+    vpns.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(vpns)
+}
+
+fn vpn_profile(settings: &HashMap<String, HashMap<String, OwnedValue>>) -> Option<VpnProfile> {
+    let section = settings.get("connection")?;
+    let wireguard = match property_string(section, "type")?.as_str() {
+        "vpn" => false,
+        "wireguard" => true,
+        _ => return None,
+    };
+
+    Some(VpnProfile {
+        name: property_string(section, "id").unwrap_or_default(),
+        uuid: property_string(section, "uuid"),
+        wireguard,
+    })
+}
+
+fn access_point_from_properties(properties: &HashMap<String, OwnedValue>) -> AccessPoint {
+    let ssid = properties
+        .get("Ssid")
+        .and_then(|value| value.try_clone().ok())
+        .and_then(|value| Vec::<u8>::try_from(value).ok())
+        .unwrap_or_default();
+    AccessPoint {
+        ssid: String::from_utf8_lossy(&ssid).into_owned(),
+        strength: properties
+            .get("Strength")
+            .and_then(|value| u8::try_from(value).ok())
+            .unwrap_or_default(),
+        frequency_mhz: property_u32(properties, "Frequency").unwrap_or_default(),
+    }
+}
+
+/// `AddressData` of an IP configuration as `address/prefix` strings.
+fn addresses(properties: &HashMap<String, OwnedValue>) -> Vec<String> {
+    properties
+        .get("AddressData")
+        .and_then(|value| value.try_clone().ok())
+        .and_then(|value| Vec::<HashMap<String, OwnedValue>>::try_from(value).ok())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|address| {
+            let ip = property_string(address, "address")?;
+            Some(match property_u32(address, "prefix") {
+                Some(prefix) => format!("{ip}/{prefix}"),
+                None => ip,
+            })
+        })
+        .collect()
+}
+
+fn get_all(
+    connection: &Connection,
+    path: &str,
+    interface: &str,
+) -> zbus::Result<HashMap<String, OwnedValue>> {
+    let properties = Proxy::new(connection, NM_BUS_NAME, path, PROPERTIES_INTERFACE)?;
+    properties.call("GetAll", &(interface,))
+}
+
+fn property_string(properties: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    <&str>::try_from(properties.get(key)?)
+        .ok()
+        .map(ToOwned::to_owned)
+}
+
+fn property_bool(properties: &HashMap<String, OwnedValue>, key: &str) -> Option<bool> {
+    bool::try_from(properties.get(key)?).ok()
+}
+
+fn property_u32(properties: &HashMap<String, OwnedValue>, key: &str) -> Option<u32> {
+    u32::try_from(properties.get(key)?).ok()
+}
+
+/// An object path property, `None` for NetworkManager's `/` placeholder.
+fn property_path(properties: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    let path = OwnedObjectPath::try_from(properties.get(key)?.try_clone().ok()?).ok()?;
+    (path.as_str() != "/").then(|| path.to_string())
+}
+
+fn property_paths(properties: &HashMap<String, OwnedValue>, key: &str) -> Vec<String> {
+    properties
+        .get(key)
+        .and_then(|value| value.try_clone().ok())
+        .and_then(|value| Vec::<OwnedObjectPath>::try_from(value).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| path.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    fn owned(value: Value<'_>) -> OwnedValue {
+        OwnedValue::try_from(value).unwrap()
+    }
+
+    fn candidate(path: &str, kind: &str, state: u32, default_route: bool) -> ActiveCandidate {
+        ActiveCandidate {
+            path: path.to_string(),
+            kind: kind.to_string(),
+            state,
+            default_route,
+        }
+    }
+
+    #[test]
+    fn primary_connection_skips_vpns_and_loopback() {
+        let candidates = [
+            candidate("/active/1", "loopback", ACTIVATED, false),
+            candidate("/active/2", "802-11-wireless", ACTIVATED, true),
+            candidate("/active/3", "wireguard", ACTIVATED, true),
+            candidate("/active/4", "802-3-ethernet", ACTIVATING, true),
+        ];
+
+        assert_eq!(
+            primary_candidate(&candidates, Some("/active/2")),
+            Some("/active/2")
+        );
+        assert_eq!(
+            primary_candidate(&candidates, Some("/active/3")),
+            Some("/active/2")
+        );
+        assert_eq!(primary_candidate(&candidates[..1], Some("/active/1")), None);
+    }
+
+    #[test]
+    fn saved_vpns_report_their_activation_state() {
+        let section = |kind: &str, uuid: &str| {
+            HashMap::from([(
+                "connection".to_string(),
+                HashMap::from([
+                    ("id".to_string(), owned(Value::from("Office"))),
+                    ("type".to_string(), owned(Value::from(kind))),
+                    ("uuid".to_string(), owned(Value::from(uuid))),
+                ]),
+            )])
+        };
+        let active = HashMap::from([("a".to_string(), ACTIVATED), ("b".to_string(), ACTIVATING)]);
+
+        let state = |kind, uuid| {
+            vpn_profile(&section(kind, uuid)).map(|profile| profile.connection(&active).state)
+        };
+
+        let vpn = vpn_profile(&section("wireguard", "a"))
+            .unwrap()
+            .connection(&active);
+        assert!(vpn.wireguard);
+        assert_eq!(vpn.state, VpnState::Up);
+        assert_eq!(state("vpn", "b"), Some(VpnState::Connecting));
+        assert_eq!(state("vpn", "c"), Some(VpnState::Down));
+        assert_eq!(vpn_profile(&section("802-3-ethernet", "a")), None);
+    }
+
+    #[test]
+    fn saved_connection_changes_invalidate_the_vpn_profiles() {
+        assert!(changes_saved_connections(
+            Some(SETTINGS_INTERFACE),
+            Some("NewConnection")
+        ));
+        assert!(changes_saved_connections(
+            Some(SETTINGS_CONNECTION_INTERFACE),
+            Some("Updated")
+        ));
+        assert!(!changes_saved_connections(
+            Some(PROPERTIES_INTERFACE),
+            Some("PropertiesChanged")
+        ));
+        assert!(!changes_saved_connections(None, None));
+    }
+
+    #[test]
+    fn access_points_decode_ssid_and_band() {
+        let properties = HashMap::from([
+            ("Ssid".to_string(), owned(Value::from(b"Cafe".to_vec()))),
+            ("Strength".to_string(), OwnedValue::from(72_u8)),
+            ("Frequency".to_string(), OwnedValue::from(5_180_u32)),
+        ]);
+
+        let access_point = access_point_from_properties(&properties);
+        assert_eq!(access_point.ssid, "Cafe");
+        assert_eq!(access_point.strength, 72);
+        assert_eq!(access_point.band(), Some("5 GHz"));
+    }
+}
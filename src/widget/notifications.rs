@@ -63,6 +63,8 @@ const NOTIFICATIONS_INTERFACE: &str = "org.freedesktop.Notifications";
 const COSMIC_HISTORY_RECONCILE_INTERVAL: Duration = Duration::from_secs(10);
 const COSMIC_HISTORY_EVENT_DEBOUNCE: Duration = Duration::from_secs(1);
 const NOTIFICATION_MONITOR_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const ALERT_APP_NAME: &str = "COSMIC Widget";
+const ALERT_ICON: &str = "dialog-warning-symbolic";
 
 // ============================================================================
 // Notification Struct
@@ -99,6 +101,7 @@ struct NotificationCache {
 enum NotificationDbusCommand {
     RefreshHistory,
     Close(Vec<(u32, String)>),
+    Notify { summary: String, body: String },
 }
 
 // ============================================================================
@@ -443,7 +446,23 @@ impl NotificationMonitor {
         log::info!("Removed notification: {} at {}", app_name, timestamp);
     }
 
+    /// Raise a desktop notification of our own, such as a threshold alert.
+    ///
+    /// Sent from the D-Bus worker thread, so this never blocks on the bus.
+    pub fn notify(&self, summary: &str, body: &str) {
+        let command = NotificationDbusCommand::Notify {
+            summary: summary.to_string(),
+            body: body.to_string(),
+        };
+        if self.dbus_commands.send(command).is_err() {
+            log::warn!("Notification D-Bus worker is unavailable");
+        }
+    }
+
     fn persist(&self, notifications: &[Notification]) {
+        if !super::cache::persistence_enabled() {
+            return;
+        }
         if let Err(error) =
             persist_cached_notifications(&self.cache_path, &self.session_key, notifications)
         {
@@ -479,6 +498,9 @@ impl NotificationMonitor {
             NotificationDbusCommand::Close(notifications) => {
                 close_remote_notifications(connection, &notifications);
             }
+            NotificationDbusCommand::Notify { summary, body } => {
+                send_notification(connection, &summary, &body);
+            }
         }
         *refresh_due = Some(Instant::now() + COSMIC_HISTORY_EVENT_DEBOUNCE);
     }
@@ -744,6 +766,61 @@ fn close_remote_notifications_inner(
     Ok(())
 }
 
+fn send_notification(
+    connection: &mut Option<zbus::blocking::Connection>,
+    summary: &str,
+    body: &str,
+) {
+    for attempt in 0..2 {
+        if !ensure_notification_connection(connection) {
+            return;
+        }
+        let active_connection = connection.as_ref().expect("connection was ensured");
+        match send_notification_inner(active_connection, summary, body) {
+            Ok(()) => return,
+            Err(error) if matches!(error, zbus::Error::InputOutput(_)) && attempt == 0 => {
+                *connection = None;
+            }
+            Err(error) => {
+                log::warn!("Failed to send notification: {error}");
+                return;
+            }
+        }
+    }
+}
+
+fn send_notification_inner(
+    connection: &zbus::blocking::Connection,
+    summary: &str,
+    body: &str,
+) -> zbus::Result<()> {
+    use zbus::blocking::Proxy;
+    use zbus::zvariant::Value;
+
+    let notifications_proxy = Proxy::new(
+        connection,
+        NOTIFICATIONS_SERVICE,
+        NOTIFICATIONS_PATH,
+        NOTIFICATIONS_INTERFACE,
+    )?;
+    let actions: Vec<&str> = Vec::new();
+    let hints: HashMap<&str, Value<'_>> = HashMap::new();
+    let _id: u32 = notifications_proxy.call(
+        "Notify",
+        &(
+            ALERT_APP_NAME,
+            0u32,
+            ALERT_ICON,
+            summary,
+            body,
+            actions,
+            hints,
+            -1i32,
+        ),
+    )?;
+    Ok(())
+}
+
 type CosmicNotificationHistoryEntry = (u32, String, String, String, u64);
 
 fn load_cosmic_notification_history(
@@ -2,7 +2,13 @@
 
 //! Process-wide NVIDIA Management Library access.
 
-use nvml_wrapper::{Nvml, enum_wrappers::device::TemperatureSensor};
+use super::gpu::{GpuDetails, GpuFan};
+use nvml_wrapper::{
+    Device, Nvml,
+    enum_wrappers::device::{Clock, TemperatureSensor},
+    enums::device::UsedGpuMemory,
+};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{LazyLock, Mutex, OnceLock};
 use std::time::{Duration, Instant};
@@ -68,37 +74,165 @@ fn with_nvml<T>(query: impl FnOnce(&Nvml) -> Option<T>) -> Option<T> {
     query(state.instance.as_ref()?)
 }
 
-pub(super) fn utilization() -> Option<f32> {
+pub(super) fn temperature() -> Option<f32> {
     with_nvml(|nvml| {
         let count = nvml.device_count().ok()?;
 
         (0..count)
             .filter_map(|index| nvml.device_by_index(index).ok())
-            .filter_map(|device| device.utilization_rates().ok())
-            .map(|rates| rates.gpu as f32)
+            .filter_map(|device| device.temperature(TemperatureSensor::Gpu).ok())
+            .map(|temperature| temperature as f32)
             .max_by(f32::total_cmp)
     })
 }
 
-pub(super) fn temperature() -> Option<f32> {
+/// Readings of one NVML device.
+pub(super) struct NvidiaDevice {
+    /// PCI address as in sysfs, such as `0000:01:00.0`
+    pub pci_address: String,
+    pub name: String,
+    pub utilization: Option<f32>,
+    pub temperature: Option<f32>,
+    pub details: GpuDetails,
+}
+
+/// Readings of every NVML device, or `None` while NVML is unavailable.
+pub(super) fn devices() -> Option<Vec<NvidiaDevice>> {
     with_nvml(|nvml| {
         let count = nvml.device_count().ok()?;
 
-        (0..count)
-            .filter_map(|index| nvml.device_by_index(index).ok())
-            .filter_map(|device| device.temperature(TemperatureSensor::Gpu).ok())
-            .map(|temperature| temperature as f32)
-            .max_by(f32::total_cmp)
+        Some(
+            (0..count)
+                .filter_map(|index| nvml.device_by_index(index).ok())
+                .filter_map(|device| {
+                    Some(NvidiaDevice {
+                        pci_address: sysfs_pci_address(&device.pci_info().ok()?.bus_id),
+                        name: device.name().unwrap_or_else(|_| "NVIDIA GPU".to_string()),
+                        utilization: device
+                            .utilization_rates()
+                            .ok()
+                            .map(|rates| rates.gpu as f32),
+                        temperature: device
+                            .temperature(TemperatureSensor::Gpu)
+                            .ok()
+                            .map(|temperature| temperature as f32),
+                        details: details(&device),
+                    })
+                })
+                .collect(),
+        )
     })
 }
 
+/// GPU use of one process on one NVML device.
+pub(super) struct NvidiaProcess {
+    /// PCI address as in sysfs, such as `0000:01:00.0`
+    pub pci_address: String,
+    pub pid: u32,
+    /// Latest SM utilization sample in percent
+    pub utilization: Option<f32>,
+    pub vram: Option<u64>,
+}
+
+/// Graphics and compute processes of every NVML device, or `None` while
+/// NVML is unavailable.
+pub(super) fn processes() -> Option<Vec<NvidiaProcess>> {
+    with_nvml(|nvml| {
+        let count = nvml.device_count().ok()?;
+        let mut processes = Vec::new();
+
+        for device in (0..count).filter_map(|index| nvml.device_by_index(index).ok()) {
+            let Ok(pci_info) = device.pci_info() else {
+                continue;
+            };
+            let pci_address = sysfs_pci_address(&pci_info.bus_id);
+
+            // Keep the latest sample of each process.
+            let mut utilization: HashMap<u32, (u64, u32)> = HashMap::new();
+            for sample in device.process_utilization_stats(None).unwrap_or_default() {
+                let latest = utilization.entry(sample.pid).or_insert((0, 0));
+                if sample.timestamp >= latest.0 {
+                    *latest = (sample.timestamp, sample.sm_util);
+                }
+            }
+
+            let running = device
+                .running_graphics_processes()
+                .unwrap_or_default()
+                .into_iter()
+                .chain(device.running_compute_processes().unwrap_or_default());
+            for process in running {
+                // A process using both engines is listed twice.
+                if processes.iter().any(|known: &NvidiaProcess| {
+                    known.pid == process.pid && known.pci_address == pci_address
+                }) {
+                    continue;
+                }
+                processes.push(NvidiaProcess {
+                    pci_address: pci_address.clone(),
+                    pid: process.pid,
+                    utilization: utilization
+                        .get(&process.pid)
+                        .map(|(_, sm_util)| *sm_util as f32),
+                    vram: match process.used_gpu_memory {
+                        UsedGpuMemory::Used(bytes) => Some(bytes),
+                        UsedGpuMemory::Unavailable => None,
+                    },
+                });
+            }
+        }
+
+        Some(processes)
+    })
+}
+
+fn details(device: &Device<'_>) -> GpuDetails {
+    let memory = device.memory_info().ok();
+    GpuDetails {
+        vram_used: memory.as_ref().map(|memory| memory.used),
+        vram_total: memory.as_ref().map(|memory| memory.total),
+        power_watts: device
+            .power_usage()
+            .ok()
+            .map(|milliwatts| milliwatts as f32 / 1000.0),
+        core_clock_mhz: device.clock_info(Clock::Graphics).ok(),
+        memory_clock_mhz: device.clock_info(Clock::Memory).ok(),
+        // Passively cooled cards have no fan to report.
+        fan: device.fan_speed(0).ok().map(GpuFan::Percent),
+    }
+}
+
+/// NVML reports bus IDs with an eight digit domain (`00000000:01:00.0`),
+/// sysfs with four.
+fn sysfs_pci_address(bus_id: &str) -> String {
+    let bus_id = bus_id.trim().to_ascii_lowercase();
+    match bus_id.split_once(':') {
+        Some((domain, rest)) if domain.len() > 4 => {
+            format!("{}:{rest}", &domain[domain.len() - 4..])
+        }
+        _ => bus_id,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
     #[ignore = "requires a working NVIDIA driver and GPU"]
     fn reads_native_nvidia_metrics() {
         assert!(super::hardware_present());
-        assert!(super::utilization().is_some_and(|value| (0.0..=100.0).contains(&value)));
+        assert!(super::devices().is_some_and(|devices| {
+            devices.iter().all(|device| {
+                device
+                    .utilization
+                    .is_some_and(|value| (0.0..=100.0).contains(&value))
+            })
+        }));
         assert!(super::temperature().is_some_and(|value| value > 0.0));
     }
+
+    #[test]
+    fn nvml_bus_ids_match_sysfs_addresses() {
+        assert_eq!(super::sysfs_pci_address("00000000:01:00.0"), "0000:01:00.0");
+        assert_eq!(super::sysfs_pci_address("0000:0A:00.0"), "0000:0a:00.0");
+    }
 }
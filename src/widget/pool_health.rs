@@ -0,0 +1,520 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Health of multi-device filesystems and RAID arrays behind mounts.
+//!
+//! - **Btrfs**: devices, missing devices and error counters per device, and
+//!   the data and metadata chunks used and allocated, from `/sys/fs/btrfs`
+//! - **ZFS**: pool state from `/proc/spl/kstat/zfs/<pool>/state`. The kstats
+//!   do not carry scrub or resilver progress, so it is read from
+//!   `zpool status -p` once a minute, on a thread of its own so a pool that
+//!   hangs the command cannot stall the storage worker
+//! - **Linux md**: array state, working and expected members and resync,
+//!   recovery, reshape or check progress from `/proc/mdstat`
+//!
+//! Mounts are matched to Btrfs filesystems and md arrays through the block
+//! devices below them in sysfs, and to ZFS pools by dataset name.
+
+use crate::storage::DiskInfo;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+const SYS_CLASS_BLOCK: &str = "/sys/class/block";
+const SYS_FS_BTRFS: &str = "/sys/fs/btrfs";
+const PROC_MDSTAT: &str = "/proc/mdstat";
+const ZFS_KSTAT_DIR: &str = "/proc/spl/kstat/zfs";
+const ZPOOL_STATUS_INTERVAL: Duration = Duration::from_secs(60);
+/// ZFS pool state of a pool with every device working.
+const ZFS_ONLINE: &str = "ONLINE";
+
+/// Kind of pool behind a mount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PoolKind {
+    Btrfs,
+    Zfs,
+    Md,
+}
+
+/// Rebuild, resync or scrub running on a pool.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PoolActivity {
+    /// e.g. "resync", "recovery", "check", "scrub" or "resilver"
+    pub operation: String,
+    /// Progress in percent, `None` while delayed or not yet estimated
+    pub percent: Option<f32>,
+}
+
+/// Health of the pool or array holding one mount.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PoolHealth {
+    pub kind: PoolKind,
+    /// md array, ZFS pool, or Btrfs label (UUID without one)
+    pub name: String,
+    /// RAID level or Btrfs data profile, e.g. "raid1"
+    pub level: Option<String>,
+    /// ZFS pool state, e.g. "ONLINE" or "DEGRADED"
+    pub state: Option<String>,
+    /// Working and expected member devices
+    pub devices: Option<(u32, u32)>,
+    /// A member is missing or failed, or the pool is not online
+    pub degraded: bool,
+    pub activity: Option<PoolActivity>,
+    /// Btrfs write, read, flush, corruption and generation errors summed
+    /// over the devices
+    pub errors: Option<u64>,
+    /// Btrfs data and metadata chunks: bytes used and allocated
+    pub data: Option<(u64, u64)>,
+    pub metadata: Option<(u64, u64)>,
+}
+
+impl PoolHealth {
+    fn new(kind: PoolKind, name: String) -> Self {
+        Self {
+            kind,
+            name,
+            level: None,
+            state: None,
+            devices: None,
+            degraded: false,
+            activity: None,
+            errors: None,
+            data: None,
+            metadata: None,
+        }
+    }
+}
+
+/// Scrub or resilver of a ZFS pool and when it was last asked for.
+#[derive(Default)]
+struct ZpoolScan {
+    read_at: Option<Instant>,
+    activity: Option<PoolActivity>,
+    /// The `zpool status` still running, at most one per pool
+    pending: Option<Receiver<Option<PoolActivity>>>,
+}
+
+impl ZpoolScan {
+    /// Take the result of a finished `zpool status` and start the next one
+    /// once it is due. Until a result arrives the last activity is kept.
+    fn refresh(&mut self, pool: &str, now: Instant) {
+        if let Some(pending) = &self.pending {
+            match pending.try_recv() {
+                Ok(activity) => self.activity = activity,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {}
+            }
+            self.pending = None;
+        }
+        if self
+            .read_at
+            .is_some_and(|read_at| now.duration_since(read_at) < ZPOOL_STATUS_INTERVAL)
+        {
+            return;
+        }
+
+        self.read_at = Some(now);
+        let (sender, receiver) = mpsc::channel();
+        let pool = pool.to_string();
+        std::thread::spawn(move || {
+            sender.send(zpool_scan(&pool)).ok();
+        });
+        self.pending = Some(receiver);
+    }
+}
+
+#[derive(Default)]
+pub struct PoolHealthMonitor {
+    zpool_scans: HashMap<String, ZpoolScan>,
+    /// Health of the pool below each mount point
+    pub pools: HashMap<String, PoolHealth>,
+}
+
+impl PoolHealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refresh the pools below `mounts`.
+    pub fn update(&mut self, mounts: &[DiskInfo]) {
+        let block = Path::new(SYS_CLASS_BLOCK);
+        let arrays = fs::read_to_string(PROC_MDSTAT)
+            .map(|mdstat| parse_mdstat(&mdstat))
+            .unwrap_or_default();
+        let btrfs = btrfs_filesystems(Path::new(SYS_FS_BTRFS));
+        let now = Instant::now();
+        let mut scans = HashMap::new();
+        let mut pools = HashMap::new();
+
+        for mount in mounts {
+            let lower = || {
+                mount
+                    .device
+                    .as_deref()
+                    .map(|device| lower_devices(block, device))
+                    .unwrap_or_default()
+            };
+            let pool = match mount.fs_type.as_str() {
+                "btrfs" => {
+                    let lower = lower();
+                    btrfs
+                        .iter()
+                        .find(|(devices, _)| devices.iter().any(|device| lower.contains(device)))
+                        .map(|(_, health)| health.clone())
+                }
+                "zfs" => {
+                    let pool = mount.source.split('/').next().unwrap_or_default();
+                    self.zfs_pool(pool, now, &mut scans)
+                }
+                _ => {
+                    let lower = lower();
+                    arrays
+                        .iter()
+                        .find(|array| lower.contains(&array.name))
+                        .cloned()
+                }
+            };
+            if let Some(pool) = pool {
+                pools.insert(mount.mount_point.clone(), pool);
+            }
+        }
+
+        // Pools that are no longer mounted are read afresh when they return.
+        self.zpool_scans = scans;
+        self.pools = pools;
+    }
+
+    fn zfs_pool(
+        &mut self,
+        pool: &str,
+        now: Instant,
+        scans: &mut HashMap<String, ZpoolScan>,
+    ) -> Option<PoolHealth> {
+        if pool.is_empty() {
+            return None;
+        }
+        let state = fs::read_to_string(Path::new(ZFS_KSTAT_DIR).join(pool).join("state")).ok()?;
+        let state = state.trim().to_string();
+
+        let scan = scans
+            .entry(pool.to_string())
+            .or_insert_with(|| self.zpool_scans.remove(pool).unwrap_or_default());
+        scan.refresh(pool, now);
+
+        let mut health = PoolHealth::new(PoolKind::Zfs, pool.to_string());
+        health.degraded = state != ZFS_ONLINE;
+        health.state = Some(state);
+        health.activity = scan.activity.clone();
+        Some(health)
+    }
+}
+
+/// `device` and every block device below it: the drive of a partition and
+/// the `slaves` of device-mapper and md devices, layer by layer.
+fn lower_devices(root: &Path, device: &str) -> Vec<String> {
+    let mut devices = vec![device.to_string()];
+    let mut index = 0;
+    // Each device adds the layer below it, e.g. LVM on LUKS on md.
+    while index < devices.len() && devices.len() < 64 {
+        let dir = root.join(&devices[index]);
+        let mut below: Vec<String> = fs::read_dir(dir.join("slaves"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        if dir.join("partition").exists()
+            && let Some(parent) = fs::canonicalize(&dir)
+                .ok()
+                .and_then(|path| Some(path.parent()?.file_name()?.to_string_lossy().into_owned()))
+        {
+            below.push(parent);
+        }
+        for device in below {
+            if !devices.contains(&device) {
+                devices.push(device);
+            }
+        }
+        index += 1;
+    }
+    devices
+}
+
+/// Every md array in `/proc/mdstat`.
+fn parse_mdstat(mdstat: &str) -> Vec<PoolHealth> {
+    let mut arrays: Vec<PoolHealth> = Vec::new();
+    for line in mdstat.lines() {
+        if let Some((name, description)) = line.split_once(" : ")
+            && name.starts_with("md")
+        {
+            let mut array = PoolHealth::new(PoolKind::Md, name.trim().to_string());
+            let mut words = description.split_whitespace();
+            let active = words.next() == Some("active");
+            let mut failed = false;
+            for word in words {
+                if word.starts_with("raid") || matches!(word, "linear" | "multipath") {
+                    array.level = Some(word.to_string());
+                } else if word.ends_with("(F)") {
+                    failed = true;
+                }
+            }
+            array.state = Some(if active { "active" } else { "inactive" }.to_string());
+            array.degraded = !active || failed;
+            arrays.push(array);
+            continue;
+        }
+
+        let Some(array) = arrays.last_mut() else {
+            continue;
+        };
+        let line = line.trim();
+        // "976630464 blocks super 1.2 [2/1] [U_]"
+        if let Some(devices) = line
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix('[')?.strip_suffix(']')?.split_once('/'))
+            .find_map(|(expected, working)| Some((working.parse().ok()?, expected.parse().ok()?)))
+        {
+            let (working, expected) = devices;
+            array.devices = Some(devices);
+            array.degraded |= working < expected;
+        }
+        // "[==>.......]  resync = 12.6% (123/976) finish=80.1min speed=100K/sec"
+        // or "resync=DELAYED" while another array on the drives resyncs.
+        let mut words = line.split_whitespace();
+        if let Some(operation) = words
+            .by_ref()
+            .filter_map(|word| word.split('=').next())
+            .find(|word| {
+                matches!(
+                    *word,
+                    "resync" | "recovery" | "reshape" | "check" | "repair"
+                )
+            })
+        {
+            array.activity = Some(PoolActivity {
+                operation: operation.to_string(),
+                percent: words.find_map(|word| word.strip_suffix('%')?.parse().ok()),
+            });
+        }
+    }
+    arrays
+}
+
+/// Every mounted Btrfs filesystem in `root`, with the kernel names of its
+/// devices.
+fn btrfs_filesystems(root: &Path) -> Vec<(Vec<String>, PoolHealth)> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().join("devices").is_dir())
+        .map(|entry| {
+            let dir = entry.path();
+            let devices: Vec<String> = fs::read_dir(dir.join("devices"))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|device| device.file_name().to_string_lossy().into_owned())
+                .collect();
+            (devices, btrfs_health(&dir))
+        })
+        .collect()
+}
+
+/// Health of the Btrfs filesystem in `dir`, a `/sys/fs/btrfs/<uuid>`
+/// directory.
+fn btrfs_health(dir: &Path) -> PoolHealth {
+    let label = read_trimmed(&dir.join("label")).filter(|label| !label.is_empty());
+    let uuid = dir
+        .file_name()
+        .map(|uuid| uuid.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut health = PoolHealth::new(PoolKind::Btrfs, label.unwrap_or(uuid));
+
+    let mut expected = 0;
+    let mut missing = 0;
+    let mut errors = None;
+    for device in fs::read_dir(dir.join("devinfo"))
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        expected += 1;
+        if read_trimmed(&device.path().join("missing")).as_deref() == Some("1") {
+            missing += 1;
+        }
+        // Counters of the device stats item, as `btrfs device stats` shows.
+        if let Ok(stats) = fs::read_to_string(device.path().join("error_stats")) {
+            *errors.get_or_insert(0) += parse_btrfs_error_stats(&stats);
+        }
+    }
+    if expected > 0 {
+        health.devices = Some((expected - missing, expected));
+        health.degraded = missing > 0;
+    }
+    health.errors = errors;
+
+    let allocation = dir.join("allocation");
+    let chunks = |kind: &str| {
+        let used = read_trimmed(&allocation.join(kind).join("bytes_used"))?;
+        let total = read_trimmed(&allocation.join(kind).join("total_bytes"))?;
+        Some((used.parse().ok()?, total.parse().ok()?))
+    };
+    health.data = chunks("data");
+    health.metadata = chunks("metadata");
+    // Each profile in use has a directory, e.g. allocation/data/raid1.
+    health.level = fs::read_dir(allocation.join("data"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .min();
+    health
+}
+
+/// Sum of the counters in a Btrfs `error_stats` file.
+fn parse_btrfs_error_stats(stats: &str) -> u64 {
+    stats
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1)?.parse::<u64>().ok())
+        .sum()
+}
+
+/// The running scrub or resilver of a ZFS pool, from `zpool status -p`.
+fn zpool_scan(pool: &str) -> Option<PoolActivity> {
+    let output = Command::new("zpool")
+        .args(["status", "-p", pool])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_zpool_scan(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The scan in progress in `zpool status` output, e.g.
+/// "scan: scrub in progress since ..." followed by "..., 23.40% done, ...".
+fn parse_zpool_scan(status: &str) -> Option<PoolActivity> {
+    let mut lines = status.lines().map(str::trim);
+    let scan = lines.find_map(|line| line.strip_prefix("scan:"))?;
+    if !scan.contains("in progress") {
+        return None;
+    }
+    let operation = scan.split_whitespace().next()?.to_string();
+    // Continuation lines are indented until the next `key:` line.
+    let percent = lines
+        .take_while(|line| !line.is_empty() && !line.ends_with(':'))
+        .find_map(|line| {
+            let (before, _) = line.split_once("% done")?;
+            before
+                .rsplit(|character: char| character == ',' || character.is_whitespace())
+                .next()?
+                .parse()
+                .ok()
+        });
+    Some(PoolActivity { operation, percent })
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|text| text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PoolActivity, btrfs_health, parse_mdstat, parse_zpool_scan};
+    use std::fs;
+
+    #[test]
+    fn mdstat_reports_members_failures_and_resync_progress() {
+        let mdstat = "Personalities : [raid1] [raid6] [raid5] [raid4]\n\
+            md0 : active raid1 sdb1[1] sda1[0]\n      \
+            976630464 blocks super 1.2 [2/2] [UU]\n      \
+            [==>..................]  resync = 12.6% (123456/976630464) finish=80.1min speed=100000K/sec\n      \
+            bitmap: 0/8 pages [0KB], 65536KB chunk\n\n\
+            md1 : active raid5 sdd1[3](F) sdc1[1] sdb2[0]\n      \
+            1953260544 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/2] [UU_]\n\n\
+            md2 : inactive sde1[0](S)\n      976630464 blocks super 1.2\n\n\
+            unused devices: <none>\n";
+
+        let arrays = parse_mdstat(mdstat);
+
+        assert_eq!(arrays.len(), 3);
+        assert_eq!(arrays[0].name, "md0");
+        assert_eq!(arrays[0].level.as_deref(), Some("raid1"));
+        assert_eq!(arrays[0].devices, Some((2, 2)));
+        assert!(!arrays[0].degraded);
+        assert_eq!(
+            arrays[0].activity,
+            Some(PoolActivity {
+                operation: "resync".to_string(),
+                percent: Some(12.6),
+            })
+        );
+        assert_eq!(arrays[1].devices, Some((2, 3)));
+        assert!(arrays[1].degraded);
+        assert!(arrays[2].degraded);
+    }
+
+    #[test]
+    fn zpool_status_shows_the_scan_in_progress() {
+        let scrubbing = "  pool: tank\n state: ONLINE\n  \
+            scan: scrub in progress since Sun Jul 25 16:05:12 2021\n\t\
+            1352914944 scanned at 1095216660/s, 600000000 issued at 500000000/s, 2748779069440 total\n\t\
+            0 repaired, 23.40% done, 01:05:12 to go\nconfig:\n";
+        assert_eq!(
+            parse_zpool_scan(scrubbing),
+            Some(PoolActivity {
+                operation: "scrub".to_string(),
+                percent: Some(23.4),
+            })
+        );
+
+        let finished = "  pool: tank\n state: ONLINE\n  \
+            scan: scrub repaired 0B in 00:10:21 with 0 errors on Sun Jul 25 16:15:33 2021\n\
+            config:\n";
+        assert_eq!(parse_zpool_scan(finished), None);
+    }
+
+    #[test]
+    fn btrfs_sysfs_gives_profile_allocation_errors_and_missing_devices() {
+        let dir = std::env::temp_dir().join(format!(
+            "cosmic-widget-btrfs-{}/0b8f2c1e-uuid",
+            std::process::id()
+        ));
+        let data = dir.join("allocation/data");
+        fs::create_dir_all(data.join("raid1")).unwrap();
+        fs::create_dir_all(dir.join("allocation/metadata")).unwrap();
+        fs::create_dir_all(dir.join("devinfo/1")).unwrap();
+        fs::create_dir_all(dir.join("devinfo/2")).unwrap();
+        fs::write(dir.join("label"), "pool\n").unwrap();
+        fs::write(data.join("bytes_used"), "600\n").unwrap();
+        fs::write(data.join("total_bytes"), "1000\n").unwrap();
+        fs::write(dir.join("allocation/metadata/bytes_used"), "20\n").unwrap();
+        fs::write(dir.join("allocation/metadata/total_bytes"), "64\n").unwrap();
+        fs::write(
+            dir.join("devinfo/1/error_stats"),
+            "write_errs 0\nread_errs 3\nflush_errs 0\ncorruption_errs 1\ngeneration_errs 0\n",
+        )
+        .unwrap();
+        fs::write(dir.join("devinfo/1/missing"), "0\n").unwrap();
+        fs::write(dir.join("devinfo/2/missing"), "1\n").unwrap();
+
+        let health = btrfs_health(&dir);
+
+        assert_eq!(health.name, "pool");
+        assert_eq!(health.level.as_deref(), Some("raid1"));
+        assert_eq!(health.data, Some((600, 1000)));
+        assert_eq!(health.metadata, Some((20, 64)));
+        assert_eq!(health.errors, Some(4));
+        assert_eq!(health.devices, Some((1, 2)));
+        assert!(health.degraded);
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
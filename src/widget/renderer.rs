@@ -415,6 +415,10 @@ pub fn render_widget(
                         media_button_bounds = buttons;
                     }
                 }
+                WidgetSection::Commands | WidgetSection::Processes | WidgetSection::Custom(_) => {
+                    // Command, process and template sections are only drawn
+                    // by the Iced overlay.
+                }
             }
         }
 
@@ -600,6 +604,7 @@ pub fn render_main_widget(
                         y_pos = new_y;
                     }
                 }
+                WidgetSection::Commands | WidgetSection::Processes | WidgetSection::Custom(_) => {}
             }
         }
     }
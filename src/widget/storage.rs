@@ -9,20 +9,27 @@
 //!
 //! - **Disk usage tracking**: Total, available, and used space percentages
 //! - **Smart filtering**: Only shows meaningful mounts (/, /home, external drives)
+//!   unless the configuration's storage rules say otherwise
+//! - **Btrfs grouping**: Subvolumes of one filesystem are listed as one row
 //! - **Friendly names**: Reads vendor/model names from sysfs instead of device paths
 //! - **Caching**: Shows cached disk list immediately while loading real data
 //! - **Background updates**: Disk model fetching runs in a separate thread
 //!
 //! ## Mount Point Filtering
 //!
-//! To avoid showing system partitions, the module only displays:
+//! Every mount except kernel, container and snap filesystems is a candidate
+//! and kept in `mounts`. Without a matching storage rule in the configuration
+//! the module only displays:
 //! - **Root (`/`)**: Main system partition
 //! - **Home (`/home`)**: User data partition
 //! - **External mounts (`/mnt/*`, `/media/*`)**: USB drives, network shares
 //!
-//! Filtered out:
+//! Hidden by default:
 //! - `/boot`, `/snap`, `/run`, `/sys`, `/proc`, `/dev`, `/tmp`, `/var/snap`
 //!
+//! [`arrange_disks`] applies the rules, groups Btrfs subvolumes, replaces
+//! names with user-defined labels and sorts the rows.
+//!
 //! ## Device Name Resolution
 //!
 //! ```text
@@ -38,12 +45,18 @@
 //! - Background thread: Reads sysfs every 10 seconds to update model names
 //! - Shared state: `disk_models` HashMap protected by Arc<Mutex>
 
+use crate::config::{
+    REMOTE_FS_TYPE, StorageLabel, StorageRule, StorageSort, storage_label, storage_mount_shown,
+    storage_mount_shown_by_default,
+};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::ffi::CString;
 use std::fs;
 use std::mem::MaybeUninit;
 use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use sysinfo::Disks;
 
@@ -55,7 +68,7 @@ use sysinfo::Disks;
 ///
 /// This struct holds all data needed to display a disk in the widget,
 /// including human-readable names and usage statistics.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct DiskInfo {
     /// Display name for the disk (model name, "Home", "System", or mount name)
     pub name: String,
@@ -69,6 +82,28 @@ pub struct DiskInfo {
     pub available_space: u64,
     /// True if showing cached data while loading real data
     pub is_loading: bool,
+    /// Kernel name of the filesystem's block device (e.g., "nvme0n1p2",
+    /// "dm-0"), `None` for remote and cached entries
+    pub device: Option<String>,
+    /// Filesystem type (e.g., "ext4", "btrfs"), [`REMOTE_FS_TYPE`] for
+    /// desktop network mounts
+    pub fs_type: String,
+    /// Mounted device path (e.g., "/dev/nvme0n1p2"), empty for remote mounts
+    pub source: String,
+    /// Further mount points of the same Btrfs filesystem, listed as part of
+    /// this row
+    pub subvolumes: Vec<String>,
+}
+
+impl DiskInfo {
+    /// Device path and kernel device name, which storage rules match.
+    pub fn device_names(&self) -> Vec<&str> {
+        [Some(self.source.as_str()), self.device.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
 }
 
 // ============================================================================
@@ -95,6 +130,9 @@ pub struct StorageMonitor {
     disks: Disks,
     /// List of filtered disk information for display
     pub disk_info: Vec<DiskInfo>,
+    /// Every candidate mount, for filtering by the configuration's storage
+    /// rules
+    pub mounts: Vec<DiskInfo>,
     /// Map of device name → model name (e.g., "nvme0n1" → "Samsung 970 EVO")
     /// Updated by the background thread from sysfs
     disk_models: Arc<Mutex<HashMap<String, String>>>,
@@ -103,8 +141,12 @@ pub struct StorageMonitor {
     remote_disks: Arc<Mutex<Vec<DiskInfo>>>,
     /// Flag to track first update for cache saving
     is_first_update: bool,
+    /// Candidate mounts last written to the cache for the settings app
+    cached_mounts: Vec<super::cache::CachedDiskInfo>,
     /// Counter for periodic full disk list refresh (to detect new mounts)
     update_counter: u32,
+    /// Cleared on drop so the background model-fetching thread exits
+    running: Arc<AtomicBool>,
 }
 
 impl StorageMonitor {
@@ -124,32 +166,45 @@ impl StorageMonitor {
         // Load cached disk info to show immediately
         // This provides instant display while real data loads
         let cache = super::cache::WidgetCache::load();
-        let disk_info: Vec<DiskInfo> = cache
-            .disks
-            .iter()
-            .map(|d| DiskInfo {
-                name: d.name.clone(),
-                mount_point: d.mount_point.clone(),
-                used_percentage: 0.0, // Will be updated on first refresh
-                total_space: 0,
-                available_space: 0,
-                is_loading: true, // Mark as loading until real data arrives
-            })
-            .collect();
+        let cached_disk = |d: &super::cache::CachedDiskInfo| DiskInfo {
+            name: d.name.clone(),
+            mount_point: d.mount_point.clone(),
+            used_percentage: 0.0, // Will be updated on first refresh
+            total_space: 0,
+            available_space: 0,
+            is_loading: true, // Mark as loading until real data arrives
+            device: d.device.clone(),
+            fs_type: d.fs_type.clone(),
+            source: d.source.clone(),
+            subvolumes: Vec::new(),
+        };
+        let disk_info: Vec<DiskInfo> = cache.disks.iter().map(cached_disk).collect();
+        // Caches written before candidate mounts were recorded only hold the
+        // shown disks.
+        let mounts: Vec<DiskInfo> = if cache.storage_mounts.is_empty() {
+            disk_info.clone()
+        } else {
+            cache.storage_mounts.iter().map(cached_disk).collect()
+        };
         let remote_disks = Arc::new(Mutex::new(
-            disk_info
+            mounts
                 .iter()
                 .filter(|disk| disk.mount_point.contains("/gvfs/"))
-                .cloned()
+                .map(|disk| DiskInfo {
+                    fs_type: REMOTE_FS_TYPE.to_string(),
+                    ..disk.clone()
+                })
                 .collect(),
         ));
 
         // Fetch local model names and remote filesystem capacity off the sampler
         // thread. A disconnected network filesystem can otherwise stall all stats.
+        let running = Arc::new(AtomicBool::new(true));
         let disk_models_clone = Arc::clone(&disk_models);
         let remote_disks_clone = Arc::clone(&remote_disks);
+        let running_clone = Arc::clone(&running);
         std::thread::spawn(move || {
-            loop {
+            while running_clone.load(Ordering::Relaxed) {
                 if let Some(models) = Self::fetch_disk_models() {
                     *disk_models_clone.lock().unwrap() = models;
                 }
@@ -162,10 +217,13 @@ impl StorageMonitor {
         Self {
             disks: Disks::new_with_refreshed_list(),
             disk_info,
+            cached_mounts: cache.storage_mounts,
+            mounts,
             disk_models,
             remote_disks,
             is_first_update: true,
             update_counter: 0,
+            running,
         }
     }
 
@@ -183,7 +241,7 @@ impl StorageMonitor {
     /// /sys/class/block/nvme0n1/device/model: "Samsung SSD 970 EVO Plus"
     /// Result: {"nvme0n1" => "Samsung SSD 970 EVO Plus"}
     /// ```
-    fn fetch_disk_models() -> Option<HashMap<String, String>> {
+    pub(crate) fn fetch_disk_models() -> Option<HashMap<String, String>> {
         Self::fetch_disk_models_from(Path::new("/sys/class/block"))
     }
 
@@ -253,6 +311,10 @@ impl StorageMonitor {
             total_space,
             available_space,
             is_loading: false,
+            device: None,
+            fs_type: REMOTE_FS_TYPE.to_string(),
+            source: String::new(),
+            subvolumes: Vec::new(),
         })
     }
 
@@ -264,12 +326,13 @@ impl StorageMonitor {
     /// # Processing Steps
     ///
     /// 1. Refresh sysinfo disk data (NOT disk list to avoid FD leaks)
-    /// 2. Filter to only meaningful mount points
+    /// 2. Skip kernel, container and snap filesystems
     /// 3. Calculate usage percentages
     /// 4. Map device names to friendly model names
-    /// 5. Update cache on first successful refresh
+    /// 5. Keep the mounts shown by default in `disk_info`
+    /// 6. Update cache on first successful refresh and when mounts change
     ///
-    /// # Mount Point Filtering Rules
+    /// # Default Mount Point Filtering Rules
     ///
     /// **Included:**
     /// - `/` (root filesystem)
@@ -298,39 +361,21 @@ impl StorageMonitor {
             self.disks.refresh();
         }
 
-        self.disk_info.clear();
+        self.mounts.clear();
 
         // Get disk models from cache (updated by background thread)
         let disk_models = self.disk_models.lock().unwrap().clone();
 
         for disk in &self.disks {
             let mount_point = disk.mount_point().to_string_lossy().to_string();
+            let fs_type = disk.file_system().to_string_lossy().to_string();
 
             // ================================================================
             // Mount Point Filtering
             // ================================================================
-            // Skip non-meaningful mount points
-            // Only show root, /home, and top-level /mnt or /media mounts
-            let is_root = mount_point == "/";
-            let is_home = mount_point == "/home";
-            let is_top_level_mount =
-                mount_point.starts_with("/mnt/") || mount_point.starts_with("/media/");
-
-            // Skip boot partitions, snap mounts, and other system partitions
-            if mount_point.starts_with("/boot")
-                || mount_point.starts_with("/snap")
-                || mount_point.starts_with("/run")
-                || mount_point.starts_with("/sys")
-                || mount_point.starts_with("/proc")
-                || mount_point.starts_with("/dev")
-                || mount_point.starts_with("/tmp")
-                || mount_point.starts_with("/var/snap")
-            {
-                continue;
-            }
-
-            // Only include root, /home, or external mounts
-            if !is_root && !is_home && !is_top_level_mount {
+            // Skip mounts that never hold user-visible storage. Which of the
+            // rest are shown is decided by the storage rules.
+            if !is_storage_candidate(&mount_point, &fs_type) {
                 continue;
             }
 
@@ -394,36 +439,168 @@ impl StorageMonitor {
                     })
             };
 
-            self.disk_info.push(DiskInfo {
+            // Device-mapper paths such as /dev/mapper/root resolve to dm-N.
+            let device = device_name
+                .starts_with("/dev/")
+                .then(|| fs::canonicalize(&device_name).ok())
+                .flatten()
+                .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()));
+
+            self.mounts.push(DiskInfo {
                 name: display_name,
                 mount_point,
                 used_percentage,
                 total_space: total,
                 available_space: available,
                 is_loading: false,
+                device,
+                fs_type,
+                source: device_name,
+                subvolumes: Vec::new(),
             });
         }
 
         for remote_disk in self.remote_disks.lock().unwrap().iter() {
             if !self
-                .disk_info
+                .mounts
                 .iter()
                 .any(|disk| disk.mount_point == remote_disk.mount_point)
             {
-                self.disk_info.push(remote_disk.clone());
+                self.mounts.push(remote_disk.clone());
             }
         }
 
+        self.disk_info = self
+            .mounts
+            .iter()
+            .filter(|disk| storage_mount_shown_by_default(&disk.mount_point, &disk.fs_type))
+            .cloned()
+            .collect();
+
         // Update cache after first successful update
         // This saves disk names for instant display on next startup
         if self.is_first_update && !self.disk_info.is_empty() {
-            let mut cache = super::cache::WidgetCache::load();
-            cache.update_disks(&self.disk_info);
+            super::cache::WidgetCache::update(|cache| cache.update_disks(&self.disk_info));
             self.is_first_update = false;
         }
+
+        // The settings app lists every candidate so hidden mounts can be
+        // turned on.
+        let mounts = super::cache::cached_disks(&self.mounts);
+        if !self.mounts.is_empty() && mounts != self.cached_mounts {
+            super::cache::WidgetCache::update(|cache| cache.update_storage_mounts(mounts.clone()));
+            self.cached_mounts = mounts;
+        }
     }
 }
 
+impl Drop for StorageMonitor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Mounts shown as storage rows, in the configured order: those the
+/// storage rules show, with the subvolumes of each Btrfs filesystem grouped
+/// under its shortest mount point and user-defined labels as names.
+pub fn arrange_disks(
+    mounts: &[DiskInfo],
+    rules: &[StorageRule],
+    labels: &[StorageLabel],
+    sort: StorageSort,
+) -> Vec<DiskInfo> {
+    let mut disks: Vec<DiskInfo> = Vec::new();
+    for mount in mounts {
+        if !storage_mount_shown(
+            rules,
+            &mount.mount_point,
+            &mount.fs_type,
+            &mount.device_names(),
+        ) {
+            continue;
+        }
+
+        let pool = (mount.fs_type == "btrfs" && !mount.source.is_empty())
+            .then(|| {
+                disks
+                    .iter()
+                    .position(|disk| disk.fs_type == "btrfs" && disk.source == mount.source)
+            })
+            .flatten();
+        let Some(pool) = pool else {
+            disks.push(mount.clone());
+            continue;
+        };
+
+        let group = &mut disks[pool];
+        if mount.mount_point.len() < group.mount_point.len() {
+            let mut subvolumes = std::mem::take(&mut group.subvolumes);
+            subvolumes.push(group.mount_point.clone());
+            *group = DiskInfo {
+                subvolumes,
+                ..mount.clone()
+            };
+        } else {
+            group.subvolumes.push(mount.mount_point.clone());
+        }
+    }
+
+    for disk in &mut disks {
+        disk.subvolumes.sort();
+        let label = std::iter::once(&disk.mount_point)
+            .chain(&disk.subvolumes)
+            .find_map(|mount_point| storage_label(labels, mount_point));
+        if let Some(label) = label {
+            disk.name = label.to_string();
+        }
+    }
+
+    match sort {
+        StorageSort::Discovered => {}
+        StorageSort::Name => disks.sort_by_key(|disk| disk.name.to_lowercase()),
+        StorageSort::MountPoint => disks.sort_by(|a, b| a.mount_point.cmp(&b.mount_point)),
+        StorageSort::Usage => {
+            disks.sort_by(|a, b| b.used_percentage.total_cmp(&a.used_percentage));
+        }
+        StorageSort::Size => disks.sort_by_key(|disk| std::cmp::Reverse(disk.total_space)),
+    }
+    disks
+}
+
+/// Whether a mount can hold user-visible storage, as opposed to kernel,
+/// container and snap filesystems.
+fn is_storage_candidate(mount_point: &str, fs_type: &str) -> bool {
+    const VIRTUAL_FS_TYPES: [&str; 8] = [
+        "autofs",
+        "devtmpfs",
+        "fuse.gvfsd-fuse",
+        "fuse.portal",
+        "overlay",
+        "ramfs",
+        "squashfs",
+        "tmpfs",
+    ];
+    const SYSTEM_DIRS: [&str; 6] = [
+        "/dev",
+        "/proc",
+        "/snap",
+        "/sys",
+        "/var/lib/docker",
+        "/var/snap",
+    ];
+
+    let under = |dir: &str| {
+        mount_point
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    };
+    // Removable drives are mounted below /run/media by udisks.
+    if under("/run") && !under("/run/media") {
+        return false;
+    }
+    !VIRTUAL_FS_TYPES.contains(&fs_type) && !SYSTEM_DIRS.iter().any(|dir| under(dir))
+}
+
 fn read_sysfs_text(path: &Path) -> Option<String> {
     let bytes = fs::read(path).ok()?;
     let normalized = String::from_utf8_lossy(&bytes)
@@ -514,9 +691,87 @@ fn filesystem_space(path: &Path) -> Option<(u64, u64)> {
 
 #[cfg(test)]
 mod tests {
-    use super::{StorageMonitor, is_gvfs_remote_mount, remote_display_name};
+    use super::{
+        DiskInfo, StorageMonitor, arrange_disks, is_gvfs_remote_mount, is_storage_candidate,
+        remote_display_name,
+    };
+    use crate::config::{StorageLabel, StorageRule, StorageRuleField, StorageSort};
     use std::fs;
 
+    fn mount(mount_point: &str, fs_type: &str, source: &str, used_percentage: f32) -> DiskInfo {
+        DiskInfo {
+            name: mount_point.to_string(),
+            mount_point: mount_point.to_string(),
+            used_percentage,
+            total_space: 1_000,
+            available_space: 500,
+            is_loading: false,
+            device: None,
+            fs_type: fs_type.to_string(),
+            source: source.to_string(),
+            subvolumes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn candidates_leave_out_kernel_snap_and_container_mounts() {
+        assert!(is_storage_candidate("/boot/efi", "vfat"));
+        assert!(is_storage_candidate("/run/media/user/USB", "exfat"));
+        assert!(is_storage_candidate("/develop", "ext4"));
+        assert!(!is_storage_candidate("/run/user/1000/doc", "fuse.portal"));
+        assert!(!is_storage_candidate("/run/credentials", "ext4"));
+        assert!(!is_storage_candidate("/snap/core22/1380", "squashfs"));
+        assert!(!is_storage_candidate("/tmp", "tmpfs"));
+    }
+
+    #[test]
+    fn btrfs_subvolumes_are_one_row_under_the_shortest_mount_point() {
+        let mounts = [
+            mount("/home", "btrfs", "/dev/nvme0n1p2", 40.0),
+            mount("/", "btrfs", "/dev/nvme0n1p2", 40.0),
+            mount("/mnt/games", "btrfs", "/dev/nvme0n1p2", 40.0),
+            mount("/mnt/usb", "exfat", "/dev/sda1", 90.0),
+            mount("/boot", "vfat", "/dev/nvme0n1p1", 10.0),
+        ];
+
+        let disks = arrange_disks(&mounts, &[], &[], StorageSort::Discovered);
+
+        assert_eq!(disks.len(), 2);
+        assert_eq!(disks[0].mount_point, "/");
+        assert_eq!(disks[0].subvolumes, ["/home", "/mnt/games"]);
+        assert_eq!(disks[1].mount_point, "/mnt/usb");
+    }
+
+    #[test]
+    fn rules_labels_and_sort_order_shape_the_rows() {
+        let mounts = [
+            mount("/", "ext4", "/dev/sda2", 40.0),
+            mount("/mnt/usb", "exfat", "/dev/sdb1", 90.0),
+            mount("/boot", "vfat", "/dev/sda1", 10.0),
+        ];
+        let rules = [
+            StorageRule {
+                field: StorageRuleField::Device,
+                value: "/dev/sda1".to_string(),
+                shown: true,
+            },
+            StorageRule {
+                field: StorageRuleField::FilesystemType,
+                value: "exfat".to_string(),
+                shown: false,
+            },
+        ];
+        let labels = [StorageLabel {
+            mount_point: "/".to_string(),
+            name: "Workstation".to_string(),
+        }];
+
+        let disks = arrange_disks(&mounts, &rules, &labels, StorageSort::Usage);
+
+        let rows: Vec<_> = disks.iter().map(|disk| disk.name.as_str()).collect();
+        assert_eq!(rows, ["Workstation", "/boot"]);
+    }
+
     #[test]
     fn recognizes_supported_gvfs_network_mounts() {
         assert!(is_gvfs_remote_mount("sftp:host=192.0.2.9"));
@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Persistent daily network traffic per interface.
+//!
+//! The ledger adds the growth of each interface's byte counters to the
+//! current day and keeps the last counters it saw, so traffic between two
+//! runs of the overlay is still counted. A counter below the last one was
+//! reset, by a reboot or by the interface being recreated, and counts from
+//! zero. The kernel's boot ID tells reboots apart from an overlay restart.
+//!
+//! Days are kept for a little over a year in `traffic.json` in the cache
+//! directory. Months and billing cycles are sums of days.
+
+use chrono::{Datelike, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const RETAINED_DAYS: u64 = 400;
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+/// Bytes received and transmitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficBytes {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+impl TrafficBytes {
+    pub fn total(self) -> u64 {
+        self.rx.saturating_add(self.tx)
+    }
+
+    fn add(&mut self, other: TrafficBytes) {
+        self.rx = self.rx.saturating_add(other.rx);
+        self.tx = self.tx.saturating_add(other.tx);
+    }
+}
+
+/// Traffic of one interface over the periods shown by the overlay.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct InterfaceTraffic {
+    pub name: String,
+    pub today: TrafficBytes,
+    pub month: TrafficBytes,
+    pub cycle: TrafficBytes,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TrafficSummary {
+    /// Interfaces with traffic this month or billing cycle, by name
+    pub interfaces: Vec<InterfaceTraffic>,
+    /// First day of the current billing cycle as `YYYY-MM-DD`
+    pub cycle_start: String,
+}
+
+/// Traffic per day and interface, and the counters last seen.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct LedgerFile {
+    boot_id: String,
+    counters: HashMap<String, (u64, u64)>,
+    /// Days formatted as `YYYY-MM-DD`
+    days: BTreeMap<String, BTreeMap<String, TrafficBytes>>,
+}
+
+pub struct TrafficLedger {
+    file: LedgerFile,
+    dirty: bool,
+    last_saved: Instant,
+}
+
+impl TrafficLedger {
+    /// Load the ledger saved by a previous run. A file that does not parse
+    /// is moved aside rather than replaced, so its history can be recovered.
+    pub fn load() -> Self {
+        let path = ledger_path();
+        let file = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|error| {
+                log::warn!("Failed to parse network traffic: {error}");
+                if super::cache::persistence_enabled() {
+                    let aside = path.with_extension("json.corrupt");
+                    if let Err(error) = fs::rename(&path, &aside) {
+                        log::warn!("Failed to move aside {}: {error}", path.display());
+                    }
+                }
+                LedgerFile::default()
+            }),
+            Err(_) => LedgerFile::default(),
+        };
+        Self {
+            file,
+            dirty: false,
+            last_saved: Instant::now(),
+        }
+    }
+
+    /// Count the growth of `counters` towards `today`, then save if the last
+    /// save is a while ago.
+    pub fn record(&mut self, today: NaiveDate, counters: &HashMap<String, (u64, u64)>) {
+        let boot_id = fs::read_to_string(BOOT_ID_PATH)
+            .map(|id| id.trim().to_string())
+            .unwrap_or_default();
+        if self.file.record(today, &boot_id, counters) {
+            self.dirty = true;
+        }
+        if self.dirty && self.last_saved.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    /// Today's, this month's and this billing cycle's traffic of each
+    /// interface. Billing cycles start on `billing_day` of each month.
+    pub fn summary(&self, today: NaiveDate, billing_day: u32) -> TrafficSummary {
+        self.file.summary(today, billing_day)
+    }
+
+    fn save(&mut self) {
+        if !super::cache::persistence_enabled() {
+            self.dirty = false;
+            return;
+        }
+        match serde_json::to_string(&self.file) {
+            Ok(json) => {
+                if let Err(error) = super::cache::write_atomically(&ledger_path(), &json) {
+                    log::warn!("Failed to save network traffic: {error}");
+                }
+            }
+            Err(error) => log::warn!("Failed to serialize network traffic: {error}"),
+        }
+        self.dirty = false;
+        self.last_saved = Instant::now();
+    }
+}
+
+impl Drop for TrafficLedger {
+    fn drop(&mut self) {
+        if self.dirty {
+            self.save();
+        }
+    }
+}
+
+impl LedgerFile {
+    /// Returns whether any traffic or counter changed.
+    fn record(
+        &mut self,
+        today: NaiveDate,
+        boot_id: &str,
+        counters: &HashMap<String, (u64, u64)>,
+    ) -> bool {
+        // After a reboot every counter started again from zero.
+        if self.boot_id != boot_id {
+            self.boot_id = boot_id.to_string();
+            self.counters.clear();
+        }
+
+        let day = today.format(DATE_FORMAT).to_string();
+        let mut changed = false;
+        for (name, &(rx, tx)) in counters {
+            let (previous_rx, previous_tx) = self.counters.get(name).copied().unwrap_or((0, 0));
+            let growth = TrafficBytes {
+                rx: counter_growth(previous_rx, rx),
+                tx: counter_growth(previous_tx, tx),
+            };
+            if growth.total() > 0 {
+                self.days
+                    .entry(day.clone())
+                    .or_default()
+                    .entry(name.clone())
+                    .or_default()
+                    .add(growth);
+                changed = true;
+            }
+            changed |= self.counters.insert(name.clone(), (rx, tx)) != Some((rx, tx));
+        }
+
+        if let Some(oldest) = today.checked_sub_days(chrono::Days::new(RETAINED_DAYS)) {
+            let oldest = oldest.format(DATE_FORMAT).to_string();
+            self.days.retain(|day, _| *day >= oldest);
+        }
+        changed
+    }
+
+    fn summary(&self, today: NaiveDate, billing_day: u32) -> TrafficSummary {
+        let month_start = today.with_day(1).unwrap_or(today);
+        let cycle_start = cycle_start(today, billing_day);
+        let from = month_start.min(cycle_start).format(DATE_FORMAT).to_string();
+
+        let mut interfaces: BTreeMap<&str, InterfaceTraffic> = BTreeMap::new();
+        for (day, traffic) in self.days.range(from..) {
+            let Ok(date) = NaiveDate::parse_from_str(day, DATE_FORMAT) else {
+                continue;
+            };
+            for (name, bytes) in traffic {
+                let interface = interfaces.entry(name).or_insert_with(|| InterfaceTraffic {
+                    name: name.clone(),
+                    ..InterfaceTraffic::default()
+                });
+                if date == today {
+                    interface.today.add(*bytes);
+                }
+                if date >= month_start {
+                    interface.month.add(*bytes);
+                }
+                if date >= cycle_start {
+                    interface.cycle.add(*bytes);
+                }
+            }
+        }
+
+        TrafficSummary {
+            interfaces: interfaces.into_values().collect(),
+            cycle_start: cycle_start.format(DATE_FORMAT).to_string(),
+        }
+    }
+}
+
+/// Bytes added to a cumulative counter; a smaller counter was reset.
+fn counter_growth(previous: u64, current: u64) -> u64 {
+    current.checked_sub(previous).unwrap_or(current)
+}
+
+/// The last `billing_day` of a month on or before `today`. Days past the
+/// end of a short month fall on its last day.
+fn cycle_start(today: NaiveDate, billing_day: u32) -> NaiveDate {
+    let in_month = |month_start: NaiveDate| {
+        (0..4)
+            .find_map(|shorter| month_start.with_day(billing_day.max(1).saturating_sub(shorter)))
+            .unwrap_or(month_start)
+    };
+    let this_month = today.with_day(1).unwrap_or(today);
+    let start = in_month(this_month);
+    if start <= today {
+        start
+    } else {
+        this_month
+            .checked_sub_months(Months::new(1))
+            .map_or(this_month, in_month)
+    }
+}
+
+fn ledger_path() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("/tmp"));
+    path.push("cosmic-widget-applet");
+    fs::create_dir_all(&path).ok();
+    path.push("traffic.json");
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LedgerFile, TrafficBytes, cycle_start};
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn counters(rx: u64, tx: u64) -> HashMap<String, (u64, u64)> {
+        HashMap::from([("enp5s0".to_string(), (rx, tx))])
+    }
+
+    #[test]
+    fn traffic_survives_restarts_counter_resets_and_reboots() {
+        let mut ledger = LedgerFile::default();
+        let day = date(2026, 3, 14);
+
+        // Counters since boot count on the first run of a boot.
+        ledger.record(day, "boot-a", &counters(1_000, 100));
+        // An overlay restart continues from the saved counters.
+        ledger.record(day, "boot-a", &counters(1_500, 150));
+        // A recreated interface starts again from zero.
+        ledger.record(day, "boot-a", &counters(200, 20));
+        // So does every interface after a reboot.
+        ledger.record(date(2026, 3, 15), "boot-b", &counters(300, 30));
+
+        let summary = ledger.summary(date(2026, 3, 15), 1);
+        let traffic = &summary.interfaces[0];
+        assert_eq!(traffic.today, TrafficBytes { rx: 300, tx: 30 });
+        assert_eq!(traffic.month, TrafficBytes { rx: 2_000, tx: 200 });
+    }
+
+    #[test]
+    fn periods_split_at_month_and_billing_day() {
+        let mut ledger = LedgerFile::default();
+        ledger.record(date(2026, 2, 27), "boot", &counters(100, 0));
+        ledger.record(date(2026, 3, 2), "boot", &counters(300, 0));
+        ledger.record(date(2026, 3, 20), "boot", &counters(700, 0));
+
+        let summary = ledger.summary(date(2026, 3, 20), 25);
+        let traffic = &summary.interfaces[0];
+        assert_eq!(summary.cycle_start, "2026-02-25");
+        assert_eq!(traffic.today.rx, 400);
+        assert_eq!(traffic.month.rx, 600);
+        assert_eq!(traffic.cycle.rx, 700);
+    }
+
+    #[test]
+    fn billing_cycles_start_on_the_billing_day_or_the_end_of_short_months() {
+        assert_eq!(cycle_start(date(2026, 3, 20), 1), date(2026, 3, 1));
+        assert_eq!(cycle_start(date(2026, 3, 20), 20), date(2026, 3, 20));
+        assert_eq!(cycle_start(date(2026, 3, 19), 20), date(2026, 2, 20));
+        assert_eq!(cycle_start(date(2026, 3, 5), 31), date(2026, 2, 28));
+        assert_eq!(cycle_start(date(2026, 1, 5), 15), date(2025, 12, 15));
+    }
+}
@@ -3,19 +3,27 @@
 //! CPU, Memory, and GPU Utilization Monitoring
 //!
 //! This module provides real-time system resource utilization monitoring for:
-//! - **CPU**: Overall CPU usage percentage via sysinfo
-//! - **Memory**: Used/total RAM with percentage
+//! - **CPU**: Overall and per-core usage, core frequencies, and the load
+//!   average via sysinfo, with maximum frequencies from cpufreq sysfs
+//! - **Memory**: Used/total RAM with percentage, plus swap, zram, page cache
+//!   and PSI pressure from `/proc` and sysfs
 //! - **GPU**: Utilization for NVIDIA, AMD, and Intel GPUs
+//! - **Processes**: The processes using the most CPU and resident memory,
+//!   while enabled with `set_processes_enabled(true)`
 //!
 //! # GPU Monitoring
 //!
 //! GPU utilization is monitored in a background thread to avoid blocking the UI.
-//! The detection order is:
+//! Every GPU is read on its own (see [`super::gpu`]); the combined value comes
+//! from the first vendor found in this order:
 //!
 //! 1. **NVIDIA**: Queries the NVIDIA Management Library through `nvml-wrapper`
 //! 2. **AMD**: Reads `/sys/class/drm/card*/device/gpu_busy_percent`
 //! 3. **Intel**: Reads current and maximum GPU frequencies from sysfs
 //!
+//! While enabled with `set_gpu_processes_enabled(true)`, the same thread also
+//! samples the busiest GPU processes (see [`super::gpu_processes`]).
+//!
 //! # Usage
 //!
 //! ```rust
@@ -31,27 +39,140 @@
 //!
 //! # Thread Safety
 //!
-//! GPU usage is stored in an `Arc<Mutex<f32>>` and updated by a background thread.
-//! The `get_gpu_usage()` method safely reads the current value.
-
+//! GPU usage is stored in an `Arc<Mutex<f32>>` and the per-device readings in an
+//! `Arc<Mutex<Vec<GpuDevice>>>`, both updated by a background thread. The
+//! `get_gpu_usage()`, `gpu_devices()` and `gpu_processes()` methods safely read
+//! the current values.
+//! The thread stops when GPU monitoring is disabled or the monitor is dropped.
+
+use super::gpu::{GpuDevice, GpuVendor};
+use super::gpu_processes::{GpuProcess, GpuProcessSampler};
+use crate::config::MAX_TOP_PROCESSES;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use sysinfo::System;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+/// Lists of the logical CPUs on performance and efficiency cores. Only hybrid
+/// Intel processors expose both.
+const PERFORMANCE_CPUS_PATH: &str = "/sys/devices/cpu_core/cpus";
+const EFFICIENCY_CPUS_PATH: &str = "/sys/devices/cpu_atom/cpus";
+
+const MEMINFO_PATH: &str = "/proc/meminfo";
+const MEMORY_PRESSURE_PATH: &str = "/proc/pressure/memory";
+
+// ============================================================================
+// Per-Core Readings
+// ============================================================================
+
+/// Core type of a logical CPU on a hybrid processor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CoreKind {
+    Performance,
+    Efficiency,
+}
+
+/// Usage and frequency of one logical CPU.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CpuCore {
+    /// Logical CPU number, as in `/sys/devices/system/cpu/cpuN`
+    pub id: usize,
+    /// Usage percentage (0-100)
+    pub usage: f32,
+    /// Current frequency in MHz, 0 if unknown
+    pub frequency_mhz: u64,
+    /// Maximum frequency in MHz, 0 if unknown
+    pub max_frequency_mhz: u64,
+    /// Core type, `None` on processors with only one kind of core
+    pub kind: Option<CoreKind>,
+}
+
+/// System load average over 1, 5 and 15 minutes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+// ============================================================================
+// Memory Details
+// ============================================================================
+
+/// Original and compressed size of the data stored in zram devices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ZramUsage {
+    pub original: u64,
+    pub compressed: u64,
+}
+
+/// PSI averages over 10, 60 and 300 seconds, in percent of wall time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PressureAverages {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+}
+
+/// Memory pressure from `/proc/pressure/memory`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct MemoryPressure {
+    /// Share of time at least one task stalled on memory
+    pub some: PressureAverages,
+    /// Share of time all non-idle tasks stalled on memory
+    pub full: PressureAverages,
+}
+
+/// Swap, zram, cache and pressure readings beyond plain RAM usage.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MemoryDetails {
+    /// Swap space in bytes, including zram swap devices
+    pub swap_total: u64,
+    pub swap_used: u64,
+    /// `None` if no zram device holds data
+    pub zram: Option<ZramUsage>,
+    /// Page cache in bytes
+    pub cached: u64,
+    /// Block device buffers in bytes
+    pub buffers: u64,
+    /// `None` on kernels without PSI
+    pub pressure: Option<MemoryPressure>,
+}
 
 // ============================================================================
-// GPU Vendor Detection
+// Top Processes
 // ============================================================================
 
-/// Supported GPU vendors for utilization monitoring.
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum GpuVendor {
-    /// NVIDIA GPU (uses NVML)
-    Nvidia,
-    /// AMD GPU (uses sysfs)
-    Amd,
-    /// Intel integrated/discrete GPU (uses sysfs)
-    Intel,
-    /// No supported GPU detected
-    None,
+/// CPU and memory use of a process, or of a process tree counted as one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProcessUsage {
+    /// PID of the process, or of the topmost process of a tree
+    pub pid: u32,
+    pub name: String,
+    /// CPU usage in percent of one core, so above 100 for processes busy on
+    /// several cores
+    pub cpu_usage: f32,
+    /// Resident memory in bytes
+    pub memory: u64,
+    /// Processes counted, more than one for a grouped tree
+    pub count: u32,
+}
+
+/// The processes using the most CPU and the most resident memory, busiest
+/// first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TopProcesses {
+    pub by_cpu: Vec<ProcessUsage>,
+    pub by_memory: Vec<ProcessUsage>,
+}
+
+/// A process with its parent and executable, for grouping trees.
+struct ProcessEntry {
+    parent: Option<u32>,
+    exe: Option<PathBuf>,
+    usage: ProcessUsage,
 }
 
 // ============================================================================
@@ -69,6 +190,15 @@ pub struct UtilizationMonitor {
     /// Current CPU usage percentage (0-100)
     pub cpu_usage: f32,
 
+    /// Usage and frequency of each logical CPU
+    pub cpu_cores: Vec<CpuCore>,
+
+    /// 1, 5 and 15 minute load average
+    pub load_average: LoadAverage,
+
+    /// Number, maximum frequency and core type of each logical CPU, read once
+    core_info: Vec<(usize, u64, Option<CoreKind>)>,
+
     /// Current memory usage percentage (0-100)
     pub memory_usage: f32,
 
@@ -78,11 +208,35 @@ pub struct UtilizationMonitor {
     /// Used system memory in bytes
     pub memory_used: u64,
 
+    /// Swap, zram, cache and pressure readings
+    pub memory_details: MemoryDetails,
+
+    /// Busiest single processes, empty while process sampling is disabled
+    pub top_processes: TopProcesses,
+
+    /// Busiest process trees, empty while process sampling is disabled
+    pub top_process_trees: TopProcesses,
+
+    /// Whether `update()` refreshes the process lists
+    processes_enabled: bool,
+
     /// GPU usage percentage, updated by background thread
     pub gpu_usage: Arc<Mutex<f32>>,
 
-    /// Detected GPU vendor (determines monitoring method)
-    gpu_vendor: GpuVendor,
+    /// Readings of each GPU, updated by background thread
+    gpus: Arc<Mutex<Vec<GpuDevice>>>,
+
+    /// Busiest GPU processes, updated by background thread while enabled
+    gpu_processes: Arc<Mutex<Vec<GpuProcess>>>,
+
+    /// Whether the background thread samples GPU processes
+    gpu_processes_enabled: Arc<AtomicBool>,
+
+    /// Vendor whose GPUs make up `gpu_usage`, `None` if no GPU was found
+    gpu_vendor: Option<GpuVendor>,
+
+    /// Run flag of the active GPU polling thread, if one is running
+    gpu_running: Option<Arc<AtomicBool>>,
 }
 
 // ============================================================================
@@ -95,43 +249,106 @@ impl UtilizationMonitor {
     /// Automatically detects GPU vendor and spawns a background thread
     /// for GPU monitoring if a supported GPU is found.
     pub fn new() -> Self {
-        // Shared GPU usage value for thread-safe access
-        let gpu_usage = Arc::new(Mutex::new(0.0f32));
+        Self::new_with_gpu(true)
+    }
+
+    /// Create a monitor with GPU polling explicitly enabled or disabled.
+    ///
+    /// When disabled, no GPU vendor probe or background thread is started
+    /// until `set_gpu_enabled(true)` is called.
+    pub fn new_with_gpu(enable_gpu: bool) -> Self {
+        let mut monitor = Self {
+            sys: System::new_all(),
+            cpu_usage: 0.0,
+            cpu_cores: Vec::new(),
+            load_average: LoadAverage::default(),
+            core_info: Vec::new(),
+            memory_usage: 0.0,
+            memory_total: 0,
+            memory_used: 0,
+            memory_details: MemoryDetails::default(),
+            top_processes: TopProcesses::default(),
+            top_process_trees: TopProcesses::default(),
+            processes_enabled: false,
+            // Shared GPU usage value for thread-safe access
+            gpu_usage: Arc::new(Mutex::new(0.0f32)),
+            gpus: Arc::new(Mutex::new(Vec::new())),
+            gpu_processes: Arc::new(Mutex::new(Vec::new())),
+            gpu_processes_enabled: Arc::new(AtomicBool::new(false)),
+            gpu_vendor: None,
+            gpu_running: None,
+        };
+        monitor.set_gpu_enabled(enable_gpu);
+        monitor
+    }
+
+    /// Start or stop the background GPU polling thread.
+    ///
+    /// Stopping clears the published GPU usage so a later restart does not
+    /// briefly show a stale reading.
+    pub fn set_gpu_enabled(&mut self, enabled: bool) {
+        if enabled == self.gpu_running.is_some() {
+            return;
+        }
+
+        if let Some(running) = self.gpu_running.take() {
+            running.store(false, Ordering::Relaxed);
+            *self.gpu_usage.lock().unwrap() = 0.0;
+            self.gpus.lock().unwrap().clear();
+            self.gpu_processes.lock().unwrap().clear();
+            return;
+        }
 
         // Detect which GPU monitoring method to use
-        let gpu_vendor = Self::detect_gpu_vendor();
+        self.gpu_vendor = Self::detect_gpu_vendor();
+        let gpu_vendor = self.gpu_vendor;
+        let running = Arc::new(AtomicBool::new(true));
 
         // Spawn background thread for GPU monitoring (if GPU detected)
-        if gpu_vendor != GpuVendor::None {
-            let gpu_usage_clone = Arc::clone(&gpu_usage);
+        if let Some(gpu_vendor) = gpu_vendor {
+            let gpu_usage_clone = Arc::clone(&self.gpu_usage);
+            let gpus_clone = Arc::clone(&self.gpus);
+            let processes_clone = Arc::clone(&self.gpu_processes);
+            let processes_enabled = Arc::clone(&self.gpu_processes_enabled);
+            let running_clone = Arc::clone(&running);
             std::thread::spawn(move || {
+                let mut cached_identities = Vec::new();
+                let mut process_sampler = GpuProcessSampler::new();
                 loop {
                     // Poll every second for smooth updates
                     std::thread::sleep(std::time::Duration::from_secs(1));
+                    if !running_clone.load(Ordering::Relaxed) {
+                        break;
+                    }
 
-                    let usage = match gpu_vendor {
-                        GpuVendor::Nvidia => super::nvidia::utilization(),
-                        GpuVendor::Amd => Self::fetch_amd_gpu_usage(),
-                        GpuVendor::Intel => Self::fetch_intel_gpu_usage(),
-                        GpuVendor::None => None,
-                    };
-
-                    if let Some(usage) = usage {
+                    let devices = super::gpu::read_devices();
+                    if let Some(usage) = super::gpu::combined_usage(gpu_vendor, &devices) {
                         *gpu_usage_clone.lock().unwrap() = usage;
                     }
+                    // Let the settings app list the GPUs to choose from.
+                    let identities: Vec<_> = devices
+                        .iter()
+                        .map(|device| (device.id.clone(), device.name.clone()))
+                        .collect();
+                    if identities != cached_identities {
+                        super::cache::WidgetCache::update(|cache| cache.update_gpus(&devices));
+                        cached_identities = identities;
+                    }
+                    *gpus_clone.lock().unwrap() = devices;
+
+                    let processes = if processes_enabled.load(Ordering::Relaxed) {
+                        process_sampler.sample()
+                    } else {
+                        // Start over so usage is not averaged across the pause.
+                        process_sampler = GpuProcessSampler::new();
+                        Vec::new()
+                    };
+                    *processes_clone.lock().unwrap() = processes;
                 }
             });
         }
 
-        Self {
-            sys: System::new_all(),
-            cpu_usage: 0.0,
-            memory_usage: 0.0,
-            memory_total: 0,
-            memory_used: 0,
-            gpu_usage,
-            gpu_vendor,
-        }
+        self.gpu_running = Some(running);
     }
 
     /// Update CPU and memory statistics.
@@ -142,6 +359,7 @@ impl UtilizationMonitor {
         // Refresh CPU usage (requires multiple calls for accurate averaging)
         self.sys.refresh_cpu_all();
         self.cpu_usage = self.sys.global_cpu_usage();
+        self.update_cores();
 
         // Refresh memory statistics
         self.sys.refresh_memory();
@@ -152,10 +370,161 @@ impl UtilizationMonitor {
         } else {
             0.0
         };
+        self.update_memory_details();
+
+        if self.processes_enabled {
+            self.update_processes();
+        }
 
         // Note: GPU usage is updated in background thread
     }
 
+    /// Start or stop refreshing the top process lists on `update()`.
+    pub fn set_processes_enabled(&mut self, enabled: bool) {
+        self.processes_enabled = enabled;
+        if !enabled {
+            self.top_processes = TopProcesses::default();
+            self.top_process_trees = TopProcesses::default();
+        }
+    }
+
+    /// Refresh every process and rank them, singly and grouped into trees.
+    fn update_processes(&mut self) {
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::new()
+                .with_cpu()
+                .with_memory()
+                .with_exe(UpdateKind::OnlyIfNotSet),
+        );
+
+        let entries: Vec<ProcessEntry> = self
+            .sys
+            .processes()
+            .values()
+            // sysinfo also lists the threads of each process.
+            .filter(|process| process.thread_kind().is_none())
+            .map(|process| ProcessEntry {
+                parent: process.parent().map(|pid| pid.as_u32()),
+                exe: process.exe().map(PathBuf::from),
+                usage: ProcessUsage {
+                    pid: process.pid().as_u32(),
+                    name: process.name().to_string_lossy().into_owned(),
+                    cpu_usage: process.cpu_usage(),
+                    memory: process.memory(),
+                    count: 1,
+                },
+            })
+            .collect();
+
+        self.top_process_trees = rank_processes(group_process_trees(&entries));
+        self.top_processes = rank_processes(entries.into_iter().map(|entry| entry.usage).collect());
+    }
+
+    /// Refresh swap, zram, cache and pressure readings.
+    fn update_memory_details(&mut self) {
+        let (cached, buffers) = std::fs::read_to_string(MEMINFO_PATH)
+            .map(|meminfo| parse_meminfo_cache(&meminfo))
+            .unwrap_or_default();
+        self.memory_details = MemoryDetails {
+            swap_total: self.sys.total_swap(),
+            swap_used: self.sys.used_swap(),
+            zram: Self::read_zram_usage(),
+            cached,
+            buffers,
+            pressure: std::fs::read_to_string(MEMORY_PRESSURE_PATH)
+                .ok()
+                .and_then(|pressure| parse_memory_pressure(&pressure)),
+        };
+    }
+
+    /// Sum the data stored in all zram devices.
+    fn read_zram_usage() -> Option<ZramUsage> {
+        let entries = std::fs::read_dir("/sys/block").ok()?;
+        let usage = entries
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("zram"))
+            .filter_map(|entry| std::fs::read_to_string(entry.path().join("mm_stat")).ok())
+            .filter_map(|mm_stat| parse_zram_mm_stat(&mm_stat))
+            .fold(ZramUsage::default(), |total, usage| ZramUsage {
+                original: total.original + usage.original,
+                compressed: total.compressed + usage.compressed,
+            });
+        (usage.original > 0).then_some(usage)
+    }
+
+    /// Refresh per-core readings from the last CPU refresh.
+    fn update_cores(&mut self) {
+        let cpus = self.sys.cpus();
+        if self.core_info.len() != cpus.len() {
+            // Offline CPUs are missing from the list, so take the number
+            // from the name rather than the position.
+            let ids = cpus.iter().enumerate().map(|(index, cpu)| {
+                cpu.name()
+                    .strip_prefix("cpu")
+                    .and_then(|id| id.parse().ok())
+                    .unwrap_or(index)
+            });
+            self.core_info = Self::read_core_info(ids);
+        }
+
+        self.cpu_cores = cpus
+            .iter()
+            .zip(&self.core_info)
+            .map(|(cpu, (id, max_frequency_mhz, kind))| CpuCore {
+                id: *id,
+                usage: cpu.cpu_usage(),
+                frequency_mhz: cpu.frequency(),
+                max_frequency_mhz: *max_frequency_mhz,
+                kind: *kind,
+            })
+            .collect();
+
+        let load = System::load_average();
+        self.load_average = LoadAverage {
+            one: load.one,
+            five: load.five,
+            fifteen: load.fifteen,
+        };
+    }
+
+    /// Read the maximum frequency and core type of the logical CPUs `ids`.
+    ///
+    /// Core types are only assigned when the processor reports both
+    /// performance and efficiency cores.
+    fn read_core_info(ids: impl Iterator<Item = usize>) -> Vec<(usize, u64, Option<CoreKind>)> {
+        let read_list = |path| {
+            std::fs::read_to_string(path)
+                .ok()
+                .map(|list| parse_cpu_list(&list))
+                .unwrap_or_default()
+        };
+        let performance = read_list(PERFORMANCE_CPUS_PATH);
+        let efficiency = read_list(EFFICIENCY_CPUS_PATH);
+        let hybrid = !performance.is_empty() && !efficiency.is_empty();
+
+        ids.map(|id| {
+            let max_frequency_mhz = std::fs::read_to_string(format!(
+                "/sys/devices/system/cpu/cpu{id}/cpufreq/cpuinfo_max_freq"
+            ))
+            .ok()
+            .and_then(|khz| khz.trim().parse::<u64>().ok())
+            .map_or(0, |khz| khz / 1000);
+            let kind = if !hybrid {
+                None
+            } else if performance.contains(&id) {
+                Some(CoreKind::Performance)
+            } else if efficiency.contains(&id) {
+                Some(CoreKind::Efficiency)
+            } else {
+                None
+            };
+            (id, max_frequency_mhz, kind)
+        })
+        .collect()
+    }
+
     /// Get current GPU usage percentage.
     ///
     /// Thread-safe read from the background-updated value.
@@ -164,16 +533,34 @@ impl UtilizationMonitor {
         *self.gpu_usage.lock().unwrap()
     }
 
+    /// Readings of each GPU, in DRM card order.
+    ///
+    /// Empty while GPU monitoring is disabled or before the first poll.
+    pub fn gpu_devices(&self) -> Vec<GpuDevice> {
+        self.gpus.lock().unwrap().clone()
+    }
+
+    /// Start or stop sampling the busiest GPU processes, which reads the
+    /// fdinfo of every process.
+    pub fn set_gpu_processes_enabled(&self, enabled: bool) {
+        self.gpu_processes_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Busiest GPU processes, empty while sampling is disabled.
+    pub fn gpu_processes(&self) -> Vec<GpuProcess> {
+        self.gpu_processes.lock().unwrap().clone()
+    }
+
     // ========================================================================
     // GPU Vendor Detection
     // ========================================================================
 
-    /// Detect which GPU vendor is present on the system.
+    /// Detect which GPU vendor makes up the combined GPU usage.
     ///
     /// Checks NVML first, then detects AMD or Intel DRM devices through sysfs.
-    fn detect_gpu_vendor() -> GpuVendor {
+    fn detect_gpu_vendor() -> Option<GpuVendor> {
         if super::nvidia::hardware_present() {
-            return GpuVendor::Nvidia;
+            return Some(GpuVendor::Nvidia);
         }
 
         let mut amd_found = false;
@@ -189,86 +576,151 @@ impl UtilizationMonitor {
 
                 let vendor =
                     std::fs::read_to_string(entry.path().join("device/vendor")).unwrap_or_default();
-                match vendor.trim().to_ascii_lowercase().as_str() {
-                    "0x1002" => amd_found = true,
-                    "0x8086" => intel_found = true,
+                match GpuVendor::from_pci_id(&vendor) {
+                    Some(GpuVendor::Amd) => amd_found = true,
+                    Some(GpuVendor::Intel) => intel_found = true,
                     _ => {}
                 }
             }
         }
 
         if amd_found {
-            GpuVendor::Amd
+            Some(GpuVendor::Amd)
         } else if intel_found {
-            GpuVendor::Intel
+            Some(GpuVendor::Intel)
         } else {
-            GpuVendor::None
+            None
         }
     }
+}
 
-    // ========================================================================
-    // GPU Usage Fetching (called from background thread)
-    // ========================================================================
+impl Drop for UtilizationMonitor {
+    fn drop(&mut self) {
+        if let Some(running) = &self.gpu_running {
+            running.store(false, Ordering::Relaxed);
+        }
+    }
+}
 
-    /// Fetch AMD GPU utilization.
-    ///
-    /// Reads the kernel driver's utilization value from sysfs.
-    fn fetch_amd_gpu_usage() -> Option<f32> {
-        // Primary method: Read from sysfs (most reliable, no permissions needed)
-        // AMD GPUs expose utilization in /sys/class/drm/card*/device/gpu_busy_percent
-        if let Ok(entries) = std::fs::read_dir("/sys/class/drm") {
-            for entry in entries.flatten() {
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
+/// Keep the [`MAX_TOP_PROCESSES`] processes using the most CPU and the most
+/// memory.
+fn rank_processes(mut processes: Vec<ProcessUsage>) -> TopProcesses {
+    let limit = MAX_TOP_PROCESSES as usize;
 
-                if name_str.starts_with("card") && !name_str.contains("-") {
-                    let busy_path = entry.path().join("device/gpu_busy_percent");
-                    if let Ok(content) = std::fs::read_to_string(&busy_path) {
-                        if let Ok(usage) = content.trim().parse::<f32>() {
-                            return Some(usage);
-                        }
-                    }
-                }
-            }
-        }
+    processes.sort_by(|a, b| b.memory.cmp(&a.memory));
+    let by_memory = processes.iter().take(limit).cloned().collect();
+    processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    processes.truncate(limit);
 
-        None
+    TopProcesses {
+        by_cpu: processes,
+        by_memory,
     }
+}
 
-    /// Fetch Intel GPU utilization.
-    ///
-    /// Calculates from the current/maximum frequency ratio exposed by sysfs.
-    fn fetch_intel_gpu_usage() -> Option<f32> {
-        // Primary method: Calculate usage from frequency ratio
-        // Intel GPUs expose frequency in sysfs
-        if let Ok(entries) = std::fs::read_dir("/sys/class/drm") {
-            for entry in entries.flatten() {
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
-
-                if name_str.starts_with("card") && !name_str.contains("-") {
-                    // Try gt0 (most common)
-                    let cur_freq_path = entry.path().join("gt/gt0/rps_cur_freq_mhz");
-                    let max_freq_path = entry.path().join("gt/gt0/rps_max_freq_mhz");
-
-                    if let (Ok(cur_str), Ok(max_str)) = (
-                        std::fs::read_to_string(&cur_freq_path),
-                        std::fs::read_to_string(&max_freq_path),
-                    ) {
-                        if let (Ok(cur_freq), Ok(max_freq)) =
-                            (cur_str.trim().parse::<f32>(), max_str.trim().parse::<f32>())
-                        {
-                            if max_freq > 0.0 {
-                                return Some((cur_freq / max_freq) * 100.0);
-                            }
-                        }
-                    }
-                }
+/// Add each process to its topmost ancestor running the same executable,
+/// so a browser and its content processes form one entry.
+fn group_process_trees(entries: &[ProcessEntry]) -> Vec<ProcessUsage> {
+    let by_pid: HashMap<u32, &ProcessEntry> = entries
+        .iter()
+        .map(|entry| (entry.usage.pid, entry))
+        .collect();
+
+    let mut groups: HashMap<u32, ProcessUsage> = HashMap::new();
+    for entry in entries {
+        let mut root = entry;
+        // Kernel threads and processes whose executable is unreadable stay
+        // on their own.
+        if entry.exe.is_some() {
+            while let Some(parent) = root.parent.and_then(|pid| by_pid.get(&pid))
+                && parent.exe == entry.exe
+                && parent.usage.pid != root.usage.pid
+            {
+                root = parent;
             }
         }
 
-        None
+        let group = groups
+            .entry(root.usage.pid)
+            .or_insert_with(|| ProcessUsage {
+                count: 0,
+                cpu_usage: 0.0,
+                memory: 0,
+                ..root.usage.clone()
+            });
+        group.cpu_usage += entry.usage.cpu_usage;
+        group.memory += entry.usage.memory;
+        group.count += 1;
     }
+    groups.into_values().collect()
+}
+
+/// Parse a kernel CPU list such as `0-7,16,18-19`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => {
+                let start = start.trim().parse::<usize>().ok()?;
+                let end = end.trim().parse::<usize>().ok()?;
+                Some((start..=end).collect::<Vec<_>>())
+            }
+            None => range.trim().parse().ok().map(|cpu| vec![cpu]),
+        })
+        .flatten()
+        .collect()
+}
+
+/// Page cache and buffers in bytes from `/proc/meminfo`.
+fn parse_meminfo_cache(meminfo: &str) -> (u64, u64) {
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| {
+                value
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+            })
+            .map_or(0, |kib| kib * 1024)
+    };
+    (field("Cached"), field("Buffers"))
+}
+
+/// Original and compressed data size from a zram `mm_stat` file, whose
+/// first two columns hold them in bytes.
+fn parse_zram_mm_stat(mm_stat: &str) -> Option<ZramUsage> {
+    let mut columns = mm_stat.split_whitespace();
+    Some(ZramUsage {
+        original: columns.next()?.parse().ok()?,
+        compressed: columns.next()?.parse().ok()?,
+    })
+}
+
+/// `some` and `full` averages from `/proc/pressure/memory`.
+fn parse_memory_pressure(pressure: &str) -> Option<MemoryPressure> {
+    let averages = |kind: &str| {
+        let line = pressure
+            .lines()
+            .find_map(|line| line.strip_prefix(kind)?.strip_prefix(' '))?;
+        let value = |name: &str| {
+            line.split_whitespace()
+                .find_map(|field| field.strip_prefix(name)?.strip_prefix('='))
+                .and_then(|value| value.parse::<f32>().ok())
+        };
+        Some(PressureAverages {
+            avg10: value("avg10")?,
+            avg60: value("avg60")?,
+            avg300: value("avg300")?,
+        })
+    };
+    Some(MemoryPressure {
+        some: averages("some")?,
+        full: averages("full").unwrap_or_default(),
+    })
 }
 
 // ============================================================================
@@ -422,3 +874,104 @@ pub fn draw_progress_bar(
         cr.fill().expect("Failed to fill");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MemoryPressure, PressureAverages, ProcessEntry, ProcessUsage, ZramUsage,
+        group_process_trees, parse_cpu_list, parse_meminfo_cache, parse_memory_pressure,
+        parse_zram_mm_stat, rank_processes,
+    };
+    use std::path::PathBuf;
+
+    fn process(pid: u32, parent: u32, exe: &str, cpu_usage: f32, memory: u64) -> ProcessEntry {
+        ProcessEntry {
+            parent: Some(parent),
+            exe: (!exe.is_empty()).then(|| PathBuf::from(exe)),
+            usage: ProcessUsage {
+                pid,
+                name: exe.rsplit('/').next().unwrap_or_default().to_string(),
+                cpu_usage,
+                memory,
+                count: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn process_trees_group_under_the_topmost_same_executable() {
+        let firefox = "/usr/lib/firefox/firefox";
+        let entries = [
+            process(1, 0, "/usr/lib/systemd/systemd", 0.0, 10),
+            process(100, 1, firefox, 5.0, 300),
+            process(101, 100, firefox, 20.0, 500),
+            process(102, 100, firefox, 10.0, 200),
+            process(103, 101, "/usr/bin/bash", 1.0, 5),
+            process(200, 1, "", 2.0, 0),
+        ];
+
+        let top = rank_processes(group_process_trees(&entries));
+
+        assert_eq!(
+            top.by_cpu[0],
+            ProcessUsage {
+                pid: 100,
+                name: "firefox".to_string(),
+                cpu_usage: 35.0,
+                memory: 1000,
+                count: 3,
+            }
+        );
+        assert_eq!(top.by_cpu.len(), 4);
+        assert_eq!(
+            top.by_memory
+                .iter()
+                .map(|process| process.pid)
+                .collect::<Vec<_>>(),
+            [100, 1, 103, 200]
+        );
+    }
+
+    #[test]
+    fn cpu_lists_expand_ranges() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list(""), Vec::<usize>::new());
+    }
+    #[test]
+    fn meminfo_cache_fields_are_read_in_bytes() {
+        let meminfo = "MemTotal:       16303408 kB\nBuffers:          204800 kB\n\
+                       Cached:          3145728 kB\nSwapCached:        10240 kB\n";
+
+        assert_eq!(parse_meminfo_cache(meminfo), (3_221_225_472, 209_715_200));
+    }
+
+    #[test]
+    fn zram_and_pressure_files_are_parsed() {
+        assert_eq!(
+            parse_zram_mm_stat("1258291200 402653184 420478976 0 430000000 12 0 0 0\n"),
+            Some(ZramUsage {
+                original: 1_258_291_200,
+                compressed: 402_653_184,
+            })
+        );
+
+        let pressure = "some avg10=1.25 avg60=0.50 avg300=0.10 total=123456\n\
+                        full avg10=0.75 avg60=0.25 avg300=0.05 total=65432\n";
+        assert_eq!(
+            parse_memory_pressure(pressure),
+            Some(MemoryPressure {
+                some: PressureAverages {
+                    avg10: 1.25,
+                    avg60: 0.5,
+                    avg300: 0.1,
+                },
+                full: PressureAverages {
+                    avg10: 0.75,
+                    avg60: 0.25,
+                    avg300: 0.05,
+                },
+            })
+        );
+        assert_eq!(parse_memory_pressure(""), None);
+    }
+}
@@ -184,6 +184,9 @@ impl WeatherCache {
     }
 
     fn save(&self) {
+        if !super::cache::persistence_enabled() {
+            return;
+        }
         let Ok(json) = serde_json::to_string(self) else {
             return;
         };